@@ -0,0 +1,400 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::compiler::output_dir_for;
+use crate::latex::dependencies::{dependency_graph, DependencyKind};
+use crate::latex::root::{canonical, is_document};
+use crate::latex::{resolve_tex_path, strip_comment};
+use crate::document::detect_encoding;
+use crate::project::{read_config, write_config, ProjectConfig};
+use crate::scope::WindowScope;
+use crate::workspace::project_walker;
+
+/// 依赖图不追踪的本地类和样式；投稿缺了它们无法编译，所以总是打包
+const SUPPORT_EXTENSIONS: &[&str] = &["cls", "sty", "bst", "bbx", "cbx", "clo", "def", "cfg"];
+/// 展开时防止包含循环
+const MAX_FLATTEN_DEPTH: usize = 32;
+/// 解压后超过这个大小的 zip 直接拒绝，而不是占满磁盘
+const MAX_IMPORT_SIZE: u64 = 4 * 1024 * 1024 * 1024;
+
+/// 只内联 `\input` 和 `\include`；`\subfile` 和 `\import` 会改变被包含文件内路径的解析方式，这些文件保留原样
+static FLATTEN_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\\(input|include)\s*\{([^}]+)\}").unwrap());
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct ArchiveOptions {
+    /// 要写入的 `.zip`，用 `save_file_dialog` 选择
+    pub output_path: String,
+    /// 相对项目根目录；默认为项目配置中的根文档，或者唯一的完整文档
+    pub root_document: Option<String>,
+    /// 把所有 `\input`/`\include` 内联到根文档中
+    pub flatten: bool,
+    /// 只打包根文档用到的文件（加上本地的 `.cls`/`.sty` 文件），而不是整个项目
+    pub referenced_only: bool,
+    /// 在根文档旁加上编译得到的 `.bbl`；arXiv 不运行 BibTeX 或 biber
+    pub include_bbl: bool,
+}
+
+impl Default for ArchiveOptions {
+    fn default() -> Self {
+        ArchiveOptions {
+            output_path: String::new(),
+            root_document: None,
+            flatten: false,
+            referenced_only: true,
+            include_bbl: true,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ArchiveResult {
+    output_path: String,
+    /// 归档中的条目名
+    files: Vec<String>,
+    /// 缺失的引用、项目外的文件、没有 `.bbl` 等
+    warnings: Vec<String>,
+    size: u64,
+}
+
+enum Entry {
+    File(PathBuf),
+    Generated(String),
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+}
+
+fn find_root_document(root: &Path, requested: Option<&str>) -> Result<PathBuf, String> {
+    if let Some(name) = requested {
+        let path = root.join(name);
+        return if path.is_file() { Ok(path) } else { Err(format!("无法读取文件: {}", path.to_string_lossy())) };
+    }
+    if let Some(path) = read_config(root)?.and_then(|config| config.root_document_path(root)) {
+        return Ok(path);
+    }
+    let documents: Vec<PathBuf> = fs::read_dir(root)
+        .map_err(|e| format!("无法读取目录: {}", e))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| has_extension(path, &["tex"]))
+        .filter(|path| fs::read_to_string(path).is_ok_and(|content| is_document(&content)))
+        .collect();
+    if let Some(main) = documents.iter().find(|path| path.file_name().is_some_and(|name| name == "main.tex")) {
+        return Ok(main.clone());
+    }
+    match documents.as_slice() {
+        [only] => Ok(only.clone()),
+        [] => Err("项目中没有找到根文档".to_string()),
+        _ => Err("找到多个文档；请选择根文档".to_string()),
+    }
+}
+
+/// `path` 相对 `root` 的路径，以 `/` 分隔，即 zip 条目使用的形式
+fn entry_name(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let parts: Vec<String> = relative.components().map(|c| c.as_os_str().to_string_lossy().to_string()).collect();
+    Some(parts.join("/"))
+}
+
+struct Flattener<'a> {
+    base_dir: &'a Path,
+    /// 已内联文件的规范路径
+    inlined: HashSet<PathBuf>,
+    warnings: Vec<String>,
+}
+
+impl Flattener<'_> {
+    /// 把 `content` 中注释外的每个 `\input`/`\include` 替换为被包含的文件。找不到的文件保留原命令
+    fn flatten(&mut self, content: &str, depth: usize) -> String {
+        let mut out = String::with_capacity(content.len());
+        for line in content.lines() {
+            let code = strip_comment(line);
+            let mut last = 0;
+            for caps in FLATTEN_RE.captures_iter(code) {
+                let matched = caps.get(0).unwrap();
+                let path = resolve_tex_path(self.base_dir, &caps[2]);
+                let Ok(included) = fs::read_to_string(&path) else {
+                    self.warnings.push(format!("找不到被包含的文件: {}", path.to_string_lossy()));
+                    continue;
+                };
+                if depth >= MAX_FLATTEN_DEPTH {
+                    self.warnings.push(format!("包含嵌套过深，已保留: {}", path.to_string_lossy()));
+                    continue;
+                }
+                self.inlined.insert(canonical(&path));
+                out.push_str(&line[last..matched.start()]);
+                // 文件的结尾也结束了它的最后一行，所以这一行的剩余部分另起一行，不会被它注释掉
+                let is_include = &caps[1] == "include";
+                if is_include {
+                    out.push_str("\\clearpage\n");
+                }
+                out.push_str(&self.flatten(&included, depth + 1));
+                if is_include {
+                    out.push_str("\\clearpage\n");
+                }
+                last = matched.end();
+            }
+            out.push_str(&line[last..]);
+            out.push('\n');
+        }
+        out
+    }
+}
+
+fn write_archive(path: &Path, entries: &BTreeMap<String, Entry>) -> io::Result<()> {
+    let mut zip = ZipWriter::new(File::create(path)?);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated).large_file(true);
+    for (name, entry) in entries {
+        zip.start_file(name.as_str(), options)?;
+        match entry {
+            Entry::File(source) => {
+                io::copy(&mut File::open(source)?, &mut zip)?;
+            }
+            Entry::Generated(content) => zip.write_all(content.as_bytes())?,
+        }
+    }
+    zip.finish()?.sync_all()
+}
+
+fn export_archive_blocking(root: &Path, options: &ArchiveOptions) -> Result<ArchiveResult, String> {
+    if options.output_path.trim().is_empty() {
+        return Err("没有选择输出文件".to_string());
+    }
+    if !root.is_dir() {
+        return Err(format!("无法读取目录: {}", root.to_string_lossy()));
+    }
+    let output_path = PathBuf::from(&options.output_path);
+    let root_document = find_root_document(root, options.root_document.as_deref())?;
+    let root_name = entry_name(root, &root_document).ok_or("根文档不在项目内")?;
+    let output_dir = canonical(&output_dir_for(&root_document, None)?);
+    let skip = |path: &Path| {
+        let path = canonical(path);
+        path.starts_with(&output_dir) || path == canonical(&output_path)
+    };
+
+    let graph = dependency_graph(&root_document);
+    let mut warnings: Vec<String> = graph.diagnostic_messages().map(str::to_string).collect();
+    let mut entries = BTreeMap::new();
+    let project_files: Vec<PathBuf> = project_walker(root)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| entry.into_path())
+        .collect();
+    let files: Vec<PathBuf> = if options.referenced_only {
+        let support = project_files.into_iter().filter(|path| has_extension(path, SUPPORT_EXTENSIONS));
+        graph.existing_files().chain(support).collect()
+    } else {
+        std::iter::once(root_document.clone()).chain(project_files).collect()
+    };
+    for path in files {
+        if skip(&path) {
+            continue;
+        }
+        match entry_name(root, &path) {
+            Some(name) => {
+                entries.entry(name).or_insert(Entry::File(path));
+            }
+            None => warnings.push(format!("在项目外，未包含: {}", path.to_string_lossy())),
+        }
+    }
+
+    if options.flatten {
+        let content = fs::read_to_string(&root_document).map_err(|e| format!("无法读取文件: {}", e))?;
+        let mut flattener = Flattener {
+            base_dir: root_document.parent().unwrap_or(Path::new(".")),
+            inlined: HashSet::new(),
+            warnings: Vec::new(),
+        };
+        let flattened = flattener.flatten(&content, 0);
+        entries.retain(|_, entry| !matches!(entry, Entry::File(path) if flattener.inlined.contains(&canonical(path))));
+        entries.insert(root_name.clone(), Entry::Generated(flattened));
+        warnings.extend(flattener.warnings);
+    }
+
+    if options.include_bbl {
+        let stem = root_document.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        let bbl = output_dir.join(format!("{}.bbl", stem));
+        if bbl.is_file() {
+            let name = Path::new(&root_name).with_extension("bbl").to_string_lossy().replace('\\', "/");
+            entries.insert(name, Entry::File(bbl));
+        } else if graph.existing_of_kind(DependencyKind::Bib).next().is_some() {
+            warnings.push("没有找到编译得到的 .bbl；请先编译文档再导出".to_string());
+        }
+    }
+
+    if let Err(e) = write_archive(&output_path, &entries) {
+        fs::remove_file(&output_path).ok();
+        return Err(format!("无法写入文件: {}", e));
+    }
+    Ok(ArchiveResult {
+        output_path: output_path.to_string_lossy().to_string(),
+        files: entries.into_keys().collect(),
+        warnings,
+        size: fs::metadata(&output_path).map(|m| m.len()).unwrap_or(0),
+    })
+}
+
+/// 把 `root` 处的项目打包成可以提交给 arXiv 或期刊的 `.zip`：只包含编译需要的内容，不含 `AuxiliaryFiles`、`.git`
+/// 或其他编译产物，可选展开为单个 `.tex`
+#[command]
+pub async fn export_project_archive(
+    scope: WindowScope,
+    root: String,
+    options: ArchiveOptions,
+) -> Result<ArchiveResult, String> {
+    scope.check(&root)?;
+    scope.check(&options.output_path)?;
+    tauri::async_runtime::spawn_blocking(move || export_archive_blocking(Path::new(&root), &options))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[derive(Serialize)]
+pub struct ImportResult {
+    /// 相对 `dest_dir`；没有找到唯一的根文档时为空
+    root_document: Option<String>,
+    files: Vec<String>,
+    /// 没有解压的条目及原因
+    skipped: Vec<String>,
+}
+
+/// 条目名的文本。Windows 上的 zip 常以本地代码页（GBK、Shift_JIS）存储文件名而不做标记；像文件内容一样猜测编码
+fn decode_entry_name(raw: &[u8]) -> String {
+    let encoding = detect_encoding(raw);
+    encoding.decode_without_bom_handling(raw).0.into_owned()
+}
+
+/// 条目解压到的相对路径；会落到目标目录之外（绝对路径、`..`、盘符）时为 `None`
+fn safe_relative_path(name: &str) -> Option<PathBuf> {
+    if name.starts_with(['/', '\\']) {
+        return None;
+    }
+    let mut path = PathBuf::new();
+    for part in name.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => return None,
+            part if part.contains([':', '\0']) => return None,
+            part => path.push(part),
+        }
+    }
+    (!path.as_os_str().is_empty()).then_some(path)
+}
+
+/// macOS 归档工具添加的资源分支和 Finder 元数据
+fn is_junk(path: &Path) -> bool {
+    path.components().any(|c| c.as_os_str() == "__MACOSX") || path.file_name().is_some_and(|name| name == ".DS_Store")
+}
+
+fn import_zip_blocking(zip_path: &Path, dest: &Path) -> Result<ImportResult, String> {
+    let file = File::open(zip_path).map_err(|e| format!("无法读取文件: {}", e))?;
+    let mut zip = zip::ZipArchive::new(file).map_err(|e| format!("无效的归档: {}", e))?;
+
+    let mut skipped = Vec::new();
+    let mut planned = Vec::new();
+    let mut total_size = 0u64;
+    for index in 0..zip.len() {
+        let entry = zip.by_index_raw(index).map_err(|e| format!("无效的归档: {}", e))?;
+        let name = decode_entry_name(entry.name_raw());
+        if entry.is_dir() {
+            continue;
+        }
+        if entry.is_symlink() {
+            skipped.push(format!("{}（符号链接）", name));
+            continue;
+        }
+        let Some(path) = safe_relative_path(&name) else {
+            skipped.push(format!("{}（在项目外）", name));
+            continue;
+        };
+        if is_junk(&path) {
+            continue;
+        }
+        total_size += entry.size();
+        planned.push((index, path));
+    }
+    if total_size > MAX_IMPORT_SIZE {
+        return Err("归档太大，无法导入".to_string());
+    }
+
+    // GitHub 和一些 Overleaf 导出把所有内容包在一个文件夹里；拆开它，让项目根目录就是 `dest_dir`
+    let first_dirs: HashSet<_> = planned.iter().map(|(_, path)| path.components().next()).collect();
+    if first_dirs.len() == 1 && planned.iter().all(|(_, path)| path.components().count() > 1) {
+        for (_, path) in &mut planned {
+            *path = path.components().skip(1).collect();
+        }
+    }
+
+    if let Some((_, existing)) = planned.iter().find(|(_, path)| dest.join(path).exists()) {
+        return Err(format!("文件已存在: {}", dest.join(existing).to_string_lossy()));
+    }
+
+    let mut files = Vec::new();
+    for (index, relative) in planned {
+        let mut entry = zip.by_index(index).map_err(|e| format!("无效的归档: {}", e))?;
+        let path = dest.join(&relative);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("无法创建目录: {}", e))?;
+        }
+        let mut out = File::create(&path).map_err(|e| format!("无法写入文件: {}", e))?;
+        if let Err(e) = io::copy(&mut entry, &mut out) {
+            drop(out);
+            fs::remove_file(&path).ok();
+            return Err(format!("无法写入文件: {}", e));
+        }
+        files.push(relative.to_string_lossy().replace('\\', "/"));
+    }
+
+    let root_document = find_root_document(dest, None).ok().and_then(|path| entry_name(dest, &path));
+    if let Some(root_document) = &root_document {
+        if read_config(dest)?.is_none() {
+            let config = ProjectConfig { root_document: Some(root_document.clone()), ..ProjectConfig::default() };
+            write_config(dest, &config)?;
+        }
+    }
+    files.sort();
+    Ok(ImportResult { root_document, files, skipped })
+}
+
+/// 把项目 zip（Overleaf 下载、arXiv 源码包）解压到 `dest_dir`，并在项目配置中记录根文档。
+/// 会逃出 `dest_dir` 的条目被跳过，也不覆盖任何文件
+#[command]
+pub async fn import_project_zip(
+    scope: WindowScope,
+    zip_path: String,
+    dest_dir: String,
+) -> Result<ImportResult, String> {
+    scope.check(&zip_path)?;
+    scope.check(&dest_dir)?;
+    tauri::async_runtime::spawn_blocking(move || import_zip_blocking(Path::new(&zip_path), Path::new(&dest_dir)))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_stay_inside_the_destination() {
+        assert_eq!(safe_relative_path("chapters/intro.tex"), Some(Path::new("chapters").join("intro.tex")));
+        assert_eq!(safe_relative_path("./figs\\a.png"), Some(Path::new("figs").join("a.png")));
+        for name in ["/etc/passwd", "\\server\\share", "../x", "a/../../x", "C:/x", "C:x", "", "./", "a\0b"] {
+            assert_eq!(safe_relative_path(name), None, "{:?}", name);
+        }
+    }
+}
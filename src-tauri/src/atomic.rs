@@ -0,0 +1,55 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 临时文件名中的序号，同一文件同时进行的两次保存不会写到同一个临时文件
+static TEMP_SEQ: AtomicU64 = AtomicU64::new(0);
+
+fn temp_path_for(target: &Path) -> PathBuf {
+    let name = target.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let seq = TEMP_SEQ.fetch_add(1, Ordering::Relaxed);
+    target.with_file_name(format!(".{}.{}.{}.tmp", name, process::id(), seq))
+}
+
+/// 崩溃安全地替换 `path`：写入同目录的临时文件并 fsync，沿用原文件的权限，再重命名覆盖目标。
+/// 任何时刻崩溃，磁盘上留下的要么是旧内容，要么是新内容。会跟随符号链接，链接本身不会被普通文件替换。
+/// 返回写入文件的修改时间
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<SystemTime> {
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let temp = temp_path_for(&target);
+
+    let result = (|| {
+        let mut file = OpenOptions::new().write(true).create_new(true).open(&temp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        drop(file);
+
+        if let Ok(metadata) = fs::metadata(&target) {
+            fs::set_permissions(&temp, metadata.permissions())?;
+        }
+        fs::rename(&temp, &target)
+    })();
+
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp);
+        return Err(e);
+    }
+
+    // 让重命名本身落盘；Windows 上不支持对目录这样做
+    #[cfg(unix)]
+    if let Some(dir) = target.parent() {
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+
+    fs::metadata(&target)?.modified()
+}
+
+/// Unix 纪元以来的毫秒数，即传给前端的表示
+pub fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
@@ -0,0 +1,210 @@
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use regex::Regex;
+use tauri::command;
+use ureq::Agent;
+
+use super::parser::{clean_value, format_entry, parse_bib, split_names, BibEntry};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+/// CrossRef 要求客户端表明身份，否则可能被限流
+const USER_AGENT: &str = "MyMD-IDE (https://github.com/Yinghao-Guan/MyMD-IDE)";
+/// 拼进 URL 路径的 DOI 只保留非保留字符；DOI 里的 `/`、`?`、`#` 等都要编码，否则会改变请求的路径或查询
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+static DOI_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^10\.\d{4,9}/\S+$").unwrap());
+/// 新格式 `2101.00001v2` 与旧格式 `hep-th/9901001`
+static ARXIV_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:\d{4}\.\d{4,5}|[a-z\-]+(?:\.[A-Z]{2})?/\d{7})(?:v\d+)?$").unwrap()
+});
+static ARXIV_VERSION_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"v\d+$").unwrap());
+static ATOM_ENTRY_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)<entry>(.*?)</entry>").unwrap());
+static ATOM_AUTHOR_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<author>\s*<name>(.*?)</name>").unwrap());
+static ATOM_CATEGORY_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"<arxiv:primary_category[^>]*term="([^"]+)""#).unwrap());
+
+enum Identifier {
+    Doi(String),
+    Arxiv(String),
+}
+
+/// 接受裸 id，也接受 `doi:` / `arXiv:` 前缀和 doi.org / arxiv.org 链接
+fn classify(input: &str) -> Result<Identifier, String> {
+    let trimmed = input.trim();
+    let lower = trimmed.to_lowercase();
+    for prefix in ["https://doi.org/", "http://doi.org/", "https://dx.doi.org/", "doi:"] {
+        if lower.starts_with(prefix) {
+            return Ok(Identifier::Doi(trimmed[prefix.len()..].trim().to_string()));
+        }
+    }
+    for prefix in ["https://arxiv.org/abs/", "http://arxiv.org/abs/", "https://arxiv.org/pdf/", "arxiv:"] {
+        if lower.starts_with(prefix) {
+            let id = trimmed[prefix.len()..].trim().trim_end_matches(".pdf");
+            return Ok(Identifier::Arxiv(id.to_string()));
+        }
+    }
+    if DOI_RE.is_match(trimmed) {
+        Ok(Identifier::Doi(trimmed.to_string()))
+    } else if ARXIV_RE.is_match(trimmed) {
+        Ok(Identifier::Arxiv(trimmed.to_string()))
+    } else {
+        Err(format!("无法识别的 DOI 或 arXiv 编号: {}", trimmed))
+    }
+}
+
+fn agent() -> Agent {
+    Agent::config_builder()
+        .timeout_global(Some(REQUEST_TIMEOUT))
+        .user_agent(USER_AGENT)
+        .build()
+        .into()
+}
+
+/// 返回 Ok(None) 表示 404，调用方可以换下一个数据源
+fn get(agent: &Agent, url: &str, accept: &str) -> Result<Option<String>, String> {
+    match agent.get(url).header("Accept", accept).call() {
+        Ok(mut response) => response
+            .body_mut()
+            .read_to_string()
+            .map(Some)
+            .map_err(|e| format!("读取响应失败: {}", e)),
+        Err(ureq::Error::StatusCode(404)) => Ok(None),
+        Err(e) => Err(format!("请求 {} 失败: {}", url, e)),
+    }
+}
+
+/// CrossRef 覆盖绝大多数期刊论文；数据集、软件等的 DOI 由 DataCite 注册
+fn fetch_doi(agent: &Agent, doi: &str) -> Result<String, String> {
+    let segment = utf8_percent_encode(doi, PATH_SEGMENT);
+    let sources = [
+        format!("https://api.crossref.org/works/{}/transform/application/x-bibtex", segment),
+        format!("https://api.datacite.org/dois/application/x-bibtex/{}", segment),
+    ];
+    for url in &sources {
+        if let Some(body) = get(agent, url, "application/x-bibtex")? {
+            if body.trim_start().starts_with('@') {
+                return Ok(body);
+            }
+        }
+    }
+    Err(format!("找不到 DOI: {}", doi))
+}
+
+fn decode_xml(text: &str) -> String {
+    let decoded = text
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&");
+    decoded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn atom_field(entry: &str, tag: &str) -> Option<String> {
+    let start = entry.find(&format!("<{}", tag))?;
+    let open_end = start + entry[start..].find('>')? + 1;
+    let close = open_end + entry[open_end..].find(&format!("</{}>", tag))?;
+    Some(decode_xml(&entry[open_end..close]))
+}
+
+fn escape_bibtex(text: &str) -> String {
+    text.replace('&', "\\&").replace('%', "\\%")
+}
+
+/// arXiv API 返回 Atom，按 biblatex 推荐的 eprint 字段生成 @misc 条目
+fn fetch_arxiv(agent: &Agent, id: &str) -> Result<String, String> {
+    let url = format!("https://export.arxiv.org/api/query?id_list={}", id);
+    let body = get(agent, &url, "application/atom+xml")?.ok_or_else(|| format!("找不到 arXiv 论文: {}", id))?;
+    let entry = ATOM_ENTRY_RE
+        .captures(&body)
+        .map(|caps| caps[1].to_string())
+        .ok_or_else(|| format!("找不到 arXiv 论文: {}", id))?;
+    // 无效 id 时 API 返回一个 id 指向 /api/errors 的条目
+    if atom_field(&entry, "id").is_some_and(|entry_id| entry_id.contains("/api/errors")) {
+        return Err(format!("找不到 arXiv 论文: {}", id));
+    }
+
+    let title = atom_field(&entry, "title").unwrap_or_default();
+    let authors: Vec<String> = ATOM_AUTHOR_RE
+        .captures_iter(&entry)
+        .map(|caps| escape_bibtex(&decode_xml(&caps[1])))
+        .collect();
+    let year = atom_field(&entry, "published").map(|date| date.chars().take(4).collect::<String>());
+    let bare_id = ARXIV_VERSION_RE.replace(id, "");
+
+    let mut fields = vec![
+        ("title".to_string(), escape_bibtex(&title)),
+        ("author".to_string(), authors.join(" and ")),
+    ];
+    if let Some(year) = year {
+        fields.push(("year".to_string(), year));
+    }
+    fields.push(("eprint".to_string(), bare_id.to_string()));
+    fields.push(("archiveprefix".to_string(), "arXiv".to_string()));
+    if let Some(caps) = ATOM_CATEGORY_RE.captures(&entry) {
+        fields.push(("primaryclass".to_string(), caps[1].to_string()));
+    }
+    if let Some(doi) = atom_field(&entry, "arxiv:doi") {
+        fields.push(("doi".to_string(), doi));
+    }
+    fields.push(("url".to_string(), format!("https://arxiv.org/abs/{}", bare_id)));
+
+    let entry = BibEntry { entry_type: "misc".to_string(), key: bare_id.to_string(), fields, line: 1 };
+    Ok(format_entry(&entry))
+}
+
+/// Google Scholar 风格的引用键：第一作者姓 + 年份 + 标题首个实词，如 `knuth1984texbook`
+fn generate_key(entry: &BibEntry) -> Option<String> {
+    const STOP_WORDS: &[&str] = &["a", "an", "the", "on", "of", "in", "for", "and", "to", "with"];
+    let author = entry.field("author").or_else(|| entry.field("editor")).map(split_names)?;
+    let first = author.first()?;
+    // "Last, First" 或 "First Last"
+    let last_name = match first.split_once(',') {
+        Some((last, _)) => last.to_string(),
+        None => first.split_whitespace().last()?.to_string(),
+    };
+    let normalize = |s: &str| -> String {
+        s.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_lowercase()
+    };
+    let year = entry.field("year").map(clean_value).unwrap_or_default();
+    let word = entry
+        .field("title")
+        .map(clean_value)
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(normalize)
+        .find(|w| !w.is_empty() && !STOP_WORDS.contains(&w.as_str()))
+        .unwrap_or_default();
+    let key = format!("{}{}{}", normalize(&last_name), normalize(&year), word);
+    (!key.is_empty()).then_some(key)
+}
+
+fn fetch_blocking(identifier: &str) -> Result<String, String> {
+    let agent = agent();
+    let raw = match classify(identifier)? {
+        Identifier::Doi(doi) => fetch_doi(&agent, &doi)?,
+        Identifier::Arxiv(id) => fetch_arxiv(&agent, &id)?,
+    };
+    // 统一格式和引用键；数据源给出的键（如 CrossRef 的 `Knuth_1984`）风格不一
+    let parsed = parse_bib(&raw);
+    let mut entry = parsed
+        .entries
+        .into_iter()
+        .next()
+        .ok_or_else(|| "返回的 BibTeX 无法解析".to_string())?;
+    if let Some(key) = generate_key(&entry) {
+        entry.key = key;
+    }
+    Ok(format_entry(&entry))
+}
+
+/// 根据 DOI 或 arXiv 编号在线获取元数据，返回格式化后的 BibTeX 条目。
+#[command]
+pub async fn fetch_bibtex(identifier: String) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || fetch_blocking(&identifier))
+        .await
+        .map_err(|e| e.to_string())?
+}
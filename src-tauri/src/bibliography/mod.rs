@@ -0,0 +1,209 @@
+pub mod fetch;
+pub mod parser;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+use tauri::{command, AppHandle, Manager};
+
+use crate::atomic::write_atomic;
+use crate::index::ProjectIndex;
+use crate::latex::root::canonical;
+use crate::scope::WindowScope;
+use crate::workspace::project_walker;
+use parser::{clean_value, format_entry, parse_bib, split_names, BibEntry, BibFile};
+
+#[derive(Serialize)]
+pub struct Citation {
+    pub key: String,
+    pub entry_type: String,
+    pub authors: Vec<String>,
+    pub title: Option<String>,
+    pub year: Option<String>,
+    pub file: String,
+    pub line: u32,
+}
+
+#[derive(Serialize)]
+pub struct BibFileDiagnostic {
+    file: String,
+    line: u32,
+    message: String,
+    severity: &'static str,
+}
+
+#[derive(Serialize)]
+pub struct AppendResult {
+    /// 实际使用的引用键；与已有条目冲突时会追加后缀
+    key: String,
+    /// 文件中已有同一 DOI / arXiv 编号的条目时为 false，返回已有条目的键
+    inserted: bool,
+}
+
+#[derive(Serialize)]
+pub struct CitationIndex {
+    citations: Vec<Citation>,
+    diagnostics: Vec<BibFileDiagnostic>,
+}
+
+fn citation(entry: &BibEntry, file: &str) -> Citation {
+    // 没有作者的条目（如论文集）退而显示编者
+    let authors = entry
+        .field("author")
+        .or_else(|| entry.field("editor"))
+        .map(split_names)
+        .unwrap_or_default();
+    // biblatex 用 date 代替 year，取其中的年份部分
+    let year = entry
+        .field("year")
+        .map(clean_value)
+        .or_else(|| entry.field("date").map(|d| d.chars().take(4).collect()));
+    Citation {
+        key: entry.key.clone(),
+        entry_type: entry.entry_type.clone(),
+        authors,
+        title: entry.field("title").map(clean_value),
+        year,
+        file: file.to_string(),
+        line: entry.line,
+    }
+}
+
+/// 项目中所有 .bib 文件，遵循与文件树相同的忽略规则
+pub fn bib_files(root: &Path) -> Vec<std::path::PathBuf> {
+    let mut files: Vec<_> = project_walker(root)
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| entry.into_path())
+        .filter(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("bib")))
+        .collect();
+    files.sort();
+    files
+}
+
+#[derive(Default)]
+struct IndexBuilder {
+    citations: Vec<Citation>,
+    diagnostics: Vec<BibFileDiagnostic>,
+    seen: HashMap<String, (String, u32)>,
+}
+
+impl IndexBuilder {
+    fn add(&mut self, file: String, parsed: &BibFile) {
+        self.diagnostics.extend(parsed.diagnostics.iter().map(|d| BibFileDiagnostic {
+            file: file.clone(),
+            line: d.line,
+            message: d.message.clone(),
+            severity: d.severity,
+        }));
+        for entry in &parsed.entries {
+            // BibTeX 只使用第一个同名条目
+            if let Some((first_file, first_line)) = self.seen.get(&entry.key) {
+                self.diagnostics.push(BibFileDiagnostic {
+                    file: file.clone(),
+                    line: entry.line,
+                    message: format!("引用键 {} 重复（首次定义于 {}:{}）", entry.key, first_file, first_line),
+                    severity: "warning",
+                });
+                continue;
+            }
+            self.seen.insert(entry.key.clone(), (file.clone(), entry.line));
+            self.citations.push(citation(entry, &file));
+        }
+    }
+}
+
+/// 根目录在被监听的文件夹里时直接用工作区索引中已解析的 .bib，否则逐个读取解析
+fn index_blocking(app: &AppHandle, root: &Path) -> Result<CitationIndex, String> {
+    if !root.is_dir() {
+        return Err(format!("无法读取目录: {}", root.to_string_lossy()));
+    }
+    let mut builder = IndexBuilder::default();
+    if let Some(index) = app.state::<ProjectIndex>().watched(root) {
+        let index = index.read().unwrap();
+        for (path, parsed) in index.bibliographies(&canonical(root)) {
+            builder.add(path.to_string_lossy().to_string(), parsed);
+        }
+    } else {
+        for path in bib_files(root) {
+            let file = path.to_string_lossy().to_string();
+            match fs::read_to_string(&path) {
+                Ok(content) => builder.add(file, &parse_bib(&content)),
+                Err(e) => builder.diagnostics.push(BibFileDiagnostic {
+                    file,
+                    line: 0,
+                    message: format!("无法读取文件: {}", e),
+                    severity: "error",
+                }),
+            }
+        }
+    }
+    Ok(CitationIndex { citations: builder.citations, diagnostics: builder.diagnostics })
+}
+
+/// 补全只需要引用列表，解析诊断直接丢弃
+pub fn project_citations(app: &AppHandle, root: &Path) -> Vec<Citation> {
+    index_blocking(app, root).map(|index| index.citations).unwrap_or_default()
+}
+
+/// 供 `\cite{}` 补全使用的引用列表，附带 .bib 中的语法问题。
+#[command]
+pub async fn list_citations(app: AppHandle, scope: WindowScope, root: String) -> Result<CitationIndex, String> {
+    scope.check(&root)?;
+    tauri::async_runtime::spawn_blocking(move || index_blocking(&app, Path::new(&root)))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// 判断两个条目是否指向同一文献
+fn same_work(a: &BibEntry, b: &BibEntry) -> bool {
+    ["doi", "eprint"].iter().any(|field| match (a.field(field), b.field(field)) {
+        (Some(x), Some(y)) => x.trim().eq_ignore_ascii_case(y.trim()),
+        _ => false,
+    })
+}
+
+/// 把一条 BibTeX 追加到 .bib 文件末尾；同一文献不会重复插入，引用键冲突时改为 `key-a`、`key-b`……
+#[command]
+pub fn append_bib_entry(scope: WindowScope, bib_path: String, entry: String) -> Result<AppendResult, String> {
+    scope.check(&bib_path)?;
+    let path = Path::new(&bib_path);
+    if !path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("bib")) {
+        return Err(format!("不是 .bib 文件: {}", bib_path));
+    }
+    let mut new_entry = parse_bib(&entry)
+        .entries
+        .into_iter()
+        .next()
+        .ok_or("无法解析要插入的 BibTeX 条目")?;
+    let existing = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("无法读取文件: {}", e)),
+    };
+    let parsed = parse_bib(&existing);
+
+    if let Some(duplicate) = parsed.entries.iter().find(|e| same_work(e, &new_entry)) {
+        return Ok(AppendResult { key: duplicate.key.clone(), inserted: false });
+    }
+    // BibTeX 的引用键不区分大小写
+    let taken = |key: &str| parsed.entries.iter().any(|e| e.key.eq_ignore_ascii_case(key));
+    if taken(&new_entry.key) {
+        let base = new_entry.key.clone();
+        new_entry.key = ('a'..='z')
+            .map(|suffix| format!("{}-{}", base, suffix))
+            .find(|key| !taken(key))
+            .ok_or_else(|| format!("引用键 {} 冲突过多", base))?;
+    }
+
+    let mut content = existing.trim_end().to_string();
+    if !content.is_empty() {
+        content.push_str("\n\n");
+    }
+    content.push_str(&format_entry(&new_entry));
+    write_atomic(path, content.as_bytes()).map_err(|e| format!("无法写入文件: {}", e))?;
+    Ok(AppendResult { key: new_entry.key, inserted: true })
+}
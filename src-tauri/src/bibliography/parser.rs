@@ -0,0 +1,454 @@
+use std::collections::HashMap;
+
+/// 一个 BibTeX 条目；字段名统一为小写，值已展开 `@string` 宏和 `#` 拼接。
+pub struct BibEntry {
+    pub entry_type: String,
+    pub key: String,
+    pub fields: Vec<(String, String)>,
+    /// 条目 `@` 所在行，从 1 开始
+    pub line: u32,
+}
+
+impl BibEntry {
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+}
+
+pub struct BibDiagnostic {
+    pub line: u32,
+    pub message: String,
+    /// "error" 表示条目被丢弃，"warning" 表示条目仍然可用
+    pub severity: &'static str,
+}
+
+#[derive(Default)]
+pub struct BibFile {
+    pub entries: Vec<BibEntry>,
+    pub diagnostics: Vec<BibDiagnostic>,
+}
+
+/// BibTeX 内置的月份宏
+const MONTH_MACROS: &[(&str, &str)] = &[
+    ("jan", "January"), ("feb", "February"), ("mar", "March"), ("apr", "April"),
+    ("may", "May"), ("jun", "June"), ("jul", "July"), ("aug", "August"),
+    ("sep", "September"), ("oct", "October"), ("nov", "November"), ("dec", "December"),
+];
+
+struct ParseError {
+    pos: usize,
+    message: String,
+}
+
+type ParseResult<T> = Result<T, ParseError>;
+
+struct Parser<'a> {
+    src: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+    line_starts: Vec<usize>,
+    macros: HashMap<String, String>,
+    diagnostics: Vec<BibDiagnostic>,
+}
+
+/// 容错解析：出错的条目记一条诊断并跳到下一个 `@`，不影响其余条目。
+pub fn parse_bib(src: &str) -> BibFile {
+    let mut line_starts = vec![0];
+    line_starts.extend(src.match_indices('\n').map(|(i, _)| i + 1));
+    let mut parser = Parser {
+        src,
+        bytes: src.as_bytes(),
+        pos: 0,
+        line_starts,
+        macros: MONTH_MACROS.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+        diagnostics: Vec::new(),
+    };
+
+    let mut entries = Vec::new();
+    // `@` 之外的文本都是注释
+    while let Some(offset) = src[parser.pos..].find('@') {
+        let start = parser.pos + offset;
+        parser.pos = start + 1;
+        match parser.parse_item(start) {
+            Ok(Some(entry)) => entries.push(entry),
+            Ok(None) => {}
+            Err(error) => {
+                let line = parser.line_of(error.pos);
+                parser.diagnostics.push(BibDiagnostic { line, message: error.message, severity: "error" });
+                parser.recover(start);
+            }
+        }
+    }
+
+    BibFile { entries, diagnostics: parser.diagnostics }
+}
+
+impl Parser<'_> {
+    fn line_of(&self, pos: usize) -> u32 {
+        match self.line_starts.binary_search(&pos) {
+            Ok(index) => index as u32 + 1,
+            Err(index) => index as u32,
+        }
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> ParseResult<T> {
+        Err(ParseError { pos: self.pos.min(self.src.len()), message: message.into() })
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn at_line_start(&self, pos: usize) -> bool {
+        self.src[..pos].rsplit('\n').next().is_some_and(|before| before.trim().is_empty())
+    }
+
+    /// 跳到出错条目之后、下一个位于行首的 `@`
+    fn recover(&mut self, start: usize) {
+        let rest = &self.src[start + 1..];
+        self.pos = rest
+            .match_indices('@')
+            .map(|(i, _)| start + 1 + i)
+            .find(|&i| self.at_line_start(i))
+            .unwrap_or(self.src.len());
+    }
+
+    fn identifier(&mut self) -> &str {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|b| !b.is_ascii_whitespace() && !b"{}(),=#\"%".contains(&b))
+        {
+            self.pos += 1;
+        }
+        &self.src[start..self.pos]
+    }
+
+    /// 解析 `@type{...}`；`@comment` / `@preamble` / `@string` 不产生条目
+    fn parse_item(&mut self, start: usize) -> ParseResult<Option<BibEntry>> {
+        let line = self.line_of(start);
+        self.skip_whitespace();
+        let entry_type = self.identifier().to_lowercase();
+        if entry_type.is_empty() {
+            return Ok(None);
+        }
+        self.skip_whitespace();
+        let close = match self.peek() {
+            Some(b'{') => b'}',
+            Some(b'(') => b')',
+            _ => {
+                // 行中间的 @（例如注释里的邮箱地址）不是条目
+                if entry_type == "comment" || !self.at_line_start(start) {
+                    return Ok(None);
+                }
+                return self.error(format!("@{} 后缺少 {{", entry_type));
+            }
+        };
+        self.pos += 1;
+
+        match entry_type.as_str() {
+            "comment" => {
+                self.pos -= 1;
+                self.skip_group()?;
+                Ok(None)
+            }
+            "preamble" => {
+                self.skip_whitespace();
+                self.parse_value()?;
+                self.expect_close(close)?;
+                Ok(None)
+            }
+            "string" => {
+                self.skip_whitespace();
+                let name = self.identifier().to_lowercase();
+                if name.is_empty() {
+                    return self.error("@string 缺少宏名");
+                }
+                self.skip_whitespace();
+                if self.peek() != Some(b'=') {
+                    return self.error(format!("@string{{{}}} 缺少 =", name));
+                }
+                self.pos += 1;
+                self.skip_whitespace();
+                let value = self.parse_value()?;
+                self.expect_close(close)?;
+                self.macros.insert(name, value);
+                Ok(None)
+            }
+            _ => self.parse_entry(entry_type, close, line).map(Some),
+        }
+    }
+
+    fn expect_close(&mut self, close: u8) -> ParseResult<()> {
+        self.skip_whitespace();
+        if self.peek() == Some(close) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            self.error(format!("缺少 {}", close as char))
+        }
+    }
+
+    fn parse_entry(&mut self, entry_type: String, close: u8, line: u32) -> ParseResult<BibEntry> {
+        self.skip_whitespace();
+        let key = self.identifier().to_string();
+        if key.is_empty() {
+            return self.error(format!("@{} 条目缺少引用键", entry_type));
+        }
+        let mut entry = BibEntry { entry_type, key, fields: Vec::new(), line };
+
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(c) if c == close => {
+                    self.pos += 1;
+                    return Ok(entry);
+                }
+                None => return self.error(format!("条目 {} 没有结束", entry.key)),
+                _ => return self.error(format!("条目 {} 中字段之间缺少逗号", entry.key)),
+            }
+            self.skip_whitespace();
+            // 允许最后一个字段后面多一个逗号
+            if self.peek() == Some(close) {
+                self.pos += 1;
+                return Ok(entry);
+            }
+
+            let field_pos = self.pos;
+            let name = self.identifier().to_lowercase();
+            if name.is_empty() {
+                return self.error(format!("条目 {} 中缺少字段名", entry.key));
+            }
+            self.skip_whitespace();
+            if self.peek() != Some(b'=') {
+                return self.error(format!("字段 {} 缺少 =", name));
+            }
+            self.pos += 1;
+            self.skip_whitespace();
+            let value = self.parse_value()?;
+
+            if entry.field(&name).is_some() {
+                self.diagnostics.push(BibDiagnostic {
+                    line: self.line_of(field_pos),
+                    message: format!("条目 {} 中字段 {} 重复", entry.key, name),
+                    severity: "warning",
+                });
+            } else {
+                entry.fields.push((name, value));
+            }
+        }
+    }
+
+    /// 值由 `{...}`、`"..."`、数字或宏名用 `#` 拼接而成
+    fn parse_value(&mut self) -> ParseResult<String> {
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                Some(b'{') => {
+                    let start = self.pos + 1;
+                    self.skip_group()?;
+                    value.push_str(&self.src[start..self.pos - 1]);
+                }
+                Some(b'"') => {
+                    self.pos += 1;
+                    let start = self.pos;
+                    let mut depth = 0usize;
+                    loop {
+                        match self.peek() {
+                            None => return self.error("字符串缺少结束的引号"),
+                            Some(b'{') => depth += 1,
+                            Some(b'}') => depth = depth.saturating_sub(1),
+                            Some(b'"') if depth == 0 => break,
+                            _ => {}
+                        }
+                        self.pos += 1;
+                    }
+                    value.push_str(&self.src[start..self.pos]);
+                    self.pos += 1;
+                }
+                Some(b) if b.is_ascii_digit() => {
+                    let start = self.pos;
+                    while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+                        self.pos += 1;
+                    }
+                    value.push_str(&self.src[start..self.pos]);
+                }
+                _ => {
+                    let pos = self.pos;
+                    let name = self.identifier().to_lowercase();
+                    if name.is_empty() {
+                        return self.error("缺少字段值");
+                    }
+                    match self.macros.get(&name) {
+                        Some(expanded) => value.push_str(expanded),
+                        None => {
+                            self.diagnostics.push(BibDiagnostic {
+                                line: self.line_of(pos),
+                                message: format!("未定义的 @string 宏: {}", name),
+                                severity: "warning",
+                            });
+                            value.push_str(&name);
+                        }
+                    }
+                }
+            }
+            self.skip_whitespace();
+            if self.peek() == Some(b'#') {
+                self.pos += 1;
+                self.skip_whitespace();
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// 跳过一个以 `{` 或 `(` 开始的分组（含嵌套的花括号），结束时 pos 位于对应的 `}` 或 `)` 之后
+    fn skip_group(&mut self) -> ParseResult<()> {
+        let open_pos = self.pos;
+        let close = if self.peek() == Some(b'(') { b')' } else { b'}' };
+        self.pos += 1;
+        let mut depth = 0usize;
+        while let Some(b) = self.peek() {
+            self.pos += 1;
+            match b {
+                _ if b == close && depth == 0 => return Ok(()),
+                b'{' => depth += 1,
+                b'}' => match depth.checked_sub(1) {
+                    Some(outer) => depth = outer,
+                    None => {
+                        self.pos -= 1;
+                        return self.error("多余的 }");
+                    }
+                },
+                _ => {}
+            }
+        }
+        self.pos = open_pos;
+        self.error(if close == b')' { "圆括号不匹配" } else { "花括号不匹配" })
+    }
+}
+
+/// 按统一格式输出条目：字段各占一行，值一律用花括号包裹。
+pub fn format_entry(entry: &BibEntry) -> String {
+    let width = entry.fields.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let mut out = format!("@{}{{{},\n", entry.entry_type, entry.key);
+    for (name, value) in &entry.fields {
+        out.push_str(&format!("  {:width$} = {{{}}},\n", name, value, width = width));
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// 展示用：去掉保护大小写的花括号并合并空白
+pub fn clean_value(value: &str) -> String {
+    value
+        .replace(['{', '}'], "")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `author` / `editor` 字段按顶层的 `and` 拆分成人名
+pub fn split_names(value: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut depth = 0usize;
+    let mut current = String::new();
+    let words: Vec<&str> = value.split_whitespace().collect();
+    for word in words {
+        if depth == 0 && word.eq_ignore_ascii_case("and") {
+            names.push(std::mem::take(&mut current));
+            continue;
+        }
+        for c in word.chars() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    names.push(current);
+    names
+        .iter()
+        .map(|name| clean_value(name))
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stray_brace_in_a_parenthesized_comment_is_an_error() {
+        let bib = parse_bib("@comment(x})\n@misc{a, title = {T}}\n@comment(keep {this} one)\n");
+        assert_eq!(bib.diagnostics.len(), 1);
+        assert_eq!((bib.diagnostics[0].line, bib.diagnostics[0].message.as_str()), (1, "多余的 }"));
+        let keys: Vec<&str> = bib.entries.iter().map(|entry| entry.key.as_str()).collect();
+        assert_eq!(keys, ["a"]);
+    }
+
+    #[test]
+    fn macros_months_and_concatenation_are_expanded() {
+        let src = "@string{conf = \"Proc. of \"}\n\
+                   @InProceedings{Key1,\n\
+                   Title = {The {TeX}book},\n\
+                   booktitle = conf # {SODA},\n\
+                   month = jan,\n\
+                   year = 1984,\n\
+                   }\n";
+        let bib = parse_bib(src);
+        assert!(bib.diagnostics.is_empty());
+        let entry = &bib.entries[0];
+        assert_eq!((entry.entry_type.as_str(), entry.key.as_str(), entry.line), ("inproceedings", "Key1", 2));
+        assert_eq!(entry.field("title"), Some("The {TeX}book"));
+        assert_eq!(entry.field("booktitle"), Some("Proc. of SODA"));
+        assert_eq!(entry.field("month"), Some("January"));
+        assert_eq!(entry.field("year"), Some("1984"));
+    }
+
+    #[test]
+    fn broken_entries_are_skipped_and_the_rest_parsed() {
+        let bib = parse_bib("@article{a, title = {T} year = 2000}\n@book{b, title = \"B\"}\n");
+        assert_eq!(bib.diagnostics.len(), 1);
+        let diagnostic = &bib.diagnostics[0];
+        assert_eq!((diagnostic.line, diagnostic.severity), (1, "error"));
+        assert_eq!(diagnostic.message, "条目 a 中字段之间缺少逗号");
+        let keys: Vec<&str> = bib.entries.iter().map(|entry| entry.key.as_str()).collect();
+        assert_eq!(keys, ["b"]);
+    }
+
+    #[test]
+    fn duplicate_fields_and_unknown_macros_are_warnings() {
+        let bib = parse_bib("@misc{m, title = {A}, title = {B}, publisher = acm}\n");
+        let messages: Vec<(&str, &str)> =
+            bib.diagnostics.iter().map(|d| (d.severity, d.message.as_str())).collect();
+        assert_eq!(messages, [("warning", "条目 m 中字段 title 重复"), ("warning", "未定义的 @string 宏: acm")]);
+        assert_eq!(bib.entries[0].field("title"), Some("A"));
+        assert_eq!(bib.entries[0].field("publisher"), Some("acm"));
+    }
+
+    #[test]
+    fn names_split_on_top_level_and_only() {
+        let names = split_names("Knuth, Donald and {Barnes and Noble} and  Lamport");
+        assert_eq!(names, ["Knuth, Donald", "Barnes and Noble", "Lamport"]);
+        assert_eq!(clean_value("  The {\\TeX}book\n  Vol. 1"), "The \\TeXbook Vol. 1");
+    }
+
+    #[test]
+    fn entries_are_formatted_with_aligned_fields() {
+        let bib = parse_bib("@book{k, author = \"A\", title={T}}");
+        assert_eq!(format_entry(&bib.entries[0]), "@book{k,\n  author = {A},\n  title  = {T},\n}\n");
+    }
+}
@@ -0,0 +1,416 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Manager};
+
+use crate::atomic::{unix_millis, write_atomic};
+use crate::document::content_version;
+use crate::latex::root::canonical;
+use crate::project::{config_dir, state_key, state_root};
+use crate::scope::WindowScope;
+
+/// 与项目的其他审阅状态放在一起，批注随项目流转
+const COMMENTS_FILE: &str = "comments.json";
+/// 批注范围两侧各保留的文本字符数，用于在编辑后区分被批注文本的多次出现
+const CONTEXT_CHARS: usize = 32;
+
+/// 编辑器中的范围：从 1 开始的行号和按字符计的列号，结束位置不含，与 Monaco 一致
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct TextRange {
+    start_line: u32,
+    start_column: u32,
+    end_line: u32,
+    end_column: u32,
+}
+
+/// 批注附着的位置，用文本而不是位置描述，文档改动后仍能重新找到
+#[derive(Serialize, Deserialize)]
+struct Anchor {
+    /// 被批注的文本；批注在一个点上时为空
+    quote: String,
+    prefix: String,
+    suffix: String,
+    /// 上次找到引文的字节偏移
+    offset: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredComment {
+    id: String,
+    author: String,
+    text: String,
+    /// Unix 毫秒
+    time: u64,
+    #[serde(default)]
+    resolved: bool,
+    anchor: Anchor,
+}
+
+/// 按相对项目根目录的路径（以 `/` 分隔）记录的批注
+#[derive(Serialize, Deserialize, Default)]
+struct CommentsFile {
+    files: BTreeMap<String, Vec<StoredComment>>,
+}
+
+#[derive(Serialize)]
+pub struct Comment {
+    id: String,
+    author: String,
+    text: String,
+    time: u64,
+    resolved: bool,
+    range: TextRange,
+    quote: String,
+    /// 被批注的文本已不存在；`range` 是它原来所在处的空区间
+    orphaned: bool,
+}
+
+/// 从 1 开始的行号和字符列对应的字节偏移；超出行尾即为行尾
+fn offset(text: &str, line: u32, column: u32) -> Result<usize, String> {
+    let mut line_start = 0;
+    for _ in 1..line {
+        let newline = text[line_start..].find('\n').ok_or_else(|| format!("第 {} 行超出了文件末尾", line))?;
+        line_start += newline + 1;
+    }
+    let line_text = text[line_start..].split('\n').next().unwrap_or("");
+    let column = line_text
+        .char_indices()
+        .nth(column.saturating_sub(1) as usize)
+        .map_or(line_text.len(), |(byte, _)| byte);
+    Ok(line_start + column)
+}
+
+/// 字节 `offset` 所在的行和字符列，从 1 开始
+fn position(text: &str, offset: usize) -> (u32, u32) {
+    let before = &text[..offset];
+    let line = before.matches('\n').count() as u32 + 1;
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    (line, before[line_start..].chars().count() as u32 + 1)
+}
+
+fn text_range(text: &str, range: &Range<usize>) -> TextRange {
+    let (start_line, start_column) = position(text, range.start);
+    let (end_line, end_column) = position(text, range.end);
+    TextRange { start_line, start_column, end_line, end_column }
+}
+
+fn floor_boundary(text: &str, mut offset: usize) -> usize {
+    offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+fn anchor(text: &str, range: Range<usize>) -> Anchor {
+    let prefix_start = text[..range.start].char_indices().rev().nth(CONTEXT_CHARS - 1).map_or(0, |(byte, _)| byte);
+    let suffix_end =
+        text[range.end..].char_indices().nth(CONTEXT_CHARS).map_or(text.len(), |(byte, _)| range.end + byte);
+    Anchor {
+        quote: text[range.clone()].to_string(),
+        prefix: text[prefix_start..range.start].to_string(),
+        suffix: text[range.end..suffix_end].to_string(),
+        offset: range.start,
+    }
+}
+
+/// `text` 中 `range` 周围与锚点上下文吻合的程度
+fn context_score(anchor: &Anchor, text: &str, range: &Range<usize>) -> usize {
+    let before = text[..range.start].chars().rev().zip(anchor.prefix.chars().rev()).take_while(|(a, b)| a == b);
+    let after = text[range.end..].chars().zip(anchor.suffix.chars()).take_while(|(a, b)| a == b);
+    before.count() + after.count()
+}
+
+/// 被锚定的文本现在所在的位置。上次所在处的引文优先，其次是上下文吻合最多的出现，相同时取离原位置最近的。
+/// 引文本身被编辑过时，改取其前缀和后缀之间的范围。已认不出任何内容时为 None
+fn locate(anchor: &Anchor, text: &str) -> Option<Range<usize>> {
+    let end = anchor.offset + anchor.quote.len();
+    if !anchor.quote.is_empty() && text.get(anchor.offset..end) == Some(anchor.quote.as_str()) {
+        return Some(anchor.offset..end);
+    }
+    let distance = |start: usize| start.abs_diff(anchor.offset);
+    if !anchor.quote.is_empty() {
+        let best = text
+            .match_indices(&anchor.quote)
+            .map(|(start, quote)| start..start + quote.len())
+            .max_by_key(|range| (context_score(anchor, text, range), std::cmp::Reverse(distance(range.start))));
+        if best.is_some() {
+            return best;
+        }
+    }
+    // 引文已改动或为空：查找它周围的上下文，上下文必须足够具体，不能随处匹配
+    if anchor.prefix.chars().count() + anchor.suffix.chars().count() < CONTEXT_CHARS / 2 {
+        return None;
+    }
+    let limit = anchor.quote.len() * 2 + CONTEXT_CHARS;
+    text.match_indices(&anchor.prefix)
+        .map(|(start, _)| start + anchor.prefix.len())
+        .filter_map(|start| {
+            let window = &text[start..floor_boundary(text, start + limit + anchor.suffix.len())];
+            window.find(&anchor.suffix).map(|relative| start..start + relative)
+        })
+        .min_by_key(|range| distance(range.start))
+}
+
+fn read_comments(root: &Path) -> Result<CommentsFile, String> {
+    let path = config_dir(root).join(COMMENTS_FILE);
+    match fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content).map_err(|e| format!("无效的 {}: {}", path.display(), e)),
+        Err(_) => Ok(CommentsFile::default()),
+    }
+}
+
+fn write_comments(root: &Path, comments: &CommentsFile) -> Result<(), String> {
+    let dir = config_dir(root);
+    if comments.files.is_empty() {
+        let _ = fs::remove_file(dir.join(COMMENTS_FILE));
+        return Ok(());
+    }
+    fs::create_dir_all(&dir).map_err(|e| format!("无法创建目录: {}", e))?;
+    let content = serde_json::to_string_pretty(comments).map_err(|e| e.to_string())?;
+    write_atomic(&dir.join(COMMENTS_FILE), content.as_bytes()).map_err(|e| format!("无法写入文件: {}", e))?;
+    Ok(())
+}
+
+fn current_text(path: &Path, content: Option<String>) -> Result<String, String> {
+    match content {
+        Some(content) => Ok(content),
+        None => fs::read_to_string(path).map_err(|e| format!("无法读取文件: {}", e)),
+    }
+}
+
+/// 每个项目根目录一把锁。批注文件的读-改-写在锁内完成，两个窗口同时添加批注不会互相覆盖
+#[derive(Default)]
+pub struct CommentLocks {
+    roots: Mutex<HashMap<PathBuf, Arc<Mutex<()>>>>,
+}
+
+impl CommentLocks {
+    fn root(&self, root: &Path) -> Arc<Mutex<()>> {
+        self.roots.lock().unwrap().entry(root.to_path_buf()).or_default().clone()
+    }
+}
+
+/// 一个文件的批注，为一次操作加载
+struct FileComments {
+    root: PathBuf,
+    key: String,
+    comments: CommentsFile,
+    /// 有需要写回的改动
+    changed: bool,
+}
+
+impl FileComments {
+    /// 持有 `path` 所属项目的锁加载它的批注，交给 `update` 处理；有改动时在释放锁之前写回
+    fn with<T>(
+        app: &AppHandle,
+        path: &Path,
+        update: impl FnOnce(&mut FileComments) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let path = canonical(path);
+        let root = canonical(&state_root(&path, COMMENTS_FILE));
+        let lock = app.state::<CommentLocks>().root(&root);
+        let _guard = lock.lock().unwrap();
+        let key = state_key(&root, &path)?;
+        let comments = read_comments(&root)?;
+        let mut file = FileComments { root, key, comments, changed: false };
+        let result = update(&mut file)?;
+        if file.changed {
+            file.save()?;
+        }
+        Ok(result)
+    }
+
+    fn entries(&mut self) -> &mut Vec<StoredComment> {
+        self.comments.files.entry(self.key.clone()).or_default()
+    }
+
+    /// 在 `text` 中重新锚定的所有批注，按文档顺序排列。移动过的锚点会更新，下次从新位置开始查找
+    fn list(&mut self, text: &str) -> Vec<Comment> {
+        let mut moved = false;
+        let mut comments: Vec<Comment> = self
+            .entries()
+            .iter_mut()
+            .map(|stored| {
+                let found = locate(&stored.anchor, text);
+                let range = match &found {
+                    Some(range) => {
+                        moved |= stored.anchor.offset != range.start;
+                        stored.anchor.offset = range.start;
+                        range.clone()
+                    }
+                    None => {
+                        let start = floor_boundary(text, stored.anchor.offset);
+                        start..start
+                    }
+                };
+                Comment {
+                    id: stored.id.clone(),
+                    author: stored.author.clone(),
+                    text: stored.text.clone(),
+                    time: stored.time,
+                    resolved: stored.resolved,
+                    range: text_range(text, &range),
+                    quote: stored.anchor.quote.clone(),
+                    orphaned: found.is_none(),
+                }
+            })
+            .collect();
+        self.changed |= moved;
+        comments.sort_by_key(|comment| (comment.range.start_line, comment.range.start_column));
+        comments
+    }
+
+    fn save(&mut self) -> Result<(), String> {
+        self.comments.files.retain(|_, comments| !comments.is_empty());
+        write_comments(&self.root, &self.comments)
+    }
+}
+
+/// 在 `path` 的 `range` 上添加批注。`content` 是范围所指的编辑器文本；没有时使用磁盘上的文件。返回该文件的批注
+#[command]
+pub async fn add_comment(
+    app: AppHandle,
+    scope: WindowScope,
+    path: String,
+    range: TextRange,
+    text: String,
+    author: String,
+    content: Option<String>,
+) -> Result<Vec<Comment>, String> {
+    scope.check(&path)?;
+    if text.trim().is_empty() {
+        return Err("批注不能为空".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let current = current_text(Path::new(&path), content)?;
+        let start = offset(&current, range.start_line, range.start_column)?;
+        let end = offset(&current, range.end_line, range.end_column)?.max(start);
+        FileComments::with(&app, Path::new(&path), |comments| {
+            let time = unix_millis(SystemTime::now());
+            let id = content_version(format!("{}\0{}\0{}\0{}\0{}", comments.key, time, author, start, text).as_bytes());
+            let anchor = anchor(&current, start..end);
+            comments.entries().push(StoredComment { id, author, text, time, resolved: false, anchor });
+            comments.changed = true;
+            Ok(comments.list(&current))
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// `path` 上的批注（包括已解决的），在 `content`（编辑器文本）或磁盘上的文件中重新锚定。只在有锚点移动时写回
+#[command]
+pub async fn list_comments(
+    app: AppHandle,
+    scope: WindowScope,
+    path: String,
+    content: Option<String>,
+) -> Result<Vec<Comment>, String> {
+    scope.check(&path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let current = current_text(Path::new(&path), content)?;
+        FileComments::with(&app, Path::new(&path), |comments| Ok(comments.list(&current)))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 把 `path` 上的一条批注标记为已解决，`resolved: false` 则重新打开
+#[command]
+pub async fn resolve_comment(
+    app: AppHandle,
+    scope: WindowScope,
+    path: String,
+    id: String,
+    resolved: Option<bool>,
+) -> Result<(), String> {
+    scope.check(&path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        FileComments::with(&app, Path::new(&path), |comments| {
+            let comment = comments
+                .entries()
+                .iter_mut()
+                .find(|comment| comment.id == id)
+                .ok_or_else(|| "该批注已不存在".to_string())?;
+            comment.resolved = resolved.unwrap_or(true);
+            comments.changed = true;
+            Ok(())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "The quick brown fox jumps over the lazy dog.\nThe fox sleeps.\n";
+
+    fn range_of(text: &str, needle: &str) -> Range<usize> {
+        let start = text.find(needle).unwrap();
+        start..start + needle.len()
+    }
+
+    #[test]
+    fn offsets_and_positions_round_trip() {
+        let text = "ab\nçd\n";
+        assert_eq!(offset(text, 2, 2).unwrap(), 5);
+        assert_eq!(position(text, 5), (2, 2));
+        // 超出行尾即为行尾
+        assert_eq!(offset(text, 1, 10).unwrap(), 2);
+        assert!(offset(text, 4, 1).is_err());
+    }
+
+    #[test]
+    fn anchor_keeps_context_around_the_quote() {
+        let anchor = anchor(TEXT, range_of(TEXT, "fox"));
+        assert_eq!(anchor.quote, "fox");
+        assert_eq!(anchor.prefix, "The quick brown ");
+        assert_eq!(anchor.suffix.chars().count(), CONTEXT_CHARS);
+        assert_eq!(anchor.offset, 16);
+    }
+
+    #[test]
+    fn unchanged_text_is_found_in_place() {
+        let anchor = anchor(TEXT, range_of(TEXT, "lazy"));
+        assert_eq!(locate(&anchor, TEXT), Some(range_of(TEXT, "lazy")));
+    }
+
+    #[test]
+    fn moved_text_is_found_by_its_context() {
+        let anchor = anchor(TEXT, range_of(TEXT, "fox"));
+        let edited = format!("Preface.\n{}", TEXT);
+        let found = locate(&anchor, &edited).unwrap();
+        assert_eq!(found.start, 9 + 16);
+        assert_eq!(&edited[found], "fox");
+    }
+
+    #[test]
+    fn repeated_text_prefers_the_matching_context() {
+        let second = TEXT.rfind("fox").unwrap();
+        let anchor = anchor(TEXT, second..second + 3);
+        let edited = TEXT.replacen("The quick", "A", 1);
+        let found = locate(&anchor, &edited).unwrap();
+        assert_eq!(found.start, edited.rfind("fox").unwrap());
+    }
+
+    #[test]
+    fn edited_quote_is_found_between_its_prefix_and_suffix() {
+        let anchor = anchor(TEXT, range_of(TEXT, "jumps over"));
+        let edited = TEXT.replace("jumps over", "leaps across");
+        assert_eq!(locate(&anchor, &edited), Some(range_of(&edited, "leaps across")));
+    }
+
+    #[test]
+    fn vanished_text_is_orphaned() {
+        let anchor = anchor(TEXT, range_of(TEXT, "lazy"));
+        assert_eq!(locate(&anchor, "Something else entirely.\n"), None);
+    }
+}
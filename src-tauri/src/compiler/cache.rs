@@ -0,0 +1,71 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::document::content_version;
+use crate::latex::dependencies::dependency_graph;
+
+use super::engine::{CompileMode, LatexEngine};
+
+/// 记录上次成功编译输入哈希的文件：`<stem>.build-hash`，放在输出目录里
+const HASH_EXTENSION: &str = "build-hash";
+/// 依赖图不跟踪 `\usepackage`/`\documentclass`，根目录下的这些本地文件一律计入
+const LOCAL_SUPPORT_EXTENSIONS: &[&str] = &["sty", "cls", "bst", "bbx", "cbx", "def", "cfg"];
+
+fn hash_path(output_dir: &Path, file_stem: &str) -> PathBuf {
+    output_dir.join(format!("{}.{}", file_stem, HASH_EXTENSION))
+}
+
+/// 引擎、影响产物的编译选项，加上依赖图中每个文件（根文档、`\input`、图片、.bib）
+/// 与根目录下本地宏包的路径和内容的哈希。有文件读不了时返回 None，不使用缓存。
+pub(super) fn build_key(engine: &dyn LatexEngine, source: &Path) -> Option<String> {
+    let options = engine.options();
+    let mut env: Vec<_> = options.env.iter().collect();
+    env.sort();
+    let mode = match options.mode {
+        CompileMode::Full => "full",
+        CompileMode::Draft => "draft",
+    };
+    let mut key = format!(
+        "{}\n{:?}\n{:?}\n{:?}\n{}\n{}\n{:?}\n",
+        engine.name(),
+        options.extra_args,
+        env,
+        options.outfmt,
+        options.shell_escape,
+        mode,
+        options.preamble_cache,
+    )
+    .into_bytes();
+
+    let mut files: Vec<PathBuf> = dependency_graph(source).existing_files().collect();
+    let base_dir = source.parent().unwrap_or(Path::new("."));
+    if let Ok(entries) = fs::read_dir(base_dir) {
+        files.extend(entries.flatten().map(|entry| entry.path()).filter(|path| {
+            let extension = path.extension().map(|ext| ext.to_string_lossy().to_lowercase()).unwrap_or_default();
+            LOCAL_SUPPORT_EXTENSIONS.contains(&extension.as_str()) && path.is_file()
+        }));
+    }
+    files.sort();
+    files.dedup();
+    for file in files {
+        key.extend_from_slice(file.to_string_lossy().as_bytes());
+        key.push(0);
+        key.extend_from_slice(&fs::read(&file).ok()?);
+        key.push(0);
+    }
+    Some(content_version(&key))
+}
+
+/// 上次成功编译的输入与 `key` 相同且产物仍在
+pub(super) fn is_fresh(output_dir: &Path, file_stem: &str, key: &str, output: &Path) -> bool {
+    output.is_file() && fs::read_to_string(hash_path(output_dir, file_stem)).is_ok_and(|recorded| recorded == key)
+}
+
+/// 编译成功后记下输入哈希；`key` 为 None（无法计算）或编译失败时清除旧记录
+pub(super) fn record(output_dir: &Path, file_stem: &str, key: Option<&str>) {
+    let path = hash_path(output_dir, file_stem);
+    match key {
+        Some(key) => fs::write(&path, key).ok(),
+        None => fs::remove_file(&path).ok(),
+    };
+}
@@ -0,0 +1,86 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tauri::command;
+
+use super::{output_dir_for, DEFAULT_OUTPUT_DIR};
+use crate::latex::root::find_root;
+use crate::scope::WindowScope;
+
+/// 非 deep 清理时删除的中间文件；PDF 以及 tectonic/latexmk 以外的文件都保留
+const AUX_EXTENSIONS: &[&str] = &[
+    ".aux", ".log", ".synctex.gz", ".synctex", ".bbl", ".blg", ".bcf", ".run.xml", ".toc", ".lof", ".lot", ".out",
+    ".fls", ".fdb_latexmk", ".xdv", ".nav", ".snm", ".idx", ".ilg", ".ind", ".fmt", "-preamble.hash", ".build-hash",
+    ".bib-state",
+];
+
+#[derive(Serialize)]
+pub struct CleanResult {
+    output_dir: String,
+    removed_files: u64,
+    freed_bytes: u64,
+}
+
+/// `path` 对应的输出目录：.tex 文件按其根文档解析，目录按其中的项目配置或默认的 AuxiliaryFiles
+fn output_dir_of(path: &Path) -> Result<PathBuf, String> {
+    if path.is_dir() {
+        // 以目录下一个假想的文件为准，`output_dir_for` 会从该目录开始查找项目配置
+        return output_dir_for(&path.join(DEFAULT_OUTPUT_DIR), None);
+    }
+    let root = find_root(path, None);
+    output_dir_for(Path::new(&root.root), None)
+}
+
+fn is_aux_file(name: &str) -> bool {
+    let name = name.to_lowercase();
+    AUX_EXTENSIONS.iter().any(|ext| name.ends_with(ext))
+}
+
+/// 递归删除 `dir` 中的文件（`deep` 时全部，否则只删中间文件），累加到 `result`。
+/// 符号链接只删链接本身，不跟进。
+fn clean_dir(dir: &Path, deep: bool, result: &mut CleanResult) -> Result<(), String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("无法读取目录: {}", e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = fs::symlink_metadata(&path) else {
+            continue;
+        };
+        if metadata.is_dir() {
+            clean_dir(&path, deep, result)?;
+            if deep {
+                fs::remove_dir(&path).map_err(|e| format!("无法删除目录: {}", e))?;
+            }
+            continue;
+        }
+        if !deep && !is_aux_file(&entry.file_name().to_string_lossy()) {
+            continue;
+        }
+        fs::remove_file(&path).map_err(|e| format!("无法删除文件: {}", e))?;
+        result.removed_files += 1;
+        result.freed_bytes += metadata.len();
+    }
+    Ok(())
+}
+
+/// 清理编译产物，用来修复残留 .aux 导致的编译失败或回收空间。
+/// `deep` 为 true 时删除整个输出目录（包括 PDF），否则只删 .aux/.log/.synctex.gz/.bbl 等中间文件。
+#[command]
+pub fn clean_aux(scope: WindowScope, root_or_tex_path: String, deep: Option<bool>) -> Result<CleanResult, String> {
+    scope.check(&root_or_tex_path)?;
+    let output_dir = output_dir_of(Path::new(&root_or_tex_path))?;
+    let deep = deep.unwrap_or(false);
+    let mut result = CleanResult {
+        output_dir: output_dir.to_string_lossy().to_string(),
+        removed_files: 0,
+        freed_bytes: 0,
+    };
+    if !output_dir.is_dir() {
+        return Ok(result);
+    }
+    clean_dir(&output_dir, deep, &mut result)?;
+    if deep {
+        fs::remove_dir(&output_dir).map_err(|e| format!("无法删除目录: {}", e))?;
+    }
+    Ok(result)
+}
@@ -0,0 +1,193 @@
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use super::engine::{CompileOptions, EngineKind, LatexEngine};
+use super::log_parser;
+use super::packages;
+use super::progress::{CompilePhase, ProgressReporter};
+use super::remote::{build_command, localize_paths};
+use super::{run_engine, CompileError, CompileJob};
+
+/// 未配置镜像时使用的 TeX Live 官方镜像，包含完整的 TeX Live 和 latexmk
+const DEFAULT_IMAGE: &str = "texlive/texlive:latest";
+/// 根文档所在目录在容器里的挂载点，也是编译时的工作目录
+const CONTAINER_WORKSPACE: &str = "/workspace";
+/// 输出目录不在项目目录下（如只读位置改用的缓存目录）时，单独挂载到这里
+const CONTAINER_OUTPUT_DIR: &str = "/mymd-out";
+
+/// 同一进程里同时运行的容器靠这个序号区分名字
+static CONTAINER_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// 应用设置中的容器编译配置，供 `docker` 引擎使用。
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DockerBuildConfig {
+    /// 容器运行时，默认 `docker`；命令行兼容的 `podman` 也可以
+    pub program: Option<String>,
+    /// 含 TeX 发行版的镜像
+    pub image: String,
+    /// 容器里使用的引擎，默认 pdflatex（经由 latexmk）；镜像里装了 tectonic 时也可以选它
+    pub engine: EngineKind,
+    /// 追加给 `docker run` 的参数，如 `--network=none`
+    pub run_args: Vec<String>,
+}
+
+impl Default for DockerBuildConfig {
+    fn default() -> Self {
+        DockerBuildConfig {
+            program: None,
+            image: DEFAULT_IMAGE.to_string(),
+            engine: EngineKind::Pdflatex,
+            run_args: Vec::new(),
+        }
+    }
+}
+
+impl DockerBuildConfig {
+    fn program(&self) -> &str {
+        self.program.as_deref().filter(|p| !p.is_empty()).unwrap_or("docker")
+    }
+
+    fn image(&self) -> &str {
+        Some(self.image.trim()).filter(|image| !image.is_empty()).unwrap_or(DEFAULT_IMAGE)
+    }
+
+    fn engine(&self) -> EngineKind {
+        match self.engine {
+            EngineKind::Remote | EngineKind::Docker => EngineKind::Pdflatex,
+            engine => engine,
+        }
+    }
+}
+
+/// 在容器里编译：挂载根文档所在目录和输出目录，用镜像里的 TeX Live 完整编译
+/// （多遍和参考文献由容器里的 latexmk 处理），不需要在本机安装 TeX 发行版。
+/// 本地没有镜像时先拉取，进度以 `pulling_image` 阶段的事件报告。
+pub struct DockerEngine {
+    config: DockerBuildConfig,
+    options: CompileOptions,
+}
+
+impl DockerEngine {
+    pub fn new(config: Option<DockerBuildConfig>, options: CompileOptions) -> Self {
+        DockerEngine { config: config.unwrap_or_default(), options }
+    }
+
+    fn image_present(&self) -> Result<bool, Vec<CompileError>> {
+        let program = self.config.program();
+        let status = Command::new(program)
+            .args(["image", "inspect", self.config.image()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map_err(|e| vec![super::spawn_error(program, e)])?;
+        Ok(status.success())
+    }
+
+    fn pull_image(
+        &self,
+        job: &CompileJob,
+        reporter: &Arc<ProgressReporter>,
+        output_dir: &Path,
+        file_stem: &str,
+    ) -> Result<(), Vec<CompileError>> {
+        let image = self.config.image();
+        reporter.note(CompilePhase::PullingImage, &format!("正在拉取 {}", image));
+        let mut cmd = Command::new(self.config.program());
+        cmd.arg("pull").arg(image);
+        let output = run_engine(job, reporter, cmd, output_dir, file_stem)?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(vec![CompileError::simple(format!("无法拉取镜像 {}: {}", image, stderr.trim()))]);
+        }
+        reporter.phase(CompilePhase::Starting);
+        Ok(())
+    }
+
+    fn run_command(&self, name: &str, project_dir: &Path, main: &str, output_dir: &Path) -> Command {
+        let mut cmd = Command::new(self.config.program());
+        cmd.args(["run", "--rm", "--name", name])
+            .arg("-v")
+            .arg(format!("{}:{}", project_dir.to_string_lossy(), CONTAINER_WORKSPACE))
+            .arg("-w")
+            .arg(CONTAINER_WORKSPACE);
+        // 输出目录在项目目录下时已随项目挂载，用相对路径即可
+        let container_output = match output_dir.strip_prefix(project_dir) {
+            Ok(relative) => relative.to_string_lossy().replace('\\', "/"),
+            Err(_) => {
+                cmd.arg("-v").arg(format!("{}:{}", output_dir.to_string_lossy(), CONTAINER_OUTPUT_DIR));
+                CONTAINER_OUTPUT_DIR.to_string()
+            }
+        };
+        // 以项目目录所有者的身份运行，产物不会变成 root 所有；任意 uid 在镜像里没有主目录，
+        // 字体缓存等写到 /tmp
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            if let Ok(metadata) = project_dir.metadata() {
+                cmd.arg("--user").arg(format!("{}:{}", metadata.uid(), metadata.gid())).args(["-e", "HOME=/tmp"]);
+            }
+        }
+        let script = build_command(self.config.engine(), &self.options, main, &container_output);
+        cmd.args(&self.config.run_args).arg(self.config.image()).args(["sh", "-c", &script]);
+        cmd
+    }
+
+    /// 终止 docker 客户端进程不会停止容器，取消或超时后按名字强制删除
+    fn remove_container(&self, name: &str) {
+        let _ = Command::new(self.config.program())
+            .args(["rm", "-f", name])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+}
+
+impl LatexEngine for DockerEngine {
+    fn name(&self) -> &'static str {
+        "docker"
+    }
+
+    fn options(&self) -> &CompileOptions {
+        &self.options
+    }
+
+    fn run_pass(
+        &self,
+        job: &CompileJob,
+        reporter: &Arc<ProgressReporter>,
+        source: &Path,
+        output_dir: &Path,
+        file_stem: &str,
+        _rerun: bool,
+    ) -> Result<Output, Vec<CompileError>> {
+        if !self.image_present()? {
+            self.pull_image(job, reporter, output_dir, file_stem)?;
+        }
+        // 只挂载根文档所在目录；`\input{../x}` 之类引用目录外的文件在容器里找不到
+        let project_dir = source.parent().unwrap_or(Path::new("."));
+        let main = source.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        let name = format!("mymd-build-{}-{}", std::process::id(), CONTAINER_SEQ.fetch_add(1, Ordering::SeqCst));
+        let cmd = self.run_command(&name, project_dir, &main, output_dir);
+        let output =
+            run_engine(job, reporter, cmd, output_dir, file_stem).inspect_err(|_| self.remove_container(&name))?;
+        // 日志和 SyncTeX 里是容器内的路径，换回本地路径，诊断定位和正反向搜索才能用
+        localize_paths(output_dir, file_stem, CONTAINER_WORKSPACE, project_dir);
+        Ok(output)
+    }
+
+    /// 容器里的 latexmk 或 tectonic 自己完成多遍编译和参考文献
+    fn handles_bibliography(&self) -> bool {
+        true
+    }
+
+    fn parse_log(&self, log: &str, source_dir: &Path) -> Vec<CompileError> {
+        let mut diagnostics = log_parser::parse_log(log, source_dir);
+        packages::annotate_missing_packages(&mut diagnostics, self.config.engine() == EngineKind::Tectonic);
+        diagnostics
+    }
+}
@@ -0,0 +1,185 @@
+use std::fmt::Arguments;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Output};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use tectonic::config::PersistentConfig;
+use tectonic::driver::{OutputFormat, ProcessingSessionBuilder};
+use tectonic::errors::SyncError;
+use tectonic::status::{MessageKind, StatusBackend};
+use tectonic::unstable_opts::UnstableOptions;
+use tectonic_bridge_core::{SecuritySettings, SecurityStance};
+use tectonic_errors::Error;
+
+use super::engine::{CompileMode, CompileOptions, LatexEngine};
+use super::progress::ProgressReporter;
+use super::{cleanup_partial_output, CompileError, CompileJob};
+
+/// tectonic 的 C 引擎使用全局状态，不可重入，同一时刻只能跑一个会话
+static ENGINE_LOCK: Mutex<()> = Mutex::new(());
+
+/// 在进程内调用 tectonic，不依赖外部可执行文件。
+/// 资源包和格式文件缓存在应用缓存目录下，而不是 tectonic 的全局缓存。
+/// 没有命令行可传，`CompileOptions` 的 `extra_args` 和 `TEXINPUTS` 以外的环境变量不生效。
+pub struct EmbeddedTectonic {
+    cache_dir: PathBuf,
+    options: CompileOptions,
+}
+
+impl EmbeddedTectonic {
+    pub fn new(cache_dir: PathBuf, options: CompileOptions) -> Self {
+        Self { cache_dir, options }
+    }
+}
+
+/// 把 tectonic 的状态回调转成与命令行版相同格式的输出行，
+/// 同时转发给进度事件，这样日志解析和阶段推断都不用区分两种调用方式。
+struct ReporterStatus {
+    reporter: Arc<ProgressReporter>,
+    output: Vec<u8>,
+}
+
+impl StatusBackend for ReporterStatus {
+    fn report(&mut self, kind: MessageKind, args: Arguments, err: Option<&Error>) {
+        let prefix = match kind {
+            MessageKind::Note => "note",
+            MessageKind::Warning => "warning",
+            MessageKind::Error => "error",
+        };
+        let mut lines = vec![format!("{}: {}", prefix, args)];
+        if let Some(err) = err {
+            lines.extend(err.chain().map(|cause| format!("caused by: {}", cause)));
+        }
+        for line in lines {
+            self.reporter.line(&line);
+            self.output.extend_from_slice(line.as_bytes());
+            self.output.push(b'\n');
+        }
+    }
+
+    fn dump_error_logs(&mut self, output: &[u8]) {
+        self.output.extend_from_slice(output);
+        self.output.push(b'\n');
+    }
+}
+
+/// 进程内编译没有真正的退出码，按命令行版的约定构造：成功 0，失败 1
+fn exit_status(success: bool) -> ExitStatus {
+    let code = if success { 0 } else { 1 };
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        ExitStatus::from_raw(code << 8)
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::ExitStatusExt;
+        ExitStatus::from_raw(code as u32)
+    }
+}
+
+impl EmbeddedTectonic {
+    fn session(
+        &self,
+        status: &mut ReporterStatus,
+        source: &Path,
+        output_dir: &Path,
+        rerun: bool,
+    ) -> tectonic::Result<()> {
+        let config = PersistentConfig::open(false)?;
+        // file: 形式的本地资源包直接打开，网络资源包缓存到应用自己的目录
+        let bundle = if config.default_bundle_loc().starts_with("file:") {
+            config.default_bundle(false, status)?
+        } else {
+            let bundles = self.cache_dir.join("bundles");
+            config.make_cached_url_provider(config.default_bundle_loc(), false, Some(bundles.as_path()), status)?
+        };
+        let formats = self.cache_dir.join("formats");
+        std::fs::create_dir_all(&formats)?;
+
+        let tex_name = source.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let outfmt = self.options.outfmt.as_deref().unwrap_or("pdf");
+        let output_format = OutputFormat::from_str(outfmt).map_err(|e| format!("{}: {}", e, outfmt))?;
+        // 默认的安全设置会无视 shell_escape，必须显式放开
+        let mut builder = if self.options.shell_escape {
+            ProcessingSessionBuilder::new_with_security(SecuritySettings::new(SecurityStance::MaybeAllowInsecures))
+        } else {
+            ProcessingSessionBuilder::default()
+        };
+        builder
+            .bundle(bundle)
+            .primary_input_path(source)
+            .tex_input_name(&tex_name)
+            .output_dir(output_dir)
+            .format_name("latex")
+            .format_cache_path(&formats)
+            .output_format(output_format)
+            .keep_intermediates(true)
+            .keep_logs(true)
+            .synctex(true)
+            .print_stdout(false)
+            .build_date_from_env(false);
+        let mut search_paths = self.options.search_paths();
+        if rerun {
+            // 让 tectonic 能读到 biber/bibtex 写在输出目录里的 .bbl
+            search_paths.push(output_dir.to_path_buf());
+        }
+        builder.unstables(UnstableOptions { extra_search_paths: search_paths, ..UnstableOptions::default() });
+        if self.options.shell_escape {
+            builder.shell_escape_with_temp_dir();
+        }
+        if self.options.mode == CompileMode::Draft {
+            builder.reruns(0);
+        }
+        builder.create(status)?.run(status)
+    }
+}
+
+impl LatexEngine for EmbeddedTectonic {
+    fn name(&self) -> &'static str {
+        "tectonic"
+    }
+
+    fn options(&self) -> &CompileOptions {
+        &self.options
+    }
+
+    /// 引擎在进程内运行、无法中途终止，取消和超时只在两遍之间生效
+    fn run_pass(
+        &self,
+        job: &CompileJob,
+        reporter: &Arc<ProgressReporter>,
+        source: &Path,
+        output_dir: &Path,
+        file_stem: &str,
+        rerun: bool,
+    ) -> Result<Output, Vec<CompileError>> {
+        if job.is_cancelled() {
+            cleanup_partial_output(output_dir, file_stem);
+            return Err(vec![CompileError::simple(job.cancel_message())]);
+        }
+        job.check_deadline()?;
+
+        let mut status = ReporterStatus { reporter: reporter.clone(), output: Vec::new() };
+        let result = {
+            // 上一个会话 panic 时锁会中毒，但引擎状态每次都会重新初始化，可以继续用
+            let _guard = ENGINE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            self.session(&mut status, source, output_dir, rerun)
+        };
+        let success = match result {
+            Ok(()) => true,
+            Err(e) => {
+                status.report_error(&SyncError::new(e).into());
+                false
+            }
+        };
+
+        if job.is_cancelled() {
+            cleanup_partial_output(output_dir, file_stem);
+            return Err(vec![CompileError::simple(job.cancel_message())]);
+        }
+
+        Ok(Output { status: exit_status(success), stdout: status.output, stderr: Vec::new() })
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use super::docker::{DockerBuildConfig, DockerEngine};
+use super::log_parser;
+use super::packages;
+use super::preamble;
+use super::progress::ProgressReporter;
+use super::remote::{RemoteBuildConfig, RemoteEngine};
+use super::{run_engine, CompileError, CompileJob};
+
+/// 前端传入的引擎名称：`tectonic`（默认）或经由 latexmk 调用的传统引擎。
+#[derive(Clone, Copy, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EngineKind {
+    #[default]
+    Tectonic,
+    Pdflatex,
+    Xelatex,
+    Lualatex,
+    /// 在设置中配置的远程服务器上编译，见 `remote::RemoteEngine`
+    Remote,
+    /// 在本机的 TeX Live 容器里编译，见 `docker::DockerEngine`
+    Docker,
+}
+
+/// `draft` 只编译一遍、不跑 biber/bibtex，适合只改了正文时快速预览；
+/// 交叉引用和参考文献可能显示为 ??，需要时再做一次完整编译。
+#[derive(Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CompileMode {
+    #[default]
+    Full,
+    Draft,
+}
+
+/// 前端随编译请求传入的选项，`extra_args` 追加在项目配置的 `compile_flags` 之后。
+#[derive(Clone, Deserialize, Default)]
+#[serde(default)]
+pub struct CompileOptions {
+    /// 原样传给引擎的额外参数，放在源文件之前
+    pub extra_args: Vec<String>,
+    /// 附加的环境变量。tectonic 不读 kpathsea 变量，`TEXINPUTS` 会转成它的搜索路径
+    pub env: HashMap<String, String>,
+    /// 仅 tectonic：`pdf`（默认）、`xdv` 或 `aux`
+    pub outfmt: Option<String>,
+    /// 允许 `\write18`，minted、TikZ externalize 等需要调用外部程序的宏包依赖它
+    pub shell_escape: bool,
+    /// 输出目录名，相对根文档所在目录；默认取项目配置，再默认 AuxiliaryFiles
+    pub output_dir: Option<String>,
+    /// 仅 pdflatex：把导言区预编译成格式文件并在导言区不变时复用。
+    /// 未指定时取应用设置；tectonic 自己缓存格式文件，不受影响
+    pub preamble_cache: Option<bool>,
+    pub mode: CompileMode,
+    /// 未保存文档（没有 `file_path`）的临时工作区 id，来自 `create_untitled_workspace`
+    pub untitled_id: Option<String>,
+    /// 即使根文档和依赖都没变也重新编译，不使用编译缓存
+    pub force: bool,
+}
+
+impl CompileOptions {
+    /// 主产物的扩展名，同时校验 `outfmt`
+    pub fn output_extension(&self) -> Result<&'static str, String> {
+        match self.outfmt.as_deref() {
+            None | Some("pdf") => Ok("pdf"),
+            Some("xdv") => Ok("xdv"),
+            Some("aux") => Ok("aux"),
+            Some(other) => Err(format!("不支持的输出格式: {}", other)),
+        }
+    }
+
+    /// `TEXINPUTS` 中的目录，按平台的路径分隔符拆分，忽略空项（kpathsea 的"默认路径"占位）
+    pub fn search_paths(&self) -> Vec<PathBuf> {
+        self.env
+            .get("TEXINPUTS")
+            .map(|value| env::split_paths(value).filter(|path| !path.as_os_str().is_empty()).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// 一个 LaTeX 编译后端：负责跑一遍编译，并把它的日志翻译成 `CompileError`。
+pub trait LatexEngine: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn options(&self) -> &CompileOptions;
+
+    /// 编译一遍 `source`，所有产物（PDF、synctex、aux、log）写入 `output_dir`。
+    /// `rerun` 表示编排器在跑完 biber/bibtex 后再次编译。
+    fn run_pass(
+        &self,
+        job: &CompileJob,
+        reporter: &Arc<ProgressReporter>,
+        source: &Path,
+        output_dir: &Path,
+        file_stem: &str,
+        rerun: bool,
+    ) -> Result<Output, Vec<CompileError>>;
+
+    /// 引擎自身是否已经负责参考文献和多遍编译（如 latexmk），是则编排器只跑一遍。
+    fn handles_bibliography(&self) -> bool {
+        false
+    }
+
+    /// 解析日志得到诊断信息；相对路径按 `source_dir`（编译时的工作目录）解析。
+    fn parse_log(&self, log: &str, source_dir: &Path) -> Vec<CompileError> {
+        let mut diagnostics = log_parser::parse_log(log, source_dir);
+        packages::annotate_missing_packages(&mut diagnostics, self.name() == "tectonic");
+        diagnostics
+    }
+}
+
+/// `tectonic_path` 来自应用设置，为空时使用 PATH 中的 tectonic；`remote` 是设置中的远程编译服务器，
+/// `docker` 是设置中的容器配置，未配置时用默认的 TeX Live 镜像。
+pub fn engine_for(
+    kind: EngineKind,
+    tectonic_path: Option<&str>,
+    remote: Option<RemoteBuildConfig>,
+    docker: Option<DockerBuildConfig>,
+    options: CompileOptions,
+) -> Box<dyn LatexEngine> {
+    match kind {
+        EngineKind::Tectonic => Box::new(TectonicEngine {
+            program: tectonic_path.filter(|p| !p.is_empty()).unwrap_or("tectonic").to_string(),
+            options,
+        }),
+        EngineKind::Pdflatex => Box::new(LatexmkEngine { flag: "-pdf", name: "pdflatex", options }),
+        EngineKind::Xelatex => Box::new(LatexmkEngine { flag: "-xelatex", name: "xelatex", options }),
+        EngineKind::Lualatex => Box::new(LatexmkEngine { flag: "-lualatex", name: "lualatex", options }),
+        EngineKind::Remote => Box::new(RemoteEngine::new(remote, options)),
+        EngineKind::Docker => Box::new(DockerEngine::new(docker, options)),
+    }
+}
+
+pub struct TectonicEngine {
+    program: String,
+    options: CompileOptions,
+}
+
+impl TectonicEngine {
+    fn command(&self, source: &Path, output_dir: &Path) -> Command {
+        // 运行命令：tectonic -o <AuxDir> --keep-intermediates --keep-logs --synctex <SourceFile>
+        // 注意：源文件不在 AuxDir 里，而在父目录。Tectonic 会自动处理。
+        // 日志解析、SyncTeX 和重跑判断都依赖这几项，不受 `CompileOptions` 影响
+        let mut cmd = Command::new(&self.program);
+        cmd.arg("-o")
+            .arg(output_dir)
+            .arg("--keep-intermediates") // 保留中间文件
+            .arg("--keep-logs")          // 保留 .log 供日志解析
+            .arg("--synctex");           // 生成 synctex
+        if let Some(outfmt) = &self.options.outfmt {
+            cmd.arg("--outfmt").arg(outfmt);
+        }
+        // tectonic 不能在源文件前注入代码，草稿模式只能省掉它自己的重跑
+        if self.options.mode == CompileMode::Draft {
+            cmd.arg("--reruns").arg("0");
+        }
+        if self.options.shell_escape {
+            cmd.arg("-Z").arg("shell-escape");
+        }
+        for path in self.options.search_paths() {
+            cmd.arg("-Z").arg(format!("search-path={}", path.to_string_lossy()));
+        }
+        cmd.args(&self.options.extra_args)
+            .arg(source) // 输入文件
+            .envs(&self.options.env);
+        if let Some(dir) = source.parent() {
+            cmd.current_dir(dir);
+        }
+        cmd
+    }
+
+    fn rerun_command(&self, source: &Path, output_dir: &Path) -> Command {
+        // 让 tectonic 能读到 biber/bibtex 写在输出目录里的 .bbl
+        let mut cmd = self.command(source, output_dir);
+        cmd.arg("-Z").arg(format!("search-path={}", output_dir.to_string_lossy()));
+        cmd
+    }
+}
+
+impl LatexEngine for TectonicEngine {
+    fn name(&self) -> &'static str {
+        "tectonic"
+    }
+
+    fn options(&self) -> &CompileOptions {
+        &self.options
+    }
+
+    fn run_pass(
+        &self,
+        job: &CompileJob,
+        reporter: &Arc<ProgressReporter>,
+        source: &Path,
+        output_dir: &Path,
+        file_stem: &str,
+        rerun: bool,
+    ) -> Result<Output, Vec<CompileError>> {
+        let cmd = if rerun { self.rerun_command(source, output_dir) } else { self.command(source, output_dir) };
+        run_engine(job, reporter, cmd, output_dir, file_stem)
+    }
+}
+
+/// 通过 latexmk 驱动 pdflatex / xelatex / lualatex，latexmk 会自动处理多遍编译。
+pub struct LatexmkEngine {
+    flag: &'static str,
+    name: &'static str,
+    options: CompileOptions,
+}
+
+impl LatexmkEngine {
+    /// `format` 是预编译导言区的格式文件（不带扩展名），由 latexmk 转给 pdflatex
+    fn command(&self, source: &Path, output_dir: &Path, format: Option<&Path>) -> Command {
+        let mut cmd = Command::new("latexmk");
+        cmd.arg(self.flag)
+            .arg("-synctex=1")
+            .arg("-interaction=nonstopmode")
+            .arg("-file-line-error")
+            .arg(format!("-outdir={}", output_dir.to_string_lossy()));
+        if let Some(format) = format {
+            // %P 在有 -usepretex 时带上注入的代码，否则等同 %S
+            cmd.arg(format!("-pdflatex=pdflatex -fmt=\"{}\" %O %P", format.to_string_lossy()));
+        }
+        if self.options.mode == CompileMode::Draft {
+            // 图片只画占位框，只跑一遍，不处理参考文献
+            cmd.arg("-usepretex=\\PassOptionsToPackage{draft}{graphicx}")
+                .arg("-bibtex-")
+                .arg("-e")
+                .arg("$max_repeat=1");
+        }
+        if self.options.shell_escape {
+            cmd.arg("-shell-escape");
+        }
+        cmd.args(&self.options.extra_args).arg(source).envs(&self.options.env);
+        // 传统引擎按当前目录解析 \input，必须在源文件所在目录运行
+        if let Some(dir) = source.parent() {
+            cmd.current_dir(dir);
+        }
+        cmd
+    }
+}
+
+impl LatexEngine for LatexmkEngine {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn options(&self) -> &CompileOptions {
+        &self.options
+    }
+
+    fn run_pass(
+        &self,
+        job: &CompileJob,
+        reporter: &Arc<ProgressReporter>,
+        source: &Path,
+        output_dir: &Path,
+        file_stem: &str,
+        _rerun: bool,
+    ) -> Result<Output, Vec<CompileError>> {
+        // xelatex/lualatex 的字体无法转储进格式文件；草稿模式注入的包选项
+        // 必须在 graphicx 加载前生效，不能用已经加载过它的格式文件
+        let use_format = self.options.preamble_cache == Some(true) && self.options.mode == CompileMode::Full;
+        let format = (self.name == "pdflatex" && use_format)
+            .then(|| {
+                let extra = format!("{:?}{}", self.options.extra_args, self.options.shell_escape);
+                preamble::prepare_format(job, reporter, source, output_dir, file_stem, &extra)
+            })
+            .flatten();
+        run_engine(job, reporter, self.command(source, output_dir, format.as_deref()), output_dir, file_stem)
+    }
+
+    fn handles_bibliography(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,218 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, Manager, Window};
+
+use crate::document::content_version;
+use crate::pdf::render_page;
+
+use super::engine::LatexEngine;
+use super::progress::{CompilePhase, ProgressReporter};
+use super::{
+    compile_timeout, handle_compilation_result, orchestrator, resolve_engine, run_engine, CompileError, CompileJob,
+    CompileJobs, CompileQueue,
+};
+
+/// 片段缓存目录（应用缓存目录下），每个片段一个以内容哈希命名的子目录
+const FRAGMENT_CACHE_DIR: &str = "fragments";
+/// 超过这么多个缓存片段时删除最久未用的
+const MAX_CACHED_FRAGMENTS: usize = 200;
+const FRAGMENT_STEM: &str = "fragment";
+/// 没有传入文档导言区时使用，覆盖最常见的数学符号
+const DEFAULT_PREAMBLE: &str = "\\usepackage{amsmath}\n\\usepackage{amssymb}\n";
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FragmentKind {
+    /// 公式内容，不带 `$`/`\[`，按行间公式排版
+    Math,
+    /// TikZ 代码；没有 `tikzpicture` 环境时自动补上
+    Tikz,
+    /// 原样放进 document 环境
+    Raw,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FragmentFormat {
+    Png,
+    /// 需要 TeX Live 的 dvisvgm
+    Svg,
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct FragmentOptions {
+    pub format: FragmentFormat,
+    /// PNG 每个 PDF point 的像素数
+    pub zoom: f32,
+}
+
+impl Default for FragmentOptions {
+    fn default() -> Self {
+        FragmentOptions { format: FragmentFormat::Png, zoom: 2.0 }
+    }
+}
+
+#[derive(Serialize)]
+pub struct FragmentImage {
+    /// PNG 字节或 SVG 文本的 UTF-8 字节
+    data: Vec<u8>,
+    /// PNG 的像素尺寸；SVG 为 0，由前端按 viewBox 缩放
+    width: u32,
+    height: u32,
+    cached: bool,
+}
+
+/// 文档导言区里的 `\documentclass` 要换成 standalone，其余照搬，
+/// 这样预览能用上文档自己的宏包和 `\newcommand`
+fn fragment_document(snippet: &str, preamble: Option<&str>, kind: FragmentKind) -> String {
+    let preamble: String = match preamble {
+        Some(preamble) => preamble
+            .lines()
+            .filter(|line| !line.trim_start().starts_with("\\documentclass"))
+            .filter(|line| !line.contains("\\begin{document}"))
+            .map(|line| format!("{}\n", line))
+            .collect(),
+        None => DEFAULT_PREAMBLE.to_string(),
+    };
+    let (class_options, body) = match kind {
+        FragmentKind::Math => ("border=2pt", format!("$\\displaystyle {}$", snippet.trim())),
+        FragmentKind::Tikz if snippet.contains("\\begin{tikzpicture}") => ("tikz,border=2pt", snippet.to_string()),
+        FragmentKind::Tikz => ("tikz,border=2pt", format!("\\begin{{tikzpicture}}\n{}\n\\end{{tikzpicture}}", snippet)),
+        FragmentKind::Raw => ("border=2pt,varwidth", snippet.to_string()),
+    };
+    format!(
+        "\\documentclass[{}]{{standalone}}\n{}\\begin{{document}}\n{}\n\\end{{document}}\n",
+        class_options, preamble, body
+    )
+}
+
+/// 按修改时间删掉最旧的缓存片段
+fn prune_cache(cache: &Path) {
+    let Ok(entries) = fs::read_dir(cache) else {
+        return;
+    };
+    let mut dirs: Vec<(SystemTime, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let pdf = entry.path().join(format!("{}.pdf", FRAGMENT_STEM));
+            let metadata = fs::metadata(pdf).or_else(|_| entry.metadata()).ok()?;
+            Some((metadata.modified().ok()?, entry.path()))
+        })
+        .collect();
+    if dirs.len() <= MAX_CACHED_FRAGMENTS {
+        return;
+    }
+    dirs.sort();
+    for (_, dir) in &dirs[..dirs.len() - MAX_CACHED_FRAGMENTS] {
+        fs::remove_dir_all(dir).ok();
+    }
+}
+
+fn svg_from_pdf(job: &CompileJob, reporter: &Arc<ProgressReporter>, dir: &Path) -> Result<(), Vec<CompileError>> {
+    let mut cmd = Command::new("dvisvgm");
+    cmd.arg("--pdf")
+        .arg("--no-fonts")
+        .arg("--exact-bbox")
+        .arg(format!("--output={}.svg", FRAGMENT_STEM))
+        .arg(format!("{}.pdf", FRAGMENT_STEM))
+        .current_dir(dir);
+    let output = run_engine(job, reporter, cmd, dir, FRAGMENT_STEM)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(vec![CompileError::simple(format!("dvisvgm 运行失败:\n{}", stderr.trim()))]);
+    }
+    Ok(())
+}
+
+fn compile_fragment_blocking(
+    window: &Window,
+    job: &Arc<CompileJob>,
+    reporter: &Arc<ProgressReporter>,
+    engine: &dyn LatexEngine,
+    cache: &Path,
+    source: &str,
+    options: &FragmentOptions,
+) -> Result<FragmentImage, Vec<CompileError>> {
+    let key = content_version(format!("{}\n{}", engine.name(), source).as_bytes());
+    let dir = cache.join(key);
+    let pdf_path = dir.join(format!("{}.pdf", FRAGMENT_STEM));
+    let svg_path = dir.join(format!("{}.svg", FRAGMENT_STEM));
+    let app = window.app_handle();
+    let queue = app.state::<CompileQueue>();
+    let _slot = queue.acquire(window.label(), &dir, job)?;
+
+    let mut cached = pdf_path.is_file();
+    if !cached {
+        fs::create_dir_all(&dir).map_err(|e| vec![CompileError::sys(e)])?;
+        let tex_path = dir.join(format!("{}.tex", FRAGMENT_STEM));
+        fs::write(&tex_path, source).map_err(|e| vec![CompileError::sys(e)])?;
+        let output = orchestrator::build(job, reporter, engine, &tex_path, &dir, FRAGMENT_STEM)?;
+        if let Err(errors) = handle_compilation_result(engine, output, &dir, pdf_path.clone()) {
+            // 失败的片段不缓存，改好后重新编译
+            fs::remove_dir_all(&dir).ok();
+            return Err(errors);
+        }
+        prune_cache(cache);
+    } else {
+        // 更新修改时间，让常用的片段留在缓存里
+        fs::File::open(&pdf_path).and_then(|file| file.set_modified(SystemTime::now())).ok();
+    }
+
+    match options.format {
+        FragmentFormat::Png => {
+            let page = render_page(app, &pdf_path.to_string_lossy(), 1, options.zoom)
+                .map_err(|e| vec![CompileError::simple(e)])?;
+            Ok(FragmentImage { data: page.png, width: page.width, height: page.height, cached })
+        }
+        FragmentFormat::Svg => {
+            if !svg_path.is_file() {
+                cached = false;
+                svg_from_pdf(job, reporter, &dir)?;
+            }
+            let data = fs::read(&svg_path).map_err(|e| vec![CompileError::sys(e)])?;
+            Ok(FragmentImage { data, width: 0, height: 0, cached })
+        }
+    }
+}
+
+/// 把公式或 TikZ 片段包进最小的 standalone 文档编译，返回裁剪好的 PNG/SVG，
+/// 供悬停预览。结果按内容哈希缓存，同一片段第二次预览不再编译。
+/// `preamble` 通常是当前文档 `\begin{document}` 之前的部分。
+#[command]
+pub async fn compile_fragment(
+    window: Window,
+    snippet: String,
+    preamble: Option<String>,
+    kind: FragmentKind,
+    job_id: Option<String>,
+    options: Option<FragmentOptions>,
+) -> Result<FragmentImage, Vec<CompileError>> {
+    let app = window.app_handle().clone();
+    let cache = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| vec![CompileError::simple(e.to_string())])?
+        .join(FRAGMENT_CACHE_DIR);
+    let engine = resolve_engine(&app, None, None, Default::default());
+    let source = fragment_document(&snippet, preamble.as_deref(), kind);
+    let options = options.unwrap_or_default();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let jobs = app.state::<CompileJobs>();
+        let (job_id, job) = jobs.register(job_id, compile_timeout(&app));
+        let reporter = Arc::new(ProgressReporter::new(app.clone(), job_id.clone()));
+        reporter.phase(CompilePhase::Starting);
+        let result = compile_fragment_blocking(&window, &job, &reporter, engine.as_ref(), &cache, &source, &options);
+        reporter.phase(CompilePhase::Finished);
+        jobs.finish(&job_id);
+        result
+    })
+    .await
+    .map_err(|e| vec![CompileError::simple(e.to_string())])?
+}
@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+
+use serde::Deserialize;
+use tauri::{command, AppHandle, Manager};
+
+use crate::atomic::write_atomic;
+use crate::git::log::show_file_at;
+use crate::latex::root::{canonical, collect_inputs, find_root};
+use crate::scope::WindowScope;
+
+use super::engine::LatexEngine;
+use super::progress::{CompilePhase, ProgressReporter};
+use super::{
+    build_document, check_scope, compile_timeout, output_dir_for, prepare_output_dir, resolve_engine, run_engine,
+    CompileError, CompileJob, CompileJobs, CompileQueue, CompileResult, EngineKind, TRUNCATED_MARKER,
+};
+
+/// git 旧版本的源文件临时导出到输出目录下的这个子目录，用完即删
+const OLD_REVISION_DIR: &str = "latexdiff-old";
+
+/// 与当前文档比较的旧版本
+pub enum OldVersion {
+    /// 另一个 .tex 文件，直接比较
+    File(PathBuf),
+    /// git revspec，如 `HEAD~1`、提交 id、标签
+    Revision(String),
+    /// 修订追踪的基线，按 `canonical` 路径索引；没有基线的文件取当前内容
+    Baselines(HashMap<PathBuf, String>),
+}
+
+/// 前端指定的旧版本；明确给出类型，不按路径是否存在去猜
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OldSource {
+    /// 另一个 .tex 文件的路径
+    File { path: String },
+    /// git revspec，如 `HEAD~1`、提交 id、标签
+    Revision { revision: String },
+}
+
+impl OldVersion {
+    fn content(&self, path: &Path) -> Result<String, String> {
+        match self {
+            OldVersion::File(_) => fs::read_to_string(path).map_err(|e| format!("无法读取文件: {}", e)),
+            OldVersion::Revision(revision) => show_file_at(path, revision),
+            OldVersion::Baselines(baselines) => match baselines.get(&canonical(path)) {
+                Some(baseline) => Ok(baseline.clone()),
+                None => fs::read_to_string(path).map_err(|e| format!("无法读取文件: {}", e)),
+            },
+        }
+    }
+}
+
+/// 旧版本是已有文件时直接比较；否则导出根文档及其 \input 的全部 .tex 在旧版本的内容，
+/// 保证 `--flatten` 能展开旧版本的章节
+fn old_document(old: &OldVersion, root: &Path, output_dir: &Path) -> Result<PathBuf, CompileError> {
+    if let OldVersion::File(path) = old {
+        return Ok(path.clone());
+    }
+    let root_dir = root.parent().unwrap_or(Path::new("."));
+    let old_dir = output_dir.join(OLD_REVISION_DIR);
+    if old_dir.exists() {
+        fs::remove_dir_all(&old_dir).map_err(CompileError::sys)?;
+    }
+    for (i, path) in std::iter::once(root.to_path_buf()).chain(collect_inputs(root)).enumerate() {
+        let Ok(relative) = path.strip_prefix(root_dir) else {
+            continue;
+        };
+        let content = match old.content(&path) {
+            Ok(content) => content,
+            // 旧版本中还不存在的章节在 diff 里显示为整体新增
+            Err(_) if i > 0 => continue,
+            Err(e) => return Err(CompileError::simple(e)),
+        };
+        let target = old_dir.join(relative);
+        if let Some(dir) = target.parent() {
+            fs::create_dir_all(dir).map_err(CompileError::sys)?;
+        }
+        fs::write(&target, content).map_err(CompileError::sys)?;
+    }
+    Ok(old_dir.join(root.strip_prefix(root_dir).unwrap_or(root)))
+}
+
+fn latexdiff_blocking(
+    queue: &CompileQueue,
+    window: &str,
+    job: &Arc<CompileJob>,
+    reporter: &Arc<ProgressReporter>,
+    engine: &dyn LatexEngine,
+    old: &OldVersion,
+    new_path: &Path,
+) -> Result<CompileResult, Vec<CompileError>> {
+    let root = find_root(new_path, None);
+    let root = Path::new(&root.root);
+    let root_dir = root.parent().unwrap_or(Path::new("."));
+    let file_stem = root.file_stem()
+        .ok_or_else(|| vec![CompileError::simple("无法获取文件名")])?
+        .to_string_lossy()
+        .to_string();
+    let diff_name = format!("{}-diff.tex", file_stem);
+    // diff 文档要放在根文档旁边编译，图片、.bib 和本地宏包才能按原来的相对路径找到
+    let diff_path = root_dir.join(&diff_name);
+    if diff_path.exists() {
+        return Err(vec![CompileError::simple(format!("已存在同名文件: {}", diff_path.to_string_lossy()))]);
+    }
+
+    let output_dir = output_dir_for(root, engine.options().output_dir.as_deref()).map_err(|e| vec![CompileError::simple(e)])?;
+    let output_dir = prepare_output_dir(&output_dir).map_err(|e| vec![CompileError::sys(e)])?;
+    // 与普通编译共用输出目录，需要排同一个队
+    let _slot = queue.acquire(window, root, job)?;
+
+    let old_path = old_document(old, root, &output_dir).map_err(|e| vec![e])?;
+    reporter.note(CompilePhase::Starting, "正在运行 latexdiff");
+    let mut cmd = Command::new("latexdiff");
+    cmd.arg("--flatten").arg(&old_path).arg(root).current_dir(root_dir);
+    let output = run_engine(job, reporter, cmd, &output_dir, &format!("{}-diff", file_stem));
+    fs::remove_dir_all(output_dir.join(OLD_REVISION_DIR)).ok();
+    let output = output?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(vec![CompileError::simple(format!("latexdiff 运行失败:\n{}", stderr.trim()))]);
+    }
+    let diff = String::from_utf8_lossy(&output.stdout);
+    if diff.contains(TRUNCATED_MARKER) {
+        return Err(vec![CompileError::simple("latexdiff 输出过大，无法编译")]);
+    }
+
+    write_atomic(&diff_path, diff.as_bytes()).map_err(|e| vec![CompileError::sys(e)])?;
+    let result = build_document(job, reporter, engine, &diff_path);
+    // 编译完把 diff 源文件挪进输出目录，留作查看，不弄乱项目目录
+    if fs::rename(&diff_path, output_dir.join(&diff_name)).is_err() {
+        fs::remove_file(&diff_path).ok();
+    }
+    result
+}
+
+/// 用 latexdiff 比较 `new_path` 所属根文档与 `old`，并编译出带修订标记的 PDF。
+pub async fn compile_diff(
+    app: AppHandle,
+    scope: WindowScope,
+    old: OldVersion,
+    new_path: String,
+    job_id: Option<String>,
+    engine: Option<EngineKind>,
+    return_path: Option<bool>,
+) -> Result<CompileResult, Vec<CompileError>> {
+    check_scope(&scope, Some(&new_path))?;
+    let engine = resolve_engine(&app, engine, Some(Path::new(&new_path)), Default::default());
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let jobs = app.state::<CompileJobs>();
+        let (job_id, job) = jobs.register(job_id, compile_timeout(&app));
+        let reporter = Arc::new(ProgressReporter::new(app.clone(), job_id.clone()));
+        reporter.phase(CompilePhase::Starting);
+        let queue = app.state::<CompileQueue>();
+        let new_path = Path::new(&new_path);
+        let result = latexdiff_blocking(&queue, scope.window(), &job, &reporter, engine.as_ref(), &old, new_path)
+            .and_then(|result| result.deliver(&app, &scope, return_path.unwrap_or(false)));
+        reporter.phase(CompilePhase::Finished);
+        jobs.finish(&job_id);
+        result
+    })
+    .await
+    .map_err(|e| vec![CompileError::simple(e.to_string())])?
+}
+
+/// 用 latexdiff 比较 `new_path` 所属根文档与旧版本，并编译出带修订标记的 PDF。
+/// `old_source` 为 `{ kind: "file", path }` 或 `{ kind: "revision", revision }`。
+#[command]
+pub async fn latexdiff_compile(
+    app: AppHandle,
+    scope: WindowScope,
+    old_source: OldSource,
+    new_path: String,
+    job_id: Option<String>,
+    engine: Option<EngineKind>,
+    return_path: Option<bool>,
+) -> Result<CompileResult, Vec<CompileError>> {
+    let old = match old_source {
+        OldSource::File { path } => {
+            // 先检查范围，范围外的路径连是否存在都不查
+            check_scope(&scope, Some(&path))?;
+            if !Path::new(&path).is_file() {
+                return Err(vec![CompileError::simple(format!("找不到文件: {}", path))]);
+            }
+            OldVersion::File(PathBuf::from(path))
+        }
+        OldSource::Revision { revision } => OldVersion::Revision(revision),
+    };
+    compile_diff(app, scope, old, new_path, job_id, engine, return_path).await
+}
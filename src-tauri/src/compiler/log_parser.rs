@@ -0,0 +1,330 @@
+use std::path::{Component, Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use super::CompileError;
+
+/// TeX 默认在 79 个字符处折行（max_print_line），折行处没有任何标记。
+const TEX_LINE_WIDTH: usize = 79;
+
+/// `! ...` 之后最多往下找多少行来寻找 `l.<行号>`
+const ERROR_CONTEXT_LINES: usize = 12;
+
+static FILE_LINE_ERROR_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^((?:[A-Za-z]:)?[^:\s()]+\.[A-Za-z0-9]+):(\d+):\s*(.*)$").unwrap());
+static TECTONIC_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(error|warning):\s*(?:((?:[A-Za-z]:)?[^:\s]+\.[A-Za-z0-9]+):(\d+):\s*)?(.*)$").unwrap()
+});
+static WARNING_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:LaTeX|(?:Package|Class)\s+(\S+))\s+Warning:\s*(.*)$").unwrap()
+});
+static BADBOX_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(?:Overfull|Underfull) \\[hv]box").unwrap());
+static INPUT_LINE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"on input line (\d+)").unwrap());
+static BADBOX_LINE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"at lines? (\d+)").unwrap());
+static ERROR_LINE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^l\.(\d+)").unwrap());
+static FILE_NAME_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:\.{1,2}/|/|[A-Za-z]:[\\/])?[^\s()]*\.[A-Za-z0-9]+$").unwrap()
+});
+
+struct PendingError {
+    message: String,
+    file: Option<String>,
+    waited: usize,
+}
+
+/// 解析 TeX 日志（或 tectonic 的终端输出），跟踪 `(file ... )` 嵌套以确定每条诊断
+/// 所属的文件，并区分 error / warning / badbox。
+pub fn parse_log(log: &str, base_dir: &Path) -> Vec<CompileError> {
+    let lines = unwrap_lines(log);
+    let mut parser = LogParser { base_dir, stack: Vec::new(), diagnostics: Vec::new() };
+    let mut pending: Option<PendingError> = None;
+    let mut index = 0;
+
+    while index < lines.len() {
+        let line = lines[index].as_str();
+        let trimmed = line.trim();
+        index += 1;
+
+        if let Some(error) = pending.as_mut() {
+            if let Some(caps) = ERROR_LINE_RE.captures(trimmed) {
+                let error = pending.take().unwrap();
+                parser.push(error.message, error.file, caps[1].parse().unwrap_or(0), "error");
+                continue;
+            }
+            error.waited += 1;
+            if error.waited > ERROR_CONTEXT_LINES || trimmed.starts_with('!') {
+                let error = pending.take().unwrap();
+                parser.push(error.message, error.file, 0, "error");
+            } else {
+                // 错误上下文里也可能有文件结束的 `)`，不记下的话之后的诊断会归到错误的文件
+                parser.track_files(line);
+                continue;
+            }
+        }
+
+        if let Some(message) = trimmed.strip_prefix('!') {
+            pending = Some(PendingError {
+                message: message.trim().to_string(),
+                file: parser.current_file(),
+                waited: 0,
+            });
+            continue;
+        }
+
+        if let Some(caps) = TECTONIC_RE.captures(trimmed) {
+            let severity = if &caps[1] == "error" { "error" } else { "warning" };
+            let file = caps.get(2).map(|m| parser.resolve(m.as_str())).or_else(|| parser.current_file());
+            let line_number = caps.get(3).and_then(|m| m.as_str().parse().ok()).unwrap_or(0);
+            let message = caps[4].trim().to_string();
+            let severity = if BADBOX_RE.is_match(&message) { "badbox" } else { severity };
+            parser.push(message, file, line_number, severity);
+            continue;
+        }
+
+        if let Some(caps) = FILE_LINE_ERROR_RE.captures(trimmed) {
+            let file = Some(parser.resolve(&caps[1]));
+            parser.push(caps[3].trim().to_string(), file, caps[2].parse().unwrap_or(0), "error");
+            continue;
+        }
+
+        if let Some(caps) = WARNING_RE.captures(trimmed) {
+            let mut message = caps[2].trim().to_string();
+            // Package 警告的续行以 "(包名)" 开头
+            if let Some(package) = caps.get(1) {
+                let prefix = format!("({})", package.as_str());
+                while index < lines.len() && lines[index].trim_start().starts_with(&prefix) {
+                    message.push(' ');
+                    message.push_str(lines[index].trim_start()[prefix.len()..].trim());
+                    index += 1;
+                }
+            }
+            let line_number = INPUT_LINE_RE
+                .captures(&message)
+                .and_then(|c| c[1].parse().ok())
+                .unwrap_or(0);
+            let file = parser.current_file();
+            parser.push(message, file, line_number, "warning");
+            continue;
+        }
+
+        if BADBOX_RE.is_match(trimmed) {
+            let line_number = BADBOX_LINE_RE
+                .captures(trimmed)
+                .and_then(|c| c[1].parse().ok())
+                .unwrap_or(0);
+            let file = parser.current_file();
+            parser.push(trimmed.to_string(), file, line_number, "badbox");
+            continue;
+        }
+
+        parser.track_files(line);
+    }
+
+    if let Some(error) = pending {
+        parser.push(error.message, error.file, 0, "error");
+    }
+
+    // "Emergency stop" 只是前面真正错误的后果
+    let has_real_error = parser
+        .diagnostics
+        .iter()
+        .any(|d| d.severity == "error" && !is_fatal_summary(&d.message));
+    if has_real_error {
+        parser.diagnostics.retain(|d| !is_fatal_summary(&d.message));
+    }
+    parser.diagnostics
+}
+
+fn is_fatal_summary(message: &str) -> bool {
+    message.starts_with("Emergency stop") || message.starts_with("==> Fatal error occurred")
+}
+
+/// 还原被 TeX 按 79 列硬折行的日志行
+fn unwrap_lines(log: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for line in log.lines() {
+        current.push_str(line);
+        if line.chars().count() != TEX_LINE_WIDTH {
+            lines.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+struct LogParser<'a> {
+    base_dir: &'a Path,
+    /// `(` 打开的条目；不是文件名的括号记为 None，保证 `)` 能正确配对
+    stack: Vec<Option<String>>,
+    diagnostics: Vec<CompileError>,
+}
+
+impl LogParser<'_> {
+    fn current_file(&self) -> Option<String> {
+        self.stack.iter().rev().find_map(|entry| entry.clone())
+    }
+
+    fn resolve(&self, name: &str) -> String {
+        let path = Path::new(name);
+        let joined = if path.is_absolute() { path.to_path_buf() } else { self.base_dir.join(path) };
+        let mut normalized = PathBuf::new();
+        for component in joined.components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    normalized.pop();
+                }
+                other => normalized.push(other.as_os_str()),
+            }
+        }
+        normalized.to_string_lossy().to_string()
+    }
+
+    fn track_files(&mut self, line: &str) {
+        let chars: Vec<char> = line.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                '(' => {
+                    let start = i + 1;
+                    let mut end = start;
+                    while end < chars.len() && !chars[end].is_whitespace() && chars[end] != '(' && chars[end] != ')' {
+                        end += 1;
+                    }
+                    let name: String = chars[start..end].iter().collect();
+                    if FILE_NAME_RE.is_match(&name) {
+                        self.stack.push(Some(self.resolve(&name)));
+                    } else {
+                        self.stack.push(None);
+                    }
+                    i = end;
+                }
+                ')' => {
+                    self.stack.pop();
+                    i += 1;
+                }
+                _ => i += 1,
+            }
+        }
+    }
+
+    fn push(&mut self, message: String, file: Option<String>, line: u32, severity: &str) {
+        let duplicate = self.diagnostics.iter().any(|d| {
+            d.line == line && d.message == message && d.file == file && d.severity == severity
+        });
+        if !duplicate {
+            let severity = severity.to_string();
+            self.diagnostics.push(CompileError { line, message, severity, file, missing_package: None });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(log: &str) -> Vec<CompileError> {
+        parse_log(log, Path::new("/project"))
+    }
+
+    #[test]
+    fn error_is_attributed_to_the_included_file() {
+        let log = "This is pdfTeX, Version 3.141592653\n\
+                   (./main.tex\n\
+                   (./chapters/intro.tex\n\
+                   ! Undefined control sequence.\n\
+                   l.12 \\foo\n\
+                   )\n\
+                   ! Emergency stop.\n\
+                   <*> main.tex\n\
+                   )\n";
+        let diagnostics = parse(log);
+        assert_eq!(diagnostics.len(), 1);
+        let error = &diagnostics[0];
+        assert_eq!(error.severity, "error");
+        assert_eq!(error.message, "Undefined control sequence.");
+        assert_eq!(error.line, 12);
+        assert_eq!(error.file.as_deref(), Some("/project/chapters/intro.tex"));
+    }
+
+    #[test]
+    fn files_closed_inside_the_error_context_are_tracked() {
+        let log = "(./main.tex\n\
+                   (./chapters/intro.tex\n\
+                   ! Package foo Error: Something broke.\n\
+                   See the foo package documentation for explanation.\n\
+                   )\n\
+                   ! Undefined control sequence.\n\
+                   l.9 \\bar\n\
+                   )\n";
+        let diagnostics = parse(log);
+        let summary: Vec<(&str, u32, Option<&str>)> =
+            diagnostics.iter().map(|d| (d.message.as_str(), d.line, d.file.as_deref())).collect();
+        assert_eq!(
+            summary,
+            [
+                ("Package foo Error: Something broke.", 0, Some("/project/chapters/intro.tex")),
+                ("Undefined control sequence.", 9, Some("/project/main.tex")),
+            ]
+        );
+    }
+
+    #[test]
+    fn emergency_stop_is_kept_when_it_is_the_only_error() {
+        let diagnostics = parse("(./main.tex\n! Emergency stop.\n<*> main.tex\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Emergency stop.");
+        assert_eq!(diagnostics[0].line, 0);
+    }
+
+    #[test]
+    fn warnings_and_badboxes() {
+        let log = "(./main.tex\n\
+                   LaTeX Warning: Reference `fig:a' on page 1 undefined on input line 7.\n\
+                   Package natbib Warning: Citation `knuth' on page 1 undefined on input line 9.\n\
+                   (natbib)                Rerun to get citations correct.\n\
+                   Overfull \\hbox (12.0pt too wide) in paragraph at lines 20--22\n\
+                   )\n";
+        let diagnostics = parse(log);
+        let summary: Vec<(&str, u32)> = diagnostics.iter().map(|d| (d.severity.as_str(), d.line)).collect();
+        assert_eq!(summary, [("warning", 7), ("warning", 9), ("badbox", 20)]);
+        assert_eq!(
+            diagnostics[1].message,
+            "Citation `knuth' on page 1 undefined on input line 9. Rerun to get citations correct."
+        );
+        assert!(diagnostics.iter().all(|d| d.file.as_deref() == Some("/project/main.tex")));
+    }
+
+    #[test]
+    fn tectonic_and_file_line_errors() {
+        let log = "error: main.tex:5: Undefined control sequence\n\
+                   warning: Overfull \\hbox (3.0pt too wide) in paragraph at lines 8--9\n\
+                   ./sections/a.tex:3: Missing $ inserted.\n";
+        let diagnostics = parse(log);
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(diagnostics[0].severity, "error");
+        assert_eq!(diagnostics[0].file.as_deref(), Some("/project/main.tex"));
+        assert_eq!(diagnostics[0].line, 5);
+        assert_eq!(diagnostics[1].severity, "badbox");
+        assert_eq!(diagnostics[2].file.as_deref(), Some("/project/sections/a.tex"));
+        assert_eq!((diagnostics[2].line, diagnostics[2].message.as_str()), (3, "Missing $ inserted."));
+    }
+
+    #[test]
+    fn duplicate_diagnostics_are_reported_once() {
+        let log = "(./main.tex\nLaTeX Warning: There were undefined references.\n\
+                   LaTeX Warning: There were undefined references.\n)\n";
+        assert_eq!(parse(log).len(), 1);
+    }
+
+    #[test]
+    fn lines_wrapped_at_79_columns_are_joined() {
+        let long = "a".repeat(TEX_LINE_WIDTH);
+        let log = format!("{}\nbc\nd", long);
+        assert_eq!(unwrap_lines(&log), [format!("{}bc", long), "d".to_string()]);
+    }
+}
@@ -0,0 +1,173 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+
+use serde::Deserialize;
+use tauri::{command, AppHandle, Manager};
+
+use crate::atomic::write_atomic;
+use crate::scope::WindowScope;
+use crate::settings::SettingsStore;
+
+use super::progress::{CompilePhase, ProgressReporter};
+use super::{
+    check_scope, compile_timeout, log_parser, output_dir_for, prepare_output_dir, run_engine, untitled, CompileError,
+    CompileJob, CompileJobs, CompileQueue, CompileResult,
+};
+
+/// Markdown 导出选项；YAML front matter 由 pandoc 自行读取，这里只放命令行层面的设置。
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct MarkdownOptions {
+    /// pandoc 模板路径（`--template`）
+    pub template: Option<String>,
+    /// 启用 `--citeproc` 处理 `[@key]` 引用
+    pub citeproc: bool,
+    pub bibliography: Option<String>,
+    pub csl: Option<String>,
+    /// 默认使用 tectonic（应用设置中的路径优先），与 LaTeX 编译保持一致
+    pub pdf_engine: Option<String>,
+    pub extra_args: Vec<String>,
+    /// 未保存文档的临时工作区 id，见 `create_untitled_workspace`
+    pub untitled_id: Option<String>,
+}
+
+fn pandoc_command(source: &Path, pdf_path: &Path, options: &MarkdownOptions) -> Command {
+    let mut cmd = Command::new("pandoc");
+    cmd.arg(source)
+        .arg("-o")
+        .arg(pdf_path)
+        .arg("--standalone")
+        .arg(format!("--pdf-engine={}", options.pdf_engine.as_deref().unwrap_or("tectonic")));
+    if let Some(template) = &options.template {
+        cmd.arg(format!("--template={}", template));
+    }
+    if options.citeproc || options.bibliography.is_some() {
+        cmd.arg("--citeproc");
+    }
+    if let Some(bibliography) = &options.bibliography {
+        cmd.arg(format!("--bibliography={}", bibliography));
+    }
+    if let Some(csl) = &options.csl {
+        cmd.arg(format!("--csl={}", csl));
+    }
+    cmd.args(&options.extra_args);
+    // 图片和参考文献等相对路径以 Markdown 文件所在目录为准
+    if let Some(dir) = source.parent() {
+        cmd.current_dir(dir);
+    }
+    cmd
+}
+
+/// pandoc 的 `[WARNING]` 行是 Markdown 层面的警告；TeX 错误的行号指向中间生成的
+/// .tex，对编辑器没有意义，因此清零。
+fn parse_pandoc_log(log: &str, source: &Path) -> Vec<CompileError> {
+    let source_file = Some(source.to_string_lossy().to_string());
+    let mut diagnostics: Vec<CompileError> = log
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("[WARNING]"))
+        .map(|message| CompileError {
+            line: 0,
+            message: message.trim().to_string(),
+            severity: "warning".to_string(),
+            file: source_file.clone(),
+            missing_package: None,
+        })
+        .collect();
+    let base_dir = source.parent().unwrap_or(Path::new("."));
+    for mut error in log_parser::parse_log(log, base_dir).into_iter().filter(|d| d.severity == "error") {
+        error.line = 0;
+        error.file = source_file.clone();
+        diagnostics.push(error);
+    }
+    diagnostics
+}
+
+fn compile_markdown_blocking(
+    queue: &CompileQueue,
+    window: &str,
+    job: &Arc<CompileJob>,
+    reporter: &Arc<ProgressReporter>,
+    md_code: String,
+    file_path: Option<String>,
+    options: &MarkdownOptions,
+) -> Result<CompileResult, Vec<CompileError>> {
+    let (source_path, output_dir): (PathBuf, PathBuf) = match file_path {
+        // 未保存的文档：与 LaTeX 一样放到该文档的临时工作区
+        None => {
+            let temp_dir = untitled::workspace_dir(options.untitled_id.as_deref())
+                .map_err(|e| vec![CompileError::simple(e)])?;
+            (temp_dir.join("input.md"), temp_dir)
+        }
+        Some(path) => {
+            let source_path = PathBuf::from(path);
+            let aux_dir = output_dir_for(&source_path, None).map_err(|e| vec![CompileError::simple(e)])?;
+            (source_path, aux_dir)
+        }
+    };
+    let _slot = queue.acquire(window, &source_path, job)?;
+    let output_dir = prepare_output_dir(&output_dir).map_err(|e| vec![CompileError::sys(e)])?;
+    if fs::read(&source_path).ok().as_deref() != Some(md_code.as_bytes()) {
+        write_atomic(&source_path, md_code.as_bytes()).map_err(|e| vec![CompileError::sys(e)])?;
+    }
+
+    let file_stem = source_path.file_stem()
+        .ok_or_else(|| vec![CompileError::simple("无法获取文件名")])?
+        .to_string_lossy()
+        .to_string();
+    let pdf_path = output_dir.join(format!("{}.pdf", file_stem));
+
+    reporter.note(CompilePhase::TexPass, "正在运行 pandoc");
+    let cmd = pandoc_command(&source_path, &pdf_path, options);
+    let output = run_engine(job, reporter, cmd, &output_dir, &file_stem)?;
+
+    let log = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let diagnostics = parse_pandoc_log(&log, &source_path);
+
+    if !output.status.success() {
+        let mut errors: Vec<CompileError> = diagnostics.into_iter().filter(|d| d.severity == "error").collect();
+        if errors.is_empty() {
+            errors.push(CompileError::simple(log.trim()));
+        }
+        return Err(errors);
+    }
+
+    let pdf = fs::read(&pdf_path).map_err(|e| vec![CompileError::sys(e)])?;
+    Ok(CompileResult::new(Some(pdf), &pdf_path, diagnostics, log))
+}
+
+#[command]
+pub async fn compile_markdown(
+    app: AppHandle,
+    scope: WindowScope,
+    md_code: String,
+    file_path: Option<String>,
+    options: Option<MarkdownOptions>,
+    job_id: Option<String>,
+) -> Result<CompileResult, Vec<CompileError>> {
+    check_scope(&scope, file_path.as_deref())?;
+    let mut options = options.unwrap_or_default();
+    if options.pdf_engine.is_none() {
+        options.pdf_engine = app.state::<SettingsStore>().get(&app).tectonic_path.filter(|p| !p.is_empty());
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let jobs = app.state::<CompileJobs>();
+        let (job_id, job) = jobs.register(job_id, compile_timeout(&app));
+        let reporter = Arc::new(ProgressReporter::new(app.clone(), job_id.clone()));
+        reporter.phase(CompilePhase::Starting);
+        let queue = app.state::<CompileQueue>();
+        let result = compile_markdown_blocking(&queue, scope.window(), &job, &reporter, md_code, file_path, &options)
+            .and_then(|result| result.deliver(&app, &scope, false));
+        reporter.phase(CompilePhase::Finished);
+        jobs.finish(&job_id);
+        result
+    })
+    .await
+    .map_err(|e| vec![CompileError::simple(e.to_string())])?
+}
@@ -0,0 +1,680 @@
+mod cache;
+pub mod clean;
+mod docker;
+#[cfg(feature = "embedded-tectonic")]
+mod embedded;
+mod engine;
+pub mod fragment;
+pub mod latexdiff;
+mod log_parser;
+pub mod markdown;
+mod orchestrator;
+mod packages;
+pub mod preamble;
+mod progress;
+mod queue;
+mod remote;
+pub mod untitled;
+pub mod watch;
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, ErrorKind, Read};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::ipc::Response;
+use tauri::{command, AppHandle, Manager, State, Window};
+
+use crate::atomic::write_atomic;
+use crate::latex::root::find_root;
+use crate::project::{find_project_config, ProjectConfig};
+use crate::scope::WindowScope;
+use crate::settings::SettingsStore;
+use crate::toolchain::installed_tectonic;
+
+use engine::{engine_for, LatexEngine};
+use progress::{CompilePhase, ProgressReporter};
+
+pub use docker::DockerBuildConfig;
+pub use engine::{CompileOptions, EngineKind};
+pub use queue::CompileQueue;
+pub use remote::RemoteBuildConfig;
+pub use watch::WatchBuilds;
+
+/// 编译输出（进程输出和 .log）保留的上限，防止失控的文档把日志撑到几百 MB
+const MAX_LOG_SIZE: usize = 4 * 1024 * 1024;
+
+const TRUNCATED_MARKER: &str = "\n[... log truncated ...]\n";
+
+/// 没有配置时的输出目录名
+pub const DEFAULT_OUTPUT_DIR: &str = "AuxiliaryFiles";
+
+/// 源文件所在位置只读（网络共享、DMG 等）时，输出目录改放到应用缓存下的这个目录里，
+/// 按原输出目录路径的哈希区分项目。启动时由 `init_output_fallback` 设置，未设置时用系统临时目录。
+static OUTPUT_FALLBACK_ROOT: OnceLock<PathBuf> = OnceLock::new();
+const OUTPUT_FALLBACK_DIR: &str = "output";
+
+/// 正在运行的编译任务，按 job id 索引，供 `cancel_compile` 查找并终止。
+#[derive(Default)]
+pub struct CompileJobs {
+    jobs: Mutex<HashMap<String, Arc<CompileJob>>>,
+    next_id: AtomicU64,
+}
+
+struct CompileJob {
+    child: Mutex<Option<Child>>,
+    cancelled: AtomicBool,
+    /// 因同一文档有了更新的编译请求而被终止
+    superseded: AtomicBool,
+    /// 超过该时间仍在运行的子进程会被终止，None 表示不限时
+    deadline: Option<Instant>,
+    timeout: Option<Duration>,
+}
+
+impl CompileJob {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    fn cancel_message(&self) -> &'static str {
+        if self.superseded.load(Ordering::SeqCst) {
+            queue::SUPERSEDED_MESSAGE
+        } else {
+            "Compilation cancelled"
+        }
+    }
+
+    /// 标记取消并终止正在运行的子进程
+    fn cancel(&self) -> std::io::Result<()> {
+        self.cancelled.store(true, Ordering::SeqCst);
+        match self.child.lock().unwrap().as_mut() {
+            Some(child) => child.kill(),
+            None => Ok(()),
+        }
+    }
+
+    /// 超时返回 `Timeout` 诊断
+    fn check_deadline(&self) -> Result<(), Vec<CompileError>> {
+        match (self.deadline, self.timeout) {
+            (Some(deadline), Some(timeout)) if Instant::now() >= deadline => Err(vec![CompileError::timeout(timeout)]),
+            _ => Ok(()),
+        }
+    }
+
+    fn supersede(&self) {
+        self.superseded.store(true, Ordering::SeqCst);
+        let _ = self.cancel();
+    }
+}
+
+impl CompileJobs {
+    /// `timeout` 从登记时开始计时，包括在队列中等待的时间
+    fn register(&self, job_id: Option<String>, timeout: Option<Duration>) -> (String, Arc<CompileJob>) {
+        let id = job_id.unwrap_or_else(|| {
+            format!("job-{}", self.next_id.fetch_add(1, Ordering::SeqCst) + 1)
+        });
+        let job = Arc::new(CompileJob {
+            child: Mutex::new(None),
+            cancelled: AtomicBool::new(false),
+            superseded: AtomicBool::new(false),
+            deadline: timeout.map(|timeout| Instant::now() + timeout),
+            timeout,
+        });
+        self.jobs.lock().unwrap().insert(id.clone(), job.clone());
+        (id, job)
+    }
+
+    fn finish(&self, job_id: &str) {
+        self.jobs.lock().unwrap().remove(job_id);
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct CompileError {
+    line: u32,
+    message: String,
+    /// "error" | "warning" | "badbox" | "timeout"
+    severity: String,
+    /// 诊断所在的源文件（可能是被 \input 的章节），无法确定时为 None
+    file: Option<String>,
+    /// 找不到宏包或文档类时，其名称和修复建议
+    #[serde(skip_serializing_if = "Option::is_none")]
+    missing_package: Option<Box<packages::MissingPackage>>,
+}
+
+/// 编译成功时返回给前端的结果：除 PDF 外还带上警告/badbox 诊断和完整日志。
+#[derive(Serialize)]
+pub struct CompileResult {
+    /// PDF 内容；请求只返回路径时为 None，前端通过 asset 协议加载
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pdf: Option<Vec<u8>>,
+    pdf_path: String,
+    diagnostics: Vec<CompileError>,
+    log: String,
+    /// 实际使用的输出目录
+    output_dir: String,
+    /// 源文件目录不可写，输出目录被改到了应用缓存里
+    output_redirected: bool,
+    /// 输入没有变化，没有运行引擎，返回的是上次的产物
+    cached: bool,
+}
+
+impl CompileResult {
+    fn new(pdf: Option<Vec<u8>>, pdf_path: &Path, diagnostics: Vec<CompileError>, log: String) -> Self {
+        let output_dir = pdf_path.parent().unwrap_or(Path::new("."));
+        let output_redirected = output_dir.starts_with(fallback_root());
+        CompileResult {
+            pdf,
+            pdf_path: pdf_path.to_string_lossy().to_string(),
+            diagnostics,
+            log,
+            output_dir: output_dir.to_string_lossy().to_string(),
+            output_redirected,
+            cached: false,
+        }
+    }
+
+    /// 按需把 PDF 读进结果，或者把它加入 asset 协议的允许范围。
+    /// 产物可能在缓存目录或未保存文档的临时工作区里，也加入发起编译的窗口的访问范围，预览和 SyncTeX 才能读取
+    fn deliver(mut self, app: &AppHandle, scope: &WindowScope, return_path: bool) -> Result<Self, Vec<CompileError>> {
+        scope.allow(Path::new(&self.pdf_path));
+        if return_path {
+            app.asset_protocol_scope()
+                .allow_file(&self.pdf_path)
+                .map_err(|e| vec![CompileError::simple(e.to_string())])?;
+        } else if self.pdf.is_none() {
+            self.pdf = Some(fs::read(&self.pdf_path).map_err(|e| vec![CompileError::sys(e)])?);
+        }
+        Ok(self)
+    }
+}
+
+// 扩展 CompileError 方便构建
+impl CompileError {
+    pub(crate) fn simple(msg: impl Into<String>) -> Self {
+        Self { line: 0, message: msg.into(), severity: "error".to_string(), file: None, missing_package: None }
+    }
+    fn sys(e: std::io::Error) -> Self {
+        Self { line: 0, message: e.to_string(), severity: "error".to_string(), file: None, missing_package: None }
+    }
+    fn timeout(limit: Duration) -> Self {
+        Self {
+            line: 0,
+            message: format!("编译超过 {} 秒未结束，已终止（可在设置中调整 compile_timeout）", limit.as_secs()),
+            severity: "timeout".to_string(),
+            file: None,
+            missing_package: None,
+        }
+    }
+}
+
+/// 编译会先把内容写回 `file_path`，与 `save_file` 一样只允许打开过的目录内的文件
+fn check_scope(scope: &WindowScope, file_path: Option<&str>) -> Result<(), Vec<CompileError>> {
+    scope.check_optional(file_path).map_err(|e| vec![CompileError::simple(e)])
+}
+
+/// 应用设置中的编译超时，0 表示不限时
+fn compile_timeout(app: &AppHandle) -> Option<Duration> {
+    let seconds = app.state::<SettingsStore>().get(app).compile_timeout;
+    (seconds > 0).then(|| Duration::from_secs(seconds.into()))
+}
+
+/// 引擎选择顺序：前端指定 > 项目配置 (.mymd/project.toml) > 应用设置中的默认引擎。
+/// tectonic 依次用设置中的路径、内嵌引擎（启用 `embedded-tectonic` 时）、
+/// `install_tectonic` 安装的版本、PATH 中的版本。
+/// 项目配置的 `compile_flags` 排在 `options.extra_args` 之前。
+fn resolve_engine(
+    app: &AppHandle,
+    requested: Option<EngineKind>,
+    source: Option<&Path>,
+    mut options: CompileOptions,
+) -> Box<dyn LatexEngine> {
+    let settings = app.state::<SettingsStore>().get(app);
+    let project = source.and_then(find_project_config).map(|(_, config)| config);
+    let engine = requested
+        .or_else(|| project.as_ref()?.engine)
+        .unwrap_or(settings.default_engine);
+    if let Some(project) = project {
+        options.extra_args.splice(0..0, project.compile_flags);
+    }
+    options.preamble_cache = options.preamble_cache.or(Some(settings.preamble_cache));
+    let configured = settings.tectonic_path.filter(|p| !p.is_empty());
+    #[cfg(feature = "embedded-tectonic")]
+    if engine == EngineKind::Tectonic && configured.is_none() {
+        if let Ok(cache) = app.path().app_cache_dir() {
+            return Box::new(embedded::EmbeddedTectonic::new(cache.join("tectonic"), options));
+        }
+    }
+    let tectonic = configured.or_else(|| {
+        let installed = installed_tectonic(app).ok().filter(|path| path.is_file())?;
+        Some(installed.to_string_lossy().to_string())
+    });
+    engine_for(engine, tectonic.as_deref(), settings.remote_build, settings.docker_build, options)
+}
+
+#[command]
+pub async fn compile_latex(
+    window: Window,
+    latex_code: String,
+    file_path: Option<String>,
+    job_id: Option<String>,
+    engine: Option<EngineKind>,
+    return_path: Option<bool>,
+    options: Option<CompileOptions>,
+) -> Result<CompileResult, Vec<CompileError>> {
+    tracing::info!("Frontend requested compilation");
+    let app = window.app_handle().clone();
+    let scope = WindowScope::new(&app, window.label());
+    check_scope(&scope, file_path.as_deref())?;
+    let engine = resolve_engine(&app, engine, file_path.as_deref().map(Path::new), options.unwrap_or_default());
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let jobs = app.state::<CompileJobs>();
+        let (job_id, job) = jobs.register(job_id, compile_timeout(&app));
+        let reporter = Arc::new(ProgressReporter::new(app.clone(), job_id.clone()));
+        reporter.phase(CompilePhase::Starting);
+        let queue = app.state::<CompileQueue>();
+        let result = compile_blocking(&queue, scope.window(), &job, &reporter, engine.as_ref(), latex_code, file_path)
+            .and_then(|result| result.deliver(&app, &scope, return_path.unwrap_or(false)));
+        reporter.phase(CompilePhase::Finished);
+        jobs.finish(&job_id);
+        result
+    })
+    .await
+    .map_err(|e| vec![CompileError::simple(e.to_string())])?
+}
+
+/// 终止指定的编译任务。被终止的任务会清理未写完的输出并返回 "Compilation cancelled"。
+#[command]
+pub fn cancel_compile(jobs: State<'_, CompileJobs>, job_id: String) -> Result<(), String> {
+    let job = jobs
+        .jobs
+        .lock()
+        .unwrap()
+        .get(&job_id)
+        .cloned()
+        .ok_or_else(|| format!("没有正在运行的编译任务: {}", job_id))?;
+
+    job.cancel().map_err(|e| format!("无法终止编译进程: {}", e))
+}
+
+fn compile_blocking(
+    queue: &CompileQueue,
+    window: &str,
+    job: &Arc<CompileJob>,
+    reporter: &Arc<ProgressReporter>,
+    engine: &dyn LatexEngine,
+    latex_code: String,
+    file_path: Option<String>,
+) -> Result<CompileResult, Vec<CompileError>> {
+    // 情况 A: 未保存的新文件 (Untitled)
+    // 每个未保存文档有自己的临时工作区，源文件名固定为 input.tex
+    if file_path.is_none() {
+        let temp_dir = untitled::workspace_dir(engine.options().untitled_id.as_deref())
+            .map_err(|e| vec![CompileError::simple(e)])?;
+        if !temp_dir.exists() {
+            fs::create_dir_all(&temp_dir).map_err(|e| vec![CompileError::sys(e)])?;
+        }
+        let tex_file_path = temp_dir.join("input.tex");
+        let extension = engine.options().output_extension().map_err(|e| vec![CompileError::simple(e)])?;
+        let pdf_file_path = temp_dir.join(format!("input.{}", extension));
+
+        // 同一个工作区的编译仍要排队
+        let _slot = queue.acquire(window, &temp_dir, job)?;
+        fs::write(&tex_file_path, &latex_code).map_err(|e| vec![CompileError::sys(e)])?;
+
+        let output = orchestrator::build(job, reporter, engine, &tex_file_path, &temp_dir, "input")?;
+        return handle_compilation_result(engine, output, &temp_dir, pdf_file_path);
+    }
+
+    // 情况 B: 已存在的本地文件
+    let path_str = file_path.unwrap();
+    let edited_path = Path::new(&path_str);
+
+    // 1. 【关键】保存当前编辑器内容到源文件
+    // 编译引擎需要读取磁盘上的文件，所以我们必须先保存
+    // 内容未变时不写，只读位置上的文件也能直接编译
+    if fs::read(edited_path).ok().as_deref() != Some(latex_code.as_bytes()) {
+        write_atomic(edited_path, latex_code.as_bytes()).map_err(|e| vec![CompileError::sys(e)])?;
+    }
+
+    // 2. 当前文件可能只是被 \input 的章节，真正要编译的是根文档
+    let root = find_root(edited_path, None);
+    let root = Path::new(&root.root);
+    let _slot = queue.acquire(window, root, job)?;
+    build_document(job, reporter, engine, root)
+}
+
+/// 编译磁盘上已有的根文档，产物写入 `output_dir_for` 给出的目录（默认为其旁边的 AuxiliaryFiles）。
+fn build_document(
+    job: &CompileJob,
+    reporter: &Arc<ProgressReporter>,
+    engine: &dyn LatexEngine,
+    source_path: &Path,
+) -> Result<CompileResult, Vec<CompileError>> {
+    let parent_dir = source_path.parent().unwrap_or(Path::new("."));
+
+    // 3. 获取文件名 (如 "main.tex" -> stem 是 "main")
+    let file_stem = source_path.file_stem()
+        .ok_or_else(|| vec![CompileError::simple("无法获取文件名")])?
+        .to_string_lossy()
+        .to_string();
+
+    // 并创建输出目录（默认是根文档旁的 AuxiliaryFiles）
+    let options = engine.options();
+    let extension = options.output_extension().map_err(|e| vec![CompileError::simple(e)])?;
+    let aux_dir = output_dir_for(source_path, options.output_dir.as_deref()).map_err(|e| vec![CompileError::simple(e)])?;
+    let aux_dir = prepare_output_dir(&aux_dir).map_err(|e| vec![CompileError::sys(e)])?;
+
+    // PDF 会生成在 aux_dir 下，名字是 <file_stem>.pdf（`outfmt` 为 xdv/aux 时换成对应扩展名）
+    let pdf_filename = format!("{}.{}", file_stem, extension);
+    let pdf_file_path = aux_dir.join(&pdf_filename);
+
+    // 4. 根文档和所有依赖自上次成功编译后都没变，直接返回上次的产物
+    let key = cache::build_key(engine, source_path);
+    if let Some(key) = key.as_deref().filter(|_| !options.force) {
+        if cache::is_fresh(&aux_dir, &file_stem, key, &pdf_file_path) {
+            tracing::info!("{:?} is up to date, skipping {}", source_path, engine.name());
+            reporter.note(CompilePhase::WritingPdf, "已是最新；沿用上次的编译结果");
+            return Ok(cached_result(engine, parent_dir, &pdf_file_path));
+        }
+    }
+    cache::record(&aux_dir, &file_stem, None);
+
+    // 5. 执行编译，所有引擎都把产物写进输出目录
+    // 需要时由编排器自动补跑 biber/bibtex 和额外的 LaTeX 遍数
+    tracing::info!("Compiling {:?} with {} to output dir {:?}", source_path, engine.name(), aux_dir);
+
+    let output = orchestrator::build(job, reporter, engine, source_path, &aux_dir, &file_stem)?;
+
+    // 6. 结果处理
+    let result = handle_compilation_result(engine, output, parent_dir, pdf_file_path)?;
+    cache::record(&aux_dir, &file_stem, key.as_deref());
+    Ok(result)
+}
+
+/// 命中编译缓存时的结果：诊断信息从上次留下的 .log 重新解析
+fn cached_result(engine: &dyn LatexEngine, source_dir: &Path, pdf_path: &Path) -> CompileResult {
+    let log = fs::read(pdf_path.with_extension("log"))
+        .map(|bytes| truncate_log(String::from_utf8_lossy(&bytes).to_string()))
+        .unwrap_or_default();
+    let diagnostics = engine.parse_log(&log, source_dir);
+    CompileResult { cached: true, ..CompileResult::new(None, pdf_path, diagnostics, log) }
+}
+
+/// 已保存文档的输出目录：`name`（相对根文档所在目录）> 项目配置的 `output_directory`
+/// （相对项目根目录）> 与根文档同级的 AuxiliaryFiles。
+/// 目录名只能是不含 `..` 的相对路径，保证产物留在项目内、不越过文件访问范围。
+/// 之前编译时发现该目录不可写、已改用应用缓存的，返回缓存中的目录。
+pub fn output_dir_for(source_path: &Path, name: Option<&str>) -> Result<PathBuf, String> {
+    let preferred = preferred_output_dir(source_path, name)?;
+    let fallback = fallback_output_dir(&preferred);
+    Ok(if fallback.is_dir() { fallback } else { preferred })
+}
+
+fn preferred_output_dir(source_path: &Path, name: Option<&str>) -> Result<PathBuf, String> {
+    let parent_dir = source_path.parent().unwrap_or(Path::new("."));
+    let (base, name) = match name {
+        Some(name) => (parent_dir.to_path_buf(), name.to_string()),
+        None => match find_project_config(source_path) {
+            Some((root, ProjectConfig { output_directory: Some(dir), .. })) => (root, dir),
+            _ => return Ok(parent_dir.join(DEFAULT_OUTPUT_DIR)),
+        },
+    };
+    let relative = Path::new(&name);
+    if name.is_empty() || relative.components().any(|c| !matches!(c, Component::Normal(_))) {
+        return Err(format!("输出目录必须是项目内的相对路径: {}", name));
+    }
+    Ok(base.join(relative))
+}
+
+/// 在应用缓存目录下为只读项目存放产物，由 main 的 setup 调用一次
+pub fn init_output_fallback(app: &AppHandle) {
+    if let Ok(cache) = app.path().app_cache_dir() {
+        let _ = OUTPUT_FALLBACK_ROOT.set(cache.join(OUTPUT_FALLBACK_DIR));
+    }
+}
+
+fn fallback_root() -> PathBuf {
+    OUTPUT_FALLBACK_ROOT.get().cloned().unwrap_or_else(|| std::env::temp_dir().join("mymd_output"))
+}
+
+fn fallback_output_dir(preferred: &Path) -> PathBuf {
+    let root = fallback_root();
+    let key = crate::document::content_version(preferred.to_string_lossy().as_bytes());
+    let name = preferred.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    root.join(format!("{}-{}", name, key))
+}
+
+fn is_read_only_error(e: &std::io::Error) -> bool {
+    matches!(e.kind(), ErrorKind::PermissionDenied | ErrorKind::ReadOnlyFilesystem)
+}
+
+/// 创建输出目录并确认可写；源文件所在位置只读时改用应用缓存中的目录（之后
+/// `output_dir_for` 也会返回它）。返回实际使用的目录。
+pub fn prepare_output_dir(dir: &Path) -> std::io::Result<PathBuf> {
+    let writable = fs::create_dir_all(dir).and_then(|_| {
+        let probe = dir.join(".mymd-write-test");
+        fs::write(&probe, b"")?;
+        fs::remove_file(&probe)
+    });
+    match writable {
+        Ok(()) => Ok(dir.to_path_buf()),
+        Err(e) if is_read_only_error(&e) => {
+            // `dir` 可能已经是缓存目录（`output_dir_for` 找到了它），此时不能再换
+            if dir.starts_with(fallback_root()) {
+                return Err(e);
+            }
+            let fallback = fallback_output_dir(dir);
+            tracing::warn!("Output directory {:?} is not writable ({}), using {:?}", dir, e, fallback);
+            fs::create_dir_all(&fallback)?;
+            Ok(fallback)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// 找不到程序时给出可操作的提示，而不是 io 错误原文
+fn spawn_error(program: &str, e: std::io::Error) -> CompileError {
+    if e.kind() == ErrorKind::NotFound {
+        CompileError::simple(format!(
+            "未找到 {}：请安装后重试，或在设置中指定路径（tectonic 可在工具链检查中一键安装）",
+            program
+        ))
+    } else {
+        CompileError::sys(e)
+    }
+}
+
+/// 启动编译子进程并登记到任务中，轮询等待其结束，期间允许 `cancel_compile` 终止它。
+fn run_engine(
+    job: &CompileJob,
+    reporter: &Arc<ProgressReporter>,
+    mut cmd: Command,
+    output_dir: &Path,
+    file_stem: &str,
+) -> Result<Output, Vec<CompileError>> {
+    // 多步构建时，取消或超时可能发生在两步之间
+    if job.is_cancelled() {
+        cleanup_partial_output(output_dir, file_stem);
+        return Err(vec![CompileError::simple(job.cancel_message())]);
+    }
+    job.check_deadline()?;
+
+    let program = cmd.get_program().to_string_lossy().to_string();
+    let mut child = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| vec![spawn_error(&program, e)])?;
+
+    // 必须在后台持续读取管道，否则输出过多时子进程会阻塞在写入上
+    let stdout_reader = spawn_pipe_reader(child.stdout.take(), reporter.clone());
+    let stderr_reader = spawn_pipe_reader(child.stderr.take(), reporter.clone());
+    *job.child.lock().unwrap() = Some(child);
+
+    let status = loop {
+        let mut guard = job.child.lock().unwrap();
+        let child = guard.as_mut().expect("compile child registered");
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                guard.take();
+                break status;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                guard.take();
+                return Err(vec![CompileError::sys(e)]);
+            }
+        }
+        // 文档写错时引擎可能一直卡住（如等待终端输入），到点直接终止
+        if let Err(timeout) = job.check_deadline() {
+            let mut child = guard.take().expect("compile child registered");
+            let _ = child.kill();
+            let _ = child.wait();
+            drop(guard);
+            let _ = stdout_reader.join();
+            let _ = stderr_reader.join();
+            cleanup_partial_output(output_dir, file_stem);
+            return Err(timeout);
+        }
+        drop(guard);
+        thread::sleep(Duration::from_millis(50));
+    };
+
+    let stdout = stdout_reader.join().unwrap_or_default();
+    let stderr = stderr_reader.join().unwrap_or_default();
+
+    if job.is_cancelled() {
+        cleanup_partial_output(output_dir, file_stem);
+        return Err(vec![CompileError::simple(job.cancel_message())]);
+    }
+
+    Ok(Output { status, stdout, stderr })
+}
+
+/// 逐行读取子进程输出：既累积日志供错误解析，又实时转发为进度事件。
+/// 超过 `MAX_LOG_SIZE` 后只继续读空管道，不再保存和转发。
+fn spawn_pipe_reader<R: Read + Send + 'static>(
+    pipe: Option<R>,
+    reporter: Arc<ProgressReporter>,
+) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        let Some(pipe) = pipe else {
+            return buf;
+        };
+        let mut reader = BufReader::new(pipe);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match reader.read_until(b'\n', &mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) if buf.len() >= MAX_LOG_SIZE => {}
+                Ok(_) => {
+                    reporter.line(String::from_utf8_lossy(&line).trim_end());
+                    buf.extend_from_slice(&line);
+                    if buf.len() >= MAX_LOG_SIZE {
+                        buf.extend_from_slice(TRUNCATED_MARKER.as_bytes());
+                    }
+                }
+            }
+        }
+        buf
+    })
+}
+
+/// 删除被中断的编译可能留下的半成品（PDF/XDV），避免前端读到损坏的文件。
+fn cleanup_partial_output(output_dir: &Path, file_stem: &str) {
+    for ext in ["pdf", "xdv", "synctex.gz"] {
+        let path = output_dir.join(format!("{}.{}", file_stem, ext));
+        if path.exists() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// 保留开头和结尾各一半：TeX 通常在开头报出第一个错误，结尾是总结和输出信息
+fn truncate_log(log: String) -> String {
+    if log.len() <= MAX_LOG_SIZE {
+        return log;
+    }
+    let half = MAX_LOG_SIZE / 2;
+    let mut head_end = half;
+    while !log.is_char_boundary(head_end) {
+        head_end -= 1;
+    }
+    let mut tail_start = log.len() - half;
+    while !log.is_char_boundary(tail_start) {
+        tail_start += 1;
+    }
+    format!("{}{}{}", &log[..head_end], TRUNCATED_MARKER, &log[tail_start..])
+}
+
+// 辅助函数：统一处理编译输出和错误解析
+fn handle_compilation_result(
+    engine: &dyn LatexEngine,
+    output: Output,
+    source_dir: &Path,
+    pdf_path: PathBuf,
+) -> Result<CompileResult, Vec<CompileError>> {
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let terminal_log = format!("{}\n{}", stdout, stderr);
+
+    // 优先解析完整的 .log；引擎在 TeX 启动前就失败时（如缺少 bundle）只有终端输出
+    let log_path = pdf_path.with_extension("log");
+    let log = fs::read(&log_path)
+        .map(|bytes| truncate_log(String::from_utf8_lossy(&bytes).to_string()))
+        .unwrap_or_else(|_| terminal_log.clone());
+    let mut diagnostics = engine.parse_log(&log, source_dir);
+
+    if !output.status.success() {
+        let mut errors: Vec<CompileError> = diagnostics
+            .into_iter()
+            .filter(|d| d.severity == "error")
+            .collect();
+        if errors.is_empty() {
+            errors = engine
+                .parse_log(&terminal_log, source_dir)
+                .into_iter()
+                .filter(|d| d.severity == "error")
+                .collect();
+        }
+        if errors.is_empty() {
+            errors.push(CompileError::simple(terminal_log.trim()));
+        }
+        return Err(errors);
+    }
+
+    // tectonic 的终端输出里也有警告，但与 .log 重复，只在没有 .log 时使用
+    if diagnostics.is_empty() && log != terminal_log {
+        diagnostics = engine.parse_log(&terminal_log, source_dir);
+    }
+
+    if pdf_path.exists() {
+        Ok(CompileResult::new(None, &pdf_path, diagnostics, log))
+    } else {
+        Err(vec![CompileError::simple("编译成功但未找到生成的 PDF 文件")])
+    }
+}
+
+/// 读取编译产物的原始字节，用于无法走 asset 协议的场景（如未保存文档的临时输出）。
+/// 以二进制响应返回，避免序列化成 JSON 数组。
+#[command]
+pub fn read_pdf_bytes(scope: WindowScope, path: String) -> Result<Response, String> {
+    scope.check(&path)?;
+    let path = Path::new(&path);
+    if !path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("pdf")) {
+        return Err(format!("不是 PDF 文件: {}", path.to_string_lossy()));
+    }
+    fs::read(path)
+        .map(Response::new)
+        .map_err(|e| format!("无法读取文件: {}", e))
+}
@@ -0,0 +1,240 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::{Arc, LazyLock};
+
+use regex::Regex;
+
+use crate::document::content_version;
+
+use super::engine::{CompileMode, LatexEngine};
+use super::progress::{CompilePhase, ProgressReporter};
+use super::{run_engine, CompileError, CompileJob};
+
+/// 防止 label 来回变化导致无限重跑
+const MAX_PASSES: u32 = 5;
+
+/// 日志中提示需要再编译一遍的标记。"There were undefined citations" 不算：
+/// 引用 key 不在 .bib 里时再跑也解决不了，只有本次重新生成了参考文献才重跑
+const RERUN_MARKERS: &[&str] = &[
+    "Rerun to get",
+    "Label(s) may have changed",
+    "Please rerun LaTeX",
+    "Rerun LaTeX",
+];
+
+/// 上次成功运行 biber/bibtex 时的引用状态（引用的 key、.bib 文件及其内容的哈希），
+/// 存在 `<stem>.bib-state`；与本次不同就说明参考文献需要重新生成
+const BIB_STATE_EXTENSION: &str = "bib-state";
+
+static AUX_CITATION_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\\citation\{([^}]*)\}").unwrap());
+static AUX_BIBDATA_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\\bib(data|style)\{([^}]*)\}").unwrap());
+/// `\include` 的章节有各自的 .aux，由主 .aux 用 `\@input` 引入
+static AUX_INPUT_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\\@input\{([^}]*)\}").unwrap());
+static BCF_CITEKEY_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<bcf:citekey[^>]*>([^<]*)</bcf:citekey>").unwrap());
+static BCF_DATASOURCE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<bcf:datasource[^>]*>([^<]*)</bcf:datasource>").unwrap());
+
+#[derive(Clone, Copy, PartialEq)]
+enum BibTool {
+    Biber,
+    Bibtex,
+}
+
+impl BibTool {
+    fn name(self) -> &'static str {
+        match self {
+            BibTool::Biber => "biber",
+            BibTool::Bibtex => "bibtex",
+        }
+    }
+}
+
+/// 一次完整构建：LaTeX → (biber/bibtex) → LaTeX …，直到日志不再要求重跑。
+/// 返回的 `Output` 拼接了所有步骤的输出，状态码取最后一步。
+pub(super) fn build(
+    job: &CompileJob,
+    reporter: &Arc<ProgressReporter>,
+    engine: &dyn LatexEngine,
+    source: &Path,
+    output_dir: &Path,
+    file_stem: &str,
+) -> Result<Output, Vec<CompileError>> {
+    let mut combined = engine.run_pass(job, reporter, source, output_dir, file_stem, false)?;
+
+    // latexmk 自己就会处理 bibtex/biber 和重跑；草稿模式只要一遍
+    if engine.handles_bibliography() || engine.options().mode == CompileMode::Draft {
+        return Ok(combined);
+    }
+
+    let source_dir = source.parent().unwrap_or(Path::new("."));
+    let mut bib_done = false;
+    for pass in 2..=MAX_PASSES {
+        if !combined.status.success() {
+            break;
+        }
+        let log = read_log(output_dir, file_stem, &combined);
+
+        let mut needs_rerun = RERUN_MARKERS.iter().any(|marker| log.contains(marker));
+        if !bib_done {
+            if let Some((tool, state, reason)) = detect_bib_tool(output_dir, source_dir, file_stem, &log) {
+                reporter.note(CompilePhase::Bibliography, &format!("正在运行 {}（{}）", tool.name(), reason));
+                let cmd = bib_command(tool, source, output_dir, file_stem);
+                let output = run_engine(job, reporter, cmd, output_dir, file_stem)?;
+                let ok = output.status.success();
+                append_output(&mut combined, output);
+                if !ok {
+                    fs::remove_file(bib_state_path(output_dir, file_stem)).ok();
+                    break;
+                }
+                fs::write(bib_state_path(output_dir, file_stem), state).ok();
+                bib_done = true;
+                needs_rerun = true;
+            } else if pass == 2 && bib_state_path(output_dir, file_stem).exists() {
+                reporter.note(CompilePhase::Bibliography, "参考文献已是最新");
+            }
+        }
+        if !needs_rerun {
+            break;
+        }
+
+        reporter.note(CompilePhase::TexPass, &format!("重新运行 {}（第 {} 遍）", engine.name(), pass));
+        let output = engine.run_pass(job, reporter, source, output_dir, file_stem, true)?;
+        let status = output.status;
+        append_output(&mut combined, output);
+        combined.status = status;
+    }
+
+    Ok(combined)
+}
+
+fn append_output(combined: &mut Output, next: Output) {
+    combined.stdout.push(b'\n');
+    combined.stdout.extend(next.stdout);
+    combined.stderr.push(b'\n');
+    combined.stderr.extend(next.stderr);
+    if !next.status.success() {
+        combined.status = next.status;
+    }
+}
+
+/// 以 `<stem>.log` 为准，不存在时退回到进程输出（tectonic 不一定保留 log）。
+fn read_log(output_dir: &Path, file_stem: &str, output: &Output) -> String {
+    let log_path = output_dir.join(format!("{}.log", file_stem));
+    let mut log = fs::read(&log_path)
+        .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+        .unwrap_or_default();
+    log.push_str(&String::from_utf8_lossy(&output.stdout));
+    log.push_str(&String::from_utf8_lossy(&output.stderr));
+    log
+}
+
+/// 主 .aux 及其 `\@input` 的章节 .aux 中的引用 key 和 `\bibdata`/`\bibstyle`
+fn collect_aux(path: &Path, keys: &mut BTreeSet<String>, data: &mut BTreeSet<String>, depth: u32) {
+    let Ok(aux) = fs::read_to_string(path) else {
+        return;
+    };
+    for caps in AUX_CITATION_RE.captures_iter(&aux) {
+        keys.extend(caps[1].split(',').map(|key| key.trim().to_string()).filter(|key| !key.is_empty()));
+    }
+    for caps in AUX_BIBDATA_RE.captures_iter(&aux) {
+        data.extend(caps[2].split(',').map(|name| format!("{}:{}", &caps[1], name.trim())));
+    }
+    if depth < 4 {
+        let dir = path.parent().unwrap_or(Path::new("."));
+        for caps in AUX_INPUT_RE.captures_iter(&aux) {
+            collect_aux(&dir.join(&caps[1]), keys, data, depth + 1);
+        }
+    }
+}
+
+/// 本次编译后的引用状态：用到的工具、所有引用 key，以及 .bib（和 .bst）文件连同内容的哈希。
+/// 文档不引用任何文献时返回 None。
+fn bib_state(output_dir: &Path, source_dir: &Path, file_stem: &str) -> Option<(BibTool, String)> {
+    let mut keys = BTreeSet::new();
+    let mut files = BTreeSet::new();
+    let tool = if let Ok(bcf) = fs::read_to_string(output_dir.join(format!("{}.bcf", file_stem))) {
+        keys.extend(BCF_CITEKEY_RE.captures_iter(&bcf).map(|caps| caps[1].trim().to_string()));
+        files.extend(BCF_DATASOURCE_RE.captures_iter(&bcf).map(|caps| format!("data:{}", caps[1].trim())));
+        BibTool::Biber
+    } else {
+        collect_aux(&output_dir.join(format!("{}.aux", file_stem)), &mut keys, &mut files, 0);
+        if files.is_empty() {
+            return None;
+        }
+        BibTool::Bibtex
+    };
+    if keys.is_empty() {
+        return None;
+    }
+    let mut state = keys.into_iter().collect::<Vec<_>>().join(",");
+    for entry in files {
+        let (kind, name) = entry.split_once(':').unwrap_or(("data", &entry));
+        let extension = if kind == "style" { "bst" } else { "bib" };
+        let mut path = source_dir.join(name);
+        if path.extension().is_none() {
+            path.set_extension(extension);
+        }
+        // 系统里的 .bst 读不到，只记名字
+        let content = fs::read(&path).map(|bytes| content_version(&bytes)).unwrap_or_default();
+        state.push_str(&format!("\n{}={}", entry, content));
+    }
+    Some((tool, state))
+}
+
+fn bib_state_path(output_dir: &Path, file_stem: &str) -> PathBuf {
+    output_dir.join(format!("{}.{}", file_stem, BIB_STATE_EXTENSION))
+}
+
+/// 是否需要跑 biber/bibtex，以及原因（显示在进度里）。除了日志中的提示，还比较引用状态：
+/// 增删引用或修改 .bib 之后不用手动连编三次。
+fn detect_bib_tool(
+    output_dir: &Path,
+    source_dir: &Path,
+    file_stem: &str,
+    log: &str,
+) -> Option<(BibTool, String, &'static str)> {
+    let (tool, state) = bib_state(output_dir, source_dir, file_stem)?;
+    let bbl = output_dir.join(format!("{}.bbl", file_stem));
+    let recorded = fs::read_to_string(bib_state_path(output_dir, file_stem)).ok();
+    let reason = if !bbl.exists() {
+        "no bibliography yet"
+    } else if recorded.as_deref() != Some(state.as_str()) {
+        if recorded.is_some_and(|recorded| recorded.lines().next() == state.lines().next()) {
+            "bibliography files changed"
+        } else {
+            "citations changed"
+        }
+    } else if tool == BibTool::Biber && (log.contains("Please (re)run Biber") || log.contains("Please rerun Biber")) {
+        "biblatex requested it"
+    } else if tool == BibTool::Bibtex && log.contains("Citation") && log.contains("undefined") {
+        "undefined citations"
+    } else {
+        return None;
+    };
+    Some((tool, state, reason))
+}
+
+fn bib_command(tool: BibTool, source: &Path, output_dir: &Path, file_stem: &str) -> Command {
+    let source_dir = source.parent().unwrap_or(Path::new("."));
+    let mut cmd = Command::new(tool.name());
+    match tool {
+        BibTool::Biber => {
+            cmd.arg("--input-directory")
+                .arg(source_dir)
+                .arg("--output-directory")
+                .arg(output_dir)
+                .arg(file_stem);
+        }
+        BibTool::Bibtex => {
+            // bibtex 只在当前目录和 BIBINPUTS 里找 .bib，这里把源目录加进去
+            let separator = if cfg!(windows) { ";" } else { ":" };
+            cmd.arg(file_stem)
+                .env("BIBINPUTS", format!("{}{}", source_dir.to_string_lossy(), separator));
+        }
+    }
+    cmd.current_dir(output_dir);
+    cmd
+}
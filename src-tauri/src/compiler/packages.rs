@@ -0,0 +1,74 @@
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::latex::strip_comment;
+
+use super::preamble::PACKAGE_RE;
+use super::CompileError;
+
+/// `LaTeX Error: File `foo.sty' not found.`，tectonic 的终端输出里引号形式不一定相同
+static MISSING_FILE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"File [`'"]([\w@.+-]+)\.(sty|cls)['`"]\s*(?:was )?not found"#).unwrap());
+
+#[derive(Clone, Serialize)]
+pub struct MissingPackage {
+    /// 不带扩展名，如 `tikz-cd`
+    name: String,
+    /// "package" | "class"
+    kind: &'static str,
+    /// 给用户的修复建议，按所用引擎不同
+    suggestion: String,
+}
+
+fn suggestion(name: &str, file: &str, tectonic: bool) -> String {
+    if tectonic {
+        format!(
+            "tectonic 会从 bundle 自动下载宏包，找不到说明 bundle 中没有 {}：检查名称是否拼错，\
+             或把 {} 放到根文档所在目录",
+            file, file
+        )
+    } else {
+        format!(
+            "TeX Live 运行 `tlmgr install {}`（宏包名不同时用 `tlmgr search --global --file /{}` 查找），\
+             MiKTeX 运行 `mpm --install={}`",
+            name, file, name
+        )
+    }
+}
+
+/// `\usepackage{name}`/`\documentclass{name}` 在 `file` 中的行号（从 1 开始）
+fn usage_line(file: &Path, name: &str, class: bool) -> Option<u32> {
+    let content = fs::read_to_string(file).ok()?;
+    content.lines().enumerate().find_map(|(index, line)| {
+        PACKAGE_RE.captures_iter(strip_comment(line)).find_map(|caps| {
+            let used_as_class = &caps[1] == "documentclass";
+            let names = caps[2].split(',').map(str::trim);
+            (used_as_class == class && names.into_iter().any(|used| used == name)).then_some(index as u32 + 1)
+        })
+    })
+}
+
+/// 把"找不到 .sty/.cls"的错误改写成带宏包名和修复建议的诊断；日志里没有行号时，
+/// 从诊断所在文件的 `\usepackage` 找出引用它的那一行。
+pub(super) fn annotate_missing_packages(diagnostics: &mut [CompileError], tectonic: bool) {
+    for diagnostic in diagnostics.iter_mut().filter(|d| d.severity == "error") {
+        let Some(caps) = MISSING_FILE_RE.captures(&diagnostic.message) else {
+            continue;
+        };
+        let name = caps[1].to_string();
+        let class = &caps[2] == "cls";
+        let file = format!("{}.{}", name, &caps[2]);
+        if diagnostic.line == 0 {
+            let line = diagnostic.file.as_deref().and_then(|path| usage_line(Path::new(path), &name, class));
+            diagnostic.line = line.unwrap_or(0);
+        }
+        let suggestion = suggestion(&name, &file, tectonic);
+        diagnostic.message = format!("未找到{} {}：{}", if class { "文档类" } else { "宏包" }, name, suggestion);
+        let kind = if class { "class" } else { "package" };
+        diagnostic.missing_package = Some(Box::new(MissingPackage { name, kind, suggestion }));
+    }
+}
@@ -0,0 +1,114 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, LazyLock};
+
+use regex::Regex;
+use tauri::command;
+
+use crate::document::content_version;
+use crate::latex::root::find_root;
+use crate::latex::strip_comment;
+use crate::scope::WindowScope;
+
+use super::progress::{CompilePhase, ProgressReporter};
+use super::{output_dir_for, run_engine, CompileJob};
+
+/// 格式文件与记录导言区哈希的文件都以 `<stem>-preamble` 命名，放在输出目录里
+const PREAMBLE_SUFFIX: &str = "-preamble";
+/// 哈希文件的扩展名；内容以此开头表示上次预编译失败，导言区改动前不再尝试
+const HASH_EXTENSION: &str = "hash";
+const FAILED_PREFIX: &str = "failed:";
+
+pub(super) static PACKAGE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\\(usepackage|RequirePackage|documentclass)\s*(?:\[[^\]]*\])?\s*\{([^}]+)\}").unwrap());
+
+fn preamble_paths(output_dir: &Path, file_stem: &str) -> (PathBuf, PathBuf) {
+    let name = format!("{}{}", file_stem, PREAMBLE_SUFFIX);
+    (output_dir.join(format!("{}.fmt", name)), output_dir.join(format!("{}.{}", name, HASH_EXTENSION)))
+}
+
+/// 导言区（`\begin{document}` 之前）加上其中引用的本地 .sty/.cls 的内容哈希；
+/// 没有 `\begin{document}` 的文件不预编译
+fn preamble_key(source: &Path, extra: &str) -> Option<String> {
+    let content = fs::read_to_string(source).ok()?;
+    let end = content.find("\\begin{document}")?;
+    let preamble = &content[..end];
+    let base_dir = source.parent().unwrap_or(Path::new("."));
+    let mut key = format!("{}\n{}", extra, preamble);
+    for line in preamble.lines() {
+        for caps in PACKAGE_RE.captures_iter(strip_comment(line)) {
+            let extension = if &caps[1] == "documentclass" { "cls" } else { "sty" };
+            for name in caps[2].split(',').map(str::trim) {
+                if let Ok(local) = fs::read_to_string(base_dir.join(format!("{}.{}", name, extension))) {
+                    key.push_str(&local);
+                }
+            }
+        }
+    }
+    Some(content_version(key.as_bytes()))
+}
+
+/// 导言区不变时复用上次的格式文件，否则用 mylatexformat 重新生成。
+/// 返回不带扩展名的格式文件路径（`-fmt` 的参数）；无法使用时返回 None，按普通方式编译。
+pub(super) fn prepare_format(
+    job: &CompileJob,
+    reporter: &Arc<ProgressReporter>,
+    source: &Path,
+    output_dir: &Path,
+    file_stem: &str,
+    extra: &str,
+) -> Option<PathBuf> {
+    let key = preamble_key(source, extra)?;
+    let (fmt_path, hash_path) = preamble_paths(output_dir, file_stem);
+    let recorded = fs::read_to_string(&hash_path).unwrap_or_default();
+    if recorded == format!("{}{}", FAILED_PREFIX, key) {
+        return None;
+    }
+    if recorded == key && fmt_path.is_file() {
+        return Some(fmt_path.with_extension(""));
+    }
+
+    reporter.note(CompilePhase::TexPass, "正在预编译导言区");
+    let jobname = format!("{}{}", file_stem, PREAMBLE_SUFFIX);
+    let mut cmd = Command::new("pdflatex");
+    cmd.arg("-ini")
+        .arg("-interaction=nonstopmode")
+        .arg(format!("-jobname={}", jobname))
+        .arg(format!("-output-directory={}", output_dir.to_string_lossy()))
+        .arg("&pdflatex")
+        .arg("mylatexformat.ltx")
+        .arg(source.file_name().unwrap_or_default());
+    if let Some(dir) = source.parent() {
+        cmd.current_dir(dir);
+    }
+    let built = run_engine(job, reporter, cmd, output_dir, &jobname).is_ok_and(|output| output.status.success());
+    if built && fmt_path.is_file() {
+        fs::write(&hash_path, &key).ok();
+        Some(fmt_path.with_extension(""))
+    } else {
+        // 导言区里有不能转储的内容（如加载字体的宏包）时会失败，记下来避免每次编译都重试
+        reporter.note(CompilePhase::TexPass, "无法预编译导言区；按常规方式编译");
+        fs::remove_file(&fmt_path).ok();
+        fs::write(&hash_path, format!("{}{}", FAILED_PREFIX, key)).ok();
+        None
+    }
+}
+
+/// 删除 `path`（.tex 文件按其根文档）的预编译导言区，下次编译时重新生成。
+/// 导言区依赖的宏包升级后，哈希不会变，需要手动调用。
+#[command]
+pub fn invalidate_preamble_cache(scope: WindowScope, path: String) -> Result<(), String> {
+    scope.check(&path)?;
+    let root = find_root(Path::new(&path), None);
+    let root = Path::new(&root.root);
+    let file_stem = root.file_stem().ok_or("无法获取文件名")?.to_string_lossy().to_string();
+    let (fmt_path, hash_path) = preamble_paths(&output_dir_for(root, None)?, &file_stem);
+    for path in [fmt_path, hash_path] {
+        match fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(format!("无法删除文件: {}", e)),
+            _ => {}
+        }
+    }
+    Ok(())
+}
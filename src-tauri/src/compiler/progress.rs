@@ -0,0 +1,115 @@
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+/// 编译进度事件名，前端通过 `listen("compile-progress", ...)` 订阅。
+pub const COMPILE_PROGRESS_EVENT: &str = "compile-progress";
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompilePhase {
+    Starting,
+    /// `docker` 引擎拉取容器镜像，事件的 `line` 是 `docker pull` 的输出
+    PullingImage,
+    DownloadingBundle,
+    TexPass,
+    Bibliography,
+    WritingPdf,
+    Finished,
+}
+
+#[derive(Clone, Serialize)]
+pub struct CompileProgress {
+    job_id: String,
+    phase: CompilePhase,
+    /// 当前是第几遍 TeX 编译，从 1 开始；尚未进入 TeX 阶段时为 0
+    pass: u32,
+    /// 0.0 ~ 1.0 的粗略进度，用于前端进度条
+    progress: f32,
+    /// 触发本次事件的原始日志行
+    line: Option<String>,
+}
+
+struct PhaseState {
+    phase: CompilePhase,
+    pass: u32,
+}
+
+/// 把编译引擎的逐行输出（tectonic 或 latexmk）翻译成 `compile-progress` 事件。
+pub struct ProgressReporter {
+    app: AppHandle,
+    job_id: String,
+    state: Mutex<PhaseState>,
+}
+
+impl ProgressReporter {
+    pub fn new(app: AppHandle, job_id: String) -> Self {
+        Self {
+            app,
+            job_id,
+            state: Mutex::new(PhaseState { phase: CompilePhase::Starting, pass: 0 }),
+        }
+    }
+
+    pub fn phase(&self, phase: CompilePhase) {
+        let mut state = self.state.lock().unwrap();
+        state.phase = phase;
+        self.emit(&state, None);
+    }
+
+    /// 由编排器主动报告的步骤（如 "Running biber"），不经过输出行的阶段推断。
+    pub fn note(&self, phase: CompilePhase, text: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.phase = phase;
+        self.emit(&state, Some(text.to_string()));
+    }
+
+    pub fn line(&self, text: &str) {
+        let mut state = self.state.lock().unwrap();
+        // 镜像层的下载进度不是 bundle 下载，也还没进入编译
+        if state.phase == CompilePhase::PullingImage {
+            self.emit(&state, Some(text.to_string()));
+            return;
+        }
+
+        let lower = text.to_lowercase();
+        if lower.contains("downloading") || lower.contains("indexing") {
+            state.phase = CompilePhase::DownloadingBundle;
+        } else if lower.contains("running tex")
+            || lower.contains("rerunning tex")
+            || lower.starts_with("run number")
+        {
+            state.phase = CompilePhase::TexPass;
+            state.pass += 1;
+        } else if lower.contains("running xdvipdfmx")
+            || lower.contains("writing `")
+            || lower.starts_with("output written on")
+        {
+            state.phase = CompilePhase::WritingPdf;
+        }
+
+        self.emit(&state, Some(text.to_string()));
+    }
+
+    fn emit(&self, state: &PhaseState, line: Option<String>) {
+        let progress = match state.phase {
+            CompilePhase::Starting => 0.0,
+            CompilePhase::PullingImage => 0.05,
+            CompilePhase::DownloadingBundle => 0.1,
+            // 每多一遍就往前推一点，但不越过写 PDF 阶段
+            CompilePhase::TexPass => (0.2 + 0.2 * state.pass as f32).min(0.8),
+            CompilePhase::Bibliography => (0.3 + 0.2 * state.pass as f32).min(0.8),
+            CompilePhase::WritingPdf => 0.9,
+            CompilePhase::Finished => 1.0,
+        };
+        let payload = CompileProgress {
+            job_id: self.job_id.clone(),
+            phase: state.phase,
+            pass: state.pass,
+            progress,
+            line,
+        };
+        let _ = self.app.emit(COMPILE_PROGRESS_EVENT, payload);
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use super::{CompileError, CompileJob};
+use crate::latex::root::canonical;
+
+/// 连续点击编译时只保留最后一次：空闲后再等这么久，期间有新请求就放弃当前请求
+const DEBOUNCE: Duration = Duration::from_millis(150);
+/// 排队时定期醒来检查任务是否已被 `cancel_compile` 取消
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+pub const SUPERSEDED_MESSAGE: &str = "Compilation superseded by a newer request";
+
+/// 按（窗口标签，根文档或输出目录）排队的编译请求，与监听编译一样按窗口区分：
+/// 新请求只终止同一窗口正在运行的构建，仍在排队的旧请求直接放弃。
+/// 同一文档不论来自哪个窗口，同时只跑一个构建，避免多个进程争用 AuxiliaryFiles。
+#[derive(Default)]
+pub struct CompileQueue {
+    slots: Mutex<HashMap<(String, PathBuf), Slot>>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct Slot {
+    /// 最新请求的序号，序号落后的请求已被取代
+    latest: u64,
+    running: Option<Arc<CompileJob>>,
+}
+
+/// 持有期间独占对应文档的构建，释放时唤醒排队的请求
+pub struct QueueSlot<'a> {
+    queue: &'a CompileQueue,
+    key: (String, PathBuf),
+}
+
+impl Drop for QueueSlot<'_> {
+    fn drop(&mut self) {
+        if let Some(slot) = self.queue.slots.lock().unwrap().get_mut(&self.key) {
+            slot.running = None;
+        }
+        self.queue.changed.notify_all();
+    }
+}
+
+impl CompileQueue {
+    /// 等待轮到 `window` 的 `job`；被同一窗口更新的请求取代或被取消时返回错误
+    pub(super) fn acquire(
+        &self,
+        window: &str,
+        path: &Path,
+        job: &Arc<CompileJob>,
+    ) -> Result<QueueSlot<'_>, Vec<CompileError>> {
+        // 手动编译与监听编译给出的路径形式可能不同
+        let key = (window.to_string(), canonical(path));
+        let mut slots = self.slots.lock().unwrap();
+        let slot = slots.entry(key.clone()).or_default();
+        slot.latest += 1;
+        let ticket = slot.latest;
+        if let Some(running) = &slot.running {
+            running.supersede();
+        }
+        self.changed.notify_all();
+
+        let mut ready_at: Option<Instant> = None;
+        loop {
+            let slot = slots.get(&key).expect("queue slot exists");
+            if slot.latest != ticket {
+                return Err(vec![CompileError::simple(SUPERSEDED_MESSAGE)]);
+            }
+            if job.is_cancelled() {
+                return Err(vec![CompileError::simple(job.cancel_message())]);
+            }
+            // 其他窗口在编译同一文档时也要等
+            let busy = slots.iter().any(|((_, other), slot)| other == &key.1 && slot.running.is_some());
+            let wait = if busy {
+                ready_at = None;
+                POLL_INTERVAL
+            } else {
+                let deadline = *ready_at.get_or_insert_with(|| Instant::now() + DEBOUNCE);
+                let now = Instant::now();
+                if now >= deadline {
+                    let slot = slots.get_mut(&key).expect("queue slot exists");
+                    slot.running = Some(job.clone());
+                    return Ok(QueueSlot { queue: self, key });
+                }
+                (deadline - now).min(POLL_INTERVAL)
+            };
+            slots = self.changed.wait_timeout(slots, wait).unwrap().0;
+        }
+    }
+
+    /// 窗口关闭时丢掉它空闲的队列项；正在运行的构建结束后照常释放
+    pub fn remove_window(&self, label: &str) {
+        self.slots.lock().unwrap().retain(|(owner, _), slot| owner != label || slot.running.is_some());
+    }
+}
@@ -0,0 +1,555 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Component, Path};
+use std::process::{Command, ExitStatus, Output, Stdio};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use ureq::Agent;
+
+use crate::document::content_version;
+use crate::secrets::{self, Service};
+use crate::workspace::project_walker;
+
+use super::engine::{CompileMode, CompileOptions, EngineKind, LatexEngine};
+use super::log_parser;
+use super::packages;
+use super::progress::{CompilePhase, ProgressReporter};
+use super::{run_engine, CompileError, CompileJob};
+
+/// 记录上次上传到服务器的文件及其哈希：`<stem>.remote-manifest`，放在输出目录里
+const MANIFEST_EXTENSION: &str = "remote-manifest";
+/// ssh 未配置 `remote_dir` 时，各项目工作区放在远程用户主目录下的这个目录里
+const DEFAULT_REMOTE_DIR: &str = ".mymd-builds";
+/// 工作区内的输出目录，与上传的源文件分开，删除源文件时不会误删产物
+const REMOTE_OUTPUT_DIR: &str = ".mymd-out";
+/// 服务器返回的产物包的上限
+const MAX_ARTIFACTS_SIZE: u64 = 512 * 1024 * 1024;
+/// HTTP 服务在产物包里用这两个条目返回退出码和终端输出
+const EXIT_STATUS_ENTRY: &str = "exit-status";
+const TERMINAL_OUTPUT_ENTRY: &str = "output";
+/// 没有编译超时的任务，HTTP 请求最多等这么久
+const DEFAULT_HTTP_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RemoteTransport {
+    /// 通过系统的 `ssh` 上传、编译、取回产物，需要免密（密钥或 agent）登录
+    #[default]
+    Ssh,
+    /// 向构建服务 POST 项目增量包，响应是产物包
+    Http,
+}
+
+/// 应用设置中的远程编译服务器，供 `remote` 引擎使用。
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RemoteBuildConfig {
+    pub transport: RemoteTransport,
+    /// ssh：`host`、`user@host` 或 ~/.ssh/config 中的别名；http：服务地址，如 `https://build.example.com`
+    pub host: String,
+    /// 仅 ssh
+    pub port: Option<u16>,
+    /// 仅 ssh：存放各项目工作区的目录，相对远程用户主目录
+    pub remote_dir: Option<String>,
+    /// 仅 http：以 `Authorization: Bearer` 发送。不要写在设置文件里，用
+    /// `store_secret("remote_build", host, token)` 存进系统钥匙串；这里的值只为兼容旧设置
+    pub token: Option<String>,
+    /// 服务器上使用的引擎，默认 tectonic
+    pub engine: EngineKind,
+}
+
+impl RemoteBuildConfig {
+    fn engine(&self) -> EngineKind {
+        match self.engine {
+            EngineKind::Remote | EngineKind::Docker => EngineKind::Tectonic,
+            engine => engine,
+        }
+    }
+
+    fn token(&self) -> Result<Option<String>, String> {
+        match self.token.as_deref().filter(|token| !token.is_empty()) {
+            Some(token) => Ok(Some(token.to_string())),
+            None => secrets::get(Service::RemoteBuild, &self.host),
+        }
+    }
+
+    /// 上传目标的标识：换了服务器或工作区目录时清单作废，全部重传
+    fn target(&self, workspace: &str) -> String {
+        let dir = self.remote_dir.as_deref().unwrap_or(DEFAULT_REMOTE_DIR);
+        format!("{:?}:{}:{}:{}/{}", self.transport, self.host, self.port.unwrap_or(0), dir, workspace)
+    }
+}
+
+/// 已上传的文件：相对项目目录、以 `/` 分隔的路径 → 内容哈希
+#[derive(Default, Deserialize, Serialize)]
+struct Manifest {
+    target: String,
+    files: BTreeMap<String, String>,
+}
+
+/// 本次需要上传的增量
+struct Upload {
+    manifest: Manifest,
+    /// 新增或修改的文件打成的 tar.gz
+    archive: Vec<u8>,
+    changed: usize,
+    deleted: Vec<String>,
+}
+
+/// 一次远程编译涉及的本地位置
+struct Build<'a> {
+    job: &'a CompileJob,
+    reporter: &'a Arc<ProgressReporter>,
+    /// 根文档所在目录，整个上传
+    project_dir: &'a Path,
+    /// 根文档的文件名
+    main: String,
+    output_dir: &'a Path,
+    file_stem: &'a str,
+}
+
+/// 从服务器取回的产物之外的信息
+#[derive(Default)]
+struct Artifacts {
+    /// 工作区在服务器上的绝对路径，用来把日志和 SyncTeX 里的路径换回本地路径
+    remote_path: Option<String>,
+    /// 仅 http：服务器报告的退出码和终端输出
+    exit_code: Option<i32>,
+    output: Vec<u8>,
+}
+
+/// 在服务器上编译：只上传自上次以来变化的文件（类似 rsync），在服务器上完整编译
+/// （多遍和参考文献由服务器上的引擎处理），再取回 PDF、日志和 SyncTeX。
+pub struct RemoteEngine {
+    config: Option<RemoteBuildConfig>,
+    options: CompileOptions,
+}
+
+impl RemoteEngine {
+    pub fn new(config: Option<RemoteBuildConfig>, options: CompileOptions) -> Self {
+        RemoteEngine { config, options }
+    }
+
+    fn config(&self) -> Result<&RemoteBuildConfig, Vec<CompileError>> {
+        self.config
+            .as_ref()
+            .filter(|config| !config.host.trim().is_empty())
+            .ok_or_else(|| vec![CompileError::simple("未配置远程编译服务器：请在设置中填写 remote_build")])
+    }
+}
+
+pub(super) fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// 服务器上的工作区名：项目目录名加本地路径的哈希，不同机器上的同名项目互不覆盖
+fn workspace_id(project_dir: &Path) -> String {
+    let name: String = project_dir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    let key = content_version(project_dir.to_string_lossy().as_bytes());
+    format!("{}-{}", name, &key[..12])
+}
+
+fn manifest_path(output_dir: &Path, file_stem: &str) -> std::path::PathBuf {
+    output_dir.join(format!("{}.{}", file_stem, MANIFEST_EXTENSION))
+}
+
+fn read_manifest(output_dir: &Path, file_stem: &str) -> Manifest {
+    fs::read_to_string(manifest_path(output_dir, file_stem))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write_manifest(output_dir: &Path, file_stem: &str, manifest: &Manifest) {
+    if let Ok(content) = serde_json::to_string(manifest) {
+        let _ = fs::write(manifest_path(output_dir, file_stem), content);
+    }
+}
+
+/// 项目目录下（不含输出目录）相对上次上传有变化的文件，以及已删除的文件
+fn plan_upload(project_dir: &Path, output_dir: &Path, previous: &Manifest, target: &str) -> Result<Upload, String> {
+    let same_target = previous.target == target;
+    let mut manifest = Manifest { target: target.to_string(), files: BTreeMap::new() };
+    let mut tar = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    let mut changed = 0;
+    let files = project_walker(project_dir)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| entry.into_path())
+        .filter(|path| !path.starts_with(output_dir));
+    for path in files {
+        let Ok(relative) = path.strip_prefix(project_dir) else {
+            continue;
+        };
+        let parts: Vec<String> = relative
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(part) => Some(part.to_string_lossy().to_string()),
+                _ => None,
+            })
+            .collect();
+        let name = parts.join("/");
+        let content = fs::read(&path).map_err(|e| format!("无法读取文件: {}", e))?;
+        let hash = content_version(&content);
+        if !same_target || previous.files.get(&name) != Some(&hash) {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            tar.append_data(&mut header, &name, content.as_slice()).map_err(|e| e.to_string())?;
+            changed += 1;
+        }
+        manifest.files.insert(name, hash);
+    }
+    let deleted = if same_target {
+        previous.files.keys().filter(|name| !manifest.files.contains_key(*name)).cloned().collect()
+    } else {
+        Vec::new()
+    };
+    let archive = tar.into_inner().and_then(|gz| gz.finish()).map_err(|e| e.to_string())?;
+    Ok(Upload { manifest, archive, changed, deleted })
+}
+
+/// 服务器上编译的命令，在工作区内以相对路径运行；`output_dir` 是产物目录，相对工作区或绝对路径。
+/// 也用于 `docker` 引擎在容器内编译
+pub(super) fn build_command(engine: EngineKind, options: &CompileOptions, main: &str, output_dir: &str) -> String {
+    let owned = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>();
+    let draft = options.mode == CompileMode::Draft;
+    let mut args = match engine {
+        EngineKind::Tectonic | EngineKind::Remote | EngineKind::Docker => {
+            let mut args = owned(&["tectonic", "-o", output_dir, "--keep-intermediates", "--keep-logs"]);
+            args.push("--synctex".to_string());
+            if let Some(outfmt) = &options.outfmt {
+                args.extend(owned(&["--outfmt", outfmt]));
+            }
+            if draft {
+                args.extend(owned(&["--reruns", "0"]));
+            }
+            if options.shell_escape {
+                args.extend(owned(&["-Z", "shell-escape"]));
+            }
+            args
+        }
+        EngineKind::Pdflatex | EngineKind::Xelatex | EngineKind::Lualatex => {
+            let flag = match engine {
+                EngineKind::Xelatex => "-xelatex",
+                EngineKind::Lualatex => "-lualatex",
+                _ => "-pdf",
+            };
+            let mut args = owned(&["latexmk", flag, "-synctex=1", "-interaction=nonstopmode", "-file-line-error"]);
+            args.push(format!("-outdir={}", output_dir));
+            if draft {
+                args.extend(owned(&["-bibtex-", "-e", "$max_repeat=1"]));
+            }
+            if options.shell_escape {
+                args.push("-shell-escape".to_string());
+            }
+            args
+        }
+    };
+    args.extend(options.extra_args.iter().cloned());
+    args.push(main.to_string());
+    // 本地的搜索路径在服务器上没有意义
+    let env: String = options
+        .env
+        .iter()
+        .filter(|(key, _)| key.as_str() != "TEXINPUTS")
+        .map(|(key, value)| format!("{}={} ", key, shell_quote(value)))
+        .collect();
+    let command: Vec<String> = args.iter().map(|arg| shell_quote(arg)).collect();
+    format!("mkdir -p {} && {}{}", shell_quote(output_dir), env, command.join(" "))
+}
+
+/// 产物包中只接受这几个文件，防止服务器写到输出目录以外
+fn artifact_names(file_stem: &str, extension: &str) -> Vec<String> {
+    [extension, "log", "synctex.gz"].iter().map(|ext| format!("{}.{}", file_stem, ext)).collect()
+}
+
+fn unpack_artifacts(
+    reader: impl Read,
+    output_dir: &Path,
+    names: &[String],
+    artifacts: &mut Artifacts,
+) -> Result<(), String> {
+    let mut archive = tar::Archive::new(GzDecoder::new(reader.take(MAX_ARTIFACTS_SIZE)));
+    for entry in archive.entries().map_err(|e| format!("产物包无效: {}", e))? {
+        let mut entry = entry.map_err(|e| format!("产物包无效: {}", e))?;
+        let name = entry.path().map_err(|e| e.to_string())?.to_string_lossy().to_string();
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content).map_err(|e| format!("产物包无效: {}", e))?;
+        if name == EXIT_STATUS_ENTRY {
+            artifacts.exit_code = String::from_utf8_lossy(&content).trim().parse().ok();
+        } else if name == TERMINAL_OUTPUT_ENTRY {
+            artifacts.output = content;
+        } else if names.contains(&name) {
+            crate::atomic::write_atomic(&output_dir.join(&name), &content).map_err(|e| format!("无法写入文件: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+/// 日志和 SyncTeX 里的路径是服务器上的，换成本地项目目录，诊断定位和正反向搜索才能用
+pub(super) fn localize_paths(output_dir: &Path, file_stem: &str, remote_path: &str, project_dir: &Path) {
+    let remote = format!("{}/", remote_path.trim_end_matches('/'));
+    let local = format!("{}/", project_dir.to_string_lossy().trim_end_matches(['/', '\\']));
+    let log_path = output_dir.join(format!("{}.log", file_stem));
+    if let Ok(log) = fs::read(&log_path) {
+        let log = String::from_utf8_lossy(&log).replace(&remote, &local);
+        let _ = fs::write(&log_path, log);
+    }
+    let synctex_path = output_dir.join(format!("{}.synctex.gz", file_stem));
+    let Ok(compressed) = fs::read(&synctex_path) else {
+        return;
+    };
+    let mut synctex = String::new();
+    if GzDecoder::new(compressed.as_slice()).read_to_string(&mut synctex).is_err() {
+        return;
+    }
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    if encoder.write_all(synctex.replace(&remote, &local).as_bytes()).is_ok() {
+        if let Ok(rewritten) = encoder.finish() {
+            let _ = fs::write(&synctex_path, rewritten);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn exit_status(code: i32) -> ExitStatus {
+    use std::os::unix::process::ExitStatusExt;
+    ExitStatus::from_raw(code << 8)
+}
+
+#[cfg(windows)]
+fn exit_status(code: i32) -> ExitStatus {
+    use std::os::windows::process::ExitStatusExt;
+    ExitStatus::from_raw(code as u32)
+}
+
+fn ssh_command(config: &RemoteBuildConfig, script: &str) -> Command {
+    let mut cmd = Command::new("ssh");
+    // 没有终端可以输入密码，需要密钥登录时直接失败而不是卡住
+    cmd.arg("-o").arg("BatchMode=yes");
+    if let Some(port) = config.port {
+        cmd.arg("-p").arg(port.to_string());
+    }
+    cmd.arg(&config.host).arg(script);
+    cmd
+}
+
+/// 运行一条 ssh 命令，把 `input` 写进它的标准输入
+fn ssh_exchange(config: &RemoteBuildConfig, script: &str, input: &[u8]) -> Result<Output, Vec<CompileError>> {
+    let mut child = ssh_command(config, script)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| vec![super::spawn_error("ssh", e)])?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input).map_err(|e| vec![CompileError::sys(e)])?;
+    }
+    let output = child.wait_with_output().map_err(|e| vec![CompileError::sys(e)])?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(vec![CompileError::simple(format!("与 {} 的 ssh 连接失败: {}", config.host, stderr.trim()))]);
+    }
+    Ok(output)
+}
+
+impl RemoteEngine {
+    /// 强制编译时整体重传，服务器上的工作区被清理过也能恢复
+    fn previous_upload(&self, output_dir: &Path, file_stem: &str) -> Manifest {
+        if self.options.force {
+            Manifest::default()
+        } else {
+            read_manifest(output_dir, file_stem)
+        }
+    }
+
+    fn run_ssh(&self, config: &RemoteBuildConfig, build: &Build) -> Result<Output, Vec<CompileError>> {
+        let Build { job, reporter, project_dir, output_dir, file_stem, .. } = *build;
+        let workspace = workspace_id(project_dir);
+        let dir = config.remote_dir.as_deref().unwrap_or(DEFAULT_REMOTE_DIR);
+        let remote = shell_quote(&format!("{}/{}", dir.trim_start_matches("~/").trim_end_matches('/'), workspace));
+
+        let previous = self.previous_upload(output_dir, file_stem);
+        let upload = plan_upload(project_dir, output_dir, &previous, &config.target(&workspace))
+            .map_err(|e| vec![CompileError::simple(e)])?;
+        if upload.changed > 0 || !upload.deleted.is_empty() {
+            let note = format!("正在向 {1} 上传 {0} 个改动的文件", upload.changed, config.host);
+            reporter.note(CompilePhase::Starting, &note);
+            let mut script = format!("mkdir -p {} && cd {}", remote, remote);
+            if !upload.deleted.is_empty() {
+                let deleted: Vec<String> = upload.deleted.iter().map(|name| shell_quote(name)).collect();
+                script.push_str(&format!(" && rm -f -- {}", deleted.join(" ")));
+            }
+            script.push_str(" && tar xzf -");
+            ssh_exchange(config, &script, &upload.archive)?;
+        }
+        write_manifest(output_dir, file_stem, &upload.manifest);
+
+        // 编译输出经 ssh 实时传回，与本地引擎一样转成进度事件，也能被取消和超时终止
+        let command = build_command(config.engine(), &self.options, &build.main, REMOTE_OUTPUT_DIR);
+        let script = format!("cd {} && {}", remote, command);
+        let output = run_engine(job, reporter, ssh_command(config, &script), output_dir, file_stem)?;
+
+        reporter.note(CompilePhase::WritingPdf, &format!("正在从 {} 下载编译结果", config.host));
+        let extension = self.options.output_extension().map_err(|e| vec![CompileError::simple(e)])?;
+        let names = artifact_names(file_stem, extension);
+        let files: Vec<String> = names.iter().map(|name| shell_quote(name)).collect();
+        let script = format!(
+            "cd {} && pwd >&2 && cd {} && set -- && for f in {}; do [ -f \"$f\" ] && set -- \"$@\" \"$f\"; done; \
+             [ $# -gt 0 ] && tar czf - \"$@\"; true",
+            remote,
+            REMOTE_OUTPUT_DIR,
+            files.join(" ")
+        );
+        let download = ssh_exchange(config, &script, &[])?;
+        let mut artifacts = Artifacts {
+            remote_path: String::from_utf8_lossy(&download.stderr).lines().last().map(str::to_string),
+            ..Artifacts::default()
+        };
+        if !download.stdout.is_empty() {
+            unpack_artifacts(download.stdout.as_slice(), output_dir, &names, &mut artifacts)
+                .map_err(|e| vec![CompileError::simple(e)])?;
+        }
+        if let Some(remote_path) = &artifacts.remote_path {
+            localize_paths(output_dir, file_stem, remote_path, project_dir);
+        }
+        Ok(output)
+    }
+
+    /// 服务器丢失了工作区（返回 409）时清空清单、整体重传一次
+    fn run_http(&self, config: &RemoteBuildConfig, build: &Build) -> Result<Output, Vec<CompileError>> {
+        let Build { job, reporter, project_dir, output_dir, file_stem, .. } = *build;
+        let workspace = workspace_id(project_dir);
+        let url = format!("{}/build", config.host.trim_end_matches('/'));
+        let timeout =
+            job.deadline.map_or(DEFAULT_HTTP_TIMEOUT, |deadline| deadline.saturating_duration_since(Instant::now()));
+        let agent: Agent = Agent::config_builder().timeout_global(Some(timeout)).build().into();
+        let extension = self.options.output_extension().map_err(|e| vec![CompileError::simple(e)])?;
+        let names = artifact_names(file_stem, extension);
+        let token = config.token().map_err(|e| vec![CompileError::simple(e)])?;
+        let engine = serde_json::to_value(config.engine()).map_err(|e| vec![CompileError::simple(e.to_string())])?;
+        let options = serde_json::json!({
+            "extra_args": self.options.extra_args,
+            "shell_escape": self.options.shell_escape,
+            "outfmt": self.options.outfmt,
+            "draft": self.options.mode == CompileMode::Draft,
+        });
+
+        let mut previous = self.previous_upload(output_dir, file_stem);
+        for attempt in 0..2 {
+            let upload = plan_upload(project_dir, output_dir, &previous, &config.target(&workspace))
+                .map_err(|e| vec![CompileError::simple(e)])?;
+            let note = format!("正在向 {1} 上传 {0} 个改动的文件", upload.changed, config.host);
+            reporter.note(CompilePhase::Starting, &note);
+            let mut request = agent
+                .post(&url)
+                .header("Content-Type", "application/gzip")
+                .header("X-MyMD-Workspace", &workspace)
+                .header("X-MyMD-Main", &build.main)
+                .header("X-MyMD-Engine", engine.as_str().unwrap_or("tectonic"))
+                .header("X-MyMD-Deleted", &serde_json::to_string(&upload.deleted).unwrap_or_default())
+                .header("X-MyMD-Options", &options.to_string());
+            if let Some(token) = &token {
+                request = request.header("Authorization", &format!("Bearer {}", token));
+            }
+            let mut response = match request.send(&upload.archive[..]) {
+                Ok(response) => response,
+                Err(ureq::Error::StatusCode(409)) if attempt == 0 => {
+                    previous = Manifest::default();
+                    continue;
+                }
+                Err(e) => return Err(vec![CompileError::simple(format!("远程编译请求失败: {}", e))]),
+            };
+            if job.is_cancelled() {
+                return Err(vec![CompileError::simple(job.cancel_message())]);
+            }
+            write_manifest(output_dir, file_stem, &upload.manifest);
+            reporter.note(CompilePhase::WritingPdf, &format!("正在从 {} 下载编译结果", config.host));
+            let mut artifacts = Artifacts {
+                remote_path: response
+                    .headers()
+                    .get("X-MyMD-Workspace-Path")
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string),
+                ..Artifacts::default()
+            };
+            let reader = response.body_mut().with_config().limit(MAX_ARTIFACTS_SIZE).reader();
+            unpack_artifacts(reader, output_dir, &names, &mut artifacts).map_err(|e| vec![CompileError::simple(e)])?;
+            if let Some(remote_path) = &artifacts.remote_path {
+                localize_paths(output_dir, file_stem, remote_path, project_dir);
+            }
+            for line in String::from_utf8_lossy(&artifacts.output).lines() {
+                reporter.line(line);
+            }
+            return Ok(Output {
+                status: exit_status(artifacts.exit_code.unwrap_or(1)),
+                stdout: artifacts.output,
+                stderr: Vec::new(),
+            });
+        }
+        Err(vec![CompileError::simple("远程编译服务器拒绝了上传的工作区")])
+    }
+}
+
+impl LatexEngine for RemoteEngine {
+    fn name(&self) -> &'static str {
+        "remote"
+    }
+
+    fn options(&self) -> &CompileOptions {
+        &self.options
+    }
+
+    fn run_pass(
+        &self,
+        job: &CompileJob,
+        reporter: &Arc<ProgressReporter>,
+        source: &Path,
+        output_dir: &Path,
+        file_stem: &str,
+        _rerun: bool,
+    ) -> Result<Output, Vec<CompileError>> {
+        let config = self.config()?;
+        job.check_deadline()?;
+        // 上传根文档所在目录；`\input{../x}` 之类引用目录外的文件不会被上传
+        let build = Build {
+            job,
+            reporter,
+            project_dir: source.parent().unwrap_or(Path::new(".")),
+            main: source.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default(),
+            output_dir,
+            file_stem,
+        };
+        match config.transport {
+            RemoteTransport::Ssh => self.run_ssh(config, &build),
+            RemoteTransport::Http => self.run_http(config, &build),
+        }
+    }
+
+    /// 服务器上的引擎自己完成多遍编译和参考文献
+    fn handles_bibliography(&self) -> bool {
+        true
+    }
+
+    fn parse_log(&self, log: &str, source_dir: &Path) -> Vec<CompileError> {
+        let mut diagnostics = log_parser::parse_log(log, source_dir);
+        let tectonic = self.config.as_ref().is_none_or(|config| config.engine() == EngineKind::Tectonic);
+        packages::annotate_missing_packages(&mut diagnostics, tectonic);
+        diagnostics
+    }
+}
@@ -0,0 +1,87 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tauri::command;
+
+/// 系统临时目录下的父目录，每个未保存文档在其中有自己的子目录
+const WORKSPACES_DIR: &str = "tauri_latex_build";
+/// 前端没有传 id 时（旧版本或一次性的 Save 前编译）共用的子目录
+const SHARED_WORKSPACE: &str = "shared";
+/// 启动时删除超过这么久没有编译过的工作区；留出余量给同时运行的其他实例
+const STALE_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// 只接受 `create_untitled_workspace` 生成的那类 id，防止 `..` 之类的路径逃出临时目录
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 64 && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// 未保存文档 `id` 的临时工作区，编译会把 `input.tex`/`input.md` 和产物写在这里。
+/// 目录不存在时由调用方创建。
+pub fn workspace_dir(id: Option<&str>) -> Result<PathBuf, String> {
+    let id = id.unwrap_or(SHARED_WORKSPACE);
+    if !is_valid_id(id) {
+        return Err(format!("无效的临时工作区 id: {}", id));
+    }
+    Ok(std::env::temp_dir().join(WORKSPACES_DIR).join(id))
+}
+
+/// 为新建的未保存文档分配 id 并创建其临时工作区，避免多个 Untitled 标签页互相覆盖
+/// `input.tex` 和 PDF。id 同时用作会话中该缓冲区的 id。
+#[command]
+pub fn create_untitled_workspace() -> Result<String, String> {
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or_default();
+    let id = format!("untitled-{}-{}-{}", millis, std::process::id(), NEXT_ID.fetch_add(1, Ordering::SeqCst) + 1);
+    let dir = workspace_dir(Some(&id))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("无法创建目录: {}", e))?;
+    Ok(id)
+}
+
+/// 标签页关闭（或文档另存为正式文件）后删除其临时工作区；不存在时什么也不做。
+#[command]
+pub fn discard_untitled_workspace(id: String) -> Result<(), String> {
+    let dir = workspace_dir(Some(&id))?;
+    match fs::remove_dir_all(&dir) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("无法删除目录: {}", e)),
+    }
+}
+
+/// 目录本身与其中文件的最晚修改时间：原地重写 `input.tex` 不会更新目录的 mtime
+fn last_modified(dir: &Path) -> Option<SystemTime> {
+    let entries = fs::read_dir(dir).ok()?.flatten().filter_map(|entry| entry.metadata().ok()?.modified().ok());
+    entries.chain(fs::metadata(dir).ok()?.modified().ok()).max()
+}
+
+/// 启动时清理上次运行遗留的工作区：超过 `STALE_AFTER` 未修改的子目录，
+/// 以及旧版本直接写在父目录里的 `input.tex`/`input.pdf` 等文件。失败只记日志。
+pub fn cleanup_stale_workspaces() {
+    let root = std::env::temp_dir().join(WORKSPACES_DIR);
+    let Ok(entries) = fs::read_dir(&root) else {
+        return;
+    };
+    let now = SystemTime::now();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let result = if metadata.is_dir() {
+            let modified = last_modified(&path).unwrap_or(now);
+            if !now.duration_since(modified).is_ok_and(|age| age > STALE_AFTER) {
+                continue;
+            }
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        match result {
+            Ok(()) => tracing::info!("Removed stale untitled workspace {:?}", path),
+            Err(e) => tracing::warn!("Failed to remove stale untitled workspace {:?}: {}", path, e),
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use tauri::{command, AppHandle, Emitter, Manager, State, Window};
+
+use super::engine::{CompileOptions, EngineKind};
+use super::progress::{CompilePhase, ProgressReporter};
+use super::{build_document, compile_timeout, resolve_engine, CompileError, CompileJobs, CompileQueue};
+use crate::latex::dependencies::dependency_graph;
+use crate::latex::root::canonical;
+use crate::scope::WindowScope;
+
+/// 每次自动编译结束后发出的事件
+pub const WATCH_BUILD_EVENT: &str = "watch-build";
+
+/// 保存时编辑器往往连续写入多次，等文件安静下来再编译
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Clone, Serialize)]
+pub struct WatchBuildEvent {
+    root: String,
+    success: bool,
+    pdf_path: Option<String>,
+    diagnostics: Vec<CompileError>,
+}
+
+/// 处于监听编译模式的根文档，按（窗口标签，根文档）区分，结果只发给开启它的窗口；
+/// 移除 watcher 即关闭通道，后台线程随之退出。
+#[derive(Default)]
+pub struct WatchBuilds {
+    builds: Mutex<HashMap<(String, String), RecommendedWatcher>>,
+}
+
+impl WatchBuilds {
+    /// 窗口关闭时停止它开启的所有监听编译
+    pub fn remove_window(&self, label: &str) {
+        self.builds.lock().unwrap().retain(|(owner, _), _| owner != label);
+    }
+}
+
+/// 根文档及其引用的 .tex、图片和 .bib，任何一个变化都要重新编译
+fn dependency_set(root: &Path) -> HashSet<PathBuf> {
+    let mut deps: HashSet<PathBuf> = dependency_graph(root).existing_files().map(|p| canonical(&p)).collect();
+    deps.insert(canonical(root));
+    deps
+}
+
+#[command]
+pub fn start_watch_build(
+    app: AppHandle,
+    window: Window,
+    scope: WindowScope,
+    builds: State<'_, WatchBuilds>,
+    root_tex: String,
+    engine: Option<EngineKind>,
+    options: Option<CompileOptions>,
+) -> Result<(), String> {
+    scope.check(&root_tex)?;
+    let root = canonical(Path::new(&root_tex));
+    if !root.is_file() {
+        return Err(format!("无法读取文件: {}", root_tex));
+    }
+    let root_dir = root.parent().ok_or("无效的源文件路径")?.to_path_buf();
+
+    let key = (window.label().to_string(), root_tex.clone());
+    let mut active = builds.builds.lock().unwrap();
+    if active.contains_key(&key) {
+        return Ok(());
+    }
+
+    let (tx, rx) = mpsc::channel::<notify::Result<notify::Event>>();
+    let mut watcher = notify::recommended_watcher(tx).map_err(|e| format!("无法创建文件监听: {}", e))?;
+    watcher
+        .watch(&root_dir, RecursiveMode::Recursive)
+        .map_err(|e| format!("无法监听目录: {}", e))?;
+    // 位于根目录之外的依赖（如 ../shared/macros.tex）单独监听其所在目录
+    for dep in dependency_set(&root) {
+        if let Some(dir) = dep.parent().filter(|dir| !dir.starts_with(&root_dir)) {
+            let _ = watcher.watch(dir, RecursiveMode::NonRecursive);
+        }
+    }
+
+    // 两个窗口监听同一根文档时任务 id 不能相同，否则取消会互相影响
+    let job_id = format!("watch:{}:{}", key.0, root_tex);
+    let event_root = root_tex;
+    let label = key.0.clone();
+    let engine = resolve_engine(&app, engine, Some(&root), options.unwrap_or_default());
+    thread::spawn(move || {
+        let mut deps = dependency_set(&root);
+        while let Ok(event) = rx.recv() {
+            let touches_dependency = event
+                .map(|event| event.paths.iter().any(|path| deps.contains(&canonical(path))))
+                .unwrap_or(false);
+            if !touches_dependency {
+                continue;
+            }
+
+            // 去抖：直到 DEBOUNCE 时间内没有新事件才开始编译
+            loop {
+                match rx.recv_timeout(DEBOUNCE) {
+                    Ok(_) => continue,
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+
+            let jobs = app.state::<CompileJobs>();
+            let (job_id, job) = jobs.register(Some(job_id.clone()), compile_timeout(&app));
+            let reporter = Arc::new(ProgressReporter::new(app.clone(), job_id.clone()));
+            reporter.phase(CompilePhase::Starting);
+            let result = app
+                .state::<CompileQueue>()
+                .acquire(&label, &root, &job)
+                .and_then(|_slot| build_document(&job, &reporter, engine.as_ref(), &root));
+            reporter.phase(CompilePhase::Finished);
+            jobs.finish(&job_id);
+
+            let payload = match result.and_then(|result| result.deliver(&app, &scope, true)) {
+                Ok(result) => WatchBuildEvent {
+                    root: event_root.clone(),
+                    success: true,
+                    pdf_path: Some(result.pdf_path),
+                    diagnostics: result.diagnostics,
+                },
+                Err(errors) => WatchBuildEvent {
+                    root: event_root.clone(),
+                    success: false,
+                    pdf_path: None,
+                    diagnostics: errors,
+                },
+            };
+            let _ = app.emit_to(label.as_str(), WATCH_BUILD_EVENT, payload);
+
+            // 编辑可能新增或删除了 \input，重新计算依赖
+            deps = dependency_set(&root);
+        }
+    });
+
+    active.insert(key, watcher);
+    Ok(())
+}
+
+#[command]
+pub fn stop_watch_build(window: Window, builds: State<'_, WatchBuilds>, root_tex: String) -> Result<(), String> {
+    builds
+        .builds
+        .lock()
+        .unwrap()
+        .remove(&(window.label().to_string(), root_tex.clone()))
+        .map(|_| ())
+        .ok_or_else(|| format!("没有在监视编译: {}", root_tex))
+}
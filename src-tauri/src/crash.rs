@@ -0,0 +1,184 @@
+use std::backtrace::Backtrace;
+use std::collections::VecDeque;
+use std::fs;
+use std::panic::{self, PanicHookInfo};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use tauri::ipc::Invoke;
+use tauri::{command, AppHandle, Manager, Runtime, State};
+use ureq::Agent;
+
+use crate::atomic::{unix_millis, write_atomic};
+use crate::settings::SettingsStore;
+
+/// `<app data>/crashes/<millis>.json`，每次 panic 一个文件
+const CRASH_DIR: &str = "crashes";
+/// 为报告保留的命令名；从不记录参数
+const MAX_RECENT_COMMANDS: usize = 50;
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+static RECENT_COMMANDS: Mutex<VecDeque<RecentCommand>> = Mutex::new(VecDeque::new());
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RecentCommand {
+    at: u64,
+    command: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CrashReport {
+    id: String,
+    /// Unix 毫秒
+    timestamp: u64,
+    app_version: String,
+    os: String,
+    arch: String,
+    thread: String,
+    message: String,
+    location: Option<String>,
+    backtrace: String,
+    /// 最旧的在前
+    recent_commands: Vec<RecentCommand>,
+    #[serde(default)]
+    submitted: bool,
+}
+
+#[derive(Serialize)]
+pub struct CrashSummary {
+    id: String,
+    timestamp: u64,
+    message: String,
+    submitted: bool,
+}
+
+fn record_command(command: &str) {
+    let mut recent = RECENT_COMMANDS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if recent.len() == MAX_RECENT_COMMANDS {
+        recent.pop_front();
+    }
+    recent.push_back(RecentCommand { at: unix_millis(SystemTime::now()), command: command.to_string() });
+}
+
+/// 包装 invoke 处理函数，每个 IPC 命令运行前都会被记录
+pub fn recording_commands<R: Runtime>(
+    handler: impl Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static {
+    move |invoke| {
+        record_command(invoke.message.command());
+        handler(invoke)
+    }
+}
+
+fn crash_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let base = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(base.join(CRASH_DIR))
+}
+
+fn panic_message(info: &PanicHookInfo) -> String {
+    if let Some(message) = info.payload().downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = info.payload().downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    }
+}
+
+fn write_report(dir: &Path, report: &CrashReport) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("无法创建目录: {}", e))?;
+    let content = serde_json::to_string_pretty(report).map_err(|e| e.to_string())?;
+    write_atomic(&dir.join(format!("{}.json", report.id)), content.as_bytes())
+        .map_err(|e| format!("无法写入文件: {}", e))?;
+    Ok(())
+}
+
+/// 每次 panic 都写一份崩溃报告，然后运行默认的钩子。报告一直留在磁盘上，直到用户选择提交
+pub fn install(app: &AppHandle) {
+    let Ok(dir) = crash_dir(app) else {
+        return;
+    };
+    let app_version = app.package_info().version.to_string();
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let timestamp = unix_millis(SystemTime::now());
+        let recent_commands = match RECENT_COMMANDS.try_lock() {
+            Ok(recent) => recent.iter().cloned().collect(),
+            Err(_) => Vec::new(),
+        };
+        let report = CrashReport {
+            id: timestamp.to_string(),
+            timestamp,
+            app_version: app_version.clone(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            thread: std::thread::current().name().unwrap_or("<unnamed>").to_string(),
+            message: panic_message(info),
+            location: info.location().map(|location| location.to_string()),
+            backtrace: Backtrace::force_capture().to_string(),
+            recent_commands,
+            submitted: false,
+        };
+        tracing::error!("Panic in {}: {}", report.thread, report.message);
+        if let Err(e) = write_report(&dir, &report) {
+            tracing::error!("Failed to write crash report: {}", e);
+        }
+        default_hook(info);
+    }));
+}
+
+fn read_report(dir: &Path, id: &str) -> Result<CrashReport, String> {
+    // id 是时间戳；其他内容可能指向目录外的文件
+    if id.is_empty() || !id.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("未知的崩溃报告: {}", id));
+    }
+    let content = fs::read_to_string(dir.join(format!("{}.json", id))).map_err(|e| format!("无法读取文件: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
+
+/// 之前的 panic 写下的报告，最新的在前
+#[command]
+pub fn list_crash_reports(app: AppHandle) -> Result<Vec<CrashSummary>, String> {
+    let dir = crash_dir(&app)?;
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Ok(Vec::new());
+    };
+    let mut reports: Vec<CrashSummary> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let id = entry.path().file_stem()?.to_string_lossy().to_string();
+            let report = read_report(&dir, &id).ok()?;
+            Some(CrashSummary {
+                id: report.id,
+                timestamp: report.timestamp,
+                message: report.message,
+                submitted: report.submitted,
+            })
+        })
+        .collect();
+    reports.sort_by_key(|report| std::cmp::Reverse(report.timestamp));
+    Ok(reports)
+}
+
+/// 把一份报告上传到设置中的 `crash_report_url`。只在用户要求时运行；之后报告会标记为已提交
+#[command]
+pub async fn submit_crash_report(app: AppHandle, settings: State<'_, SettingsStore>, id: String) -> Result<(), String> {
+    let url = settings.get(&app).crash_report_url.ok_or("没有配置崩溃报告的提交地址")?;
+    let dir = crash_dir(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut report = read_report(&dir, &id)?;
+        let body = serde_json::to_string(&report).map_err(|e| e.to_string())?;
+        let agent: Agent = Agent::config_builder().timeout_global(Some(UPLOAD_TIMEOUT)).build().into();
+        agent
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .send(body)
+            .map_err(|e| format!("无法提交崩溃报告: {}", e))?;
+        report.submitted = true;
+        write_report(&dir, &report)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
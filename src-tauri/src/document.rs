@@ -0,0 +1,401 @@
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use base64::Engine;
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{command, AppHandle};
+
+use crate::atomic::{unix_millis, write_atomic};
+use crate::history;
+use crate::scope::WindowScope;
+use crate::workspace::looks_binary;
+
+/// 超过此大小的文件不整体读入编辑器，需按 offset/length 分段读取
+const MAX_TEXT_SIZE: u64 = 8 * 1024 * 1024;
+/// 分段读取未指定 length 时每段的大小
+const DEFAULT_CHUNK_SIZE: u64 = 1024 * 1024;
+/// 以 base64 读取（图片、PDF 预览）的上限
+const MAX_BASE64_SIZE: u64 = 64 * 1024 * 1024;
+/// 判断二进制与编码时只看文件开头这么多字节
+const SNIFF_SIZE: u64 = 64 * 1024;
+
+/// 内容哈希作为版本号：只 touch 不改内容不会被当作冲突，重启应用后依然有效。
+pub fn content_version(bytes: &[u8]) -> String {
+    format_version(&Sha256::digest(bytes))
+}
+
+fn format_version(digest: &[u8]) -> String {
+    digest.iter().take(16).map(|b| format!("{:02x}", b)).collect()
+}
+
+#[derive(Serialize)]
+pub struct ReadResult {
+    /// 文本内容；`base64` 为 true 时是文件字节的 base64 编码
+    content: String,
+    /// 交还给 `save_file` 的版本号（整个文件的哈希），用于检测外部修改
+    version: String,
+    mtime: u64,
+    /// 整个文件的字节数
+    size: u64,
+    base64: bool,
+    /// 检测到的编码（encoding_rs 名称，如 UTF-8、GBK、windows-1252），保存时交还给 `save_file`
+    encoding: &'static str,
+    /// 原文件的换行符；`content` 中统一为 LF，保存时再转换回去
+    line_ending: LineEnding,
+    /// 原文件是否以 BOM 开头；`content` 中已去掉 BOM
+    bom: bool,
+    /// 分段读取时下一段的起始偏移；已读到文件末尾时为 None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_offset: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+    /// 同时有 LF 和 CRLF；保存时按多数的一种写回
+    Mixed,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ReadError {
+    /// 二进制文件（图片、PDF 等）；可用 `base64: true` 重新读取
+    Binary { size: u64 },
+    /// 文件过大，需要分段读取或以 base64 读取
+    TooLarge { size: u64, limit: u64 },
+    Io { message: String },
+}
+
+impl ReadError {
+    fn io(e: impl std::fmt::Display) -> Self {
+        ReadError::Io { message: format!("无法读取文件: {}", e) }
+    }
+}
+
+#[derive(Serialize)]
+pub struct FileStat {
+    size: u64,
+    mtime: u64,
+    is_binary: bool,
+    /// 根据文件开头判断的编码；二进制文件为 None
+    encoding: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+pub struct SaveResult {
+    /// 保存后文件的修改时间（毫秒时间戳）
+    mtime: u64,
+    version: String,
+}
+
+/// 保存时的文件格式，各项为 None 时的行为见 `save_file`
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct SaveOptions {
+    encoding: Option<String>,
+    line_ending: Option<LineEnding>,
+    bom: Option<bool>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SaveError {
+    /// 文件在打开之后被其他程序修改过；附带磁盘上的当前内容供前端比较或合并
+    Conflict { disk_content: String, disk_version: String },
+    /// 内容中有原编码无法表示的字符；可改用 UTF-8 保存
+    Unencodable { encoding: String },
+    Io { message: String },
+}
+
+fn mtime_of(metadata: &fs::Metadata) -> u64 {
+    metadata.modified().map(unix_millis).unwrap_or(0)
+}
+
+fn read_prefix(file: &mut File, limit: u64) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    file.take(limit).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// 流式计算整个文件的版本号，分段读取大文件时不必整体读入内存
+fn file_version(file: &mut File) -> io::Result<String> {
+    file.seek(SeekFrom::Start(0))?;
+    let mut hasher = Sha256::new();
+    io::copy(file, &mut hasher)?;
+    Ok(format_version(&hasher.finalize()))
+}
+
+/// 先看 BOM，再看是否为合法 UTF-8（开头截断在多字节字符中间也算），
+/// 都不是时交给 chardetng 猜测（Latin-1、GBK、Shift_JIS 等旧文件）
+pub fn detect_encoding(bytes: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return encoding;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(_) => return UTF_8,
+        Err(e) if e.error_len().is_none() => return UTF_8,
+        Err(_) => {}
+    }
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    detector.guess(None, true)
+}
+
+fn decode(bytes: &[u8], encoding: &'static Encoding) -> Option<String> {
+    let (text, had_errors) = encoding.decode_without_bom_handling(bytes);
+    (!had_errors).then(|| text.into_owned())
+}
+
+/// 编辑器里的文本：去掉 BOM，换行统一为 LF
+struct EditorText {
+    text: String,
+    line_ending: LineEnding,
+    bom: bool,
+}
+
+fn count_line_endings(text: &str) -> (usize, usize) {
+    let crlf = text.matches("\r\n").count();
+    (text.matches('\n').count() - crlf, crlf)
+}
+
+impl EditorText {
+    fn from_disk(text: &str) -> Self {
+        let (text, bom) = match text.strip_prefix('\u{FEFF}') {
+            Some(rest) => (rest, true),
+            None => (text, false),
+        };
+        let line_ending = match count_line_endings(text) {
+            (_, 0) => LineEnding::Lf,
+            (0, _) => LineEnding::Crlf,
+            _ => LineEnding::Mixed,
+        };
+        Self { text: text.replace("\r\n", "\n"), line_ending, bom }
+    }
+}
+
+/// 磁盘上现有文件的换行符（混合时取多数）与 BOM，用于保存时保持原样
+fn disk_layout(bytes: &[u8]) -> (LineEnding, bool) {
+    let text = detect_encoding(bytes).decode_without_bom_handling(bytes).0;
+    let (lf, crlf) = count_line_endings(&text);
+    let line_ending = if crlf > lf { LineEnding::Crlf } else { LineEnding::Lf };
+    (line_ending, text.starts_with('\u{FEFF}'))
+}
+
+/// encoding_rs 不提供 UTF-16 编码器，需要手动处理
+fn encode(content: &str, encoding: &'static Encoding) -> Option<Vec<u8>> {
+    if encoding == UTF_16LE {
+        return Some(content.encode_utf16().flat_map(u16::to_le_bytes).collect());
+    }
+    if encoding == UTF_16BE {
+        return Some(content.encode_utf16().flat_map(u16::to_be_bytes).collect());
+    }
+    let (bytes, _, unmappable) = encoding.encode(content);
+    (!unmappable).then(|| bytes.into_owned())
+}
+
+/// UTF-16 文本里全是 NUL，不能按 NUL 判定为二进制
+fn is_binary(sample: &[u8]) -> bool {
+    looks_binary(sample) && Encoding::for_bom(sample).is_none_or(|(encoding, _)| encoding == UTF_8)
+}
+
+/// 读取 `[offset, offset + length)` 附近的一段文本：起点跳过被截断的 UTF-8 字符，
+/// 终点尽量落在换行之后，保证每段都是完整的行。按 UTF-8 与单字节/双字节编码切分，
+/// 不支持分段读取 UTF-16。
+fn read_chunk(
+    file: &mut File,
+    size: u64,
+    offset: u64,
+    length: u64,
+    encoding: &'static Encoding,
+) -> Result<(String, Option<u64>), ReadError> {
+    file.seek(SeekFrom::Start(offset)).map_err(ReadError::io)?;
+    let bytes = read_prefix(file, length).map_err(ReadError::io)?;
+    if offset == 0 && is_binary(&bytes) {
+        return Err(ReadError::Binary { size });
+    }
+    let utf8 = encoding == UTF_8;
+    let start = if utf8 { bytes.iter().take(3).take_while(|b| (0x80..0xC0).contains(*b)).count() } else { 0 };
+    let end_offset = offset + bytes.len() as u64;
+    let end = if end_offset >= size {
+        bytes.len()
+    } else if let Some(newline) = bytes[start..].iter().rposition(|b| *b == b'\n') {
+        start + newline + 1
+    } else if !utf8 {
+        bytes.len()
+    } else {
+        // 一整段都没有换行：退到最后一个完整字符
+        let mut end = bytes.len();
+        while end > start && (0x80..0xC0).contains(&bytes[end - 1]) {
+            end -= 1;
+        }
+        if end > start && bytes[end - 1] >= 0xC0 {
+            end -= 1;
+        }
+        end
+    };
+    let next_offset = offset + end as u64;
+    let content = encoding.decode_without_bom_handling(&bytes[start..end]).0.into_owned();
+    Ok((content, (next_offset < size).then_some(next_offset)))
+}
+
+/// 文本文件整体读入；二进制或过大的文件返回对应的错误。
+/// `base64` 为 true 时按字节读取并以 base64 返回（用于图片等预览）；
+/// 指定 `offset` / `length` 时分段读取，适合大日志文件，按 `next_offset` 继续读下一段。
+#[command]
+pub fn read_file(
+    scope: WindowScope,
+    path: String,
+    base64: Option<bool>,
+    offset: Option<u64>,
+    length: Option<u64>,
+) -> Result<ReadResult, ReadError> {
+    scope.check(&path).map_err(|message| ReadError::Io { message })?;
+    let mut file = File::open(&path).map_err(ReadError::io)?;
+    let metadata = file.metadata().map_err(ReadError::io)?;
+    let size = metadata.len();
+    let mtime = mtime_of(&metadata);
+
+    if base64.unwrap_or(false) {
+        if size > MAX_BASE64_SIZE {
+            return Err(ReadError::TooLarge { size, limit: MAX_BASE64_SIZE });
+        }
+        let bytes = read_prefix(&mut file, MAX_BASE64_SIZE).map_err(ReadError::io)?;
+        let content = base64::engine::general_purpose::STANDARD.encode(&bytes);
+        let version = content_version(&bytes);
+        let encoding = detect_encoding(&bytes).name();
+        return Ok(ReadResult {
+            content,
+            version,
+            mtime,
+            size,
+            base64: true,
+            encoding,
+            line_ending: LineEnding::Lf,
+            bom: false,
+            next_offset: None,
+        });
+    }
+
+    if offset.is_some() || length.is_some() {
+        // 至少容得下一个完整的 UTF-8 字符，否则无法前进
+        let length = length.unwrap_or(DEFAULT_CHUNK_SIZE).clamp(4, MAX_TEXT_SIZE);
+        // 编码按文件开头判断，各段保持一致
+        let encoding = detect_encoding(&read_prefix(&mut file, SNIFF_SIZE).map_err(ReadError::io)?);
+        let (content, next_offset) = read_chunk(&mut file, size, offset.unwrap_or(0), length, encoding)?;
+        let version = file_version(&mut file).map_err(ReadError::io)?;
+        // 分段内容原样返回，只用于查看
+        let layout = EditorText::from_disk(&content);
+        return Ok(ReadResult {
+            content,
+            version,
+            mtime,
+            size,
+            base64: false,
+            encoding: encoding.name(),
+            line_ending: layout.line_ending,
+            bom: layout.bom,
+            next_offset,
+        });
+    }
+
+    if size > MAX_TEXT_SIZE {
+        return Err(ReadError::TooLarge { size, limit: MAX_TEXT_SIZE });
+    }
+    let bytes = read_prefix(&mut file, MAX_TEXT_SIZE).map_err(ReadError::io)?;
+    if is_binary(&bytes) {
+        return Err(ReadError::Binary { size });
+    }
+    let version = content_version(&bytes);
+    let encoding = detect_encoding(&bytes);
+    let content = decode(&bytes, encoding)
+        .ok_or_else(|| ReadError::io(format!("无法按 {} 解码", encoding.name())))?;
+    let EditorText { text, line_ending, bom } = EditorText::from_disk(&content);
+    Ok(ReadResult {
+        content: text,
+        version,
+        mtime,
+        size,
+        base64: false,
+        encoding: encoding.name(),
+        line_ending,
+        bom,
+        next_offset: None,
+    })
+}
+
+/// 打开文件前先查看大小和类型，决定用编辑器、预览还是分段查看
+#[command]
+pub fn stat_file(scope: WindowScope, path: String) -> Result<FileStat, String> {
+    scope.check(&path)?;
+    let mut file = File::open(&path).map_err(|e| format!("无法读取文件: {}", e))?;
+    let metadata = file.metadata().map_err(|e| format!("无法读取文件: {}", e))?;
+    let sample = read_prefix(&mut file, SNIFF_SIZE).map_err(|e| format!("无法读取文件: {}", e))?;
+    let is_binary = is_binary(&sample);
+    Ok(FileStat {
+        size: metadata.len(),
+        mtime: mtime_of(&metadata),
+        is_binary,
+        encoding: (!is_binary).then(|| detect_encoding(&sample).name()),
+    })
+}
+
+/// `expected_version` 为 None 时强制覆盖（新建文件或用户确认覆盖）。
+/// `options.encoding` 为 `read_file` 返回的编码，按原编码写回；为 None 时保存为 UTF-8。
+/// `line_ending` / `bom` 为 None 时沿用磁盘上现有文件的换行符和 BOM（新文件为 LF、无 BOM），
+/// 避免在 Windows 上因整个文件换行符改变而污染 git diff。
+/// 保存成功后在历史目录中记录一个版本。
+#[command]
+pub fn save_file(
+    app: AppHandle,
+    scope: WindowScope,
+    path: String,
+    content: String,
+    expected_version: Option<String>,
+    options: Option<SaveOptions>,
+) -> Result<SaveResult, SaveError> {
+    let SaveOptions { encoding, line_ending, bom } = options.unwrap_or_default();
+    scope.check(&path).map_err(|message| SaveError::Io { message })?;
+    let target = Path::new(&path);
+    let encoding = match encoding {
+        Some(label) => Encoding::for_label(label.as_bytes())
+            .ok_or_else(|| SaveError::Io { message: format!("未知的编码: {}", label) })?,
+        None => UTF_8,
+    };
+    let disk_bytes = fs::read(target).ok();
+    if let (Some(expected), Some(disk_bytes)) = (expected_version, &disk_bytes) {
+        let disk_version = content_version(disk_bytes);
+        if disk_version != expected {
+            let disk_text = detect_encoding(disk_bytes).decode_without_bom_handling(disk_bytes).0;
+            return Err(SaveError::Conflict { disk_content: EditorText::from_disk(&disk_text).text, disk_version });
+        }
+    }
+
+    let (disk_line_ending, disk_bom) = disk_bytes.as_deref().map_or((LineEnding::Lf, false), disk_layout);
+    let line_ending = line_ending.filter(|e| *e != LineEnding::Mixed).unwrap_or(disk_line_ending);
+    // 只有 Unicode 编码能写 BOM
+    let bom = bom.unwrap_or(disk_bom) && (encoding == UTF_8 || encoding == UTF_16LE || encoding == UTF_16BE);
+    let mut text = content.strip_prefix('\u{FEFF}').unwrap_or(&content).replace("\r\n", "\n");
+    if line_ending == LineEnding::Crlf {
+        text = text.replace('\n', "\r\n");
+    }
+    if bom {
+        text.insert(0, '\u{FEFF}');
+    }
+    let bytes = encode(&text, encoding)
+        .ok_or_else(|| SaveError::Unencodable { encoding: encoding.name().to_string() })?;
+
+    let modified = write_atomic(target, &bytes)
+        .map_err(|e| SaveError::Io { message: format!("无法写入文件: {}", e) })?;
+    // 历史记录失败不影响保存本身
+    let _ = history::record(&app, target, content.as_bytes());
+    Ok(SaveResult {
+        mtime: unix_millis(modified),
+        version: content_version(&bytes),
+    })
+}
@@ -0,0 +1,189 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::scope::WindowScope;
+
+static MARKDOWN_IMAGE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"!\[[^\]]*\]\(\s*<?([^)\s>]+)>?(?:\s+"[^"]*")?\s*\)"#).unwrap());
+static HTML_IMAGE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"<img[^>]*\ssrc\s*=\s*["']([^"']+)["']"#).unwrap());
+static INCLUDEGRAPHICS_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\\includegraphics\s*(?:\[[^\]]*\])?\s*\{([^}]+)\}").unwrap());
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Html,
+    Docx,
+    Epub,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Html => "html",
+            ExportFormat::Docx => "docx",
+            ExportFormat::Epub => "epub",
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct ExportOptions {
+    /// 用户选择的目录；导出的文件及其资源放在这里
+    pub output_dir: String,
+    /// 复制到 HTML/EPUB 输出旁并用 `--css` 链接的样式表
+    pub css: Vec<String>,
+    /// 把图片和 CSS 嵌入单个 HTML 文件，而不是复制它们
+    pub self_contained: bool,
+    /// DOCX 样式的参考文档（`--reference-doc`）
+    pub reference_doc: Option<String>,
+    pub template: Option<String>,
+    pub bibliography: Option<String>,
+    pub extra_args: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct ExportResult {
+    output_path: String,
+    copied_assets: Vec<String>,
+}
+
+/// 在 Markdown 或 LaTeX 源文件中找到的相对图片引用
+fn referenced_assets(content: &str) -> Vec<String> {
+    let mut assets: Vec<String> = MARKDOWN_IMAGE_RE
+        .captures_iter(content)
+        .chain(HTML_IMAGE_RE.captures_iter(content))
+        .chain(INCLUDEGRAPHICS_RE.captures_iter(content))
+        .map(|caps| caps[1].trim().to_string())
+        .filter(|path| !path.contains("://") && !path.starts_with("data:") && !Path::new(path).is_absolute())
+        .collect();
+    assets.sort();
+    assets.dedup();
+    assets
+}
+
+/// 把 `relative` 从 `source_dir` 复制到 `output_dir`，保持相对位置，导出文档中的链接仍然有效
+fn copy_asset(source_dir: &Path, output_dir: &Path, relative: &str) -> Option<PathBuf> {
+    let from = source_dir.join(relative);
+    let from = if from.exists() {
+        from
+    } else {
+        // \includegraphics 可以省略扩展名
+        ["png", "jpg", "jpeg", "svg", "pdf", "gif"]
+            .iter()
+            .map(|ext| from.with_extension(ext))
+            .find(|p| p.exists())?
+    };
+    let relative_target = from.strip_prefix(source_dir).ok()?;
+    // 即使是 `../` 引用，也从不写到输出目录之外
+    if relative_target.components().any(|c| matches!(c, std::path::Component::ParentDir)) {
+        return None;
+    }
+    let to = output_dir.join(relative_target);
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).ok()?;
+    }
+    fs::copy(&from, &to).ok()?;
+    Some(to)
+}
+
+fn export_blocking(path: &Path, format: ExportFormat, options: &ExportOptions) -> Result<ExportResult, String> {
+    if options.output_dir.trim().is_empty() {
+        return Err("没有选择输出目录".to_string());
+    }
+    let output_dir = PathBuf::from(&options.output_dir);
+    fs::create_dir_all(&output_dir).map_err(|e| format!("无法创建目录: {}", e))?;
+
+    let content = fs::read_to_string(path).map_err(|e| format!("无法读取文件: {}", e))?;
+    let source_dir = path.parent().unwrap_or(Path::new("."));
+    let stem = path.file_stem().ok_or("无法获取文件名")?.to_string_lossy().to_string();
+    let output_path = output_dir.join(format!("{}.{}", stem, format.extension()));
+
+    let input_format = match path.extension().and_then(|e| e.to_str()) {
+        Some("tex") => "latex",
+        _ => "markdown",
+    };
+
+    let mut cmd = Command::new("pandoc");
+    cmd.arg(path)
+        .arg("-f")
+        .arg(input_format)
+        .arg("-o")
+        .arg(&output_path)
+        .arg("--standalone")
+        .arg(format!("--resource-path={}", source_dir.to_string_lossy()))
+        .current_dir(source_dir);
+
+    let mut copied_assets = Vec::new();
+    if matches!(format, ExportFormat::Html | ExportFormat::Epub) {
+        for css in &options.css {
+            let css_path = Path::new(css);
+            let Some(name) = css_path.file_name() else {
+                continue;
+            };
+            let css_source = if css_path.is_absolute() { css_path.to_path_buf() } else { source_dir.join(css_path) };
+            let target = output_dir.join(name);
+            fs::copy(&css_source, &target).map_err(|e| format!("无法复制样式表 {}: {}", css, e))?;
+            cmd.arg(format!("--css={}", name.to_string_lossy()));
+            copied_assets.push(target.to_string_lossy().to_string());
+        }
+    }
+    if format == ExportFormat::Html {
+        if options.self_contained {
+            cmd.arg("--embed-resources");
+        } else {
+            // DOCX/EPUB 自己嵌入图片；HTML 需要图片就在旁边
+            copied_assets.extend(
+                referenced_assets(&content)
+                    .iter()
+                    .filter_map(|asset| copy_asset(source_dir, &output_dir, asset))
+                    .map(|p| p.to_string_lossy().to_string()),
+            );
+        }
+    }
+    if let (ExportFormat::Docx, Some(reference)) = (format, &options.reference_doc) {
+        cmd.arg(format!("--reference-doc={}", reference));
+    }
+    if let Some(template) = &options.template {
+        cmd.arg(format!("--template={}", template));
+    }
+    if let Some(bibliography) = &options.bibliography {
+        cmd.arg("--citeproc").arg(format!("--bibliography={}", bibliography));
+    }
+    cmd.args(&options.extra_args);
+
+    let output = cmd.output().map_err(|e| format!("无法运行 pandoc: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("pandoc 出错:\n{}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    Ok(ExportResult {
+        output_path: output_path.to_string_lossy().to_string(),
+        copied_assets,
+    })
+}
+
+#[command]
+pub async fn export_document(
+    scope: WindowScope,
+    path: String,
+    format: ExportFormat,
+    options: ExportOptions,
+) -> Result<ExportResult, String> {
+    scope.check(&path)?;
+    scope.check(&options.output_dir)?;
+    let inputs = [&options.reference_doc, &options.template, &options.bibliography];
+    inputs.into_iter().try_for_each(|input| scope.check_optional(input.as_ref()))?;
+    options.css.iter().try_for_each(|css| scope.check(css))?;
+    tauri::async_runtime::spawn_blocking(move || export_blocking(Path::new(&path), format, &options))
+        .await
+        .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,251 @@
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tauri::command;
+
+use crate::scope::WindowScope;
+
+/// 文件管理命令返回的错误。序列化时带 `kind` 标签，侧边栏无需解析消息就能处理重名
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FsError {
+    AlreadyExists { path: String },
+    NotFound { path: String },
+    InvalidName { name: String },
+    InvalidMove { message: String },
+    /// 程序或脚本，`open_with_default_app` 不会启动它
+    NotOpenable { path: String },
+    /// 不在本次会话打开的文件夹和文件内
+    OutsideScope { path: String },
+    Io { path: String, message: String },
+}
+
+impl FsError {
+    pub fn io(path: &Path, e: std::io::Error) -> Self {
+        let path = path.to_string_lossy().to_string();
+        match e.kind() {
+            ErrorKind::NotFound => FsError::NotFound { path },
+            ErrorKind::AlreadyExists => FsError::AlreadyExists { path },
+            _ => FsError::Io { path, message: e.to_string() },
+        }
+    }
+}
+
+fn path_string(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+fn ensure_allowed(scope: &WindowScope, path: &Path) -> Result<(), FsError> {
+    if scope.allows(path) {
+        Ok(())
+    } else {
+        Err(FsError::OutsideScope { path: path_string(path) })
+    }
+}
+
+fn ensure_exists(path: &Path) -> Result<(), FsError> {
+    if path.exists() {
+        Ok(())
+    } else {
+        Err(FsError::NotFound { path: path_string(path) })
+    }
+}
+
+fn ensure_free(path: &Path) -> Result<(), FsError> {
+    if path.exists() {
+        Err(FsError::AlreadyExists { path: path_string(path) })
+    } else {
+        Ok(())
+    }
+}
+
+/// “默认应用”就是运行它们的扩展名
+const EXECUTABLE_EXTENSIONS: &[&str] =
+    &["exe", "com", "bat", "cmd", "msi", "ps1", "vbs", "scr", "app", "command", "sh", "desktop", "jar"];
+
+fn validate_name(name: &str) -> Result<(), FsError> {
+    let invalid = name.is_empty()
+        || name == "."
+        || name == ".."
+        || name.contains('/')
+        || name.contains('\\')
+        || name.contains('\0');
+    if invalid {
+        Err(FsError::InvalidName { name: name.to_string() })
+    } else {
+        Ok(())
+    }
+}
+
+/// 符号链接复制为指向同一处的链接，不复制它指向的内容，也不会顺着指向上级目录的链接无限递归
+fn copy_recursive(from: &Path, to: &Path) -> std::io::Result<()> {
+    let file_type = fs::symlink_metadata(from)?.file_type();
+    if file_type.is_symlink() {
+        copy_symlink(from, to)
+    } else if file_type.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        fs::copy(from, to).map(|_| ())
+    }
+}
+
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(from)?, to)
+}
+
+#[cfg(windows)]
+fn copy_symlink(from: &Path, to: &Path) -> std::io::Result<()> {
+    use std::os::windows::fs::FileTypeExt;
+    let link = fs::read_link(from)?;
+    if fs::symlink_metadata(from)?.file_type().is_symlink_dir() {
+        std::os::windows::fs::symlink_dir(link, to)
+    } else {
+        std::os::windows::fs::symlink_file(link, to)
+    }
+}
+
+/// 删除 `path` 本身；是符号链接时只删除链接（`remove_dir_all` 从不跟随链接，Windows 上的目录链接也由它处理）
+fn remove_path(path: &Path) -> std::io::Result<()> {
+    let file_type = fs::symlink_metadata(path)?.file_type();
+    if file_type.is_dir() || file_type.is_symlink() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// `fs::rename`，源和目标在不同卷上时改为复制再删除。复制失败时删掉复制了一半的目标，源保持原样
+fn rename_or_copy(from: &Path, to: &Path) -> Result<(), FsError> {
+    match fs::rename(from, to) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == ErrorKind::CrossesDevices => {
+            if let Err(e) = copy_recursive(from, to) {
+                let _ = remove_path(to);
+                return Err(FsError::io(to, e));
+            }
+            remove_path(from).map_err(|e| FsError::io(from, e))
+        }
+        Err(e) => Err(FsError::io(from, e)),
+    }
+}
+
+#[command]
+pub fn create_file(scope: WindowScope, path: String, content: Option<String>) -> Result<String, FsError> {
+    let path = PathBuf::from(path);
+    ensure_allowed(&scope, &path)?;
+    ensure_free(&path)?;
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .map_err(|e| FsError::io(&path, e))?;
+    if let Some(content) = content {
+        file.write_all(content.as_bytes()).map_err(|e| FsError::io(&path, e))?;
+    }
+    Ok(path_string(&path))
+}
+
+#[command]
+pub fn create_directory(scope: WindowScope, path: String) -> Result<String, FsError> {
+    let path = PathBuf::from(path);
+    ensure_allowed(&scope, &path)?;
+    ensure_free(&path)?;
+    fs::create_dir_all(&path).map_err(|e| FsError::io(&path, e))?;
+    Ok(path_string(&path))
+}
+
+/// 原地重命名；`new_name` 是单纯的文件名，不是路径
+#[command]
+pub fn rename_path(scope: WindowScope, path: String, new_name: String) -> Result<String, FsError> {
+    let path = PathBuf::from(path);
+    ensure_allowed(&scope, &path)?;
+    ensure_exists(&path)?;
+    validate_name(&new_name)?;
+    let target = path.with_file_name(&new_name);
+    // 单独打开的文件不能重命名到其作用域条目之外
+    ensure_allowed(&scope, &target)?;
+    if target == path {
+        return Ok(path_string(&target));
+    }
+    // 在不区分大小写的文件系统上，只改大小写的重命名会报告目标已存在
+    let case_only = path_string(&target).to_lowercase() == path_string(&path).to_lowercase();
+    if !case_only {
+        ensure_free(&target)?;
+    }
+    fs::rename(&path, &target).map_err(|e| FsError::io(&path, e))?;
+    Ok(path_string(&target))
+}
+
+/// 把 `source` 移到目录 `target_dir` 中，保留其名称
+#[command]
+pub fn move_path(scope: WindowScope, source: String, target_dir: String) -> Result<String, FsError> {
+    let source = PathBuf::from(source);
+    let target_dir = PathBuf::from(target_dir);
+    ensure_allowed(&scope, &source)?;
+    ensure_allowed(&scope, &target_dir)?;
+    ensure_exists(&source)?;
+    if !target_dir.is_dir() {
+        return Err(FsError::NotFound { path: path_string(&target_dir) });
+    }
+    let name = source.file_name().ok_or_else(|| FsError::InvalidName { name: path_string(&source) })?;
+    let target = target_dir.join(name);
+    if target == source {
+        return Ok(path_string(&target));
+    }
+    if source.is_dir() {
+        let source_canonical = fs::canonicalize(&source).map_err(|e| FsError::io(&source, e))?;
+        let target_canonical = fs::canonicalize(&target_dir).map_err(|e| FsError::io(&target_dir, e))?;
+        if target_canonical.starts_with(&source_canonical) {
+            return Err(FsError::InvalidMove {
+                message: "不能把文件夹移到它自身内".to_string(),
+            });
+        }
+    }
+    ensure_free(&target)?;
+    rename_or_copy(&source, &target)?;
+    Ok(path_string(&target))
+}
+
+/// 移到系统回收站，而不是永久删除
+#[command]
+pub fn delete_path(scope: WindowScope, path: String) -> Result<(), FsError> {
+    let path = PathBuf::from(path);
+    ensure_allowed(&scope, &path)?;
+    ensure_exists(&path)?;
+    trash::delete(&path).map_err(|e| FsError::Io { path: path_string(&path), message: e.to_string() })
+}
+
+/// 在 Finder、资源管理器或 Linux 文件管理器中显示并选中 `path`
+#[command]
+pub fn reveal_in_file_manager(scope: WindowScope, path: String) -> Result<(), FsError> {
+    let path = PathBuf::from(path);
+    ensure_allowed(&scope, &path)?;
+    ensure_exists(&path)?;
+    tauri_plugin_opener::reveal_item_in_dir(&path)
+        .map_err(|e| FsError::Io { path: path_string(&path), message: e.to_string() })
+}
+
+/// 用系统关联的应用打开 `path`，例如用系统查看器打开 PDF。拒绝可执行文件和脚本
+#[command]
+pub fn open_with_default_app(scope: WindowScope, path: String) -> Result<(), FsError> {
+    let path = PathBuf::from(path);
+    ensure_allowed(&scope, &path)?;
+    ensure_exists(&path)?;
+    let executable = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| EXECUTABLE_EXTENSIONS.iter().any(|e| ext.eq_ignore_ascii_case(e)));
+    if executable {
+        return Err(FsError::NotOpenable { path: path_string(&path) });
+    }
+    tauri_plugin_opener::open_path(&path, None::<&str>)
+        .map_err(|e| FsError::Io { path: path_string(&path), message: e.to_string() })
+}
@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, LazyLock, Mutex};
+
+use fontdb::{Database, Source, Style};
+use regex::Regex;
+use serde::Serialize;
+use tauri::command;
+
+use crate::latex::strip_comment;
+
+/// 字体很多时扫描系统字体要花一点时间，所以列表会一直保留，直到 `refresh` 要求重新扫描
+static FONTS: Mutex<Option<Arc<Vec<FontFamily>>>> = Mutex::new(None);
+
+/// `\setmainfont[opts]{Name}`、`\newfontfamily\cmd{Name}`、`\fontspec{Name}`、`xeCJK`/`luatexja` 的对应命令，
+/// 以及 2017 年后的 `{Name}[opts]` 顺序
+static FONT_COMMAND_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"\\(set(?:main|sans|mono|math)font|setCJK(?:main|sans|mono)font|set(?:main|sans)jfont",
+        r"|newfontfamily\s*\\[A-Za-z@]+|newfontface\s*\\[A-Za-z@]+|fontspec)",
+        r"\s*(\[[^\]]*\])?\s*\{([^}]*)\}\s*(\[[^\]]*\])?",
+    ))
+    .unwrap()
+});
+
+#[derive(Serialize, Clone)]
+pub struct FontStyle {
+    /// `Regular`、`Bold`、`Light Italic` 等
+    name: String,
+    /// 100-900，与 CSS 相同
+    weight: u16,
+    italic: bool,
+    /// 字体没有安装到系统时，用于 fontspec 的 `Path=`
+    path: Option<String>,
+    post_script_name: String,
+}
+
+#[derive(Serialize, Clone)]
+pub struct FontFamily {
+    /// 填入 `\setmainfont{...}` 的名称
+    family: String,
+    monospaced: bool,
+    styles: Vec<FontStyle>,
+    /// 字体族在其他语言中的名称，fontspec 也接受
+    #[serde(skip)]
+    aliases: Vec<String>,
+}
+
+/// 文档要求但没有安装的字体
+#[derive(Serialize)]
+pub struct MissingFont {
+    /// 从 1 开始
+    line: u32,
+    /// 不带反斜杠的命令，例如 `setmainfont`
+    command: String,
+    font: String,
+}
+
+fn weight_name(weight: u16) -> &'static str {
+    match weight {
+        0..=149 => "Thin",
+        150..=249 => "ExtraLight",
+        250..=349 => "Light",
+        350..=449 => "Regular",
+        450..=549 => "Medium",
+        550..=649 => "SemiBold",
+        650..=749 => "Bold",
+        750..=849 => "ExtraBold",
+        _ => "Black",
+    }
+}
+
+fn scan() -> Vec<FontFamily> {
+    let mut database = Database::new();
+    database.load_system_fonts();
+    let mut families: BTreeMap<String, FontFamily> = BTreeMap::new();
+    for face in database.faces() {
+        // 字体有多个名称时，第一个是英文名
+        let Some((family, _)) = face.families.first() else {
+            continue;
+        };
+        let italic = face.style != Style::Normal;
+        let weight = face.weight.0;
+        let name = match (weight_name(weight), italic) {
+            ("Regular", true) => "Italic".to_string(),
+            (weight, true) => format!("{} Italic", weight),
+            (weight, false) => weight.to_string(),
+        };
+        let path = match &face.source {
+            Source::File(path) | Source::SharedFile(path, _) => Some(path.to_string_lossy().to_string()),
+            Source::Binary(_) => None,
+        };
+        let entry = families.entry(family.clone()).or_insert_with(|| FontFamily {
+            family: family.clone(),
+            monospaced: face.monospaced,
+            styles: Vec::new(),
+            aliases: Vec::new(),
+        });
+        for (alias, _) in face.families.iter().skip(1) {
+            if !entry.aliases.contains(alias) {
+                entry.aliases.push(alias.clone());
+            }
+        }
+        entry.styles.push(FontStyle { name, weight, italic, path, post_script_name: face.post_script_name.clone() });
+    }
+    let mut families: Vec<FontFamily> = families.into_values().collect();
+    for family in &mut families {
+        family.styles.sort_by_key(|style| (style.italic, style.weight));
+        family.styles.dedup_by(|a, b| a.name == b.name && a.post_script_name == b.post_script_name);
+    }
+    families
+}
+
+fn system_fonts(refresh: bool) -> Arc<Vec<FontFamily>> {
+    let mut cache = FONTS.lock().unwrap();
+    if refresh || cache.is_none() {
+        *cache = Some(Arc::new(scan()));
+    }
+    cache.clone().unwrap()
+}
+
+/// 已安装的字体族及其样式，按名称排序，供 XeLaTeX/LuaLaTeX 文档中的 `\setmainfont` 选择器使用
+#[command]
+pub async fn list_system_fonts(refresh: Option<bool>) -> Result<Vec<FontFamily>, String> {
+    tauri::async_runtime::spawn_blocking(move || system_fonts(refresh.unwrap_or(false)).as_ref().clone())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 按文件名加载的字体（`XITS-Regular.otf`，或带 `Path=`、`Extension=`）由 fontspec 自己查找，不检查
+fn is_file_reference(name: &str, options: &str) -> bool {
+    let lower = name.to_lowercase();
+    [".otf", ".ttf", ".ttc", ".pfb"].iter().any(|ext| lower.ends_with(ext))
+        || options.contains("Path")
+        || options.contains("Extension")
+}
+
+/// `source` 中 fontspec 命令要求的、与任何已安装字体族（的任一名称）或 PostScript 名称都不匹配的字体，
+/// 在缓慢的 XeLaTeX 编译因拼写错误失败之前就能看到
+#[command]
+pub async fn validate_fonts(source: String) -> Result<Vec<MissingFont>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let fonts = system_fonts(false);
+        let installed = |name: &str| {
+            let name = name.to_lowercase();
+            fonts.iter().any(|family| {
+                family.family.to_lowercase() == name
+                    || family.aliases.iter().any(|alias| alias.to_lowercase() == name)
+                    || family.styles.iter().any(|style| style.post_script_name.to_lowercase() == name)
+            })
+        };
+        let mut missing = Vec::new();
+        for (index, line) in source.lines().enumerate() {
+            for caps in FONT_COMMAND_RE.captures_iter(strip_comment(line)) {
+                let font = caps[3].trim();
+                let options = format!(
+                    "{}{}",
+                    caps.get(2).map_or("", |m| m.as_str()),
+                    caps.get(4).map_or("", |m| m.as_str())
+                );
+                if font.is_empty() || font.contains('\\') || is_file_reference(font, &options) || installed(font) {
+                    continue;
+                }
+                let command = caps[1].split(['\\', ' ']).next().unwrap_or_default().to_string();
+                missing.push(MissingFont { line: index as u32 + 1, command, font: font.to_string() });
+            }
+        }
+        missing
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
@@ -0,0 +1,255 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::atomic::write_atomic;
+use crate::document::content_version;
+use crate::scope::WindowScope;
+
+use super::{git_error, open_repository, relative_path, run_blocking};
+
+/// 冲突标记都是行首的 7 个字符，后面跟空格和标签（或直接换行）
+const OURS_MARKER: &str = "<<<<<<<";
+const BASE_MARKER: &str = "|||||||";
+const SEPARATOR_MARKER: &str = "=======";
+const THEIRS_MARKER: &str = ">>>>>>>";
+
+#[derive(Serialize)]
+pub struct ConflictHunk {
+    /// 冲突块内容的哈希：文件在两次调用之间被改过时，旧 id 找不到块，不会误改别处
+    id: String,
+    /// `<<<<<<<` 所在行，从 1 开始
+    start_line: u32,
+    /// `>>>>>>>` 所在行
+    end_line: u32,
+    /// 标记后的标签，如 `HEAD`、分支名或提交 id
+    ours_label: String,
+    theirs_label: String,
+    ours: String,
+    /// 仅 diff3 风格（`merge.conflictStyle = diff3`）的冲突有共同祖先
+    base: Option<String>,
+    theirs: String,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictChoice {
+    Ours,
+    Theirs,
+    /// 先保留 ours 再保留 theirs
+    Both,
+}
+
+/// 解析出的冲突块，`range` 是它在文件中的字节范围（含标记行）
+struct ParsedHunk {
+    hunk: ConflictHunk,
+    range: std::ops::Range<usize>,
+}
+
+fn marker_label<'a>(line: &'a str, marker: &str) -> Option<&'a str> {
+    let rest = line.strip_prefix(marker)?;
+    let rest = rest.trim_end_matches(['\r', '\n']);
+    if rest.is_empty() {
+        Some("")
+    } else {
+        rest.strip_prefix(' ').map(str::trim)
+    }
+}
+
+enum Section {
+    Ours,
+    Base,
+    Theirs,
+}
+
+/// 已读到 `<<<<<<<`、还没读到 `>>>>>>>` 的冲突块
+struct OpenHunk {
+    start: usize,
+    start_line: u32,
+    ours_label: String,
+    section: Section,
+    ours: String,
+    base: Option<String>,
+    theirs: String,
+}
+
+/// 不完整的冲突块（缺少 `=======` 或 `>>>>>>>`）当作普通文本
+fn parse_conflicts(content: &str) -> Vec<ParsedHunk> {
+    let mut hunks = Vec::new();
+    let mut current: Option<OpenHunk> = None;
+    let mut offset = 0;
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        let line_number = index as u32 + 1;
+        let start = offset;
+        offset += line.len();
+        if let Some(label) = marker_label(line, OURS_MARKER) {
+            current = Some(OpenHunk {
+                start,
+                start_line: line_number,
+                ours_label: label.to_string(),
+                section: Section::Ours,
+                ours: String::new(),
+                base: None,
+                theirs: String::new(),
+            });
+            continue;
+        }
+        let Some(open) = current.as_mut() else {
+            continue;
+        };
+        match open.section {
+            Section::Ours | Section::Base if marker_label(line, SEPARATOR_MARKER) == Some("") => {
+                open.section = Section::Theirs;
+            }
+            Section::Ours if marker_label(line, BASE_MARKER).is_some() => {
+                open.section = Section::Base;
+                open.base = Some(String::new());
+            }
+            Section::Ours => open.ours.push_str(line),
+            Section::Base => open.base.get_or_insert_default().push_str(line),
+            Section::Theirs => match marker_label(line, THEIRS_MARKER) {
+                Some(label) => {
+                    let open = current.take().unwrap();
+                    let hunk = ConflictHunk {
+                        id: content_version(&content.as_bytes()[open.start..offset]),
+                        start_line: open.start_line,
+                        end_line: line_number,
+                        ours_label: open.ours_label,
+                        theirs_label: label.to_string(),
+                        ours: open.ours,
+                        base: open.base,
+                        theirs: open.theirs,
+                    };
+                    hunks.push(ParsedHunk { hunk, range: open.start..offset });
+                }
+                None => open.theirs.push_str(line),
+            },
+        }
+    }
+    hunks
+}
+
+fn read_text(path: &Path) -> Result<String, String> {
+    let bytes = fs::read(path).map_err(|e| format!("无法读取文件: {}", e))?;
+    String::from_utf8(bytes).map_err(|_| format!("{} 不是 UTF-8 文本", path.display()))
+}
+
+/// 冲突全部解决后，若文件在仓库的暂存区里仍标记为冲突，把它暂存，相当于 `git add`
+fn mark_resolved(path: &Path) -> Result<(), String> {
+    let Ok(repo) = open_repository(path) else {
+        return Ok(());
+    };
+    let relative = relative_path(&repo, path)?;
+    let mut index = repo.index().map_err(git_error)?;
+    let conflicted = index
+        .conflicts()
+        .map_err(git_error)?
+        .filter_map(Result::ok)
+        .filter_map(|conflict| conflict.our.or(conflict.their).or(conflict.ancestor))
+        .any(|entry| Path::new(String::from_utf8_lossy(&entry.path).as_ref()) == relative);
+    if conflicted {
+        index.add_path(&relative).map_err(git_error)?;
+        index.write().map_err(git_error)?;
+    }
+    Ok(())
+}
+
+fn resolve_blocking(path: &Path, hunk_id: &str, choice: ConflictChoice) -> Result<Vec<ConflictHunk>, String> {
+    let content = read_text(path)?;
+    let hunks = parse_conflicts(&content);
+    let Some(target) = hunks.into_iter().find(|parsed| parsed.hunk.id == hunk_id) else {
+        return Err("冲突块已变化，请重新检测冲突".to_string());
+    };
+    let ParsedHunk { hunk, range } = target;
+    let replacement = match choice {
+        ConflictChoice::Ours => hunk.ours,
+        ConflictChoice::Theirs => hunk.theirs,
+        // 两段后面都跟着标记行，各自以换行结尾，可以直接拼接
+        ConflictChoice::Both => hunk.ours + &hunk.theirs,
+    };
+    let mut resolved = String::with_capacity(content.len());
+    resolved.push_str(&content[..range.start]);
+    resolved.push_str(&replacement);
+    resolved.push_str(&content[range.end..]);
+    write_atomic(path, resolved.as_bytes()).map_err(|e| format!("无法写入文件: {}", e))?;
+
+    let remaining: Vec<ConflictHunk> = parse_conflicts(&resolved).into_iter().map(|parsed| parsed.hunk).collect();
+    if remaining.is_empty() {
+        mark_resolved(path)?;
+    }
+    Ok(remaining)
+}
+
+/// 文件中的冲突块（`<<<<<<<` … `=======` … `>>>>>>>`，支持 diff3 的 `|||||||`），按出现顺序。
+#[command]
+pub async fn detect_merge_conflicts(scope: WindowScope, path: String) -> Result<Vec<ConflictHunk>, String> {
+    scope.check(&path)?;
+    run_blocking(move || {
+        let content = read_text(Path::new(&path))?;
+        Ok(parse_conflicts(&content).into_iter().map(|parsed| parsed.hunk).collect())
+    })
+    .await
+}
+
+/// 用 `choice` 替换 `hunk_id` 对应的冲突块并保存，返回剩余的冲突块；
+/// 全部解决后在仓库中把文件标记为已解决。
+#[command]
+pub async fn resolve_conflict(
+    scope: WindowScope,
+    path: String,
+    hunk_id: String,
+    choice: ConflictChoice,
+) -> Result<Vec<ConflictHunk>, String> {
+    scope.check(&path)?;
+    run_blocking(move || resolve_blocking(Path::new(&path), &hunk_id, choice)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_merge_style_hunks() {
+        let content = "intro\n<<<<<<< HEAD\nours 1\nours 2\n=======\ntheirs\n>>>>>>> feature\noutro\n";
+        let hunks = parse_conflicts(content);
+        assert_eq!(hunks.len(), 1);
+        let parsed = &hunks[0];
+        assert_eq!((parsed.hunk.start_line, parsed.hunk.end_line), (2, 7));
+        assert_eq!(parsed.hunk.ours_label, "HEAD");
+        assert_eq!(parsed.hunk.theirs_label, "feature");
+        assert_eq!(parsed.hunk.ours, "ours 1\nours 2\n");
+        assert_eq!(parsed.hunk.theirs, "theirs\n");
+        assert!(parsed.hunk.base.is_none());
+        assert_eq!(&content[parsed.range.clone()], &content[6..content.len() - 6]);
+    }
+
+    #[test]
+    fn parses_diff3_base_and_crlf() {
+        let content = "<<<<<<< ours\r\na\r\n||||||| base\r\nbase\r\n=======\r\nb\r\n>>>>>>> theirs\r\n";
+        let hunks = parse_conflicts(content);
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(hunks[0].hunk.ours, "a\r\n");
+        assert_eq!(hunks[0].hunk.base.as_deref(), Some("base\r\n"));
+        assert_eq!(hunks[0].hunk.theirs, "b\r\n");
+        assert_eq!(hunks[0].hunk.theirs_label, "theirs");
+    }
+
+    #[test]
+    fn incomplete_hunks_are_plain_text() {
+        assert!(parse_conflicts("<<<<<<< HEAD\na\n=======\nb\n").is_empty());
+        assert!(parse_conflicts("<<<<<<< HEAD\na\n>>>>>>> other\n").is_empty());
+        // 标记后面必须是空格或换行
+        assert!(parse_conflicts("<<<<<<<<\na\n=======\nb\n>>>>>>>\n").is_empty());
+    }
+
+    #[test]
+    fn identical_hunks_get_the_same_id() {
+        let hunk = "<<<<<<< HEAD\na\n=======\nb\n>>>>>>> other\n";
+        let hunks = parse_conflicts(&format!("{}middle\n{}", hunk, hunk));
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].hunk.id, hunks[1].hunk.id);
+        assert_eq!(hunks[1].hunk.start_line, 7);
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use git2::{BlameOptions, DiffOptions, Oid, Patch, Repository};
+use serde::Serialize;
+use tauri::command;
+
+use super::{git_error, open_repository, relative_path, run_blocking};
+use crate::scope::WindowScope;
+
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HunkKind {
+    Added,
+    Modified,
+    /// 删除的行在当前文件中不存在，标记在 `start` 行之后
+    Removed,
+}
+
+/// 行号从 1 开始，`end` 包含在内；Removed 的 `start == end`。
+#[derive(Serialize)]
+pub struct DiffHunk {
+    kind: HunkKind,
+    start: u32,
+    end: u32,
+}
+
+#[derive(Serialize)]
+pub struct BlameLine {
+    line: u32,
+    /// 尚未提交的行为 None
+    commit: Option<String>,
+    author: String,
+    email: String,
+    /// 提交时间（秒级时间戳）
+    time: i64,
+    summary: String,
+}
+
+/// 当前内容：优先使用编辑器里尚未保存的内容，否则读磁盘
+fn current_content(path: &Path, content: Option<String>) -> Result<String, String> {
+    match content {
+        Some(content) => Ok(content),
+        None => fs::read_to_string(path).map_err(|e| format!("无法读取文件: {}", e)),
+    }
+}
+
+fn head_blob<'r>(repo: &'r Repository, relative: &Path) -> Option<git2::Blob<'r>> {
+    let tree = repo.head().ok()?.peel_to_tree().ok()?;
+    let entry = tree.get_path(relative).ok()?;
+    repo.find_blob(entry.id()).ok()
+}
+
+fn diff_blocking(path: &Path, content: Option<String>) -> Result<Vec<DiffHunk>, String> {
+    let repo = open_repository(path)?;
+    let relative = relative_path(&repo, path)?;
+    let current = current_content(path, content)?;
+
+    let Some(blob) = head_blob(&repo, &relative) else {
+        // HEAD 中没有这个文件：整篇都是新增
+        let lines = current.lines().count() as u32;
+        return Ok(if lines == 0 {
+            Vec::new()
+        } else {
+            vec![DiffHunk { kind: HunkKind::Added, start: 1, end: lines }]
+        });
+    };
+
+    let mut options = DiffOptions::new();
+    options.context_lines(0);
+    let patch = Patch::from_blob_and_buffer(
+        &blob,
+        Some(&relative),
+        current.as_bytes(),
+        Some(&relative),
+        Some(&mut options),
+    )
+    .map_err(git_error)?;
+
+    let mut hunks = Vec::new();
+    for index in 0..patch.num_hunks() {
+        let (hunk, _) = patch.hunk(index).map_err(git_error)?;
+        let (old_lines, new_start, new_lines) = (hunk.old_lines(), hunk.new_start(), hunk.new_lines());
+        let hunk = if new_lines == 0 {
+            // 纯删除时 new_start 指向删除位置之前的那一行
+            DiffHunk { kind: HunkKind::Removed, start: new_start, end: new_start }
+        } else {
+            let kind = if old_lines == 0 { HunkKind::Added } else { HunkKind::Modified };
+            DiffHunk { kind, start: new_start, end: new_start + new_lines - 1 }
+        };
+        hunks.push(hunk);
+    }
+    Ok(hunks)
+}
+
+fn blame_blocking(path: &Path, content: Option<String>) -> Result<Vec<BlameLine>, String> {
+    let repo = open_repository(path)?;
+    let relative = relative_path(&repo, path)?;
+    let current = current_content(path, content)?;
+
+    let mut options = BlameOptions::new();
+    let committed = repo.blame_file(&relative, Some(&mut options)).map_err(git_error)?;
+    // 基于当前内容重新计算，行号与编辑器一致，未提交的修改归为 None
+    let blame = committed.blame_buffer(current.as_bytes()).map_err(git_error)?;
+
+    // blame_buffer 产生的 hunk 不带签名（libgit2 中为空指针），作者信息从提交本身读取
+    let mut commits: HashMap<Oid, (String, String, i64, String)> = HashMap::new();
+    let mut lines = Vec::new();
+    for hunk in blame.iter() {
+        let oid = hunk.final_commit_id();
+        let (commit, (author, email, time, summary)) = if oid.is_zero() {
+            (None, Default::default())
+        } else {
+            let info = commits
+                .entry(oid)
+                .or_insert_with(|| match repo.find_commit(oid) {
+                    Ok(commit) => {
+                        let author = commit.author();
+                        (
+                            author.name().unwrap_or_default().to_string(),
+                            author.email().unwrap_or_default().to_string(),
+                            author.when().seconds(),
+                            commit.summary().unwrap_or_default().to_string(),
+                        )
+                    }
+                    Err(_) => Default::default(),
+                })
+                .clone();
+            (Some(oid.to_string()), info)
+        };
+        let start = hunk.final_start_line() as u32;
+        for offset in 0..hunk.lines_in_hunk() as u32 {
+            lines.push(BlameLine {
+                line: start + offset,
+                commit: commit.clone(),
+                author: author.clone(),
+                email: email.clone(),
+                time,
+                summary: summary.clone(),
+            });
+        }
+    }
+    Ok(lines)
+}
+
+/// 当前内容相对 HEAD 的改动范围，用于编辑器行号旁的变更标记。
+#[command]
+pub async fn git_diff_file(
+    scope: WindowScope,
+    path: String,
+    content: Option<String>,
+) -> Result<Vec<DiffHunk>, String> {
+    scope.check(&path)?;
+    run_blocking(move || diff_blocking(Path::new(&path), content)).await
+}
+
+#[command]
+pub async fn git_blame(
+    scope: WindowScope,
+    path: String,
+    content: Option<String>,
+) -> Result<Vec<BlameLine>, String> {
+    scope.check(&path)?;
+    run_blocking(move || blame_blocking(Path::new(&path), content)).await
+}
@@ -0,0 +1,179 @@
+use std::path::Path;
+
+use git2::{BranchType, Commit, Sort};
+use serde::Serialize;
+use tauri::command;
+
+use super::{git_error, open_repository, relative_path, run_blocking};
+use crate::scope::WindowScope;
+
+/// 未指定时 git_log 返回的提交数
+const DEFAULT_LOG_LIMIT: usize = 100;
+
+#[derive(Serialize)]
+pub struct CommitInfo {
+    id: String,
+    short_id: String,
+    summary: String,
+    message: String,
+    author: String,
+    email: String,
+    /// 作者时间（秒级时间戳）
+    time: i64,
+    parents: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct BranchInfo {
+    /// 本地分支为 `main`，远程分支为 `origin/main`
+    name: String,
+    is_remote: bool,
+    is_head: bool,
+    upstream: Option<String>,
+    commit: Option<String>,
+}
+
+impl From<&Commit<'_>> for CommitInfo {
+    fn from(commit: &Commit<'_>) -> Self {
+        let author = commit.author();
+        let id = commit.id().to_string();
+        CommitInfo {
+            short_id: id.chars().take(7).collect(),
+            id,
+            summary: commit.summary().unwrap_or_default().to_string(),
+            message: commit.message().unwrap_or_default().to_string(),
+            author: author.name().unwrap_or_default().to_string(),
+            email: author.email().unwrap_or_default().to_string(),
+            time: author.when().seconds(),
+            parents: commit.parent_ids().map(|id| id.to_string()).collect(),
+        }
+    }
+}
+
+fn log_blocking(root: &Path, limit: usize, skip: usize) -> Result<Vec<CommitInfo>, String> {
+    let repo = open_repository(root)?;
+    // 还没有任何提交
+    if repo.head().is_err() {
+        return Ok(Vec::new());
+    }
+    let mut walk = repo.revwalk().map_err(git_error)?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME).map_err(git_error)?;
+    walk.push_head().map_err(git_error)?;
+
+    walk.skip(skip)
+        .take(limit)
+        .map(|oid| {
+            let oid = oid.map_err(git_error)?;
+            let commit = repo.find_commit(oid).map_err(git_error)?;
+            Ok(CommitInfo::from(&commit))
+        })
+        .collect()
+}
+
+fn branches_blocking(root: &Path) -> Result<Vec<BranchInfo>, String> {
+    let repo = open_repository(root)?;
+    let mut branches = Vec::new();
+    for entry in repo.branches(None).map_err(git_error)? {
+        let (branch, kind) = entry.map_err(git_error)?;
+        let Some(name) = branch.name().map_err(git_error)?.map(str::to_string) else {
+            continue;
+        };
+        // origin/HEAD 只是指向默认分支的符号引用
+        if kind == BranchType::Remote && name.ends_with("/HEAD") {
+            continue;
+        }
+        let upstream = branch
+            .upstream()
+            .ok()
+            .and_then(|u| u.name().ok().flatten().map(str::to_string));
+        branches.push(BranchInfo {
+            commit: branch.get().target().map(|oid| oid.to_string()),
+            is_head: branch.is_head(),
+            is_remote: kind == BranchType::Remote,
+            upstream,
+            name,
+        });
+    }
+    // 本地分支在前
+    branches.sort_by(|a, b| a.is_remote.cmp(&b.is_remote).then_with(|| a.name.cmp(&b.name)));
+    Ok(branches)
+}
+
+/// 切换到本地分支；传入远程分支名（如 `origin/draft`）时创建同名的本地跟踪分支。
+/// 使用 safe 模式检出，会覆盖未提交修改时直接报错，不会丢失内容。
+fn checkout_blocking(root: &Path, branch: &str) -> Result<(), String> {
+    let repo = open_repository(root)?;
+    let local = match repo.find_branch(branch, BranchType::Local) {
+        Ok(local) => local,
+        Err(_) => {
+            let remote = repo
+                .find_branch(branch, BranchType::Remote)
+                .map_err(|_| format!("分支不存在: {}", branch))?;
+            let local_name = branch.split_once('/').map(|(_, name)| name).unwrap_or(branch);
+            let target = remote.get().peel_to_commit().map_err(git_error)?;
+            let mut local = repo.branch(local_name, &target, false).map_err(git_error)?;
+            local.set_upstream(Some(branch)).map_err(git_error)?;
+            local
+        }
+    };
+
+    let reference = local.get();
+    let refname = reference.name().ok_or("分支名不是有效的 UTF-8")?.to_string();
+    let tree = reference.peel_to_tree().map_err(git_error)?;
+    let mut checkout = git2::build::CheckoutBuilder::new();
+    checkout.safe();
+    repo.checkout_tree(tree.as_object(), Some(&mut checkout))
+        .map_err(|e| format!("无法切换分支（请先提交或还原修改）: {}", e.message()))?;
+    repo.set_head(&refname).map_err(git_error)
+}
+
+/// `commit`（任意 revspec）中 `path` 的文本内容
+pub fn show_file_at(path: &Path, commit: &str) -> Result<String, String> {
+    let repo = open_repository(path)?;
+    let relative = relative_path(&repo, path)?;
+    let commit = repo
+        .revparse_single(commit)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(git_error)?;
+    let tree = commit.tree().map_err(git_error)?;
+    let entry = tree
+        .get_path(&relative)
+        .map_err(|_| format!("提交 {} 中没有 {}", commit.id(), relative.display()))?;
+    let blob = repo.find_blob(entry.id()).map_err(git_error)?;
+    if blob.is_binary() {
+        return Err("二进制文件无法显示".to_string());
+    }
+    Ok(String::from_utf8_lossy(blob.content()).to_string())
+}
+
+/// 从 HEAD 开始按时间倒序列出提交，`skip` / `limit` 用于分页加载。
+#[command]
+pub async fn git_log(
+    scope: WindowScope,
+    root: String,
+    limit: Option<usize>,
+    skip: Option<usize>,
+) -> Result<Vec<CommitInfo>, String> {
+    scope.check(&root)?;
+    let limit = limit.unwrap_or(DEFAULT_LOG_LIMIT);
+    run_blocking(move || log_blocking(Path::new(&root), limit, skip.unwrap_or(0))).await
+}
+
+#[command]
+pub async fn git_branches(scope: WindowScope, root: String) -> Result<Vec<BranchInfo>, String> {
+    scope.check(&root)?;
+    run_blocking(move || branches_blocking(Path::new(&root))).await
+}
+
+#[command]
+pub async fn git_checkout(scope: WindowScope, root: String, branch: String) -> Result<(), String> {
+    scope.check(&root)?;
+    run_blocking(move || checkout_blocking(Path::new(&root), &branch)).await
+}
+
+/// 某个提交中的文件内容，`commit` 可以是任意 revspec（如 `HEAD~2`）。
+#[command]
+pub async fn git_show_file_at(scope: WindowScope, path: String, commit: String) -> Result<String, String> {
+    scope.check(&path)?;
+    run_blocking(move || show_file_at(Path::new(&path), &commit)).await
+}
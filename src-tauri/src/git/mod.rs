@@ -0,0 +1,261 @@
+pub mod conflicts;
+pub mod gutter;
+pub mod log;
+pub mod remote;
+
+use std::path::{Path, PathBuf};
+
+use git2::{IndexAddOption, Repository, Status, StatusOptions};
+use serde::Serialize;
+use tauri::command;
+
+use crate::scope::WindowScope;
+
+#[derive(Serialize)]
+pub struct GitFileStatus {
+    /// 绝对路径，与文件树中的路径一致
+    path: String,
+    /// 暂存区相对 HEAD 的变化：new / modified / deleted / renamed / typechange
+    index: Option<&'static str>,
+    /// 工作区相对暂存区的变化；未跟踪文件为 new
+    worktree: Option<&'static str>,
+    conflicted: bool,
+}
+
+#[derive(Serialize)]
+pub struct GitStatus {
+    /// 当前分支名；处于 detached HEAD 时为 None
+    branch: Option<String>,
+    workdir: String,
+    files: Vec<GitFileStatus>,
+}
+
+pub fn git_error(e: git2::Error) -> String {
+    format!("Git 错误: {}", e.message())
+}
+
+/// 从任意子路径向上查找仓库；bare 仓库没有工作区，不支持
+pub fn open_repository(path: &Path) -> Result<Repository, String> {
+    // 已删除的文件从最近的现存上级目录开始查找
+    let start = path.ancestors().find(|p| p.exists()).unwrap_or(path);
+    let repo = Repository::discover(start).map_err(git_error)?;
+    if repo.is_bare() {
+        return Err("不支持 bare 仓库".to_string());
+    }
+    Ok(repo)
+}
+
+pub fn workdir(repo: &Repository) -> Result<PathBuf, String> {
+    let dir = repo.workdir().ok_or("仓库没有工作区")?;
+    Ok(std::fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf()))
+}
+
+/// 把绝对路径换算成仓库内的相对路径（git2 的 index / pathspec 只接受相对路径）
+pub fn relative_path(repo: &Repository, path: &Path) -> Result<PathBuf, String> {
+    let root = workdir(repo)?;
+    let absolute = std::fs::canonicalize(path)
+        .or_else(|_| {
+            // 已删除的文件无法 canonicalize，改为规范化其父目录
+            let parent = path.parent().ok_or(())?;
+            let name = path.file_name().ok_or(())?;
+            std::fs::canonicalize(parent).map(|p| p.join(name)).map_err(|_| ())
+        })
+        .unwrap_or_else(|_| path.to_path_buf());
+    absolute
+        .strip_prefix(&root)
+        .map(Path::to_path_buf)
+        .map_err(|_| format!("{} 不在仓库 {} 内", path.display(), root.display()))
+}
+
+fn index_change(status: Status) -> Option<&'static str> {
+    if status.is_index_new() {
+        Some("new")
+    } else if status.is_index_modified() {
+        Some("modified")
+    } else if status.is_index_deleted() {
+        Some("deleted")
+    } else if status.is_index_renamed() {
+        Some("renamed")
+    } else if status.is_index_typechange() {
+        Some("typechange")
+    } else {
+        None
+    }
+}
+
+fn worktree_change(status: Status) -> Option<&'static str> {
+    if status.is_wt_new() {
+        Some("new")
+    } else if status.is_wt_modified() {
+        Some("modified")
+    } else if status.is_wt_deleted() {
+        Some("deleted")
+    } else if status.is_wt_renamed() {
+        Some("renamed")
+    } else if status.is_wt_typechange() {
+        Some("typechange")
+    } else {
+        None
+    }
+}
+
+/// 仓库操作都是阻塞 IO，大仓库的 status 可能较慢
+async fn run_blocking<T, F>(f: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(f).await.map_err(|e| e.to_string())?
+}
+
+fn status_blocking(root: &Path) -> Result<GitStatus, String> {
+    let repo = open_repository(root)?;
+    let dir = workdir(&repo)?;
+    let branch = match repo.head() {
+        Ok(head) if head.is_branch() => head.shorthand().map(str::to_string),
+        Ok(_) => None,
+        // 尚无提交的新仓库：HEAD 指向一个还不存在的分支
+        Err(_) => repo
+            .find_reference("HEAD")
+            .ok()
+            .and_then(|r| r.symbolic_target().map(|t| t.trim_start_matches("refs/heads/").to_string())),
+    };
+
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .include_ignored(false)
+        .renames_head_to_index(true);
+    let statuses = repo.statuses(Some(&mut options)).map_err(git_error)?;
+
+    let files = statuses
+        .iter()
+        .filter_map(|entry| {
+            let status = entry.status();
+            let path = entry.path()?;
+            Some(GitFileStatus {
+                path: dir.join(path).to_string_lossy().to_string(),
+                index: index_change(status),
+                worktree: worktree_change(status),
+                conflicted: status.is_conflicted(),
+            })
+        })
+        .collect();
+
+    Ok(GitStatus {
+        branch,
+        workdir: dir.to_string_lossy().to_string(),
+        files,
+    })
+}
+
+fn stage_blocking(paths: &[String]) -> Result<(), String> {
+    let first = paths.first().ok_or("没有要暂存的文件")?;
+    let repo = open_repository(Path::new(first))?;
+    let mut index = repo.index().map_err(git_error)?;
+    let mut present = Vec::new();
+    let mut removed = Vec::new();
+    for path in paths {
+        let relative = relative_path(&repo, Path::new(path))?;
+        if Path::new(path).exists() {
+            present.push(relative);
+        } else {
+            removed.push(relative);
+        }
+    }
+    // add_all 会展开目录；update_all 把目录中已删除的文件一并移出暂存区
+    if !present.is_empty() {
+        index
+            .add_all(present.iter(), IndexAddOption::DEFAULT, None)
+            .map_err(git_error)?;
+        index.update_all(present.iter(), None).map_err(git_error)?;
+    }
+    if !removed.is_empty() {
+        index.remove_all(removed.iter(), None).map_err(git_error)?;
+    }
+    index.write().map_err(git_error)
+}
+
+fn commit_blocking(root: &Path, message: &str) -> Result<String, String> {
+    if message.trim().is_empty() {
+        return Err("提交信息不能为空".to_string());
+    }
+    let repo = open_repository(root)?;
+    let signature = repo
+        .signature()
+        .map_err(|_| "未配置 user.name / user.email，无法提交".to_string())?;
+    let mut index = repo.index().map_err(git_error)?;
+    let tree_id = index.write_tree().map_err(git_error)?;
+    let tree = repo.find_tree(tree_id).map_err(git_error)?;
+
+    // 首次提交没有父提交
+    let parent = match repo.head() {
+        Ok(head) => Some(head.peel_to_commit().map_err(git_error)?),
+        Err(_) => None,
+    };
+    if let Some(parent) = &parent {
+        if parent.tree_id() == tree_id {
+            return Err("没有已暂存的更改".to_string());
+        }
+    }
+    let parents: Vec<_> = parent.iter().collect();
+    let oid = repo
+        .commit(Some("HEAD"), &signature, &signature, message, &tree, &parents)
+        .map_err(git_error)?;
+    Ok(oid.to_string())
+}
+
+/// 已跟踪的文件恢复为暂存区中的内容；未跟踪的文件移到回收站。
+fn discard_blocking(paths: &[String]) -> Result<(), String> {
+    let first = paths.first().ok_or("没有要还原的文件")?;
+    let repo = open_repository(Path::new(first))?;
+    let mut tracked = Vec::new();
+    for path in paths {
+        let relative = relative_path(&repo, Path::new(path))?;
+        let untracked = repo
+            .status_file(&relative)
+            .map(|s| s.is_wt_new())
+            .unwrap_or(false);
+        if untracked {
+            trash::delete(path).map_err(|e| format!("无法删除 {}: {}", path, e))?;
+        } else {
+            tracked.push(relative);
+        }
+    }
+    if tracked.is_empty() {
+        return Ok(());
+    }
+
+    let mut checkout = git2::build::CheckoutBuilder::new();
+    checkout.force().update_index(false);
+    for path in &tracked {
+        checkout.path(path);
+    }
+    repo.checkout_index(None, Some(&mut checkout)).map_err(git_error)
+}
+
+#[command]
+pub async fn git_status(scope: WindowScope, root: String) -> Result<GitStatus, String> {
+    scope.check(&root)?;
+    run_blocking(move || status_blocking(Path::new(&root))).await
+}
+
+#[command]
+pub async fn git_stage(scope: WindowScope, paths: Vec<String>) -> Result<(), String> {
+    paths.iter().try_for_each(|path| scope.check(path))?;
+    run_blocking(move || stage_blocking(&paths)).await
+}
+
+/// 提交暂存区的内容，返回新提交的 id
+#[command]
+pub async fn git_commit(scope: WindowScope, root: String, message: String) -> Result<String, String> {
+    scope.check(&root)?;
+    run_blocking(move || commit_blocking(Path::new(&root), &message)).await
+}
+
+#[command]
+pub async fn git_discard(scope: WindowScope, paths: Vec<String>) -> Result<(), String> {
+    paths.iter().try_for_each(|path| scope.check(path))?;
+    run_blocking(move || discard_blocking(&paths)).await
+}
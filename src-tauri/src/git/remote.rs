@@ -0,0 +1,320 @@
+use std::cell::Cell;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use git2::build::{CheckoutBuilder, RepoBuilder};
+use git2::{
+    AnnotatedCommit, AutotagOption, BranchType, Cred, CredentialType, FetchOptions, MergeAnalysis, Progress,
+    PushOptions, RemoteCallbacks, Repository,
+};
+use serde::Serialize;
+use tauri::{command, AppHandle, Emitter};
+
+use crate::scope::WindowScope;
+use crate::secrets::{self, Service};
+
+use super::{git_error, open_repository, run_blocking, workdir};
+
+/// 克隆、拉取、推送的进度事件名，前端通过 `listen("git-progress", ...)` 订阅。
+pub const GIT_PROGRESS_EVENT: &str = "git-progress";
+/// 依次尝试的默认私钥，在 ssh-agent 之后
+const DEFAULT_SSH_KEYS: &[&str] = &["id_ed25519", "id_ecdsa", "id_rsa"];
+/// 进度事件的最小间隔，大仓库的回调每秒会触发上万次
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Serialize)]
+pub struct GitProgress {
+    /// clone / pull / push
+    operation: &'static str,
+    /// receiving（接收对象）/ resolving（处理 delta）/ checkout（仅 clone）/ pushing / remote（服务器发来的消息）
+    stage: &'static str,
+    current: usize,
+    total: usize,
+    bytes: usize,
+    /// 仅 remote：服务器输出的一行，如 GitHub 的 `Counting objects: 45% ...`
+    message: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PullOutcome {
+    UpToDate,
+    FastForward,
+    Merged,
+    /// 合并有冲突，仓库停在合并中的状态，等待解决后提交
+    Conflicts,
+}
+
+#[derive(Serialize)]
+pub struct PullResult {
+    outcome: PullOutcome,
+    /// 有冲突的文件，绝对路径
+    conflicts: Vec<String>,
+}
+
+/// URL 中的主机名：`https://user@github.com/a/b.git`、`git@github.com:a/b.git` 都得到 `github.com`
+fn url_host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let rest = rest.split_once('@').map_or(rest, |(_, host)| host);
+    rest.split(['/', ':']).next().unwrap_or(rest)
+}
+
+fn ssh_dir() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+    Some(PathBuf::from(home).join(".ssh"))
+}
+
+/// 认证回调：HTTPS 用钥匙串里的令牌，SSH 先试 ssh-agent 再试默认私钥。
+/// libgit2 在认证失败后会反复调用回调，每种方式只试一次，避免死循环。
+fn credentials_callback(callbacks: &mut RemoteCallbacks) {
+    let (token_tried, agent_tried, next_key) = (Cell::new(false), Cell::new(false), Cell::new(0));
+    callbacks.credentials(move |url, username, allowed| {
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            if token_tried.replace(true) {
+                return Err(git2::Error::from_str(&format!(
+                    "{} 拒绝了令牌：请在设置中重新填写访问令牌",
+                    url_host(url)
+                )));
+            }
+            let token = secrets::get(Service::Git, url_host(url)).map_err(|e| git2::Error::from_str(&e))?;
+            let Some(token) = token else {
+                return Err(git2::Error::from_str(&format!("没有 {} 的访问令牌：请先在设置中填写", url_host(url))));
+            };
+            // GitHub 忽略用户名，GitLab 的个人令牌接受任意用户名
+            return Cred::userpass_plaintext(username.unwrap_or("oauth2"), &token);
+        }
+        if allowed.contains(CredentialType::SSH_KEY) {
+            let user = username.unwrap_or("git");
+            // 没有运行 ssh-agent 时直接试私钥
+            if !agent_tried.replace(true) {
+                if let Ok(cred) = Cred::ssh_key_from_agent(user) {
+                    return Ok(cred);
+                }
+            }
+            let keys: Vec<PathBuf> = ssh_dir()
+                .map(|dir| DEFAULT_SSH_KEYS.iter().map(|name| dir.join(name)).filter(|path| path.is_file()).collect())
+                .unwrap_or_default();
+            let Some(key) = keys.get(next_key.replace(next_key.get() + 1)) else {
+                return Err(git2::Error::from_str("SSH 认证失败：ssh-agent 和 ~/.ssh 中的私钥都被拒绝"));
+            };
+            let passphrase = secrets::get(Service::GitSsh, &key.to_string_lossy()).ok().flatten();
+            return Cred::ssh_key(user, None, key, passphrase.as_deref());
+        }
+        if allowed.contains(CredentialType::USERNAME) {
+            return Cred::username(username.unwrap_or("git"));
+        }
+        Cred::default()
+    });
+}
+
+/// 按 `PROGRESS_INTERVAL` 节流后发送进度事件；最后一个（`current == total`）总会发送
+struct ProgressEmitter {
+    app: AppHandle,
+    operation: &'static str,
+    last: Cell<Option<Instant>>,
+}
+
+impl ProgressEmitter {
+    fn emit(&self, stage: &'static str, current: usize, total: usize, bytes: usize, message: Option<String>) {
+        let due = self.last.get().is_none_or(|last| last.elapsed() >= PROGRESS_INTERVAL);
+        if !due && current < total && message.is_none() {
+            return;
+        }
+        self.last.set(Some(Instant::now()));
+        let progress = GitProgress { operation: self.operation, stage, current, total, bytes, message };
+        let _ = self.app.emit(GIT_PROGRESS_EVENT, progress);
+    }
+
+    fn transfer(&self, progress: &Progress) {
+        if progress.received_objects() < progress.total_objects() {
+            self.emit(
+                "receiving",
+                progress.received_objects(),
+                progress.total_objects(),
+                progress.received_bytes(),
+                None,
+            );
+        } else {
+            self.emit(
+                "resolving",
+                progress.indexed_deltas(),
+                progress.total_deltas(),
+                progress.received_bytes(),
+                None,
+            );
+        }
+    }
+
+    /// 服务器的输出用 `\r` 刷新同一行，只取最后一段
+    fn sideband(&self, data: &[u8]) {
+        let text = String::from_utf8_lossy(data);
+        if let Some(line) = text.split(['\r', '\n']).map(str::trim).rfind(|line| !line.is_empty()) {
+            self.emit("remote", 0, 0, 0, Some(line.to_string()));
+        }
+    }
+}
+
+fn fetch_options<'a>(progress: &'a ProgressEmitter) -> FetchOptions<'a> {
+    let mut callbacks = RemoteCallbacks::new();
+    credentials_callback(&mut callbacks);
+    callbacks.transfer_progress(|stats| {
+        progress.transfer(&stats);
+        true
+    });
+    callbacks.sideband_progress(|data| {
+        progress.sideband(data);
+        true
+    });
+    let mut options = FetchOptions::new();
+    options.remote_callbacks(callbacks).download_tags(AutotagOption::Auto);
+    options
+}
+
+fn clone_blocking(app: AppHandle, url: &str, dest: &Path) -> Result<String, String> {
+    if dest.exists() && dest.read_dir().map_err(|e| e.to_string())?.next().is_some() {
+        return Err(format!("目标目录 {} 不为空", dest.display()));
+    }
+    let progress = ProgressEmitter { app, operation: "clone", last: Cell::new(None) };
+    let mut checkout = CheckoutBuilder::new();
+    checkout.progress(|_, current, total| progress.emit("checkout", current, total, 0, None));
+    let repo = RepoBuilder::new()
+        .fetch_options(fetch_options(&progress))
+        .with_checkout(checkout)
+        .clone(url, dest)
+        .map_err(git_error)?;
+    Ok(workdir(&repo)?.to_string_lossy().to_string())
+}
+
+/// 当前分支及其上游：(本地分支名, 远程名, 上游在远程上的分支名)
+fn tracking_branch(repo: &Repository) -> Result<(String, String, String), String> {
+    let head = repo.head().map_err(|_| "仓库还没有提交".to_string())?;
+    if !head.is_branch() {
+        return Err("处于 detached HEAD，无法拉取或推送".to_string());
+    }
+    let branch = head.shorthand().ok_or("分支名不是有效的 UTF-8")?.to_string();
+    let config = repo.config().map_err(git_error)?;
+    let remote = config.get_string(&format!("branch.{}.remote", branch)).unwrap_or_else(|_| "origin".to_string());
+    let merge = config
+        .get_string(&format!("branch.{}.merge", branch))
+        .map(|merge| merge.trim_start_matches("refs/heads/").to_string())
+        .unwrap_or_else(|_| branch.clone());
+    Ok((branch, remote, merge))
+}
+
+fn conflicted_paths(repo: &Repository) -> Result<Vec<String>, String> {
+    let dir = workdir(repo)?;
+    let index = repo.index().map_err(git_error)?;
+    let conflicts = index.conflicts().map_err(git_error)?;
+    Ok(conflicts
+        .filter_map(Result::ok)
+        .filter_map(|conflict| conflict.our.or(conflict.their).or(conflict.ancestor))
+        .map(|entry| dir.join(String::from_utf8_lossy(&entry.path).as_ref()).to_string_lossy().to_string())
+        .collect())
+}
+
+/// 上游的提交已经取回，把它合并进当前分支
+fn merge_upstream(
+    repo: &Repository,
+    branch: &str,
+    upstream: &AnnotatedCommit,
+    message: &str,
+) -> Result<PullResult, String> {
+    let (analysis, _) = repo.merge_analysis(&[upstream]).map_err(git_error)?;
+    let done = |outcome| Ok(PullResult { outcome, conflicts: Vec::new() });
+    if analysis.contains(MergeAnalysis::ANALYSIS_UP_TO_DATE) {
+        return done(PullOutcome::UpToDate);
+    }
+    if analysis.contains(MergeAnalysis::ANALYSIS_FASTFORWARD) {
+        // 先检出再移动分支：工作区的本地修改与上游冲突时检出失败，分支保持原样
+        let target = repo.find_object(upstream.id(), None).map_err(git_error)?;
+        repo.checkout_tree(&target, Some(CheckoutBuilder::new().safe())).map_err(git_error)?;
+        let mut reference = repo.find_reference(&format!("refs/heads/{}", branch)).map_err(git_error)?;
+        reference.set_target(upstream.id(), "pull: fast-forward").map_err(git_error)?;
+        return done(PullOutcome::FastForward);
+    }
+
+    repo.merge(&[upstream], None, Some(CheckoutBuilder::new().safe())).map_err(git_error)?;
+    let mut index = repo.index().map_err(git_error)?;
+    if index.has_conflicts() {
+        return Ok(PullResult { outcome: PullOutcome::Conflicts, conflicts: conflicted_paths(repo)? });
+    }
+    let signature = repo.signature().map_err(|_| "未配置 user.name / user.email，无法提交".to_string())?;
+    let tree = repo.find_tree(index.write_tree().map_err(git_error)?).map_err(git_error)?;
+    let ours = repo.head().and_then(|head| head.peel_to_commit()).map_err(git_error)?;
+    let theirs = repo.find_commit(upstream.id()).map_err(git_error)?;
+    repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &[&ours, &theirs]).map_err(git_error)?;
+    repo.cleanup_state().map_err(git_error)?;
+    done(PullOutcome::Merged)
+}
+
+fn pull_blocking(app: AppHandle, root: &Path) -> Result<PullResult, String> {
+    let repo = open_repository(root)?;
+    let (branch, remote_name, merge) = tracking_branch(&repo)?;
+    let mut remote = repo.find_remote(&remote_name).map_err(git_error)?;
+    let progress = ProgressEmitter { app, operation: "pull", last: Cell::new(None) };
+    remote.fetch(&[merge.as_str()], Some(&mut fetch_options(&progress)), None).map_err(git_error)?;
+    let fetch_head = repo.find_reference("FETCH_HEAD").map_err(git_error)?;
+    let upstream = repo.reference_to_annotated_commit(&fetch_head).map_err(git_error)?;
+    let message = format!("Merge branch '{}' of {}", merge, remote.url().unwrap_or(&remote_name));
+    merge_upstream(&repo, &branch, &upstream, &message)
+}
+
+fn push_blocking(app: AppHandle, root: &Path) -> Result<(), String> {
+    let repo = open_repository(root)?;
+    let (branch, remote_name, merge) = tracking_branch(&repo)?;
+    let mut remote = repo.find_remote(&remote_name).map_err(git_error)?;
+    let progress = ProgressEmitter { app, operation: "push", last: Cell::new(None) };
+    let rejected = Cell::new(None::<String>);
+    let mut callbacks = RemoteCallbacks::new();
+    credentials_callback(&mut callbacks);
+    callbacks.push_transfer_progress(|current, total, bytes| progress.emit("pushing", current, total, bytes, None));
+    callbacks.sideband_progress(|data| {
+        progress.sideband(data);
+        true
+    });
+    // 服务器拒绝（非快进、受保护分支）时 push 本身仍返回成功，只能从这里得知
+    callbacks.push_update_reference(|_, status| {
+        if let Some(message) = status {
+            rejected.set(Some(message.to_string()));
+        }
+        Ok(())
+    });
+    let mut options = PushOptions::new();
+    options.remote_callbacks(callbacks);
+    let refspec = format!("refs/heads/{}:refs/heads/{}", branch, merge);
+    remote.push(&[refspec.as_str()], Some(&mut options)).map_err(git_error)?;
+    if let Some(message) = rejected.take() {
+        return Err(format!("推送被 {} 拒绝: {}（可能需要先拉取）", remote_name, message));
+    }
+    // 首次推送的分支设置上游，之后的拉取和推送都用它
+    let mut local = repo.find_branch(&branch, BranchType::Local).map_err(git_error)?;
+    if local.upstream().is_err() {
+        let _ = local.set_upstream(Some(&format!("{}/{}", remote_name, merge)));
+    }
+    Ok(())
+}
+
+/// 克隆到 `dest`（不存在或为空的目录），返回工作区路径；克隆好的目录加入可访问范围。
+#[command]
+pub async fn git_clone(app: AppHandle, scope: WindowScope, url: String, dest: String) -> Result<String, String> {
+    let dest = PathBuf::from(dest);
+    scope.check(dest.parent().unwrap_or(&dest))?;
+    let target = dest.clone();
+    let path = run_blocking(move || clone_blocking(app, &url, &target)).await?;
+    scope.allow(&dest);
+    Ok(path)
+}
+
+/// 取回当前分支的上游并合并；能快进时快进，否则生成合并提交，有冲突时停在合并状态。
+#[command]
+pub async fn git_pull(app: AppHandle, scope: WindowScope, root: String) -> Result<PullResult, String> {
+    scope.check(&root)?;
+    run_blocking(move || pull_blocking(app, Path::new(&root))).await
+}
+
+/// 把当前分支推送到它的上游，没有上游时推送到 origin 上的同名分支。
+#[command]
+pub async fn git_push(app: AppHandle, scope: WindowScope, root: String) -> Result<(), String> {
+    scope.check(&root)?;
+    run_blocking(move || push_blocking(app, Path::new(&root))).await
+}
@@ -0,0 +1,251 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::command;
+use ureq::Agent;
+
+use crate::spellcheck::mask_markup;
+
+/// 本机运行的 LanguageTool 服务（`languagetool-server` 默认端口）
+const DEFAULT_SERVER: &str = "http://localhost:8081";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_REPLACEMENTS: usize = 5;
+
+/// 引用和公式换成占位词，避免 LanguageTool 把 “see Figure .” 当作语法错误
+const REFERENCE_PLACEHOLDER: &str = "1";
+const CITATION_PLACEHOLDER: &str = "[1]";
+const MATH_PLACEHOLDER: &str = "X";
+
+#[derive(Serialize)]
+pub struct GrammarIssue {
+    message: String,
+    rule: String,
+    category: String,
+    /// LanguageTool 的 issueType：grammar、misspelling、style……
+    issue_type: String,
+    severity: &'static str,
+    /// 以下位置均从 1 开始，列按字符计，范围不含结束位置
+    line: u32,
+    column: u32,
+    end_line: u32,
+    end_column: u32,
+    replacements: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct CheckResponse {
+    matches: Vec<Match>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Match {
+    message: String,
+    /// 以下两项按 UTF-16 码元计
+    offset: usize,
+    length: usize,
+    #[serde(default)]
+    replacements: Vec<Replacement>,
+    rule: Rule,
+}
+
+#[derive(Deserialize)]
+struct Replacement {
+    value: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Rule {
+    id: String,
+    #[serde(default)]
+    issue_type: String,
+    category: Category,
+}
+
+#[derive(Deserialize)]
+struct Category {
+    name: String,
+}
+
+/// 去掉标记后的正文，以及每个字符在原文中的字节范围
+struct Prose {
+    text: String,
+    spans: Vec<(usize, usize)>,
+}
+
+impl Prose {
+    fn push(&mut self, c: char, start: usize, end: usize) {
+        // 连续空白合并成一个，换行保留以区分段落
+        if c == ' ' && self.text.ends_with([' ', '\n']) {
+            return;
+        }
+        if c == '\n' && self.text.ends_with(' ') {
+            self.text.pop();
+            self.spans.pop();
+        }
+        self.text.push(c);
+        self.spans.push((start, end));
+    }
+
+    fn push_str(&mut self, s: &str, start: usize, end: usize) {
+        for c in s.chars() {
+            self.push(c, start, end);
+        }
+    }
+}
+
+fn placeholder(markup: &str) -> Option<&'static str> {
+    let markup = markup.trim_start();
+    if markup.starts_with('$') || markup.starts_with("\\(") {
+        return Some(MATH_PLACEHOLDER);
+    }
+    let name: String = markup.strip_prefix('\\')?.chars().take_while(char::is_ascii_alphabetic).collect();
+    if name.to_lowercase().contains("cite") {
+        Some(CITATION_PLACEHOLDER)
+    } else if name.ends_with("ref") {
+        Some(REFERENCE_PLACEHOLDER)
+    } else {
+        None
+    }
+}
+
+/// 对照原文与遮盖后的文本，连续被遮盖的一段视为一处标记
+fn extract_prose(source: &str, masked: &str, latex: bool) -> Prose {
+    let mut prose = Prose { text: String::new(), spans: Vec::new() };
+    let mut markup_start: Option<usize> = None;
+    let flush = |prose: &mut Prose, start: usize, end: usize| {
+        let markup = &source[start..end];
+        if let Some(word) = placeholder(markup) {
+            prose.push_str(word, start, end);
+        }
+        for (offset, c) in markup.char_indices() {
+            if c == '\n' {
+                prose.push('\n', start + offset, start + offset + 1);
+            }
+        }
+    };
+
+    for (index, c) in source.char_indices() {
+        let end = index + c.len_utf8();
+        let blanked = masked.as_bytes()[index..end] != source.as_bytes()[index..end] || (latex && matches!(c, '{' | '}'));
+        if blanked {
+            markup_start.get_or_insert(index);
+            continue;
+        }
+        if let Some(start) = markup_start.take() {
+            flush(&mut prose, start, index);
+        }
+        match c {
+            '~' if latex => prose.push(' ', index, end),
+            '\r' => {}
+            c if c.is_whitespace() && c != '\n' => prose.push(' ', index, end),
+            c => prose.push(c, index, end),
+        }
+    }
+    if let Some(start) = markup_start {
+        flush(&mut prose, start, source.len());
+    }
+    prose
+}
+
+/// 原文字节偏移 -> (行, 列)，均从 1 开始
+fn position(source: &str, line_starts: &[usize], offset: usize) -> (u32, u32) {
+    let line = line_starts.partition_point(|start| *start <= offset).max(1) - 1;
+    let column = source[line_starts[line]..offset].chars().count() as u32 + 1;
+    (line as u32 + 1, column)
+}
+
+fn agent() -> Agent {
+    Agent::config_builder()
+        .timeout_global(Some(REQUEST_TIMEOUT))
+        // 错误响应体里有 LanguageTool 的说明（如文本超长），需要读出来
+        .http_status_as_error(false)
+        .build()
+        .into()
+}
+
+fn request_check(server: &str, text: &str, language: &str) -> Result<CheckResponse, String> {
+    let url = format!("{}/v2/check", server.trim_end_matches('/'));
+    let mut response = agent()
+        .post(&url)
+        .send_form([("text", text), ("language", language)])
+        .map_err(|e| format!("无法连接 LanguageTool 服务 {}: {}", server, e))?;
+    let status = response.status();
+    let body = response
+        .body_mut()
+        .read_to_string()
+        .map_err(|e| format!("读取响应失败: {}", e))?;
+    if !status.is_success() {
+        return Err(format!("LanguageTool 返回错误 ({}): {}", status.as_u16(), body.trim()));
+    }
+    serde_json::from_str(&body).map_err(|e| format!("无法解析 LanguageTool 响应: {}", e))
+}
+
+fn severity_of(issue_type: &str) -> &'static str {
+    match issue_type {
+        "misspelling" | "grammar" => "warning",
+        _ => "info",
+    }
+}
+
+fn check_blocking(source: &str, language: &str, format: Option<&str>, server: &str) -> Result<Vec<GrammarIssue>, String> {
+    let masked = mask_markup(source, format);
+    let prose = extract_prose(source, &masked, format != Some("markdown"));
+    if prose.text.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let response = request_check(server, &prose.text, language)?;
+
+    // LanguageTool 的偏移按 UTF-16 计，先换算成正文中的字符序号
+    let mut utf16_starts = Vec::with_capacity(prose.spans.len() + 1);
+    let mut units = 0;
+    for c in prose.text.chars() {
+        utf16_starts.push(units);
+        units += c.len_utf16();
+    }
+    utf16_starts.push(units);
+    let char_at = |utf16: usize| utf16_starts.partition_point(|start| *start < utf16);
+
+    let line_starts: Vec<usize> =
+        std::iter::once(0).chain(source.match_indices('\n').map(|(i, _)| i + 1)).collect();
+    let issues = response
+        .matches
+        .into_iter()
+        .filter_map(|m| {
+            let first = char_at(m.offset).min(prose.spans.len().checked_sub(1)?);
+            let last = char_at(m.offset + m.length).saturating_sub(1).clamp(first, prose.spans.len() - 1);
+            let (line, column) = position(source, &line_starts, prose.spans[first].0);
+            let (end_line, end_column) = position(source, &line_starts, prose.spans[last].1);
+            Some(GrammarIssue {
+                message: m.message,
+                severity: severity_of(&m.rule.issue_type),
+                rule: m.rule.id,
+                category: m.rule.category.name,
+                issue_type: m.rule.issue_type,
+                line,
+                column,
+                end_line,
+                end_column,
+                replacements: m.replacements.into_iter().take(MAX_REPLACEMENTS).map(|r| r.value).collect(),
+            })
+        })
+        .collect();
+    Ok(issues)
+}
+
+/// 把去掉 LaTeX / Markdown 标记的正文交给 LanguageTool 检查，结果映射回原文位置。
+/// `server` 为空时使用本机服务；`language` 为空时由 LanguageTool 自动识别。
+#[command]
+pub async fn grammar_check(
+    text: String,
+    language: Option<String>,
+    format: Option<String>,
+    server: Option<String>,
+) -> Result<Vec<GrammarIssue>, String> {
+    let language = language.filter(|l| !l.is_empty()).unwrap_or_else(|| "auto".to_string());
+    let server = server.filter(|s| !s.is_empty()).unwrap_or_else(|| DEFAULT_SERVER.to_string());
+    tauri::async_runtime::spawn_blocking(move || check_blocking(&text, &language, format.as_deref(), &server))
+        .await
+        .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,239 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::Serialize;
+use tauri::{command, AppHandle, Manager};
+
+use crate::atomic::{unix_millis, write_atomic};
+use crate::document::content_version;
+use crate::scope::WindowScope;
+
+/// 每个文件最多保留的历史版本数，超出后删除最旧的
+const MAX_VERSIONS: usize = 100;
+const SNAPSHOT_EXTENSION: &str = "snapshot";
+/// 与 unified diff 的惯例一致
+const DIFF_CONTEXT_LINES: usize = 3;
+/// 超过这个规模的差异不再逐行求 LCS，整体视为替换
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+#[derive(Serialize)]
+pub struct HistoryEntry {
+    /// 保存时间（毫秒时间戳），同时作为版本 id
+    id: String,
+    timestamp: u64,
+    size: u64,
+    version: String,
+}
+
+/// 历史目录按源文件路径的哈希划分，文件改名后历史不会跟随。
+fn history_dir(app: &AppHandle, path: &Path) -> Result<PathBuf, String> {
+    let base = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let source = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let key = content_version(source.to_string_lossy().as_bytes());
+    Ok(base.join("history").join(key))
+}
+
+fn snapshot_path(dir: &Path, id: &str) -> Result<PathBuf, String> {
+    // id 来自前端，只接受纯数字，避免拼出目录之外的路径
+    if id.is_empty() || !id.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("无效的历史版本: {}", id));
+    }
+    Ok(dir.join(format!("{}.{}", id, SNAPSHOT_EXTENSION)))
+}
+
+/// 按时间从新到旧列出快照 id
+fn snapshot_ids(dir: &Path) -> Vec<u64> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut ids: Vec<u64> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|p| p.extension().and_then(|e| e.to_str()) == Some(SNAPSHOT_EXTENSION))
+        .filter_map(|p| p.file_stem()?.to_str()?.parse().ok())
+        .collect();
+    ids.sort_by_key(|id| std::cmp::Reverse(*id));
+    ids
+}
+
+/// 保存成功后记录一个版本；内容与最新版本相同则跳过。
+pub fn record(app: &AppHandle, path: &Path, content: &[u8]) -> Result<(), String> {
+    let dir = history_dir(app, path)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("无法创建历史目录: {}", e))?;
+
+    let ids = snapshot_ids(&dir);
+    if let Some(latest) = ids.first() {
+        let latest_path = dir.join(format!("{}.{}", latest, SNAPSHOT_EXTENSION));
+        if fs::read(&latest_path).map(|bytes| bytes == content).unwrap_or(false) {
+            return Ok(());
+        }
+    }
+
+    // 同一毫秒内连续保存时顺延，保证 id 唯一且递增
+    let mut id = unix_millis(SystemTime::now());
+    if let Some(latest) = ids.first() {
+        id = id.max(latest + 1);
+    }
+    let target = dir.join(format!("{}.{}", id, SNAPSHOT_EXTENSION));
+    write_atomic(&target, content).map_err(|e| format!("无法写入历史版本: {}", e))?;
+    // 记录原始路径，便于手动查找历史目录
+    let _ = fs::write(dir.join("source"), path.to_string_lossy().as_bytes());
+
+    for old in ids.iter().skip(MAX_VERSIONS - 1) {
+        let _ = fs::remove_file(dir.join(format!("{}.{}", old, SNAPSHOT_EXTENSION)));
+    }
+    Ok(())
+}
+
+#[command]
+pub fn list_file_history(app: AppHandle, scope: WindowScope, path: String) -> Result<Vec<HistoryEntry>, String> {
+    scope.check(&path)?;
+    let dir = history_dir(&app, Path::new(&path))?;
+    let entries = snapshot_ids(&dir)
+        .into_iter()
+        .filter_map(|id| {
+            let snapshot = dir.join(format!("{}.{}", id, SNAPSHOT_EXTENSION));
+            let bytes = fs::read(&snapshot).ok()?;
+            Some(HistoryEntry {
+                id: id.to_string(),
+                timestamp: id,
+                size: bytes.len() as u64,
+                version: content_version(&bytes),
+            })
+        })
+        .collect();
+    Ok(entries)
+}
+
+#[command]
+pub fn read_history_version(
+    app: AppHandle,
+    scope: WindowScope,
+    path: String,
+    id: String,
+) -> Result<String, String> {
+    scope.check(&path)?;
+    let dir = history_dir(&app, Path::new(&path))?;
+    let snapshot = snapshot_path(&dir, &id)?;
+    fs::read_to_string(&snapshot).map_err(|e| format!("无法读取历史版本: {}", e))
+}
+
+/// 历史版本与磁盘上当前内容之间的 unified diff（历史版本为 `a`，当前文件为 `b`）。
+#[command]
+pub fn diff_history(app: AppHandle, scope: WindowScope, path: String, id: String) -> Result<String, String> {
+    scope.check(&path)?;
+    let source = Path::new(&path);
+    let dir = history_dir(&app, source)?;
+    let snapshot = snapshot_path(&dir, &id)?;
+    let old = fs::read_to_string(&snapshot).map_err(|e| format!("无法读取历史版本: {}", e))?;
+    // 文件已被删除时与空内容比较，方便找回；其他读取错误（如不是 UTF-8）照常返回
+    let new = match fs::read_to_string(source) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("无法读取文件: {}", e)),
+    };
+    let name = source.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| path.clone());
+    Ok(unified_diff(&old, &new, &format!("{}@{}", name, id), &name))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum DiffOp {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// 逐行比较，返回 (操作, 旧行号, 新行号) 序列；行号从 0 开始。
+/// 修订追踪把词切成 `&str` 传进来，按词比较。
+pub(crate) fn diff_lines(old: &[&str], new: &[&str]) -> Vec<(DiffOp, usize, usize)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut ops: Vec<(DiffOp, usize, usize)> = (0..prefix).map(|i| (DiffOp::Equal, i, i)).collect();
+    let (n, m) = (old_mid.len(), new_mid.len());
+    if n * m > MAX_DIFF_CELLS {
+        ops.extend((0..n).map(|i| (DiffOp::Delete, prefix + i, prefix)));
+        ops.extend((0..m).map(|j| (DiffOp::Insert, prefix + n, prefix + j)));
+    } else {
+        // lcs[i][j] = old_mid[i..] 与 new_mid[j..] 的最长公共子序列长度
+        let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i][j] = if old_mid[i] == new_mid[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && old_mid[i] == new_mid[j] {
+                ops.push((DiffOp::Equal, prefix + i, prefix + j));
+                i += 1;
+                j += 1;
+            } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+                // 删除优先于插入，输出顺序与 diff -u 一致
+                ops.push((DiffOp::Delete, prefix + i, prefix + j));
+                i += 1;
+            } else {
+                ops.push((DiffOp::Insert, prefix + i, prefix + j));
+                j += 1;
+            }
+        }
+    }
+    let (old_end, new_end) = (old.len() - suffix, new.len() - suffix);
+    ops.extend((0..suffix).map(|k| (DiffOp::Equal, old_end + k, new_end + k)));
+    ops
+}
+
+/// 生成带 3 行上下文的 unified diff；内容相同时返回空字符串。
+pub fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let ops = diff_lines(&old_lines, &new_lines);
+    if ops.iter().all(|(op, _, _)| *op == DiffOp::Equal) {
+        return String::new();
+    }
+
+    let mut out = format!("--- {}\n+++ {}\n", old_name, new_name);
+    let changes: Vec<usize> = (0..ops.len()).filter(|&k| ops[k].0 != DiffOp::Equal).collect();
+    let mut c = 0;
+    while c < changes.len() {
+        // 相邻改动之间的相同行不超过 2 * 上下文时合并到同一个 hunk
+        let start = changes[c].saturating_sub(DIFF_CONTEXT_LINES);
+        let mut last = changes[c];
+        while c + 1 < changes.len() && changes[c + 1] - last - 1 <= 2 * DIFF_CONTEXT_LINES {
+            c += 1;
+            last = changes[c];
+        }
+        let end = (last + DIFF_CONTEXT_LINES + 1).min(ops.len());
+        c += 1;
+
+        let hunk = &ops[start..end];
+        let old_count = hunk.iter().filter(|(op, _, _)| *op != DiffOp::Insert).count();
+        let new_count = hunk.iter().filter(|(op, _, _)| *op != DiffOp::Delete).count();
+        let (_, old_start, new_start) = hunk[0];
+        // 空范围按惯例写成“前一行, 0”
+        let old_start = if old_count == 0 { old_start } else { old_start + 1 };
+        let new_start = if new_count == 0 { new_start } else { new_start + 1 };
+        out.push_str(&format!("@@ -{},{} +{},{} @@\n", old_start, old_count, new_start, new_count));
+        for &(op, i, j) in hunk {
+            match op {
+                DiffOp::Equal => out.push_str(&format!(" {}\n", old_lines[i])),
+                DiffOp::Delete => out.push_str(&format!("-{}\n", old_lines[i])),
+                DiffOp::Insert => out.push_str(&format!("+{}\n", new_lines[j])),
+            }
+        }
+    }
+    out
+}
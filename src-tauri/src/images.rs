@@ -0,0 +1,559 @@
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{self, Cursor, Read};
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::sync::LazyLock;
+use std::time::UNIX_EPOCH;
+
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+use regex::Regex;
+use resvg::tiny_skia::{Pixmap, Transform};
+use resvg::usvg::{self, Tree};
+use serde::{Deserialize, Serialize};
+use svg2pdf::{ConversionOptions, PageOptions};
+use tauri::{command, AppHandle, Manager};
+
+use crate::atomic::write_atomic;
+use crate::document::content_version;
+use crate::latex::dependencies::dependency_graph;
+use crate::latex::root::{canonical, is_document};
+use crate::pdf::{open_document, pdfium, render_image};
+use crate::scope::WindowScope;
+use crate::workspace::project_walker;
+
+/// 粘贴的图片存放的位置，相对项目根目录
+const FIGURES_DIR: &str = "figures";
+const DEFAULT_IMAGE_NAME: &str = "pasted-image";
+const DEFAULT_JPEG_QUALITY: u8 = 85;
+/// 图片管理器列出的文件
+const FIGURE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "svg", "pdf", "eps"];
+/// 缩略图放进边长为这么多像素的正方形
+const THUMBNAIL_SIZE: u32 = 256;
+/// 在应用缓存目录下，按图片的路径、大小和修改时间命名，图片修改后会生成新的缩略图
+const THUMBNAIL_CACHE_DIR: &str = "figure-thumbnails";
+
+/// Markdown 中的 `![alt](path "title")` 和 `<img src="path">`
+static MARKDOWN_IMAGE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"!\[[^\]]*\]\(\s*<?([^)\s>]+)|<img\b[^>]*\bsrc\s*=\s*["']([^"']+)["']"#).unwrap()
+});
+static BOUNDING_BOX_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"%%BoundingBox:\s*(-?\d+)\s+(-?\d+)\s+(-?\d+)\s+(-?\d+)").unwrap());
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PasteFormat {
+    Png,
+    /// 照片截图的体积小得多；会丢失透明度
+    Jpeg,
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct PasteOptions {
+    pub format: PasteFormat,
+    /// 1-100，只用于 JPEG
+    pub quality: u8,
+    /// 更宽的图片按比例缩小
+    pub max_width: Option<u32>,
+    /// 要插入代码片段的文档。Markdown 文档得到相对它本身的 Markdown 图片；其他文档得到相对项目根目录的
+    /// `\includegraphics`，TeX 在那里解析路径
+    pub document: Option<String>,
+}
+
+impl Default for PasteOptions {
+    fn default() -> Self {
+        PasteOptions { format: PasteFormat::Png, quality: DEFAULT_JPEG_QUALITY, max_width: None, document: None }
+    }
+}
+
+#[derive(Serialize)]
+pub struct PastedImage {
+    path: String,
+    /// 相对文档（Markdown）或项目根目录，以 `/` 分隔
+    relative_path: String,
+    snippet: String,
+}
+
+/// 从 `preferred` 得出的文件名主干：只含 ASCII 字母、数字、`-` 和 `_`，在 TeX 中不需要转义
+fn sanitize_stem(preferred: Option<&str>) -> String {
+    let stem = preferred.map(|name| Path::new(name).file_stem().unwrap_or_default().to_string_lossy().to_string());
+    let stem: String = stem
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+        .collect();
+    let stem = stem.trim_matches('-');
+    if stem.is_empty() {
+        DEFAULT_IMAGE_NAME.to_string()
+    } else {
+        stem.to_string()
+    }
+}
+
+/// `dir/stem.ext`，已被占用时为 `dir/stem-2.ext`、`dir/stem-3.ext` 等
+fn unique_path(dir: &Path, stem: &str, extension: &str) -> PathBuf {
+    let path = dir.join(format!("{}.{}", stem, extension));
+    if !path.exists() {
+        return path;
+    }
+    (2..)
+        .map(|n| dir.join(format!("{}-{}.{}", stem, n, extension)))
+        .find(|path| !path.exists())
+        .unwrap()
+}
+
+/// JPEG 没有 alpha 通道；像截图印在纸上一样合成到白色背景上
+fn flatten_alpha(image: &DynamicImage) -> DynamicImage {
+    let mut background = RgbaImage::from_pixel(image.width(), image.height(), Rgba([255, 255, 255, 255]));
+    image::imageops::overlay(&mut background, &image.to_rgba8(), 0, 0);
+    DynamicImage::ImageRgba8(background)
+}
+
+fn encode(png: &[u8], options: &PasteOptions) -> Result<Vec<u8>, String> {
+    if image::guess_format(png).ok() != Some(ImageFormat::Png) {
+        return Err("剪贴板数据不是 PNG 图片".to_string());
+    }
+    if options.format == PasteFormat::Png && options.max_width.is_none() {
+        return Ok(png.to_vec());
+    }
+    let image = image::load_from_memory_with_format(png, ImageFormat::Png).map_err(|e| format!("无效的图片: {}", e))?;
+    let jpeg = options.format == PasteFormat::Jpeg;
+    encode_raster(limit_width(image, options.max_width), jpeg, options.quality)
+}
+
+fn limit_width(image: DynamicImage, max_width: Option<u32>) -> DynamicImage {
+    match max_width.filter(|max| image.width() > *max) {
+        Some(max_width) => image.resize(max_width, u32::MAX, FilterType::Lanczos3),
+        None => image,
+    }
+}
+
+/// PNG；设置了 `jpeg` 时为质量 `quality`（1-100）的 JPEG
+fn encode_raster(image: DynamicImage, jpeg: bool, quality: u8) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    if jpeg {
+        let encoder = JpegEncoder::new_with_quality(&mut bytes, quality.clamp(1, 100));
+        flatten_alpha(&image).to_rgb8().write_with_encoder(encoder)
+    } else {
+        image.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+    }
+    .map_err(|e| format!("无法编码图片: {}", e))?;
+    Ok(bytes)
+}
+
+/// `path` 相对目录 `base` 的路径，需要时带 `..`
+fn relative_to(base: &Path, path: &Path) -> String {
+    let base: Vec<Component> = base.components().collect();
+    let path: Vec<Component> = path.components().collect();
+    let common = base.iter().zip(&path).take_while(|(a, b)| a == b).count();
+    let parts: Vec<String> = std::iter::repeat_n("..".to_string(), base.len() - common)
+        .chain(path[common..].iter().map(|c| c.as_os_str().to_string_lossy().to_string()))
+        .collect();
+    parts.join("/")
+}
+
+/// 把剪贴板或拖放的图片数据以不重名的文件名存到 `<root>/figures/`，返回引用它的代码片段
+#[command]
+pub fn save_pasted_image(
+    scope: WindowScope,
+    root: String,
+    png_bytes: Vec<u8>,
+    preferred_name: Option<String>,
+    options: Option<PasteOptions>,
+) -> Result<PastedImage, String> {
+    scope.check(&root)?;
+    let options = options.unwrap_or_default();
+    let root = Path::new(&root);
+    let markdown_doc = options
+        .document
+        .as_deref()
+        .map(Path::new)
+        .filter(|doc| doc.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("md")));
+
+    let bytes = encode(&png_bytes, &options)?;
+    let dir = root.join(FIGURES_DIR);
+    fs::create_dir_all(&dir).map_err(|e| format!("无法创建目录: {}", e))?;
+    let extension = match options.format {
+        PasteFormat::Png => "png",
+        PasteFormat::Jpeg => "jpg",
+    };
+    let path = unique_path(&dir, &sanitize_stem(preferred_name.as_deref()), extension);
+    write_atomic(&path, &bytes).map_err(|e| format!("无法写入文件: {}", e))?;
+
+    let base = markdown_doc.and_then(Path::parent).unwrap_or(root);
+    let relative_path = relative_to(base, &path);
+    let snippet = if markdown_doc.is_some() {
+        let alt = path.file_stem().unwrap_or_default().to_string_lossy();
+        format!("![{}]({})", alt, relative_path)
+    } else {
+        format!("\\includegraphics[width=\\linewidth]{{{}}}", relative_path)
+    };
+    Ok(PastedImage { path: path.to_string_lossy().to_string(), relative_path, snippet })
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageTarget {
+    Pdf,
+    Png,
+    Jpeg,
+}
+
+impl ImageTarget {
+    fn extension(self) -> &'static str {
+        match self {
+            ImageTarget::Pdf => "pdf",
+            ImageTarget::Png => "png",
+            ImageTarget::Jpeg => "jpg",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct ConvertOptions {
+    /// 默认为换成目标扩展名的源文件
+    pub output_path: Option<String>,
+    pub overwrite: bool,
+    /// 只用于光栅输出；更宽的图片会缩小
+    pub max_width: Option<u32>,
+    /// 1-100，只用于 JPEG
+    pub quality: u8,
+    /// SVG 转 PNG：每个 CSS 像素对应的像素数
+    pub scale: f32,
+}
+
+impl Default for ConvertOptions {
+    fn default() -> Self {
+        ConvertOptions { output_path: None, overwrite: false, max_width: None, quality: DEFAULT_JPEG_QUALITY, scale: 2.0 }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ConvertedImage {
+    path: String,
+    size: u64,
+}
+
+/// `resources_dir` 是相对 `href` 所指向的目录
+fn parse_svg(data: &[u8], resources_dir: Option<&Path>) -> Result<Tree, String> {
+    let mut options = usvg::Options { resources_dir: resources_dir.map(Path::to_path_buf), ..usvg::Options::default() };
+    options.fontdb_mut().load_system_fonts();
+    Tree::from_data(data, &options).map_err(|e| format!("无效的 SVG: {}", e))
+}
+
+fn svg_tree(src: &Path) -> Result<Tree, String> {
+    let data = fs::read(src).map_err(|e| format!("无法读取文件: {}", e))?;
+    parse_svg(&data, src.parent())
+}
+
+/// 与 SVG 同样大小的单页 PDF，文字仍保留为文字
+fn svg_to_pdf(tree: &Tree) -> Result<Vec<u8>, String> {
+    svg2pdf::to_pdf(tree, ConversionOptions::default(), PageOptions::default())
+        .map_err(|e| format!("无法转换 SVG: {}", e))
+}
+
+/// 内存中 SVG（如渲染好的图）的 `svg_to_pdf`
+pub fn svg_data_to_pdf(data: &[u8]) -> Result<Vec<u8>, String> {
+    svg_to_pdf(&parse_svg(data, None)?)
+}
+
+fn render_svg(tree: &Tree, scale: f32) -> Result<DynamicImage, String> {
+    let size = tree.size().to_int_size().scale_by(scale).ok_or("无效的 SVG 尺寸")?;
+    let mut pixmap = Pixmap::new(size.width(), size.height()).ok_or("SVG 太大，无法渲染")?;
+    resvg::render(tree, Transform::from_scale(scale, scale), &mut pixmap.as_mut());
+    let pixels = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    let image = RgbaImage::from_raw(size.width(), size.height(), pixels).ok_or("SVG 太大，无法渲染")?;
+    Ok(DynamicImage::ImageRgba8(image))
+}
+
+/// 运行 `programs` 中第一个存在的程序；其余是同一工具的别名（Windows 上 Ghostscript 叫 `gswin64c`）
+fn run_tool(programs: &[&str], args: &[&OsStr]) -> Result<(), String> {
+    for program in programs {
+        match Command::new(program).args(args).output() {
+            Ok(output) if output.status.success() => return Ok(()),
+            Ok(output) => {
+                return Err(format!("{} error:\n{}", program, String::from_utf8_lossy(&output.stderr).trim()));
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(format!("{} 运行失败: {}", program, e)),
+        }
+    }
+    Err(format!("未找到 {}；转换这张图片需要安装它", programs[0]))
+}
+
+/// 用 Ghostscript 把 EPS 转为 PDF，像 `epstopdf` 一样裁剪到边界框
+fn eps_to_pdf(src: &Path, dest: &Path) -> Result<(), String> {
+    let output = format!("-sOutputFile={}", dest.to_string_lossy());
+    let args: [&OsStr; 8] = [
+        "-q".as_ref(),
+        "-dNOPAUSE".as_ref(),
+        "-dBATCH".as_ref(),
+        "-dSAFER".as_ref(),
+        "-dEPSCrop".as_ref(),
+        "-sDEVICE=pdfwrite".as_ref(),
+        output.as_ref(),
+        src.as_os_str(),
+    ];
+    run_tool(&["gs", "gswin64c"], &args)
+}
+
+/// HEIC 没有纯 Rust 的解码器：macOS 自带 `sips`，其他系统用 libheif 附带的 `heif-convert`。
+/// 解码成 `dest` 旁边的临时 PNG
+fn decode_heic(src: &Path, dest: &Path) -> Result<DynamicImage, String> {
+    let temp = dest.with_extension("heic-decode.png");
+    let result = if cfg!(target_os = "macos") {
+        let args: [&OsStr; 6] = ["-s".as_ref(), "format".as_ref(), "png".as_ref(), src.as_os_str(), "--out".as_ref(), temp.as_os_str()];
+        run_tool(&["sips"], &args)
+    } else {
+        run_tool(&["heif-convert"], &[src.as_os_str(), temp.as_os_str()])
+    }
+    .and_then(|()| image::open(&temp).map_err(|e| format!("无效的图片: {}", e)));
+    fs::remove_file(&temp).ok();
+    result
+}
+
+fn convert_blocking(src: &Path, target: ImageTarget, options: &ConvertOptions, dest: &Path) -> Result<(), String> {
+    let source_ext = src.extension().map(|ext| ext.to_string_lossy().to_lowercase()).unwrap_or_default();
+    let raster = match (source_ext.as_str(), target) {
+        ("svg", ImageTarget::Pdf) => {
+            let pdf = svg_to_pdf(&svg_tree(src)?)?;
+            return write_atomic(dest, &pdf).map(|_| ()).map_err(|e| format!("无法写入文件: {}", e));
+        }
+        ("eps" | "ps", ImageTarget::Pdf) => return eps_to_pdf(src, dest),
+        (_, ImageTarget::Pdf) => return Err(format!("无法把 .{} 转换为 PDF", source_ext)),
+        ("svg", _) => render_svg(&svg_tree(src)?, options.scale)?,
+        ("heic" | "heif", _) => decode_heic(src, dest)?,
+        _ => image::open(src).map_err(|e| format!("无效的图片: {}", e))?,
+    };
+    let bytes = encode_raster(limit_width(raster, options.max_width), target == ImageTarget::Jpeg, options.quality)?;
+    write_atomic(dest, &bytes).map(|_| ()).map_err(|e| format!("无法写入文件: {}", e))
+}
+
+/// 把图片转换成 TeX 引擎能直接包含的格式：SVG 和 EPS 转为 PDF，任意光栅图（包括 HEIC）转为缩放后的 PNG/JPEG
+#[command]
+pub async fn convert_image(
+    scope: WindowScope,
+    src: String,
+    format: ImageTarget,
+    options: Option<ConvertOptions>,
+) -> Result<ConvertedImage, String> {
+    let options = options.unwrap_or_default();
+    let src = PathBuf::from(src);
+    let dest = match &options.output_path {
+        Some(path) => PathBuf::from(path),
+        None => src.with_extension(format.extension()),
+    };
+    scope.check(&src)?;
+    scope.check(&dest)?;
+    if dest == src {
+        return Err("输出会覆盖源图片".to_string());
+    }
+    if dest.exists() && !options.overwrite {
+        return Err(format!("文件已存在: {}", dest.to_string_lossy()));
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        convert_blocking(&src, format, &options, &dest)?;
+        let size = fs::metadata(&dest).map(|m| m.len()).unwrap_or(0);
+        Ok(ConvertedImage { path: dest.to_string_lossy().to_string(), size })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 使用某张图片的文件，及使用它的行（从 1 开始）
+#[derive(Serialize)]
+pub struct FigureReference {
+    file: String,
+    line: u32,
+}
+
+#[derive(Serialize)]
+pub struct Figure {
+    path: String,
+    /// 相对项目根目录，以 `/` 分隔
+    relative_path: String,
+    /// 小写的扩展名
+    format: String,
+    /// 光栅图为像素，PDF 和 EPS 为点，SVG 为 CSS 像素；文件无法读取时没有
+    width: Option<f32>,
+    height: Option<f32>,
+    size: u64,
+    /// 应用缓存中的 PNG，可通过 asset 协议读取；无法在这里渲染的格式（EPS）没有
+    thumbnail: Option<String>,
+    /// 包含这张图片的文档；未使用的图片为空
+    references: Vec<FigureReference>,
+}
+
+/// 取自 EPS 文件头中的 `%%BoundingBox` 注释
+fn eps_dimensions(path: &Path) -> Option<(f32, f32)> {
+    let mut header = Vec::new();
+    fs::File::open(path).ok()?.take(16 * 1024).read_to_end(&mut header).ok()?;
+    let header = String::from_utf8_lossy(&header);
+    let caps = BOUNDING_BOX_RE.captures(&header)?;
+    let number = |i: usize| caps[i].parse::<f32>().ok();
+    Some((number(3)? - number(1)?, number(4)? - number(2)?))
+}
+
+fn dimensions(app: &AppHandle, path: &Path, format: &str) -> Option<(f32, f32)> {
+    match format {
+        "svg" => {
+            let size = svg_tree(path).ok()?.size();
+            Some((size.width(), size.height()))
+        }
+        "pdf" => {
+            let document = open_document(pdfium(app).ok()?, &path.to_string_lossy()).ok()?;
+            let page = document.pages().get(0).ok()?;
+            Some((page.width().value, page.height().value))
+        }
+        "eps" => eps_dimensions(path),
+        _ => image::image_dimensions(path).ok().map(|(width, height)| (width as f32, height as f32)),
+    }
+}
+
+/// 让 `width` x `height` 放进缩略图正方形的缩放比例
+fn thumbnail_scale(width: f32, height: f32) -> f32 {
+    THUMBNAIL_SIZE as f32 / width.max(height).max(1.0)
+}
+
+fn render_thumbnail(app: &AppHandle, path: &Path, format: &str) -> Result<DynamicImage, String> {
+    let image = match format {
+        "svg" => {
+            let tree = svg_tree(path)?;
+            let size = tree.size();
+            render_svg(&tree, thumbnail_scale(size.width(), size.height()))?
+        }
+        "pdf" => {
+            let document = open_document(pdfium(app)?, &path.to_string_lossy())?;
+            let page = document.pages().get(0).map_err(|e| format!("无法加载第 1 页: {}", e))?;
+            render_image(&page, 1, thumbnail_scale(page.width().value, page.height().value))?
+        }
+        "png" | "jpg" | "jpeg" | "gif" => image::open(path).map_err(|e| format!("无效的图片: {}", e))?,
+        _ => return Err(format!("不支持为 .{} 文件生成缩略图", format)),
+    };
+    Ok(image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE))
+}
+
+/// `path` 的缓存缩略图，文件的这个版本还没有缩略图时先渲染。无法渲染的格式为 None。
+/// 缩略图命名为 `<路径哈希>-<版本哈希>.png`，渲染新版本时删除旧版本的缩略图
+fn thumbnail(app: &AppHandle, cache: &Path, path: &Path, format: &str) -> Option<PathBuf> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_nanos();
+    let path_key = content_version(path.to_string_lossy().as_bytes());
+    let version_key = content_version(format!("{}\0{}", metadata.len(), modified).as_bytes());
+    let name = format!("{}-{}.png", &path_key[..16], &version_key[..16]);
+    let thumbnail = cache.join(&name);
+    if !thumbnail.is_file() {
+        let png = render_thumbnail(app, path, format).and_then(|image| encode_raster(image, false, 0)).ok()?;
+        fs::create_dir_all(cache).ok()?;
+        write_atomic(&thumbnail, &png).ok()?;
+        let stale_prefix = format!("{}-", &path_key[..16]);
+        for entry in fs::read_dir(cache).ok()?.flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if file_name.starts_with(&stale_prefix) && file_name != name {
+                fs::remove_file(entry.path()).ok();
+            }
+        }
+    }
+    app.asset_protocol_scope().allow_file(&thumbnail).ok()?;
+    Some(thumbnail)
+}
+
+/// `files` 中每个 `.tex` 文档和 Markdown 文件用到的图片，按规范路径
+fn figure_references(files: &[PathBuf]) -> HashMap<PathBuf, Vec<FigureReference>> {
+    let mut references: HashMap<PathBuf, Vec<FigureReference>> = HashMap::new();
+    let mut add = |figure: &Path, file: &Path, line: u32| {
+        let entry = references.entry(canonical(figure)).or_default();
+        let file = file.to_string_lossy().to_string();
+        if !entry.iter().any(|known| known.file == file && known.line == line) {
+            entry.push(FigureReference { file, line });
+        }
+    };
+    for path in files.iter().filter(|path| has_extension(path, &["tex", "md"])) {
+        let Ok(content) = fs::read_to_string(path) else {
+            continue;
+        };
+        if has_extension(path, &["tex"]) {
+            if !is_document(&content) {
+                continue;
+            }
+            // 与未使用资源一样，每个完整的文档都算作根文档，图片路径按 TeX 的方式解析
+            for (file, figure, line) in dependency_graph(path).references() {
+                add(&figure, &file, line);
+            }
+        } else {
+            let dir = path.parent().unwrap_or(Path::new("."));
+            for (i, line) in content.lines().enumerate() {
+                for caps in MARKDOWN_IMAGE_RE.captures_iter(line) {
+                    let target = caps.get(1).or(caps.get(2)).unwrap().as_str();
+                    if !target.contains("://") && !target.starts_with("data:") {
+                        add(&dir.join(target), path, i as u32 + 1);
+                    }
+                }
+            }
+        }
+    }
+    references
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+}
+
+fn list_figures_blocking(app: &AppHandle, root: &Path, cache: &Path) -> Result<Vec<Figure>, String> {
+    if !root.is_dir() {
+        return Err(format!("无法读取目录: {}", root.to_string_lossy()));
+    }
+    let mut files: Vec<PathBuf> = project_walker(root)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| entry.into_path())
+        .collect();
+    files.sort();
+    let mut references = figure_references(&files);
+    let figures = files
+        .iter()
+        .filter(|path| has_extension(path, FIGURE_EXTENSIONS))
+        // 源文件旁边编译出的 PDF 不算图片
+        .filter(|path| !(has_extension(path, &["pdf"]) && path.with_extension("tex").is_file()))
+        .map(|path| {
+            let format = path.extension().unwrap_or_default().to_string_lossy().to_lowercase();
+            let size = dimensions(app, path, &format);
+            Figure {
+                path: path.to_string_lossy().to_string(),
+                relative_path: relative_to(root, path),
+                width: size.map(|(width, _)| width),
+                height: size.map(|(_, height)| height),
+                size: fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+                thumbnail: thumbnail(app, cache, path, &format).map(|path| path.to_string_lossy().to_string()),
+                references: references.remove(&canonical(path)).unwrap_or_default(),
+                format,
+            }
+        })
+        .collect();
+    Ok(figures)
+}
+
+/// `root` 中的每张图片，附尺寸、缩略图和使用它的文档，用于图片管理器
+#[command]
+pub async fn list_figures(app: AppHandle, scope: WindowScope, root: String) -> Result<Vec<Figure>, String> {
+    scope.check(&root)?;
+    let cache = app.path().app_cache_dir().map_err(|e| e.to_string())?.join(THUMBNAIL_CACHE_DIR);
+    tauri::async_runtime::spawn_blocking(move || list_figures_blocking(&app, Path::new(&root), &cache))
+        .await
+        .map_err(|e| e.to_string())?
+}
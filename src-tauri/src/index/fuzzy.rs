@@ -0,0 +1,160 @@
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tauri::{command, AppHandle, Manager};
+
+use super::ProjectIndex;
+use crate::latex::root::canonical;
+use crate::scope::WindowScope;
+
+const DEFAULT_LIMIT: usize = 50;
+
+// fzf 的打分：每个匹配字符得 16 分，出现间隔扣 3 分、间隔每多一个字符再扣 1 分，匹配在词首另有加分
+const SCORE_MATCH: i32 = 16;
+const GAP_START: i32 = -3;
+const GAP_EXTENSION: i32 = -1;
+const BONUS_BOUNDARY: i32 = 8;
+/// `/` 之后：目录名或文件名的开头
+const BONUS_PATH_DELIMITER: i32 = 9;
+const BONUS_CAMEL: i32 = 7;
+const BONUS_CONSECUTIVE: i32 = 4;
+/// 查询第一个字符的加分按两倍计
+const FIRST_CHAR_MULTIPLIER: i32 = 2;
+/// 比这更长的路径直接跳过；打分表随长度增长
+const MAX_PATH_CHARS: usize = 512;
+
+#[derive(Serialize)]
+pub struct FuzzyMatch {
+    path: String,
+    /// 相对于根目录，以 `/` 分隔；`positions` 就是它的下标
+    relative: String,
+    score: i32,
+    /// 匹配到的查询字符在 `relative` 中的字符下标
+    positions: Vec<u32>,
+}
+
+fn bonus(prev: Option<char>, current: char) -> i32 {
+    match prev {
+        None => BONUS_PATH_DELIMITER,
+        Some('/') => BONUS_PATH_DELIMITER,
+        Some(c) if c.is_whitespace() || matches!(c, '_' | '-' | '.' | ',' | ':' | ';') => BONUS_BOUNDARY,
+        Some(c) if c.is_lowercase() && current.is_uppercase() => BONUS_CAMEL,
+        Some(c) if !c.is_numeric() && current.is_numeric() => BONUS_CAMEL,
+        _ => 0,
+    }
+}
+
+/// `query` 作为子序列在 `text` 中的最佳对齐，像 fzf 的 v2 算法一样对（查询字符，文本字符）做动态规划。
+/// 返回得分和匹配到的字符下标
+pub fn score(query: &[char], text: &[char], case_sensitive: bool) -> Option<(i32, Vec<u32>)> {
+    let (m, n) = (query.len(), text.len());
+    if m == 0 || m > n {
+        return None;
+    }
+    let eq = |a: char, b: char| if case_sensitive { a == b } else { a.to_lowercase().eq(b.to_lowercase()) };
+    let bonuses: Vec<i32> = (0..n).map(|j| bonus(j.checked_sub(1).map(|k| text[k]), text[j])).collect();
+
+    // best[i][j]：query[i] 匹配在 text[j] 时的最高得分；from[i][j]：这条路径上 query[i - 1] 匹配的文本下标
+    let mut best = vec![vec![i32::MIN; n]; m];
+    let mut from = vec![vec![usize::MAX; n]; m];
+    for i in 0..m {
+        // 至少往前两个字符的最佳前驱，已计入到达 `j` 的间隔扣分
+        let mut gapped: Option<(i32, usize)> = None;
+        for j in i..n {
+            if i > 0 && j >= 2 {
+                let k = j - 2;
+                let extended = gapped.map(|(score, k)| (score + GAP_EXTENSION, k));
+                let opened = (best[i - 1][k] != i32::MIN).then(|| (best[i - 1][k] + GAP_START, k));
+                gapped = match (extended, opened) {
+                    (Some(a), Some(b)) => Some(if b.0 >= a.0 { b } else { a }),
+                    (a, b) => a.or(b),
+                };
+            }
+            if !eq(query[i], text[j]) {
+                continue;
+            }
+            let own = SCORE_MATCH + if i == 0 { bonuses[j] * FIRST_CHAR_MULTIPLIER } else { bonuses[j] };
+            if i == 0 {
+                best[i][j] = own;
+                continue;
+            }
+            let consecutive = (j >= 1 && best[i - 1][j - 1] != i32::MIN)
+                .then(|| (best[i - 1][j - 1] + BONUS_CONSECUTIVE.max(bonuses[j]), j - 1));
+            let candidate = match (consecutive, gapped) {
+                (Some(a), Some(b)) => Some(if a.0 >= b.0 { a } else { b }),
+                (a, b) => a.or(b),
+            };
+            if let Some((previous, k)) = candidate {
+                best[i][j] = previous + own;
+                from[i][j] = k;
+            }
+        }
+    }
+
+    let (end, &total) =
+        best[m - 1].iter().enumerate().filter(|(_, score)| **score != i32::MIN).max_by_key(|(_, score)| **score)?;
+    let mut positions = vec![0u32; m];
+    let mut j = end;
+    for i in (0..m).rev() {
+        positions[i] = j as u32;
+        j = from[i][j];
+    }
+    Some((total, positions))
+}
+
+fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root).unwrap_or(path).to_string_lossy().replace('\\', "/")
+}
+
+fn find_blocking(root: &Path, files: Vec<PathBuf>, query: &str, limit: usize) -> Vec<FuzzyMatch> {
+    let query: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).collect();
+    // 与 fzf 一样的智能大小写：查询含大写字母时区分大小写
+    let case_sensitive = query.iter().any(|c| c.is_uppercase());
+    let mut matches: Vec<FuzzyMatch> = files
+        .iter()
+        .filter_map(|path| {
+            let relative = relative_path(root, path);
+            if query.is_empty() {
+                return Some(FuzzyMatch { path: path.to_string_lossy().to_string(), relative, score: 0, positions: Vec::new() });
+            }
+            let text: Vec<char> = relative.chars().collect();
+            if text.len() > MAX_PATH_CHARS {
+                return None;
+            }
+            let (score, positions) = score(&query, &text, case_sensitive)?;
+            Some(FuzzyMatch { path: path.to_string_lossy().to_string(), relative, score, positions })
+        })
+        .collect();
+    // 得分相同时路径短的在前，再按字母顺序
+    matches.sort_by(|a, b| {
+        b.score.cmp(&a.score).then(a.relative.len().cmp(&b.relative.len())).then_with(|| a.relative.cmp(&b.relative))
+    });
+    matches.truncate(limit);
+    matches
+}
+
+/// 快速打开：像 fzf 一样按 `query` 为项目文件排序，并给出匹配字符的位置供高亮。
+/// 使用索引的文件列表，由 watcher 保持更新
+#[command]
+pub async fn fuzzy_find_files(
+    app: AppHandle,
+    scope: WindowScope,
+    root: String,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<FuzzyMatch>, String> {
+    scope.check(&root)?;
+    let root = PathBuf::from(root);
+    if !root.is_dir() {
+        return Err(format!("无法读取目录: {}", root.to_string_lossy()));
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let index = app.state::<ProjectIndex>().get_or_build(&root);
+        let files: Vec<PathBuf> = index.read().unwrap().paths().cloned().collect();
+        // 索引里存的是规范路径
+        let root = canonical(&root);
+        find_blocking(&root, files, &query, limit.unwrap_or(DEFAULT_LIMIT))
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
@@ -0,0 +1,233 @@
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Serialize;
+use tauri::{command, AppHandle, Manager};
+
+use super::ProjectIndex;
+use crate::latex::strip_comment;
+use crate::scope::WindowScope;
+
+/// 从中收集定义的文件
+const MACRO_EXTENSIONS: &[&str] = &["tex", "sty", "cls"];
+/// 超过这个长度的定义会被截断；它们显示在悬停提示和补全详情里
+const MAX_DEFINITION_CHARS: usize = 400;
+
+/// 定义命令和名字；其余部分（`[n][default]{body}`）手动读取，因为定义体会嵌套花括号并跨行
+static DEFINITION_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"\\(?P<command>(?:re)?newcommand|providecommand|DeclareRobustCommand|DeclareMathOperator)\*?\s*",
+        r"(?:\{\s*\\(?P<braced>[A-Za-z@]+)\s*\}|\\(?P<bare>[A-Za-z@]+))",
+        r"|\\(?P<def>[gex]?def)\s*\\(?P<def_name>[A-Za-z@]+)",
+        r"|\\(?P<env_command>(?:re)?newenvironment)\*?\s*\{(?P<env_name>[^}]+)\}",
+    ))
+    .unwrap()
+});
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MacroKind {
+    Command,
+    Environment,
+    MathOperator,
+}
+
+/// 某个文件中的一个定义
+#[derive(Clone)]
+pub struct MacroDefinition {
+    /// 不含反斜杠
+    pub name: String,
+    pub kind: MacroKind,
+    /// `newcommand`、`def`、`DeclareMathOperator` 等
+    pub command: String,
+    pub arguments: u8,
+    /// 第一个参数为可选参数时的默认值
+    pub optional_default: Option<String>,
+    /// 替换文本；环境则为 begin 部分的代码
+    pub definition: String,
+    /// 从 1 开始
+    pub line: u32,
+}
+
+#[derive(Serialize)]
+pub struct UserMacro {
+    name: String,
+    kind: MacroKind,
+    command: String,
+    arguments: u8,
+    optional_default: Option<String>,
+    definition: String,
+    file: String,
+    line: u32,
+}
+
+impl UserMacro {
+    pub fn new(path: &Path, definition: &MacroDefinition) -> Self {
+        UserMacro {
+            name: definition.name.clone(),
+            kind: definition.kind,
+            command: definition.command.clone(),
+            arguments: definition.arguments,
+            optional_default: definition.optional_default.clone(),
+            definition: definition.definition.clone(),
+            file: path.to_string_lossy().to_string(),
+            line: definition.line,
+        }
+    }
+}
+
+fn skip_whitespace(text: &str, at: usize) -> usize {
+    at + text[at..].len() - text[at..].trim_start().len()
+}
+
+/// `at` 处（跳过空白）的 `[...]`：返回其内容和之后的下标
+fn bracketed(text: &str, at: usize) -> Option<(String, usize)> {
+    let start = skip_whitespace(text, at);
+    if !text[start..].starts_with('[') {
+        return None;
+    }
+    let end = start + text[start..].find(']')?;
+    Some((text[start + 1..end].trim().to_string(), end + 1))
+}
+
+/// `at` 处（跳过空白）可嵌套花括号的 `{...}`：返回其内容和之后的下标。其中的注释原样保留
+pub(super) fn braced(text: &str, at: usize) -> Option<(String, usize)> {
+    let start = skip_whitespace(text, at);
+    if !text[start..].starts_with('{') {
+        return None;
+    }
+    let mut depth = 0;
+    let mut escaped = false;
+    for (i, c) in text[start..].char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((text[start + 1..start + i].to_string(), start + i + 1));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn shorten(text: &str) -> String {
+    let text = text.trim();
+    if text.chars().count() <= MAX_DEFINITION_CHARS {
+        return text.to_string();
+    }
+    let mut short: String = text.chars().take(MAX_DEFINITION_CHARS).collect();
+    short.push('…');
+    short
+}
+
+/// `\newcommand` 类名字之后的 `[n][default]{body}`
+fn command_parts(text: &str, at: usize) -> (u8, Option<String>, String) {
+    let mut at = at;
+    let mut arguments = 0;
+    if let Some((count, next)) = bracketed(text, at) {
+        arguments = count.parse().unwrap_or(0);
+        at = next;
+    }
+    let mut optional_default = None;
+    if let Some((default, next)) = bracketed(text, at).filter(|_| arguments > 0) {
+        optional_default = Some(default);
+        at = next;
+    }
+    let definition = braced(text, at).map(|(body, _)| body).unwrap_or_default();
+    (arguments, optional_default, definition)
+}
+
+/// `\def` 名字之后的 `#1#2{body}`
+fn def_parts(text: &str, at: usize) -> (u8, String) {
+    let Some(open) = text[at..].find('{') else {
+        return (0, String::new());
+    };
+    let parameters = &text[at..at + open];
+    let arguments = parameters.matches('#').count().min(9) as u8;
+    let definition = braced(text, at + open).map(|(body, _)| body).unwrap_or_default();
+    (arguments, definition)
+}
+
+/// `.tex`、`.sty` 或 `.cls` 文件中的所有 `\newcommand`、`\def`、`\newenvironment` 和 `\DeclareMathOperator`
+pub fn extract_macros(path: &Path, content: &str) -> Vec<MacroDefinition> {
+    if !path.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| MACRO_EXTENSIONS.contains(&ext)) {
+        return Vec::new();
+    }
+    // 定义体会跨行，所以对去掉注释的整个文件做匹配（保留换行，行号仍然正确）
+    let text: String = content.lines().map(|line| format!("{}\n", strip_comment(line))).collect();
+    let line_of = |byte: usize| text[..byte].matches('\n').count() as u32 + 1;
+
+    let mut definitions = Vec::new();
+    for caps in DEFINITION_RE.captures_iter(&text) {
+        let whole = caps.get(0).unwrap();
+        let definition = if let Some(name) = caps.name("braced").or_else(|| caps.name("bare")) {
+            let command = caps["command"].to_string();
+            let (arguments, optional_default, body) = command_parts(&text, whole.end());
+            let kind = if command == "DeclareMathOperator" { MacroKind::MathOperator } else { MacroKind::Command };
+            MacroDefinition {
+                name: name.as_str().to_string(),
+                kind,
+                command,
+                arguments,
+                optional_default,
+                definition: shorten(&body),
+                line: line_of(whole.start()),
+            }
+        } else if let Some(name) = caps.name("def_name") {
+            let (arguments, body) = def_parts(&text, whole.end());
+            MacroDefinition {
+                name: name.as_str().to_string(),
+                kind: MacroKind::Command,
+                command: caps["def"].to_string(),
+                arguments,
+                optional_default: None,
+                definition: shorten(&body),
+                line: line_of(whole.start()),
+            }
+        } else {
+            let (arguments, optional_default, body) = command_parts(&text, whole.end());
+            MacroDefinition {
+                name: caps["env_name"].trim().to_string(),
+                kind: MacroKind::Environment,
+                command: caps["env_command"].to_string(),
+                arguments,
+                optional_default,
+                definition: shorten(&body),
+                line: line_of(whole.start()),
+            }
+        };
+        definitions.push(definition);
+    }
+    definitions
+}
+
+/// `root` 中任意位置（包括本地 `.sty` 和 `.cls` 文件）定义的自定义命令和环境，按名字排序。
+/// 定义了两次的名字列出两次
+#[command]
+pub async fn list_user_macros(
+    app: AppHandle,
+    scope: WindowScope,
+    root: String,
+) -> Result<Vec<UserMacro>, String> {
+    scope.check(&root)?;
+    let root = PathBuf::from(root);
+    if !root.is_dir() {
+        return Err(format!("无法读取目录: {}", root.to_string_lossy()));
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let index = app.state::<ProjectIndex>().get_or_build(&root);
+        let index = index.read().unwrap();
+        let mut macros: Vec<UserMacro> =
+            index.macros().map(|(path, definition)| UserMacro::new(path, definition)).collect();
+        macros.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.file.cmp(&b.file)).then(a.line.cmp(&b.line)));
+        macros
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
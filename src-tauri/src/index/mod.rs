@@ -0,0 +1,530 @@
+pub mod fuzzy;
+pub mod macros;
+pub mod symbols;
+pub mod todos;
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Manager};
+
+use crate::bibliography::parser::{parse_bib, BibFile};
+use crate::latex::references::{scan_file, FileSymbols};
+use crate::latex::root::canonical;
+use crate::scope::WindowScope;
+use crate::search::{build_matcher, read_text_file, truncate_snippet, SearchOptions};
+use crate::workspace::project_walker;
+use macros::{extract_macros, MacroDefinition};
+use symbols::{extract_symbols, FileSymbol};
+
+/// 更大的文件只列出不建索引：那是生成的输出或数据，不是要搜索的内容
+const MAX_INDEXED_FILE_SIZE: u64 = 4 * 1024 * 1024;
+const DEFAULT_MAX_RESULTS: usize = 500;
+/// 命中次数相同时，源文件排在其他文本文件之前
+const SOURCE_EXTENSIONS: &[&str] = &["tex", "md", "bib", "sty", "cls"];
+
+type FileId = u32;
+
+/// 三个字节，转为 ASCII 小写后合成一个键
+fn trigram(bytes: &[u8]) -> u32 {
+    let lower = |b: u8| b.to_ascii_lowercase() as u32;
+    (lower(bytes[0]) << 16) | (lower(bytes[1]) << 8) | lower(bytes[2])
+}
+
+/// 每一行的三元组。查询不会跨行，含换行的窗口跳过
+fn trigrams_of(text: &str) -> HashSet<u32> {
+    text.as_bytes().windows(3).filter(|window| !window.contains(&b'\n')).map(trigram).collect()
+}
+
+/// 匹配必须包含的三元组。含非 ASCII 字节的窗口不算：索引里没有合并它们的大小写变体
+fn query_trigrams(query: &str) -> HashSet<u32> {
+    query.as_bytes().windows(3).filter(|window| window.is_ascii()).map(trigram).collect()
+}
+
+struct IndexedFile {
+    path: PathBuf,
+    content: String,
+    symbols: Vec<FileSymbol>,
+    macros: Vec<MacroDefinition>,
+    /// `.tex` 文件的标签和引用
+    references: Option<Arc<FileSymbols>>,
+    /// `.bib` 文件的条目
+    bibliography: Option<BibFile>,
+}
+
+/// 修改时间和大小：文件戳没变的 watcher 事件（编辑器保存一次常会报告好几次）直接跳过
+type Stamp = (SystemTime, u64);
+
+fn stamp_of(path: &Path) -> Option<Stamp> {
+    let metadata = path.metadata().ok().filter(|metadata| metadata.is_file())?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+/// 一个项目的文件列表、其中的文本文件，以及它们的三元组倒排表
+#[derive(Default)]
+pub struct RootIndex {
+    /// 项目遍历器访问到的所有文件，文本与否都在内
+    paths: HashSet<PathBuf>,
+    stamps: HashMap<PathBuf, Stamp>,
+    files: HashMap<FileId, IndexedFile>,
+    ids: HashMap<PathBuf, FileId>,
+    postings: HashMap<u32, HashSet<FileId>>,
+    next_id: FileId,
+}
+
+impl RootIndex {
+    fn insert(&mut self, path: PathBuf, content: String) {
+        self.remove(&path);
+        let id = self.next_id;
+        self.next_id += 1;
+        for key in trigrams_of(&content) {
+            self.postings.entry(key).or_default().insert(id);
+        }
+        let symbols = extract_symbols(&path, &content);
+        let macros = extract_macros(&path, &content);
+        let references = has_extension(&path, "tex").then(|| Arc::new(scan_file(&path, &content)));
+        let bibliography = has_extension(&path, "bib").then(|| parse_bib(&content));
+        self.ids.insert(path.clone(), id);
+        self.files.insert(id, IndexedFile { path, content, symbols, macros, references, bibliography });
+    }
+
+    fn remove(&mut self, path: &Path) {
+        let Some(id) = self.ids.remove(path) else {
+            return;
+        };
+        if let Some(file) = self.files.remove(&id) {
+            for key in trigrams_of(&file.content) {
+                if let Some(ids) = self.postings.get_mut(&key) {
+                    ids.remove(&id);
+                    if ids.is_empty() {
+                        self.postings.remove(&key);
+                    }
+                }
+            }
+        }
+    }
+
+    /// 删除 `path`；它是目录时连同其下的全部内容
+    fn remove_tree(&mut self, path: &Path) {
+        self.paths.retain(|known| !known.starts_with(path));
+        self.stamps.retain(|known, _| !known.starts_with(path));
+        let under: Vec<PathBuf> = self.ids.keys().filter(|indexed| indexed.starts_with(path)).cloned().collect();
+        for indexed in under {
+            self.remove(&indexed);
+        }
+    }
+
+    /// 加入遍历器找到的文件；文本文件同时建索引
+    fn add_file(&mut self, path: PathBuf) {
+        let Ok(metadata) = path.metadata() else {
+            return;
+        };
+        if !metadata.is_file() {
+            return;
+        }
+        if metadata.len() <= MAX_INDEXED_FILE_SIZE {
+            if let Some(content) = read_text_file(&path) {
+                self.insert(path.clone(), content);
+            }
+        }
+        if let Ok(modified) = metadata.modified() {
+            self.stamps.insert(path.clone(), (modified, metadata.len()));
+        }
+        self.paths.insert(path);
+    }
+
+    /// 重新读取 watcher 事件中 `root` 下的路径。已删除的路径连同其下内容一起移除；新目录会被遍历；
+    /// 文件戳未变的文件保持不动
+    fn apply(&mut self, root: &Path, paths: &[PathBuf]) {
+        let mut seen = HashSet::new();
+        for path in paths {
+            let path = canonical(path);
+            if !path.starts_with(root) || !seen.insert(path.clone()) {
+                continue;
+            }
+            if stamp_of(&path).is_some_and(|stamp| self.stamps.get(&path) == Some(&stamp)) {
+                continue;
+            }
+            self.remove_tree(&path);
+            let Some(parent) = path.parent().filter(|_| path.exists()) else {
+                continue;
+            };
+            // 按父目录询问遍历器，忽略规则和排除目录才会作用于 `path` 本身
+            if !walk_files(parent, Some(1)).contains(&path) {
+                continue;
+            }
+            let files = if path.is_dir() { walk_files(&path, None) } else { vec![path] };
+            for file in files {
+                self.add_file(file);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.paths.iter()
+    }
+
+    pub fn symbols(&self) -> impl Iterator<Item = (&PathBuf, &FileSymbol)> {
+        self.files.values().flat_map(|file| file.symbols.iter().map(move |symbol| (&file.path, symbol)))
+    }
+
+    pub fn macros(&self) -> impl Iterator<Item = (&PathBuf, &MacroDefinition)> {
+        self.files.values().flat_map(|file| file.macros.iter().map(move |definition| (&file.path, definition)))
+    }
+
+    /// 每个建了索引的文本文件及其内容
+    pub fn texts(&self) -> impl Iterator<Item = (&PathBuf, &str)> {
+        self.files.values().map(|file| (&file.path, file.content.as_str()))
+    }
+
+    /// `dir` 下 `.tex` 文件的标签和引用
+    pub fn references<'a>(&'a self, dir: &'a Path) -> impl Iterator<Item = &'a Arc<FileSymbols>> {
+        self.files
+            .values()
+            .filter(move |file| file.path.starts_with(dir))
+            .filter_map(|file| file.references.as_ref())
+    }
+
+    /// `dir` 下解析过的 `.bib` 文件，按路径排序
+    pub fn bibliographies(&self, dir: &Path) -> Vec<(&PathBuf, &BibFile)> {
+        let mut files: Vec<_> = self
+            .files
+            .values()
+            .filter(|file| file.path.starts_with(dir))
+            .filter_map(|file| Some((&file.path, file.bibliography.as_ref()?)))
+            .collect();
+        files.sort_by_key(|(path, _)| *path);
+        files
+    }
+
+    /// 包含 `query` 所有三元组的文件；查询太短无法缩小范围时返回全部文件
+    fn candidates(&self, query: &str) -> Vec<&IndexedFile> {
+        let keys = query_trigrams(query);
+        if keys.is_empty() {
+            return self.files.values().collect();
+        }
+        let mut lists: Vec<&HashSet<FileId>> = Vec::with_capacity(keys.len());
+        for key in &keys {
+            match self.postings.get(key) {
+                Some(ids) => lists.push(ids),
+                None => return Vec::new(),
+            }
+        }
+        lists.sort_by_key(|ids| ids.len());
+        lists[0]
+            .iter()
+            .filter(|id| lists[1..].iter().all(|ids| ids.contains(id)))
+            .filter_map(|id| self.files.get(id))
+            .collect()
+    }
+}
+
+/// 项目遍历器会访问的 `dir` 下的条目（不含 `dir` 本身）；`max_depth` 为 1 时只列直接子项
+fn walk_files(dir: &Path, max_depth: Option<usize>) -> Vec<PathBuf> {
+    project_walker(dir)
+        .max_depth(max_depth)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.depth() > 0)
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+#[derive(Default)]
+struct RootEntry {
+    /// 最近一次完整的索引；第一次构建完成前为空
+    index: Arc<RwLock<RootIndex>>,
+    ready: bool,
+    /// 正在进行的构建到目前为止遍历的文件数
+    building: Option<Arc<AtomicUsize>>,
+    /// 构建期间到达的 watcher 事件中的路径，构建结束后再应用
+    pending: Vec<PathBuf>,
+    /// 该根目录上仍在运行的 watcher 数；没有 watcher 时索引只和上次构建一样新
+    watchers: usize,
+    updated: Option<SystemTime>,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexState {
+    NotIndexed,
+    /// 第一次构建正在进行
+    Building,
+    /// 正在监听且是最新的，或正在重建但仍提供之前的索引
+    Ready,
+    /// 已建索引但不再监听：之后的改动没有反映进来
+    Stale,
+}
+
+#[derive(Serialize)]
+pub struct IndexStatus {
+    state: IndexState,
+    /// 有构建正在进行（第一次构建，或已就绪索引的重建）
+    building: bool,
+    /// 正在进行的构建到目前为止遍历的文件数
+    progress: usize,
+    /// 列出的文件数与文本已建索引的文件数
+    files: usize,
+    indexed_files: usize,
+    /// 排队等待当前构建结束的 watcher 事件数
+    pending_changes: usize,
+    /// 最近一次构建或应用改动的时间，自 Unix 纪元起的毫秒数
+    updated_at: Option<u64>,
+}
+
+/// 每个被监听的根目录一份文件列表和全文索引，在第一次监听该目录时构建，之后根据 watcher 的事件保持更新，
+/// 搜索时不必遍历磁盘
+#[derive(Default)]
+pub struct ProjectIndex {
+    roots: Mutex<HashMap<PathBuf, RootEntry>>,
+}
+
+impl ProjectIndex {
+    /// 为 `root` 下所有文本文件建索引，替换之前的索引。构建时不持有锁，其他根目录仍可搜索；
+    /// 完成之前继续提供旧的索引
+    pub fn build(&self, root: &Path) -> Arc<RwLock<RootIndex>> {
+        let root = canonical(root);
+        let progress = Arc::new(AtomicUsize::new(0));
+        self.roots.lock().unwrap().entry(root.clone()).or_default().building = Some(progress.clone());
+        let mut index = RootIndex::default();
+        for path in walk_files(&root, None) {
+            index.add_file(path);
+            progress.fetch_add(1, Ordering::Relaxed);
+        }
+        let index = Arc::new(RwLock::new(index));
+        let pending = {
+            let mut roots = self.roots.lock().unwrap();
+            let entry = roots.entry(root.clone()).or_default();
+            entry.index = index.clone();
+            entry.ready = true;
+            entry.updated = Some(SystemTime::now());
+            // 在这之后开始的构建会继续为自己收集事件
+            if entry.building.as_ref().is_some_and(|running| Arc::ptr_eq(running, &progress)) {
+                entry.building = None;
+            }
+            std::mem::take(&mut entry.pending)
+        };
+        if !pending.is_empty() {
+            index.write().unwrap().apply(&root, &pending);
+        }
+        index
+    }
+
+    /// `root` 的索引，没有时先构建
+    pub fn get_or_build(&self, root: &Path) -> Arc<RwLock<RootIndex>> {
+        let roots = self.roots.lock().unwrap();
+        let existing = roots.get(&canonical(root)).filter(|entry| entry.ready).map(|entry| entry.index.clone());
+        drop(roots);
+        existing.unwrap_or_else(|| self.build(root))
+    }
+
+    /// 包含 `path` 且 `accept` 接受的最内层已建索引的根目录，不会触发构建
+    fn find(&self, path: &Path, accept: impl Fn(&RootEntry) -> bool) -> Option<Arc<RwLock<RootIndex>>> {
+        let path = canonical(path);
+        let roots = self.roots.lock().unwrap();
+        roots
+            .iter()
+            .filter(|(root, entry)| entry.ready && accept(entry) && path.starts_with(root))
+            .max_by_key(|(root, _)| root.components().count())
+            .map(|(_, entry)| entry.index.clone())
+    }
+
+    /// 包含 `path` 的已建索引根目录的索引，不会触发构建；根目录嵌套时取最内层
+    pub fn containing(&self, path: &Path) -> Option<Arc<RwLock<RootIndex>>> {
+        self.find(path, |_| true)
+    }
+
+    /// 与 `containing` 相同，但只取仍在监听的根目录：它的单文件结果是最新的，调用方可以直接用，不必重读磁盘
+    pub fn watched(&self, path: &Path) -> Option<Arc<RwLock<RootIndex>>> {
+        self.find(path, |entry| entry.watchers > 0)
+    }
+
+    /// `root` 上启动了一个 watcher；由调用方构建索引
+    pub fn watch(&self, root: &Path) {
+        self.roots.lock().unwrap().entry(canonical(root)).or_default().watchers += 1;
+    }
+
+    /// `root` 上的一个 watcher 停止了。索引保留供搜索使用，标记为过期
+    pub fn unwatch(&self, root: &Path) {
+        if let Some(entry) = self.roots.lock().unwrap().get_mut(&canonical(root)) {
+            entry.watchers = entry.watchers.saturating_sub(1);
+        }
+    }
+
+    /// 应用 `root` 下的 watcher 事件，只重读其中提到的路径。构建期间的事件先排队；
+    /// 根目录建好索引之前什么都不做
+    pub fn update(&self, root: &Path, paths: &[PathBuf]) {
+        let root = canonical(root);
+        let index = {
+            let mut roots = self.roots.lock().unwrap();
+            let Some(entry) = roots.get_mut(&root) else {
+                return;
+            };
+            if entry.building.is_some() {
+                entry.pending.extend(paths.iter().cloned());
+                return;
+            }
+            if !entry.ready {
+                return;
+            }
+            entry.updated = Some(SystemTime::now());
+            entry.index.clone()
+        };
+        index.write().unwrap().apply(&root, paths);
+    }
+
+    pub fn status(&self, root: &Path) -> IndexStatus {
+        let roots = self.roots.lock().unwrap();
+        let Some(entry) = roots.get(&canonical(root)) else {
+            return IndexStatus {
+                state: IndexState::NotIndexed,
+                building: false,
+                progress: 0,
+                files: 0,
+                indexed_files: 0,
+                pending_changes: 0,
+                updated_at: None,
+            };
+        };
+        let state = match (entry.ready, entry.building.is_some(), entry.watchers > 0) {
+            (false, true, _) => IndexState::Building,
+            (false, false, _) => IndexState::NotIndexed,
+            (true, _, true) => IndexState::Ready,
+            (true, _, false) => IndexState::Stale,
+        };
+        let (files, indexed_files) = {
+            let index = entry.index.read().unwrap();
+            (index.paths.len(), index.len())
+        };
+        IndexStatus {
+            state,
+            building: entry.building.is_some(),
+            progress: entry.building.as_ref().map_or(0, |progress| progress.load(Ordering::Relaxed)),
+            files,
+            indexed_files,
+            pending_changes: entry.pending.len(),
+            updated_at: entry
+                .updated
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|elapsed| elapsed.as_millis() as u64),
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct IndexSearchOptions {
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub max_results: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct IndexMatch {
+    pub file: String,
+    /// 行号，从 1 开始
+    pub line: u32,
+    /// 列号，从 1 开始，按字符计
+    pub column: u32,
+    /// 匹配的长度，按字符计
+    pub length: u32,
+    pub snippet: String,
+}
+
+#[derive(Serialize)]
+pub struct IndexSearchResults {
+    /// 最相关的文件在前，每个文件的匹配按行排序
+    pub matches: Vec<IndexMatch>,
+    pub truncated: bool,
+    pub indexed_files: usize,
+}
+
+fn is_source(path: &Path) -> bool {
+    path.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| SOURCE_EXTENSIONS.contains(&ext))
+}
+
+fn search_blocking(index: &RootIndex, query: &str, options: &IndexSearchOptions) -> Result<IndexSearchResults, String> {
+    let matcher = build_matcher(
+        query,
+        &SearchOptions { case_sensitive: options.case_sensitive, whole_word: options.whole_word, ..SearchOptions::default() },
+    )?;
+    let max_results = options.max_results.unwrap_or(DEFAULT_MAX_RESULTS);
+    let lowered_query = query.to_lowercase();
+
+    let mut ranked: Vec<(f64, &PathBuf, Vec<IndexMatch>)> = Vec::new();
+    for file in index.candidates(query) {
+        let mut matches = Vec::new();
+        for (number, line) in file.content.lines().enumerate() {
+            for found in matcher.find_iter(line) {
+                matches.push(IndexMatch {
+                    file: file.path.to_string_lossy().to_string(),
+                    line: number as u32 + 1,
+                    column: line[..found.start()].chars().count() as u32 + 1,
+                    length: found.as_str().chars().count() as u32,
+                    snippet: truncate_snippet(line),
+                });
+            }
+        }
+        if matches.is_empty() {
+            continue;
+        }
+        // 命中越多越靠前，但收益递减；文件名与查询相同的文件和项目源文件排在前面
+        let mut score = (matches.len() as f64).ln_1p();
+        let name = file.path.file_name().map(|name| name.to_string_lossy().to_lowercase()).unwrap_or_default();
+        if name.contains(&lowered_query) {
+            score += 2.0;
+        }
+        if is_source(&file.path) {
+            score += 1.0;
+        }
+        ranked.push((score, &file.path, matches));
+    }
+    ranked.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+
+    let mut matches: Vec<IndexMatch> = ranked.into_iter().flat_map(|(_, _, matches)| matches).collect();
+    let truncated = matches.len() > max_results;
+    matches.truncate(max_results);
+    Ok(IndexSearchResults { matches, truncated, indexed_files: index.len() })
+}
+
+/// 通过索引对 `root` 做字面全文搜索。未被监听的根目录第一次搜索时构建索引，之后只读取它
+#[command]
+pub async fn search_index(
+    app: AppHandle,
+    scope: WindowScope,
+    root: String,
+    query: String,
+    options: Option<IndexSearchOptions>,
+) -> Result<IndexSearchResults, String> {
+    scope.check(&root)?;
+    let root = PathBuf::from(root);
+    if !root.is_dir() {
+        return Err(format!("无法读取目录: {}", root.to_string_lossy()));
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let index = app.state::<ProjectIndex>().get_or_build(&root);
+        let index = index.read().unwrap();
+        search_blocking(&index, &query, &options.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// `root` 的索引是在构建、最新还是已过期，供大型项目的状态栏显示
+#[command]
+pub fn index_status(app: AppHandle, scope: WindowScope, root: String) -> Result<IndexStatus, String> {
+    scope.check(&root)?;
+    Ok(app.state::<ProjectIndex>().status(Path::new(&root)))
+}
@@ -0,0 +1,221 @@
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Serialize;
+use tauri::{command, AppHandle, Manager};
+
+use super::fuzzy::score;
+use super::ProjectIndex;
+use crate::latex::completion::{NEWCOMMAND_RE, NEWENVIRONMENT_RE};
+use crate::latex::outline::braced_argument;
+use crate::latex::strip_comment;
+use crate::scope::WindowScope;
+
+const DEFAULT_LIMIT: usize = 200;
+/// 收集符号的文件
+const LATEX_EXTENSIONS: &[&str] = &["tex", "sty", "cls"];
+/// 同一文件中没有 `\newtheorem` 也能识别的定理类环境，大多数项目在共享的导言区定义它们
+const THEOREM_ENVIRONMENTS: &[&str] = &[
+    "theorem", "lemma", "proposition", "corollary", "definition", "conjecture", "example", "remark", "claim",
+    "thm", "lem", "prop", "cor", "defn",
+];
+
+static SECTION_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\\(part|chapter|section|subsection|subsubsection|paragraph|subparagraph)\*?\s*(?:\[[^\]]*\])?\s*\{")
+        .unwrap()
+});
+static LABEL_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\\label\s*\{([^}]+)\}").unwrap());
+/// `\newtheorem{thm}{Theorem}`：环境名及其显示名称
+static NEWTHEOREM_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\\(?:newtheorem|declaretheorem)\*?\s*\{([^}]+)\}(?:\s*\[[^\]]*\])?\s*(?:\{([^}]*)\})?").unwrap()
+});
+static BEGIN_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\\begin\s*\{([^}]+)\}\s*(?:\[([^\]]*)\])?").unwrap());
+static HEADING_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(#{1,6})\s+(.+?)\s*#*\s*$").unwrap());
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolKind {
+    Section,
+    Label,
+    /// `\newcommand`、`\def`、`\DeclareMathOperator` 等
+    Macro,
+    /// `\newenvironment` 和 `\newtheorem` 定义
+    Environment,
+    /// 正文中的定理类环境，有可选标题时以标题命名
+    Theorem,
+    /// Markdown 标题
+    Heading,
+}
+
+/// 某个已建索引文件中的符号；所属文件由其存放位置得知
+#[derive(Clone)]
+pub struct FileSymbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// `subsection`、`\newtheorem`、`h2` 等
+    pub detail: String,
+    /// 从 1 开始
+    pub line: u32,
+    /// 从 1 开始，按字符计
+    pub column: u32,
+}
+
+#[derive(Serialize)]
+pub struct WorkspaceSymbol {
+    name: String,
+    kind: SymbolKind,
+    detail: String,
+    file: String,
+    line: u32,
+    column: u32,
+    score: i32,
+    /// 查询中的字符在 `name` 中的字符下标
+    positions: Vec<u32>,
+}
+
+fn column_of(line: &str, byte: usize) -> u32 {
+    line[..byte].chars().count() as u32 + 1
+}
+
+fn latex_symbols(content: &str) -> Vec<FileSymbol> {
+    let mut symbols = Vec::new();
+    let mut theorems: Vec<String> = Vec::new();
+    for (index, raw_line) in content.lines().enumerate() {
+        let line = strip_comment(raw_line);
+        let number = index as u32 + 1;
+        let mut push = |name: String, kind: SymbolKind, detail: String, byte: usize| {
+            if !name.is_empty() {
+                symbols.push(FileSymbol { name, kind, detail, line: number, column: column_of(line, byte) });
+            }
+        };
+        for caps in SECTION_RE.captures_iter(line) {
+            let whole = caps.get(0).unwrap();
+            push(braced_argument(line, whole.end()), SymbolKind::Section, caps[1].to_string(), whole.start());
+        }
+        for caps in LABEL_RE.captures_iter(line) {
+            let name = caps.get(1).unwrap();
+            push(name.as_str().trim().to_string(), SymbolKind::Label, "label".to_string(), name.start());
+        }
+        for caps in NEWCOMMAND_RE.captures_iter(line) {
+            let Some(name) = caps.get(1).or_else(|| caps.get(3)) else {
+                continue;
+            };
+            let detail = caps[0].split(['{', '\\', '*', ' ']).find(|part| !part.is_empty()).unwrap_or("newcommand");
+            push(format!("\\{}", name.as_str()), SymbolKind::Macro, format!("\\{}", detail), name.start());
+        }
+        for caps in NEWTHEOREM_RE.captures_iter(line) {
+            let name = caps.get(1).unwrap();
+            theorems.push(name.as_str().trim().to_string());
+            let printed = caps.get(2).map(|printed| printed.as_str().trim().to_string()).unwrap_or_default();
+            let detail = if printed.is_empty() { "\\newtheorem".to_string() } else { printed };
+            push(name.as_str().trim().to_string(), SymbolKind::Environment, detail, name.start());
+        }
+        if !NEWTHEOREM_RE.is_match(line) {
+            for caps in NEWENVIRONMENT_RE.captures_iter(line) {
+                let name = caps.get(1).unwrap();
+                push(name.as_str().trim().to_string(), SymbolKind::Environment, "\\newenvironment".to_string(), name.start());
+            }
+        }
+        for caps in BEGIN_RE.captures_iter(line) {
+            let environment = caps[1].trim().trim_end_matches('*').to_string();
+            if !THEOREM_ENVIRONMENTS.contains(&environment.as_str()) && !theorems.contains(&environment) {
+                continue;
+            }
+            let name = caps.get(2).map(|title| title.as_str().trim().to_string()).filter(|title| !title.is_empty());
+            let start = caps.get(0).unwrap().start();
+            push(name.unwrap_or_else(|| environment.clone()), SymbolKind::Theorem, environment, start);
+        }
+    }
+    symbols
+}
+
+fn markdown_symbols(content: &str) -> Vec<FileSymbol> {
+    let mut symbols = Vec::new();
+    let mut in_fence = false;
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        if let Some(caps) = HEADING_RE.captures(line) {
+            symbols.push(FileSymbol {
+                name: caps[2].to_string(),
+                kind: SymbolKind::Heading,
+                detail: format!("h{}", caps[1].len()),
+                line: index as u32 + 1,
+                column: 1,
+            });
+        }
+    }
+    symbols
+}
+
+/// 按扩展名提取单个文件的符号；其他文件没有符号
+pub fn extract_symbols(path: &Path, content: &str) -> Vec<FileSymbol> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) if LATEX_EXTENSIONS.contains(&ext) => latex_symbols(content),
+        Some("md" | "markdown") => markdown_symbols(content),
+        _ => Vec::new(),
+    }
+}
+
+/// “转到工作区中的符号”：所有已建索引文件中的章节、标签、宏、环境和定理定义、定理以及 Markdown 标题，
+/// 像文件查找一样按 `query` 排序
+#[command]
+pub async fn workspace_symbols(
+    app: AppHandle,
+    scope: WindowScope,
+    root: String,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<WorkspaceSymbol>, String> {
+    scope.check(&root)?;
+    let root = PathBuf::from(root);
+    if !root.is_dir() {
+        return Err(format!("无法读取目录: {}", root.to_string_lossy()));
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let index = app.state::<ProjectIndex>().get_or_build(&root);
+        let index = index.read().unwrap();
+        let query: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).collect();
+        let case_sensitive = query.iter().any(|c| c.is_uppercase());
+        let mut symbols: Vec<WorkspaceSymbol> = index
+            .symbols()
+            .filter_map(|(path, symbol)| {
+                let (score, positions) = if query.is_empty() {
+                    (0, Vec::new())
+                } else {
+                    let name: Vec<char> = symbol.name.chars().collect();
+                    score(&query, &name, case_sensitive)?
+                };
+                Some(WorkspaceSymbol {
+                    name: symbol.name.clone(),
+                    kind: symbol.kind,
+                    detail: symbol.detail.clone(),
+                    file: path.to_string_lossy().to_string(),
+                    line: symbol.line,
+                    column: symbol.column,
+                    score,
+                    positions,
+                })
+            })
+            .collect();
+        symbols.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then(a.name.len().cmp(&b.name.len()))
+                .then_with(|| a.file.cmp(&b.file))
+                .then(a.line.cmp(&b.line))
+        });
+        symbols.truncate(limit.unwrap_or(DEFAULT_LIMIT));
+        symbols
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
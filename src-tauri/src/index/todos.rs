@@ -0,0 +1,231 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Manager};
+
+use super::macros::braced;
+use super::{has_extension, ProjectIndex};
+use crate::latex::strip_comment;
+use crate::project::read_config;
+use crate::scope::WindowScope;
+
+/// 每个项目都识别的标记及其默认优先级
+const DEFAULT_MARKERS: &[(&str, TodoPriority)] =
+    &[("FIXME", TodoPriority::High), ("XXX", TodoPriority::High), ("TODO", TodoPriority::Normal)];
+/// 读取其中 `%` 注释和 `\todo` 命令的文件
+const LATEX_EXTENSIONS: &[&str] = &["tex", "sty", "cls", "bib"];
+
+/// todonotes 宏包的 `\todo[options]{text}`；文本会嵌套花括号，需要手动读取
+static TODO_COMMAND_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\\todo\*?\s*(?:\[[^\]]*\])?").unwrap());
+static HTML_COMMENT_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)<!--(.*?)-->").unwrap());
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum TodoPriority {
+    High,
+    Normal,
+    Low,
+}
+
+#[derive(Serialize)]
+pub struct TodoItem {
+    /// 原样的标记（`TODO`、`FIXME` 等），`\todo` 命令则为 `todo`
+    marker: String,
+    text: String,
+    priority: TodoPriority,
+    /// 从 1 开始
+    line: u32,
+    /// 从 1 开始，按字符计
+    column: u32,
+}
+
+#[derive(Serialize)]
+pub struct TodoFile {
+    file: String,
+    /// 按行排序
+    items: Vec<TodoItem>,
+}
+
+/// 在文件字节偏移 `offset` 处找到的一条备注
+struct Note {
+    offset: usize,
+    marker: String,
+    text: String,
+    priority: TodoPriority,
+}
+
+/// 查找 `MARKER`、`MARKER!` 或 `MARKER(priority)` 及其后的备注文本
+struct Markers {
+    re: Regex,
+    priorities: BTreeMap<String, TodoPriority>,
+}
+
+impl Markers {
+    fn new(custom: BTreeMap<String, TodoPriority>) -> Self {
+        let mut priorities: BTreeMap<String, TodoPriority> =
+            DEFAULT_MARKERS.iter().map(|(marker, priority)| (marker.to_string(), *priority)).collect();
+        priorities.extend(
+            custom
+                .into_iter()
+                .map(|(marker, priority)| (marker.trim().to_string(), priority))
+                .filter(|(marker, _)| !marker.is_empty()),
+        );
+        let alternatives: Vec<String> = priorities.keys().map(|name| regex::escape(name)).collect();
+        let pattern = format!(
+            r"\b(?P<marker>{})\b(?P<bang>!+)?(?:\((?P<priority>[^)]*)\))?:?[ \t]*(?P<text>[^\n]*)",
+            alternatives.join("|")
+        );
+        Markers { re: Regex::new(&pattern).unwrap(), priorities }
+    }
+
+    /// `comment` 中的备注，`comment` 从文件的字节偏移 `offset` 开始
+    fn scan(&self, comment: &str, offset: usize, found: &mut Vec<Note>) {
+        for caps in self.re.captures_iter(comment) {
+            let marker = caps["marker"].to_string();
+            let explicit = match caps.name("priority").map(|priority| priority.as_str().trim().to_lowercase()) {
+                Some(priority) if priority == "high" => Some(TodoPriority::High),
+                Some(priority) if priority == "normal" || priority == "medium" => Some(TodoPriority::Normal),
+                Some(priority) if priority == "low" => Some(TodoPriority::Low),
+                _ => None,
+            };
+            let priority = explicit
+                .or(caps.name("bang").map(|_| TodoPriority::High))
+                .unwrap_or_else(|| self.priorities.get(&marker).copied().unwrap_or(TodoPriority::Normal));
+            let text = caps["text"].trim().to_string();
+            found.push(Note { offset: offset + caps.get(0).unwrap().start(), marker, text, priority });
+        }
+    }
+}
+
+fn scan_file(path: &Path, content: &str, markers: &Markers) -> Vec<Note> {
+    let mut found = Vec::new();
+    if LATEX_EXTENSIONS.iter().any(|extension| has_extension(path, extension)) {
+        let mut line_start = 0;
+        for line in content.split_inclusive('\n') {
+            let code = strip_comment(line);
+            if code.len() < line.len() {
+                markers.scan(&line[code.len()..], line_start + code.len(), &mut found);
+            }
+            line_start += line.len();
+        }
+        for caps in TODO_COMMAND_RE.captures_iter(content) {
+            let whole = caps.get(0).unwrap();
+            let line_start = content[..whole.start()].rfind('\n').map_or(0, |newline| newline + 1);
+            // 已被注释掉
+            if strip_comment(&content[line_start..]).len() < whole.start() - line_start {
+                continue;
+            }
+            let Some((text, _)) = braced(content, whole.end()) else {
+                continue;
+            };
+            let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            let marker = "todo".to_string();
+            found.push(Note { offset: whole.start(), marker, text, priority: TodoPriority::Normal });
+        }
+    } else if has_extension(path, "md") {
+        for caps in HTML_COMMENT_RE.captures_iter(content) {
+            let body = caps.get(1).unwrap();
+            markers.scan(body.as_str(), body.start(), &mut found);
+        }
+    }
+    found.sort_by_key(|note| note.offset);
+    found
+}
+
+fn todo_file(path: &Path, content: &str, markers: &Markers) -> Option<TodoFile> {
+    let items: Vec<TodoItem> = scan_file(path, content, markers)
+        .into_iter()
+        .map(|Note { offset, marker, text, priority }| {
+            let before = &content[..offset];
+            let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+            TodoItem {
+                marker,
+                text,
+                priority,
+                line: before.matches('\n').count() as u32 + 1,
+                column: before[line_start..].chars().count() as u32 + 1,
+            }
+        })
+        .collect();
+    (!items.is_empty()).then(|| TodoFile { file: path.to_string_lossy().to_string(), items })
+}
+
+/// `root` 中的 `% TODO` 注释、`\todo{...}` 备注和 `<!-- TODO -->` 注释，按文件分组并按路径排序。
+/// 除 `TODO`、`FIXME` 和 `XXX` 外，还识别项目配置中的 `todo_markers`；`TODO!` 或 `TODO(low)` 设置备注的优先级
+#[command]
+pub async fn list_todos(app: AppHandle, scope: WindowScope, root: String) -> Result<Vec<TodoFile>, String> {
+    scope.check(&root)?;
+    let root = PathBuf::from(root);
+    if !root.is_dir() {
+        return Err(format!("无法读取目录: {}", root.to_string_lossy()));
+    }
+    let custom = read_config(&root).ok().flatten().map(|config| config.todo_markers).unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || {
+        let markers = Markers::new(custom);
+        let index = app.state::<ProjectIndex>().get_or_build(&root);
+        let index = index.read().unwrap();
+        let mut files: Vec<TodoFile> =
+            index.texts().filter_map(|(path, content)| todo_file(path, content, &markers)).collect();
+        files.sort_by(|a, b| a.file.cmp(&b.file));
+        files
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn markers() -> Markers {
+        Markers::new(BTreeMap::from([("REVIEW".to_string(), TodoPriority::Low), (" ".to_string(), TodoPriority::High)]))
+    }
+
+    fn summary(file: &TodoFile) -> Vec<(&str, &str, u32, u32)> {
+        file.items.iter().map(|item| (item.marker.as_str(), item.text.as_str(), item.line, item.column)).collect()
+    }
+
+    #[test]
+    fn latex_comments_and_todo_commands() {
+        let content = concat!(
+            "\\section{Intro} % TODO: write the intro\n",
+            "Text 50\\% done. % FIXME(low) check numbers\n",
+            "\\todo[inline]{Add a {nested} figure\n",
+            "  here}\n",
+            "% \\todo{commented out}\n",
+            "% REVIEW! ask Bob\n",
+            "% REVIEW ask Alice\n",
+        );
+        let file = todo_file(Path::new("main.tex"), content, &markers()).unwrap();
+        assert_eq!(
+            summary(&file),
+            [
+                ("TODO", "write the intro", 1, 19),
+                ("FIXME", "check numbers", 2, 19),
+                ("todo", "Add a {nested} figure here", 3, 1),
+                ("REVIEW", "ask Bob", 6, 3),
+                ("REVIEW", "ask Alice", 7, 3),
+            ]
+        );
+        let priorities: Vec<TodoPriority> = file.items.iter().map(|item| item.priority).collect();
+        use TodoPriority::{High, Low, Normal};
+        assert!(priorities == [Normal, Low, Normal, High, Low]);
+    }
+
+    #[test]
+    fn markdown_html_comments() {
+        let content = "# Title\n<!-- XXX: fix this -->\nTODO outside a comment\n";
+        let file = todo_file(Path::new("notes.md"), content, &markers()).unwrap();
+        assert_eq!(summary(&file), [("XXX", "fix this", 2, 6)]);
+        assert!(file.items[0].priority == TodoPriority::High);
+    }
+
+    #[test]
+    fn other_files_and_plain_text_have_no_todos() {
+        assert!(todo_file(Path::new("notes.txt"), "% TODO: nothing\n", &markers()).is_none());
+        assert!(todo_file(Path::new("main.tex"), "TODO outside a comment\n", &markers()).is_none());
+    }
+}
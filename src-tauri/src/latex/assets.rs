@@ -0,0 +1,152 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Serialize;
+use tauri::command;
+
+use super::dependencies::{dependency_graph, DependencyKind};
+use super::root::{canonical, is_document};
+use super::strip_comment;
+use crate::bibliography::parser::parse_bib;
+use crate::scope::WindowScope;
+use crate::workspace::project_walker;
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "svg", "pdf", "eps"];
+
+/// `\cite`、`\citep`、`\parencite`、`\nocite` 等，最多带两个可选参数
+static CITE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\\[A-Za-z]*cite[A-Za-z]*\*?\s*(?:\[[^\]]*\]\s*){0,2}\{([^}]+)\}").unwrap()
+});
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum UnusedKind {
+    Graphic,
+    Tex,
+    Bib,
+    /// 从未被引用的 `.bib` 条目
+    Entry,
+}
+
+#[derive(Serialize)]
+pub struct UnusedAsset {
+    kind: UnusedKind,
+    path: String,
+    /// 条目的引用 key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    /// 条目在其 `.bib` 文件中的行号；文件为 0
+    line: u32,
+    /// 文件大小，单位为字节；条目为 0
+    size: u64,
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+}
+
+fn file_candidate(kind: UnusedKind, path: &Path) -> UnusedAsset {
+    UnusedAsset {
+        kind,
+        path: path.to_string_lossy().to_string(),
+        key: None,
+        line: 0,
+        size: fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+    }
+}
+
+/// `content` 中引用的 key；`\nocite{*}` 记为 `*`
+fn cited_keys(content: &str, keys: &mut HashSet<String>) {
+    for line in content.lines() {
+        for caps in CITE_RE.captures_iter(strip_comment(line)) {
+            keys.extend(caps[1].split(',').map(str::trim).filter(|key| !key.is_empty()).map(str::to_string));
+        }
+    }
+}
+
+fn find_unused_blocking(root: &Path) -> Result<Vec<UnusedAsset>, String> {
+    if !root.is_dir() {
+        return Err(format!("无法读取目录: {}", root.to_string_lossy()));
+    }
+    let files: Vec<PathBuf> = project_walker(root)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| entry.into_path())
+        .collect();
+
+    // 每个完整的文档都算作根文档，第二个主文件或独立的图片文档也能保住自己的依赖
+    let documents: Vec<&PathBuf> = files
+        .iter()
+        .filter(|path| has_extension(path, &["tex"]))
+        .filter(|path| fs::read_to_string(path).is_ok_and(|content| is_document(&content)))
+        .collect();
+    let mut used = HashSet::new();
+    let mut used_bibs = Vec::new();
+    let mut cited = HashSet::new();
+    for document in &documents {
+        let graph = dependency_graph(document);
+        for tex in graph.existing_of_kind(DependencyKind::Tex) {
+            if let Ok(content) = fs::read_to_string(&tex) {
+                cited_keys(&content, &mut cited);
+            }
+        }
+        used_bibs.extend(graph.existing_of_kind(DependencyKind::Bib));
+        used.extend(graph.existing_files().map(|path| canonical(&path)));
+    }
+
+    // 保存在源文件旁边的编译输出（`main.tex` 旁的 `main.pdf`）不算资源
+    let tex_stems: HashSet<PathBuf> =
+        files.iter().filter(|path| has_extension(path, &["tex"])).map(|path| path.with_extension("")).collect();
+
+    let mut unused = Vec::new();
+    for path in &files {
+        if used.contains(&canonical(path)) {
+            continue;
+        }
+        if has_extension(path, &["tex"]) {
+            unused.push(file_candidate(UnusedKind::Tex, path));
+        } else if has_extension(path, &["bib"]) {
+            unused.push(file_candidate(UnusedKind::Bib, path));
+        } else if has_extension(path, IMAGE_EXTENSIONS)
+            && !(has_extension(path, &["pdf"]) && tex_stems.contains(&path.with_extension("")))
+        {
+            unused.push(file_candidate(UnusedKind::Graphic, path));
+        }
+    }
+
+    if !cited.contains("*") {
+        used_bibs.sort_by_key(|path| canonical(path));
+        used_bibs.dedup_by_key(|path| canonical(path));
+        for bib in used_bibs {
+            let Ok(content) = fs::read_to_string(&bib) else {
+                continue;
+            };
+            for entry in parse_bib(&content).entries.into_iter().filter(|entry| !cited.contains(&entry.key)) {
+                unused.push(UnusedAsset {
+                    kind: UnusedKind::Entry,
+                    path: bib.to_string_lossy().to_string(),
+                    key: Some(entry.key),
+                    line: entry.line,
+                    size: 0,
+                });
+            }
+        }
+    }
+    Ok(unused)
+}
+
+/// `root` 中没有任何文档使用的图片、`.tex` 和 `.bib` 文件，以及从未被引用的 `.bib` 条目。
+/// 这些只是候选：自定义宏或 shell-escape 读取的文件无法识别
+#[command]
+pub async fn find_unused_assets(scope: WindowScope, root: String) -> Result<Vec<UnusedAsset>, String> {
+    scope.check(&root)?;
+    tauri::async_runtime::spawn_blocking(move || find_unused_blocking(Path::new(&root)))
+        .await
+        .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,448 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Serialize;
+use tauri::{command, AppHandle, Manager};
+
+use super::packages::{known_packages, KERNEL_COMMANDS, KERNEL_ENVIRONMENTS, PACKAGE_COMMANDS, PACKAGE_ENVIRONMENTS};
+use super::references::{project_labels, scan_labels};
+use super::root::{collect_inputs, find_root};
+use super::strip_comment;
+use crate::bibliography::project_citations;
+use crate::index::macros::{MacroDefinition, MacroKind};
+use crate::index::ProjectIndex;
+use crate::scope::WindowScope;
+use crate::snippets::{SnippetScope, Snippets};
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "pdf", "eps", "svg"];
+
+/// 光标前紧挨着的 `\command[opt]{partial`
+static ARGUMENT_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\\([A-Za-z]+)\*?(?:\[[^\]]*\])*\{([^{}]*)$").unwrap());
+/// 光标前紧挨着的 `\partial`
+static COMMAND_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\\([A-Za-z@]*)$").unwrap());
+static USEPACKAGE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\\(?:usepackage|RequirePackage|documentclass)\s*(?:\[[^\]]*\])?\s*\{([^}]+)\}").unwrap()
+});
+pub static NEWCOMMAND_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"\\(?:(?:re)?newcommand\*?|providecommand\*?|DeclareMathOperator\*?|DeclareRobustCommand\*?)\s*\{?\\([A-Za-z@]+)\}?\s*(?:\[(\d)\])?|\\def\\([A-Za-z@]+)",
+    )
+    .unwrap()
+});
+pub static NEWENVIRONMENT_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\\(?:(?:re)?newenvironment|newtheorem|declaretheorem|newtcolorbox)\*?\s*\{([^}]+)\}").unwrap()
+});
+/// 光标前紧挨着的单词，可能是代码片段的触发词
+static WORD_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?:^|[^\\A-Za-z0-9@])([A-Za-z][A-Za-z0-9_-]*)$").unwrap());
+static ENVIRONMENT_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\\(begin|end)\s*\{([^}]+)\}").unwrap());
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CompletionKind {
+    Command,
+    Environment,
+    Label,
+    Citation,
+    File,
+    Package,
+    /// 用户代码片段
+    Snippet,
+}
+
+#[derive(Serialize)]
+pub struct CompletionItem {
+    label: String,
+    kind: CompletionKind,
+    detail: Option<String>,
+    insert_text: String,
+    /// `insert_text` 使用代码片段语法（`$1` 制表位）
+    snippet: bool,
+}
+
+#[derive(Serialize)]
+pub struct CompletionList {
+    items: Vec<CompletionItem>,
+    /// 要替换的文本开始的列，从 1 开始；到光标处结束
+    from: u32,
+}
+
+impl CompletionItem {
+    fn plain(label: impl Into<String>, kind: CompletionKind, detail: Option<String>) -> Self {
+        let label = label.into();
+        CompletionItem { insert_text: label.clone(), label, kind, detail, snippet: false }
+    }
+}
+
+/// 各补全场景需要知道的项目信息
+struct Project {
+    /// `\input` 和 `\includegraphics` 路径相对的目录
+    base_dir: Option<PathBuf>,
+    path: Option<PathBuf>,
+    /// 当前缓冲区加上磁盘上文档的所有文件
+    sources: Vec<(PathBuf, String)>,
+    /// 索引在打开的文件夹中任意位置（包括本地 `.sty` 文件）找到的定义；文件夹没有建索引时为空
+    indexed_macros: Vec<(PathBuf, MacroDefinition)>,
+}
+
+impl Project {
+    fn load(app: &AppHandle, path: Option<&Path>, content: &str) -> Self {
+        let Some(path) = path else {
+            return Project {
+                base_dir: None,
+                path: None,
+                sources: vec![(PathBuf::new(), content.to_string())],
+                indexed_macros: Vec::new(),
+            };
+        };
+        let root = PathBuf::from(find_root(path, None).root);
+        let mut sources = vec![(path.to_path_buf(), content.to_string())];
+        let current = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        for file in std::iter::once(root.clone()).chain(collect_inputs(&root)) {
+            if fs::canonicalize(&file).unwrap_or_else(|_| file.clone()) == current {
+                continue;
+            }
+            if let Ok(text) = fs::read_to_string(&file) {
+                sources.push((file, text));
+            }
+        }
+        let indexed_macros = app
+            .state::<ProjectIndex>()
+            .containing(path)
+            .map(|index| {
+                let index = index.read().unwrap();
+                index.macros().map(|(file, definition)| (file.clone(), definition.clone())).collect()
+            })
+            .unwrap_or_default();
+        Project {
+            base_dir: Some(root.parent().unwrap_or(Path::new(".")).to_path_buf()),
+            path: Some(path.to_path_buf()),
+            sources,
+            indexed_macros,
+        }
+    }
+
+    fn lines(&self) -> impl Iterator<Item = &str> {
+        self.sources.iter().flat_map(|(_, text)| text.lines().map(strip_comment))
+    }
+
+    fn packages(&self) -> HashSet<String> {
+        let mut packages = HashSet::new();
+        for line in self.lines() {
+            for caps in USEPACKAGE_RE.captures_iter(line) {
+                packages.extend(caps[1].split(',').map(|p| p.trim().to_string()));
+            }
+        }
+        // 这些宏包会加载提供所列命令的其他宏包
+        if packages.contains("tikz") || packages.contains("pgfplots") {
+            packages.insert("xcolor".to_string());
+        }
+        if packages.contains("mathtools") {
+            packages.insert("amsmath".to_string());
+        }
+        packages
+    }
+}
+
+fn defined_in(file: &Path) -> String {
+    format!("user-defined ({})", file.file_name().unwrap_or_default().to_string_lossy())
+}
+
+fn command_items(project: &Project) -> Vec<CompletionItem> {
+    let packages = project.packages();
+    let mut seen = HashSet::new();
+    let mut items = Vec::new();
+    let mut push = |name: &str, args: &str, detail: Option<String>| {
+        if seen.insert(name.to_string()) {
+            items.push(CompletionItem {
+                label: format!("\\{}", name),
+                kind: CompletionKind::Command,
+                detail,
+                insert_text: format!("\\\\{}{}", name, args),
+                snippet: true,
+            });
+        }
+    };
+
+    // 用户宏优先：作者最可能在输入它们
+    for line in project.lines() {
+        for caps in NEWCOMMAND_RE.captures_iter(line) {
+            let Some(name) = caps.get(1).or_else(|| caps.get(3)) else {
+                continue;
+            };
+            let arity: usize = caps.get(2).and_then(|n| n.as_str().parse().ok()).unwrap_or(0);
+            let args: String = (1..=arity).map(|i| format!("{{${}}}", i)).collect();
+            push(name.as_str(), &args, Some("user-defined".to_string()));
+        }
+    }
+    for (file, definition) in project.indexed_macros.iter().filter(|(_, d)| d.kind != MacroKind::Environment) {
+        // 可选的第一个参数不放进代码片段
+        let mandatory = definition.arguments.saturating_sub(u8::from(definition.optional_default.is_some()));
+        let args: String = (1..=mandatory).map(|i| format!("{{${}}}", i)).collect();
+        push(&definition.name, &args, Some(defined_in(file)));
+    }
+    for (name, args) in KERNEL_COMMANDS {
+        push(name, args, None);
+    }
+    for (package, commands) in PACKAGE_COMMANDS {
+        if packages.contains(*package) {
+            for (name, args) in *commands {
+                push(name, args, Some(package.to_string()));
+            }
+        }
+    }
+    items
+}
+
+/// 光标处仍未关闭的环境，最内层在最后
+fn open_environments(before_cursor: &str) -> Vec<String> {
+    let mut stack: Vec<String> = Vec::new();
+    for line in before_cursor.lines() {
+        for caps in ENVIRONMENT_RE.captures_iter(strip_comment(line)) {
+            let name = caps[2].trim().to_string();
+            if &caps[1] == "begin" {
+                stack.push(name);
+            } else if let Some(position) = stack.iter().rposition(|open| *open == name) {
+                stack.truncate(position);
+            }
+        }
+    }
+    stack
+}
+
+fn environment_items(project: &Project, closing: Option<String>) -> Vec<CompletionItem> {
+    let packages = project.packages();
+    let mut seen = HashSet::new();
+    let mut items = Vec::new();
+    let mut push = |name: &str, detail: Option<String>| {
+        if seen.insert(name.to_string()) {
+            items.push(CompletionItem::plain(name, CompletionKind::Environment, detail));
+        }
+    };
+    // `\end{` 优先提供它将关闭的环境
+    if let Some(name) = closing {
+        push(&name, Some("close".to_string()));
+    }
+    for line in project.lines() {
+        for caps in NEWENVIRONMENT_RE.captures_iter(line) {
+            push(caps[1].trim(), Some("user-defined".to_string()));
+        }
+    }
+    for (file, definition) in project.indexed_macros.iter().filter(|(_, d)| d.kind == MacroKind::Environment) {
+        push(&definition.name, Some(defined_in(file)));
+    }
+    for name in KERNEL_ENVIRONMENTS {
+        push(name, None);
+    }
+    for (package, environments) in PACKAGE_ENVIRONMENTS {
+        if packages.contains(*package) {
+            for name in *environments {
+                push(name, Some(package.to_string()));
+            }
+        }
+    }
+    items
+}
+
+fn label_items(app: &AppHandle, project: &Project) -> Vec<CompletionItem> {
+    let mut labels: HashMap<String, String> = HashMap::new();
+    if let Some(base_dir) = &project.base_dir {
+        for label in project_labels(app, base_dir) {
+            labels.entry(label.name).or_insert(label.file);
+        }
+    }
+    // 缓冲区里可能定义了还没保存的标签
+    let (path, content) = &project.sources[0];
+    for label in scan_labels(path, content) {
+        labels.insert(label.name, label.file);
+    }
+    let mut items: Vec<CompletionItem> = labels
+        .into_iter()
+        .map(|(name, file)| {
+            let file_name = Path::new(&file).file_name().map(|n| n.to_string_lossy().to_string());
+            CompletionItem::plain(name, CompletionKind::Label, file_name)
+        })
+        .collect();
+    items.sort_by(|a, b| a.label.cmp(&b.label));
+    items
+}
+
+fn citation_items(app: &AppHandle, project: &Project) -> Vec<CompletionItem> {
+    let Some(base_dir) = &project.base_dir else {
+        return Vec::new();
+    };
+    project_citations(app, base_dir)
+        .into_iter()
+        .map(|citation| {
+            let mut detail = citation.authors.first().cloned().unwrap_or_default();
+            if citation.authors.len() > 1 {
+                detail.push_str(" et al.");
+            }
+            if let Some(year) = &citation.year {
+                detail.push_str(&format!(" ({})", year));
+            }
+            if let Some(title) = &citation.title {
+                detail.push_str(&format!(" {}", title));
+            }
+            CompletionItem::plain(citation.key, CompletionKind::Citation, Some(detail.trim().to_string()))
+        })
+        .collect()
+}
+
+/// `partial` 所指目录（截至最后一个 `/`）中的条目，按扩展名过滤。目录总是列出
+fn file_items(project: &Project, partial: &str, extensions: &[&str], strip_extension: bool) -> Vec<CompletionItem> {
+    let Some(base_dir) = &project.base_dir else {
+        return Vec::new();
+    };
+    let dir_part = partial.rfind('/').map(|i| &partial[..=i]).unwrap_or("");
+    let Ok(entries) = fs::read_dir(base_dir.join(dir_part)) else {
+        return Vec::new();
+    };
+    let current = project.path.as_deref().and_then(Path::file_name);
+    let mut items: Vec<CompletionItem> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') || name == "AuxiliaryFiles" {
+                return None;
+            }
+            if path.is_dir() {
+                return Some(CompletionItem {
+                    label: format!("{}/", name),
+                    kind: CompletionKind::File,
+                    detail: Some("folder".to_string()),
+                    insert_text: format!("{}/", name),
+                    snippet: false,
+                });
+            }
+            let extension = path.extension()?.to_string_lossy().to_lowercase();
+            if !extensions.contains(&extension.as_str()) || Some(entry.file_name().as_os_str()) == current {
+                return None;
+            }
+            let insert = if strip_extension {
+                path.file_stem()?.to_string_lossy().to_string()
+            } else {
+                name.clone()
+            };
+            Some(CompletionItem { label: name, kind: CompletionKind::File, detail: None, insert_text: insert, snippet: false })
+        })
+        .collect();
+    items.sort_by(|a, b| a.label.cmp(&b.label));
+    items
+}
+
+fn package_items() -> Vec<CompletionItem> {
+    known_packages()
+        .map(|name| CompletionItem::plain(name, CompletionKind::Package, None))
+        .collect()
+}
+
+pub fn is_reference_command(name: &str) -> bool {
+    matches!(
+        name,
+        "ref" | "eqref" | "pageref" | "autoref" | "nameref" | "vref" | "cref" | "Cref" | "cpageref" | "Cpageref"
+            | "labelcref" | "crefrange"
+    )
+}
+
+pub fn is_citation_command(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.contains("cite") && !name.starts_with("bibliography")
+}
+
+fn complete(app: &AppHandle, path: Option<&Path>, content: &str, line: u32, column: u32) -> CompletionList {
+    let empty = |from| CompletionList { items: Vec::new(), from };
+    let Some(line_text) = content.lines().nth(line.saturating_sub(1) as usize) else {
+        return empty(column);
+    };
+    let before: String = line_text.chars().take(column.saturating_sub(1) as usize).collect();
+    // 注释中不提供补全
+    if strip_comment(&before).len() < before.len() {
+        return empty(column);
+    }
+    let char_column = |byte: usize| before[..byte].chars().count() as u32 + 1;
+
+    if let Some(caps) = ARGUMENT_RE.captures(&before) {
+        let command = &caps[1];
+        let argument = caps.get(2).unwrap();
+        // 逗号分隔的列表（`\cite{a,b}`）补全最后一个元素
+        let list_start = argument.start() + argument.as_str().rfind(',').map_or(0, |i| i + 1);
+        let list_start = list_start + before[list_start..].len() - before[list_start..].trim_start().len();
+        let path_start = argument.start() + argument.as_str().rfind('/').map_or(0, |i| i + 1);
+
+        let project = Project::load(app, path, content);
+        let (items, start) = match command {
+            "begin" => (environment_items(&project, None), argument.start()),
+            "end" => {
+                let mut before_cursor: String = content
+                    .lines()
+                    .take(line.saturating_sub(1) as usize)
+                    .flat_map(|l| [l, "\n"])
+                    .collect();
+                before_cursor.push_str(&before);
+                let closing = open_environments(&before_cursor).pop();
+                (environment_items(&project, closing), argument.start())
+            }
+            name if is_reference_command(name) => (label_items(app, &project), list_start),
+            name if is_citation_command(name) => (citation_items(app, &project), list_start),
+            "includegraphics" => (file_items(&project, argument.as_str(), IMAGE_EXTENSIONS, false), path_start),
+            "input" | "include" | "subfile" | "includeonly" => {
+                (file_items(&project, argument.as_str(), &["tex"], command != "input"), path_start)
+            }
+            "bibliography" | "addbibresource" => {
+                (file_items(&project, argument.as_str(), &["bib"], command == "bibliography"), path_start)
+            }
+            "usepackage" | "RequirePackage" => (package_items(), list_start),
+            _ => return empty(column),
+        };
+        return CompletionList { items, from: char_column(start) };
+    }
+
+    if let Some(caps) = COMMAND_RE.captures(&before) {
+        let project = Project::load(app, path, content);
+        let start = caps.get(0).unwrap().start();
+        return CompletionList { items: command_items(&project), from: char_column(start) };
+    }
+
+    if let Some(word) = WORD_RE.captures(&before).and_then(|caps| caps.get(1)) {
+        let items: Vec<CompletionItem> = app
+            .state::<Snippets>()
+            .in_scope(app, SnippetScope::Latex)
+            .into_iter()
+            .filter(|snippet| snippet.trigger.starts_with(word.as_str()))
+            .map(|snippet| CompletionItem {
+                label: snippet.trigger,
+                kind: CompletionKind::Snippet,
+                detail: Some(snippet.description).filter(|description| !description.is_empty()),
+                insert_text: snippet.body,
+                snippet: true,
+            })
+            .collect();
+        return CompletionList { items, from: char_column(word.start()) };
+    }
+
+    empty(column)
+}
+
+/// `content`（`path` 未保存的缓冲区）中 `line`/`column`（从 1 开始，遵循 Monaco 的约定）处的上下文相关补全
+#[command]
+pub async fn complete_at(
+    app: AppHandle,
+    scope: WindowScope,
+    path: Option<String>,
+    content: String,
+    line: u32,
+    column: u32,
+) -> Result<CompletionList, String> {
+    scope.check_optional(path.as_ref())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        complete(&app, path.as_deref().map(Path::new), &content, line, column)
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
@@ -0,0 +1,114 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tauri::{command, AppHandle, Manager};
+
+use super::dependencies::resolve_graphic;
+use super::hover::{cursor_in, find_entry, find_macro, target_at, Target};
+use super::resolve_tex_path;
+use super::root::{canonical, find_root};
+use crate::index::symbols::{extract_symbols, SymbolKind};
+use crate::index::ProjectIndex;
+use crate::scope::WindowScope;
+
+#[derive(Serialize)]
+pub struct Location {
+    file: String,
+    /// 从 1 开始
+    line: u32,
+    /// 从 1 开始，按字符计
+    column: u32,
+}
+
+impl Location {
+    fn new(file: &Path, line: u32, column: u32) -> Self {
+        Location { file: file.to_string_lossy().to_string(), line, column }
+    }
+}
+
+/// `\input` 等命令解析路径所相对的目录：根文档所在目录，因为 TeX 在那里运行
+fn base_dir(path: &Path) -> PathBuf {
+    let root = PathBuf::from(find_root(path, None).root);
+    root.parent().or_else(|| path.parent()).map(Path::to_path_buf).unwrap_or_default()
+}
+
+/// 先在缓冲区中找 `\label{name}`，再到其他已建索引的文件中找。定义了两次的标签给出两个位置
+fn find_labels(app: &AppHandle, path: &Path, source: &str, name: &str) -> Vec<Location> {
+    let is_label = |kind: SymbolKind, label: &str| kind == SymbolKind::Label && label == name;
+    let mut locations: Vec<Location> = extract_symbols(path, source)
+        .into_iter()
+        .filter(|symbol| is_label(symbol.kind, &symbol.name))
+        .map(|symbol| Location::new(path, symbol.line, symbol.column))
+        .collect();
+
+    let state = app.state::<ProjectIndex>();
+    let index = state.containing(path).unwrap_or_else(|| state.get_or_build(&base_dir(path)));
+    let index = index.read().unwrap();
+    // 缓冲区可能比它自己在索引中的副本更新
+    let own = canonical(path);
+    locations.extend(
+        index
+            .symbols()
+            .filter(|(file, symbol)| **file != own && is_label(symbol.kind, &symbol.name))
+            .map(|(file, symbol)| Location::new(file, symbol.line, symbol.column)),
+    );
+    locations
+}
+
+/// 类 `\input` 参数所指的文件（如果存在）
+fn find_file(path: &Path, command: &str, name: &str) -> Option<PathBuf> {
+    let base = base_dir(path);
+    let candidates = match command {
+        "includegraphics" => vec![resolve_graphic(&base, &[], name)],
+        "bibliography" => vec![base.join(format!("{}.bib", name.trim_end_matches(".bib")))],
+        "addbibresource" => vec![base.join(name)],
+        "usepackage" | "RequirePackage" => vec![base.join(format!("{}.sty", name))],
+        // 文件不是根文档时，`\subfile` 的路径相对文件本身
+        _ => vec![resolve_tex_path(&base, name), resolve_tex_path(path.parent()?, name)],
+    };
+    candidates.into_iter().find(|candidate| candidate.is_file())
+}
+
+fn definition(app: &AppHandle, path: &Path, source: &str, line: u32, column: u32) -> Vec<Location> {
+    let Some((line_text, cursor)) = cursor_in(source, line, column) else {
+        return Vec::new();
+    };
+    let Some((target, _, _)) = target_at(line_text, cursor) else {
+        return Vec::new();
+    };
+    let found = match target {
+        Target::Reference(name) => return find_labels(app, path, source, name),
+        Target::Command(name) => find_macro(app, Some(path), source, name, false)
+            .map(|(file, definition)| Location::new(&file, definition.line, 1)),
+        Target::Environment(name) => find_macro(app, Some(path), source, name, true)
+            .map(|(file, definition)| Location::new(&file, definition.line, 1)),
+        Target::Citation(key) => find_entry(Some(path), key).map(|(file, entry)| Location::new(&file, entry.line, 1)),
+        Target::File(command, name) => find_file(path, command, name).map(|file| Location::new(&file, 1, 1)),
+        Target::Package(name) => find_file(path, "usepackage", name).map(|file| Location::new(&file, 1, 1)),
+    };
+    found.into_iter().collect()
+}
+
+/// `path` 中 `line`/`column`（从 1 开始）处内容的定义位置：`\ref` 转到其 `\label`，引用 key 转到参考文献条目，
+/// `\input{file}` 和本地宏包转到文件，自定义命令或环境转到其 `\newcommand`。标签和宏来自工作区索引，
+/// 不打开项目中的其他文件也能找到。`source` 是编辑器中未保存的文本，没有时从磁盘读取。找不到时为空
+#[command]
+pub async fn goto_definition(
+    app: AppHandle,
+    scope: WindowScope,
+    path: String,
+    line: u32,
+    column: u32,
+    source: Option<String>,
+) -> Result<Vec<Location>, String> {
+    scope.check(&path)?;
+    let path = PathBuf::from(path);
+    let source = match source {
+        Some(source) => source,
+        None => fs::read_to_string(&path).map_err(|e| format!("无法读取文件: {}", e))?,
+    };
+    tauri::async_runtime::spawn_blocking(move || definition(&app, &path, &source, line, column))
+        .await
+        .map_err(|e| e.to_string())
+}
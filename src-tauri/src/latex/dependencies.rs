@@ -0,0 +1,211 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Serialize;
+use tauri::command;
+
+use super::root::{canonical, included_files};
+use super::strip_comment;
+use crate::scope::WindowScope;
+
+/// `\includegraphics` 省略扩展名时依次尝试，与 pdfTeX 和 XeTeX 的做法相同
+const GRAPHIC_EXTENSIONS: &[&str] = &["pdf", "png", "jpg", "jpeg", "eps"];
+
+static GRAPHICS_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\\includegraphics\s*\*?\s*(?:\[[^\]]*\])?\s*\{([^}]+)\}").unwrap());
+static GRAPHICSPATH_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\\graphicspath\s*\{((?:\{[^}]*\})+)\}").unwrap());
+static GRAPHICSPATH_ENTRY_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{([^}]*)\}").unwrap());
+/// `\bibliography{a,b}` 的名字不带 `.bib`；`\addbibresource` 的文件名原样使用
+static BIB_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\\(bibliography|addbibresource)\s*(?:\[[^\]]*\])?\s*\{([^}]+)\}").unwrap());
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyKind {
+    Tex,
+    Graphic,
+    Bib,
+}
+
+#[derive(Serialize)]
+pub struct DependencyNode {
+    path: String,
+    kind: DependencyKind,
+    exists: bool,
+}
+
+/// `from` 在第 `line` 行（从 1 开始）引用了 `to`
+#[derive(Serialize)]
+pub struct DependencyEdge {
+    from: String,
+    to: String,
+    line: u32,
+}
+
+/// 对不存在的文件的引用
+#[derive(Serialize)]
+pub struct DependencyDiagnostic {
+    file: String,
+    line: u32,
+    message: String,
+}
+
+#[derive(Serialize)]
+pub struct DependencyGraph {
+    root: String,
+    /// 根文档在前，其余按文件第一次被引用的顺序
+    nodes: Vec<DependencyNode>,
+    edges: Vec<DependencyEdge>,
+    diagnostics: Vec<DependencyDiagnostic>,
+}
+
+impl DependencyGraph {
+    /// 构建会读取的所有现存文件，包括根文档
+    pub fn existing_files(&self) -> impl Iterator<Item = PathBuf> + '_ {
+        self.nodes.iter().filter(|node| node.exists).map(|node| PathBuf::from(&node.path))
+    }
+
+    pub fn diagnostic_messages(&self) -> impl Iterator<Item = &str> + '_ {
+        self.diagnostics.iter().map(|diagnostic| diagnostic.message.as_str())
+    }
+
+    /// 每处引用的引用文件、被引用文件和行号
+    pub fn references(&self) -> impl Iterator<Item = (PathBuf, PathBuf, u32)> + '_ {
+        self.edges.iter().map(|edge| (PathBuf::from(&edge.from), PathBuf::from(&edge.to), edge.line))
+    }
+
+    pub fn existing_of_kind(&self, kind: DependencyKind) -> impl Iterator<Item = PathBuf> + '_ {
+        self.nodes
+            .iter()
+            .filter(move |node| node.exists && node.kind == kind)
+            .map(|node| PathBuf::from(&node.path))
+    }
+}
+
+struct Builder {
+    graph: DependencyGraph,
+    /// `graph.nodes` 的规范路径
+    seen: HashSet<PathBuf>,
+}
+
+impl Builder {
+    /// 加入尚未出现的节点；新加入时返回 true
+    fn node(&mut self, path: &Path, kind: DependencyKind) -> bool {
+        if !self.seen.insert(canonical(path)) {
+            return false;
+        }
+        self.graph.nodes.push(DependencyNode {
+            path: path.to_string_lossy().to_string(),
+            kind,
+            exists: path.is_file(),
+        });
+        true
+    }
+
+    fn reference(&mut self, from: &Path, line: u32, to: &Path, kind: DependencyKind) -> bool {
+        let is_new = self.node(to, kind);
+        self.graph.edges.push(DependencyEdge {
+            from: from.to_string_lossy().to_string(),
+            to: to.to_string_lossy().to_string(),
+            line,
+        });
+        if !to.is_file() {
+            let what = match kind {
+                DependencyKind::Tex => "包含的文件",
+                DependencyKind::Graphic => "图片",
+                DependencyKind::Bib => "参考文献",
+            };
+            self.graph.diagnostics.push(DependencyDiagnostic {
+                file: from.to_string_lossy().to_string(),
+                line,
+                message: format!("未找到{}: {}", what, to.to_string_lossy()),
+            });
+        }
+        is_new
+    }
+}
+
+/// `\includegraphics{name}` 解析到的位置：先相对根目录，再依次相对每个 `\graphicspath` 条目，
+/// `name` 没有扩展名时尝试常用扩展名。取第一个存在的候选；都不存在时用原名
+pub fn resolve_graphic(base_dir: &Path, graphics_paths: &[PathBuf], name: &str) -> PathBuf {
+    let name = name.trim();
+    let dirs = std::iter::once(base_dir.to_path_buf()).chain(graphics_paths.iter().cloned());
+    let mut candidates = Vec::new();
+    for dir in dirs {
+        let path = dir.join(name);
+        if path.extension().is_none() {
+            candidates.extend(GRAPHIC_EXTENSIONS.iter().map(|ext| path.with_extension(ext)));
+        }
+        candidates.push(path);
+    }
+    candidates.iter().find(|path| path.is_file()).cloned().unwrap_or_else(|| base_dir.join(name))
+}
+
+/// 从 `root` 开始跟随 `\input`/`\include`/`\subfile`/`\import`，收集每个文档用到的图片和参考文献文件。
+/// 与 TeX 一样，所有路径都相对根文档所在目录解析
+pub fn dependency_graph(root: &Path) -> DependencyGraph {
+    let base_dir = root.parent().unwrap_or(Path::new("."));
+    let mut builder = Builder {
+        graph: DependencyGraph {
+            root: root.to_string_lossy().to_string(),
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            diagnostics: Vec::new(),
+        },
+        seen: HashSet::new(),
+    };
+    builder.node(root, DependencyKind::Tex);
+    let mut graphics_paths = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
+
+    while let Some(current) = stack.pop() {
+        let Ok(content) = fs::read_to_string(&current) else {
+            continue;
+        };
+        let mut included = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            let line_number = i as u32 + 1;
+            let code = strip_comment(line);
+
+            for caps in GRAPHICSPATH_RE.captures_iter(code) {
+                graphics_paths
+                    .extend(GRAPHICSPATH_ENTRY_RE.captures_iter(&caps[1]).map(|entry| base_dir.join(entry[1].trim())));
+            }
+            for path in included_files(code, base_dir) {
+                if builder.reference(&current, line_number, &path, DependencyKind::Tex) {
+                    included.push(path);
+                }
+            }
+            for caps in GRAPHICS_RE.captures_iter(code) {
+                let path = resolve_graphic(base_dir, &graphics_paths, &caps[1]);
+                builder.reference(&current, line_number, &path, DependencyKind::Graphic);
+            }
+            for caps in BIB_RE.captures_iter(code) {
+                for name in caps[2].split(',').map(str::trim).filter(|name| !name.is_empty()) {
+                    let mut path = base_dir.join(name);
+                    if &caps[1] == "bibliography" && path.extension().is_none() {
+                        path.set_extension("bib");
+                    }
+                    builder.reference(&current, line_number, &path, DependencyKind::Bib);
+                }
+            }
+        }
+        // 按源码顺序深度优先
+        stack.extend(included.into_iter().rev());
+    }
+    builder.graph
+}
+
+/// `root_tex` 引入的文件，用于项目结构图，并在编译前报告缺失的包含文件、图片和参考文献
+#[command]
+pub fn project_dependencies(scope: WindowScope, root_tex: String) -> Result<DependencyGraph, String> {
+    scope.check(&root_tex)?;
+    let root = PathBuf::from(&root_tex);
+    if !root.is_file() {
+        return Err(format!("无法读取文件: {}", root_tex));
+    }
+    Ok(dependency_graph(&root))
+}
@@ -0,0 +1,173 @@
+//! 静态悬停文档：最常用的命令、环境和宏包的一行说明和用法。宏包为空表示 LaTeX 内核
+
+/// (名字, 用法, 宏包, 说明)
+pub type CommandDoc = (&'static str, &'static str, &'static str, &'static str);
+/// (名字, 宏包, 说明)
+pub type EnvironmentDoc = (&'static str, &'static str, &'static str);
+/// (名字, 说明)
+pub type PackageDoc = (&'static str, &'static str);
+
+pub const COMMAND_DOCS: &[CommandDoc] = &[
+    ("documentclass", "\\documentclass[options]{class}", "", "Sets the document class (article, report, book, beamer, ...) and its global options."),
+    ("usepackage", "\\usepackage[options]{package}", "", "Loads one or more packages in the preamble."),
+    ("input", "\\input{file}", "", "Inserts the contents of another file at this point."),
+    ("include", "\\include{file}", "", "Inserts a file on a new page; can be limited with \\includeonly."),
+    ("includeonly", "\\includeonly{file,...}", "", "Restricts which \\include files are typeset."),
+    ("part", "\\part[short]{title}", "", "Starts a part, the outermost sectioning level."),
+    ("chapter", "\\chapter[short]{title}", "", "Starts a chapter (report and book classes)."),
+    ("section", "\\section[short]{title}", "", "Starts a numbered section; the starred form is unnumbered."),
+    ("subsection", "\\subsection[short]{title}", "", "Starts a subsection."),
+    ("subsubsection", "\\subsubsection[short]{title}", "", "Starts a subsubsection."),
+    ("paragraph", "\\paragraph{title}", "", "Starts a run-in paragraph heading."),
+    ("title", "\\title{text}", "", "Sets the title used by \\maketitle."),
+    ("author", "\\author{names}", "", "Sets the author(s) used by \\maketitle; separate several with \\and."),
+    ("date", "\\date{text}", "", "Sets the date used by \\maketitle; \\date{} removes it."),
+    ("maketitle", "\\maketitle", "", "Typesets the title block from \\title, \\author and \\date."),
+    ("tableofcontents", "\\tableofcontents", "", "Prints the table of contents; needs a second run."),
+    ("appendix", "\\appendix", "", "Switches sectioning to appendix numbering (A, B, ...)."),
+    ("label", "\\label{key}", "", "Marks the current counter value (section, equation, figure, ...) for \\ref."),
+    ("ref", "\\ref{key}", "", "Prints the number of a \\label."),
+    ("pageref", "\\pageref{key}", "", "Prints the page a \\label is on."),
+    ("cite", "\\cite[note]{key,...}", "", "Cites bibliography entries."),
+    ("nocite", "\\nocite{key,...}", "", "Adds entries to the bibliography without citing them; \\nocite{*} adds all."),
+    ("bibliography", "\\bibliography{file,...}", "", "Prints the BibTeX bibliography from the given .bib files."),
+    ("bibliographystyle", "\\bibliographystyle{style}", "", "Chooses the BibTeX style (plain, alpha, abbrvnat, ...)."),
+    ("footnote", "\\footnote[number]{text}", "", "Adds a footnote."),
+    ("caption", "\\caption[short]{text}", "", "Numbers and captions a figure or table; put \\label after it."),
+    ("item", "\\item[label]", "", "Starts an item in a list environment."),
+    ("textbf", "\\textbf{text}", "", "Bold text."),
+    ("textit", "\\textit{text}", "", "Italic text."),
+    ("texttt", "\\texttt{text}", "", "Monospaced (typewriter) text."),
+    ("textsc", "\\textsc{text}", "", "Small capitals."),
+    ("emph", "\\emph{text}", "", "Emphasized text, italic in upright context and upright in italic context."),
+    ("underline", "\\underline{text}", "", "Underlined text; does not break across lines."),
+    ("frac", "\\frac{numerator}{denominator}", "", "A fraction (math mode)."),
+    ("sqrt", "\\sqrt[n]{expression}", "", "A square root, or n-th root with the optional argument (math mode)."),
+    ("sum", "\\sum_{lower}^{upper}", "", "Summation operator (math mode)."),
+    ("int", "\\int_{lower}^{upper}", "", "Integral sign (math mode)."),
+    ("left", "\\left<delimiter> ... \\right<delimiter>", "", "Delimiters that grow with their content; \\left. gives an invisible one."),
+    ("mathbf", "\\mathbf{symbols}", "", "Bold upright math letters."),
+    ("mathrm", "\\mathrm{symbols}", "", "Upright math letters, e.g. for units or named functions."),
+    ("mathcal", "\\mathcal{symbols}", "", "Calligraphic capital letters."),
+    ("newcommand", "\\newcommand{\\name}[n][default]{definition}", "", "Defines a new command with n arguments; errors if it exists."),
+    ("renewcommand", "\\renewcommand{\\name}[n][default]{definition}", "", "Redefines an existing command."),
+    ("providecommand", "\\providecommand{\\name}[n][default]{definition}", "", "Defines a command only if it doesn't exist yet."),
+    ("newenvironment", "\\newenvironment{name}[n][default]{begin code}{end code}", "", "Defines a new environment."),
+    ("newtheorem", "\\newtheorem{name}[shared counter]{Printed name}[within]", "", "Defines a numbered theorem-like environment."),
+    ("newpage", "\\newpage", "", "Ends the current page."),
+    ("clearpage", "\\clearpage", "", "Ends the page and flushes pending figures and tables."),
+    ("centering", "\\centering", "", "Centers the rest of the enclosing group or environment."),
+    ("noindent", "\\noindent", "", "Suppresses the indentation of the current paragraph."),
+    ("vspace", "\\vspace{length}", "", "Adds vertical space; the starred form is kept at page breaks."),
+    ("hspace", "\\hspace{length}", "", "Adds horizontal space."),
+    ("hline", "\\hline", "", "A horizontal rule across a tabular."),
+    ("linewidth", "\\linewidth", "", "Width of the current line, e.g. inside a minipage or column."),
+    ("textwidth", "\\textwidth", "", "Width of the text block of the page."),
+    ("includegraphics", "\\includegraphics[key=value]{file}", "graphicx", "Inserts an image; width, height, scale, angle and page are common keys."),
+    ("graphicspath", "\\graphicspath{{dir/}...}", "graphicx", "Directories searched by \\includegraphics."),
+    ("eqref", "\\eqref{key}", "amsmath", "Reference to an equation, in parentheses."),
+    ("text", "\\text{words}", "amsmath", "Normal text inside math mode."),
+    ("operatorname", "\\operatorname{name}", "amsmath", "Typesets a word as a math operator like \\sin."),
+    ("DeclareMathOperator", "\\DeclareMathOperator{\\name}{text}", "amsmath", "Defines a math operator command; the starred form takes limits."),
+    ("tag", "\\tag{label}", "amsmath", "Replaces an equation's number with the given label."),
+    ("binom", "\\binom{n}{k}", "amsmath", "Binomial coefficient."),
+    ("mathbb", "\\mathbb{letters}", "amssymb", "Blackboard bold capitals, e.g. \\mathbb{R}."),
+    ("href", "\\href{url}{text}", "hyperref", "A hyperlink with its own text."),
+    ("url", "\\url{url}", "hyperref", "A typeset URL that breaks at sensible places."),
+    ("autoref", "\\autoref{key}", "hyperref", "Reference with the counter's name, e.g. \"Figure 3\"."),
+    ("cref", "\\cref{key,...}", "cleveref", "Reference with the counter's name, e.g. \"fig. 3\"; \\Cref capitalizes."),
+    ("citep", "\\citep[pre][post]{key}", "natbib", "Parenthetical citation: (Author, 2020)."),
+    ("citet", "\\citet[post]{key}", "natbib", "Textual citation: Author (2020)."),
+    ("parencite", "\\parencite[pre][post]{key}", "biblatex", "Citation in parentheses."),
+    ("textcite", "\\textcite[pre][post]{key}", "biblatex", "Citation for use as part of the sentence."),
+    ("addbibresource", "\\addbibresource{file.bib}", "biblatex", "Adds a .bib file; the extension is required."),
+    ("printbibliography", "\\printbibliography[options]", "biblatex", "Prints the bibliography."),
+    ("SI", "\\SI{number}{unit}", "siunitx", "A quantity with a unit (older syntax; \\qty in siunitx 3)."),
+    ("qty", "\\qty{number}{unit}", "siunitx", "A quantity with a unit."),
+    ("num", "\\num{number}", "siunitx", "A formatted number."),
+    ("toprule", "\\toprule", "booktabs", "Top rule of a table."),
+    ("midrule", "\\midrule", "booktabs", "Rule between a table's header and body."),
+    ("bottomrule", "\\bottomrule", "booktabs", "Bottom rule of a table."),
+    ("textcolor", "\\textcolor{color}{text}", "xcolor", "Colored text."),
+    ("todo", "\\todo[options]{text}", "todonotes", "A margin note marking unfinished work."),
+    ("frametitle", "\\frametitle{title}", "beamer", "Title of the current frame."),
+    ("pause", "\\pause", "beamer", "Reveals the rest of the frame on the next slide."),
+];
+
+pub const ENVIRONMENT_DOCS: &[EnvironmentDoc] = &[
+    ("document", "", "The body of the document; everything before it is the preamble."),
+    ("abstract", "", "The abstract (article and report classes)."),
+    ("itemize", "", "A bulleted list of \\item entries."),
+    ("enumerate", "", "A numbered list of \\item entries."),
+    ("description", "", "A list whose \\item[label] entries have bold labels."),
+    ("figure", "", "A floating figure; use [htbp] to suggest placement."),
+    ("table", "", "A floating table."),
+    ("tabular", "", "A table body; the argument gives the column types (l, c, r, p{width}, |)."),
+    ("equation", "", "A numbered displayed equation."),
+    ("center", "", "Centered lines."),
+    ("quote", "", "An indented quotation for short passages."),
+    ("verbatim", "", "Text printed exactly as typed, in monospace."),
+    ("minipage", "", "A box of given width that can hold paragraphs."),
+    ("thebibliography", "", "A hand-written bibliography of \\bibitem entries."),
+    ("titlepage", "", "A title page without a page number."),
+    ("align", "amsmath", "Several equations aligned at &, each numbered."),
+    ("gather", "amsmath", "Several centered equations, each numbered."),
+    ("multline", "amsmath", "One long equation split across lines."),
+    ("split", "amsmath", "A multi-line part of an equation under one number."),
+    ("cases", "amsmath", "Piecewise definitions with a left brace."),
+    ("pmatrix", "amsmath", "A matrix in parentheses; bmatrix uses brackets."),
+    ("proof", "amsthm", "A proof, ended with a QED symbol."),
+    ("tikzpicture", "tikz", "A TikZ drawing."),
+    ("axis", "pgfplots", "A plot with axes, inside tikzpicture."),
+    ("frame", "beamer", "One slide (or a sequence of overlays)."),
+    ("columns", "beamer", "Side-by-side columns; each is a column environment."),
+    ("lstlisting", "listings", "A code listing with syntax highlighting."),
+    ("minted", "minted", "A code listing highlighted by Pygments; needs shell escape."),
+    ("algorithm", "algorithm", "A floating algorithm."),
+    ("subfigure", "subcaption", "A sub-figure with its own caption inside a figure."),
+];
+
+pub const PACKAGE_DOCS: &[PackageDoc] = &[
+    ("amsmath", "AMS mathematical typesetting: align, gather, cases, \\text, \\eqref, ..."),
+    ("amssymb", "AMS math symbols and \\mathbb."),
+    ("amsthm", "Theorem styles, \\newtheorem improvements and the proof environment."),
+    ("mathtools", "Fixes and extensions to amsmath; loads it."),
+    ("graphicx", "\\includegraphics and image scaling and rotation."),
+    ("hyperref", "Hyperlinks, PDF bookmarks and metadata; load it late."),
+    ("cleveref", "\\cref references that name their counters; load after hyperref."),
+    ("natbib", "Author-year and numeric citations with \\citet and \\citep."),
+    ("biblatex", "Bibliographies with biber: \\addbibresource, \\printbibliography, \\textcite, ..."),
+    ("geometry", "Page size and margins."),
+    ("babel", "Language-specific hyphenation and captions."),
+    ("fontenc", "Font encoding; T1 gives proper hyphenation of accented words."),
+    ("inputenc", "Input encoding; UTF-8 is the default since 2018."),
+    ("xcolor", "Colors and \\textcolor."),
+    ("tikz", "Programmatic drawings in the tikzpicture environment."),
+    ("pgfplots", "Plots drawn with TikZ."),
+    ("booktabs", "Professional table rules: \\toprule, \\midrule, \\bottomrule."),
+    ("siunitx", "Numbers and units: \\num, \\qty, the S column type."),
+    ("listings", "Code listings with highlighting, no external tools."),
+    ("minted", "Code listings highlighted by Pygments; needs -shell-escape."),
+    ("subcaption", "Sub-figures and sub-tables with their own captions."),
+    ("caption", "Customizes caption formatting."),
+    ("enumitem", "Control over list spacing and labels."),
+    ("microtype", "Character protrusion and font expansion for better justification."),
+    ("todonotes", "\\todo margin notes and \\listoftodos."),
+    ("fontspec", "OpenType fonts with XeLaTeX and LuaLaTeX."),
+    ("csquotes", "Context-sensitive quotation marks with \\enquote."),
+    ("algorithm2e", "Typesetting algorithms in pseudocode."),
+    ("beamer", "Presentation slides."),
+];
+
+pub fn command_doc(name: &str) -> Option<&'static CommandDoc> {
+    COMMAND_DOCS.iter().find(|doc| doc.0 == name)
+}
+
+pub fn environment_doc(name: &str) -> Option<&'static EnvironmentDoc> {
+    let name = name.trim_end_matches('*');
+    ENVIRONMENT_DOCS.iter().find(|doc| doc.0 == name)
+}
+
+pub fn package_doc(name: &str) -> Option<&'static PackageDoc> {
+    PACKAGE_DOCS.iter().find(|doc| doc.0 == name)
+}
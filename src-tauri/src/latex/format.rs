@@ -0,0 +1,393 @@
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::process::{Command, Stdio};
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use super::strip_comment;
+
+/// 环境体逐字节原样复制
+const VERBATIM_ENVIRONMENTS: &[&str] = &["verbatim", "verbatim*", "lstlisting", "minted", "comment", "Verbatim"];
+/// `\item` 的内容比 `\item` 所在行多缩进一级
+const LIST_ENVIRONMENTS: &[&str] = &["itemize", "enumerate", "description"];
+/// 这些环境的各行按 `&` 列对齐
+const ALIGNED_ENVIRONMENTS: &[&str] = &[
+    "tabular", "tabular*", "tabularx", "longtable", "array", "align", "align*", "alignat", "alignat*",
+    "flalign", "flalign*", "aligned", "split", "matrix", "pmatrix", "bmatrix", "vmatrix", "Vmatrix",
+    "cases", "eqnarray", "eqnarray*",
+];
+
+static TOKEN_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\\begin\s*\{([^}]+)\}|\\end\s*\{([^}]+)\}|\\\[|\\\]|\\item\b|\\.|[{}]").unwrap()
+});
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct FormatSettings {
+    pub indent_width: usize,
+    pub use_tabs: bool,
+    /// 大多数文档的 `document` 环境体不缩进
+    pub indent_document: bool,
+    pub align_columns: bool,
+    /// 安装了 `latexindent` 时使用它，否则用内置的缩进器
+    pub use_latexindent: bool,
+}
+
+impl Default for FormatSettings {
+    fn default() -> Self {
+        FormatSettings { indent_width: 4, use_tabs: false, indent_document: false, align_columns: true, use_latexindent: true }
+    }
+}
+
+impl FormatSettings {
+    fn indent_unit(&self) -> String {
+        if self.use_tabs {
+            "\t".to_string()
+        } else {
+            " ".repeat(self.indent_width)
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct FormatResult {
+    formatted: String,
+    /// `formatted` 与输入相同时为 false，保存时格式化可以不动缓冲区
+    changed: bool,
+    /// `latexindent` 或 `builtin`
+    formatter: &'static str,
+}
+
+/// `Ok(None)` 表示没有安装 latexindent
+fn run_latexindent(source: &str, settings: &FormatSettings) -> Result<Option<String>, String> {
+    // latexindent 会在工作目录里写 indent.log
+    let log_dir = std::env::temp_dir().join("latexindent");
+    let _ = fs::create_dir_all(&log_dir);
+    let mut cmd = Command::new("latexindent");
+    cmd.arg("-s")
+        .arg(format!("-c={}", log_dir.to_string_lossy()))
+        .arg(format!("-y=defaultIndent:'{}'", settings.indent_unit()))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("latexindent 运行失败: {}", e)),
+    };
+    let mut stdin = child.stdin.take().expect("latexindent stdin piped");
+    let input = source.to_string();
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output().map_err(|e| format!("latexindent 运行失败: {}", e))?;
+    let _ = writer.join();
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("latexindent 出错:\n{}", stderr.trim()));
+    }
+    Ok(Some(String::from_utf8_lossy(&output.stdout).to_string()))
+}
+
+enum FrameKind {
+    Environment(String),
+    DisplayMath,
+    Brace,
+}
+
+struct Frame {
+    kind: FrameKind,
+    /// 这一层给其内容增加的缩进级数
+    indent: usize,
+    /// 在列表中第一个 `\item` 之后
+    in_item: bool,
+    /// 标识同一个对齐环境的各行
+    align_group: Option<usize>,
+}
+
+enum Token<'a> {
+    Begin(&'a str),
+    End(&'a str),
+    OpenDisplay,
+    CloseDisplay,
+    Item,
+    Open,
+    Close,
+}
+
+fn tokens(line: &str) -> Vec<(usize, usize, Token<'_>)> {
+    TOKEN_RE
+        .captures_iter(line)
+        .filter_map(|caps| {
+            let whole = caps.get(0).unwrap();
+            let token = if let Some(name) = caps.get(1) {
+                Token::Begin(name.as_str().trim())
+            } else if let Some(name) = caps.get(2) {
+                Token::End(name.as_str().trim())
+            } else {
+                match whole.as_str() {
+                    "\\[" => Token::OpenDisplay,
+                    "\\]" => Token::CloseDisplay,
+                    "\\item" => Token::Item,
+                    "{" => Token::Open,
+                    "}" => Token::Close,
+                    // `\{` 和 `\%` 这样的转义
+                    _ => return None,
+                }
+            };
+            Some((whole.start(), whole.end(), token))
+        })
+        .collect()
+}
+
+struct Indenter<'a> {
+    settings: &'a FormatSettings,
+    stack: Vec<Frame>,
+    next_group: usize,
+}
+
+impl Indenter<'_> {
+    fn level(&self) -> usize {
+        self.stack.iter().map(|f| f.indent + usize::from(f.in_item)).sum()
+    }
+
+    fn is_closer(&self, token: &Token) -> bool {
+        match token {
+            Token::End(name) => self
+                .stack
+                .iter()
+                .any(|f| matches!(&f.kind, FrameKind::Environment(open) if open == name)),
+            Token::CloseDisplay => matches!(self.stack.last(), Some(Frame { kind: FrameKind::DisplayMath, .. })),
+            Token::Close => matches!(self.stack.last(), Some(Frame { kind: FrameKind::Brace, .. })),
+            _ => false,
+        }
+    }
+
+    fn apply(&mut self, token: &Token) {
+        match token {
+            Token::Begin(name) => {
+                let indent = usize::from(*name != "document" || self.settings.indent_document);
+                let align_group = ALIGNED_ENVIRONMENTS.contains(name).then(|| {
+                    self.next_group += 1;
+                    self.next_group
+                });
+                self.stack.push(Frame { kind: FrameKind::Environment(name.to_string()), indent, in_item: false, align_group });
+            }
+            Token::End(name) => {
+                // 容忍其中缺少的 `}` 或 `\end`：回退到匹配的那一层
+                let position = self
+                    .stack
+                    .iter()
+                    .rposition(|f| matches!(&f.kind, FrameKind::Environment(open) if open == name));
+                if let Some(position) = position {
+                    self.stack.truncate(position);
+                }
+            }
+            Token::OpenDisplay => {
+                self.stack.push(Frame { kind: FrameKind::DisplayMath, indent: 1, in_item: false, align_group: None })
+            }
+            Token::Open => self.stack.push(Frame { kind: FrameKind::Brace, indent: 1, in_item: false, align_group: None }),
+            Token::CloseDisplay | Token::Close => {
+                if self.is_closer(token) {
+                    self.stack.pop();
+                }
+            }
+            Token::Item => {
+                if let Some(frame) = self.stack.iter_mut().rev().find(|f| is_list(f)) {
+                    frame.in_item = true;
+                }
+            }
+        }
+    }
+
+    /// 缩进一行；同时返回该行直接所属的对齐环境（如果有）
+    fn line(&mut self, line: &str) -> (usize, Option<usize>) {
+        let code = strip_comment(line);
+        let tokens = tokens(code);
+        let mut rest = tokens.iter().peekable();
+        // 行首的闭合符号让该行本身少缩进一级
+        let mut start = 0;
+        while let Some((token_start, end, token)) = rest.peek() {
+            if !code[start..*token_start].trim().is_empty() || !self.is_closer(token) {
+                break;
+            }
+            self.apply(token);
+            start = *end;
+            rest.next();
+        }
+        // `\item` 与列表同级，其内容再深一级
+        if let Some((token_start, _, Token::Item)) = rest.peek() {
+            if code[start..*token_start].trim().is_empty() {
+                if let Some(frame) = self.stack.iter_mut().rev().find(|f| is_list(f)) {
+                    frame.in_item = false;
+                }
+            }
+        }
+        let level = self.level();
+        let depth = self.stack.len();
+        let group = self.stack.last().and_then(|f| f.align_group);
+        for (_, _, token) in rest {
+            self.apply(token);
+        }
+        // 只有不打开也不关闭任何结构的行参与对齐
+        let group = group.filter(|_| self.stack.len() == depth);
+        (level, group)
+    }
+}
+
+fn is_list(frame: &Frame) -> bool {
+    matches!(&frame.kind, FrameKind::Environment(name) if LIST_ENVIRONMENTS.contains(&name.as_str()))
+}
+
+/// 在花括号之外的 `&` 处拆分一行；转义的 `\&` 留在所在单元格中
+fn split_cells(code: &str) -> Vec<&str> {
+    let mut cells = Vec::new();
+    let (mut depth, mut start) = (0i32, 0);
+    let bytes = code.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'{' => depth += 1,
+            b'}' => depth -= 1,
+            b'&' if depth == 0 => {
+                cells.push(&code[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    cells.push(&code[start..]);
+    cells
+}
+
+/// 给 `rows`（同一环境中已缩进的行）的单元格补空格，让 `&` 对齐
+fn align_rows(rows: &mut [String]) {
+    let split: Vec<Option<(String, Vec<String>, String)>> = rows
+        .iter()
+        .map(|row| {
+            let code = strip_comment(row);
+            let comment = row[code.len()..].to_string();
+            let indent_len = code.len() - code.trim_start().len();
+            let cells: Vec<String> = split_cells(code[indent_len..].trim_end())
+                .iter()
+                .map(|c| c.trim().to_string())
+                .collect();
+            (cells.len() > 1).then(|| (code[..indent_len].to_string(), cells, comment))
+        })
+        .collect();
+
+    let mut widths: Vec<usize> = Vec::new();
+    for (_, cells, _) in split.iter().flatten() {
+        // 最后一个单元格含 `\\`，不补空格
+        for (column, cell) in cells[..cells.len() - 1].iter().enumerate() {
+            let width = cell.chars().count();
+            match widths.get_mut(column) {
+                Some(max) => *max = (*max).max(width),
+                None => widths.push(width),
+            }
+        }
+    }
+
+    for (row, parts) in rows.iter_mut().zip(split) {
+        let Some((indent, cells, comment)) = parts else {
+            continue;
+        };
+        let last = cells.len() - 1;
+        let mut aligned = indent;
+        for (column, cell) in cells.iter().enumerate() {
+            if column > 0 {
+                aligned.push_str(if cell.is_empty() && column == last { " &" } else { " & " });
+            }
+            aligned.push_str(cell);
+            if column < last {
+                let padding = widths[column] - cell.chars().count();
+                aligned.extend(std::iter::repeat_n(' ', padding));
+            }
+        }
+        let mut aligned = aligned.trim_end().to_string();
+        if !comment.is_empty() {
+            aligned.push(' ');
+            aligned.push_str(&comment);
+        }
+        *row = aligned;
+    }
+}
+
+fn builtin_format(source: &str, settings: &FormatSettings) -> String {
+    let unit = settings.indent_unit();
+    let mut indenter = Indenter { settings, stack: Vec::new(), next_group: 0 };
+    let mut lines: Vec<(String, Option<usize>)> = Vec::new();
+    let mut verbatim: Option<String> = None;
+
+    for raw_line in source.lines() {
+        if let Some(env) = &verbatim {
+            let end = format!("\\end{{{}}}", env);
+            if raw_line.contains(&end) {
+                verbatim = None;
+                let (level, _) = indenter.line(raw_line.trim());
+                lines.push((format!("{}{}", unit.repeat(level), raw_line.trim()), None));
+            } else {
+                lines.push((raw_line.to_string(), None));
+            }
+            continue;
+        }
+        let trimmed = raw_line.trim();
+        if trimmed.is_empty() {
+            lines.push((String::new(), None));
+            continue;
+        }
+        let (level, group) = indenter.line(trimmed);
+        if let Some(env) = tokens(strip_comment(trimmed)).iter().rev().find_map(|(_, _, t)| match t {
+            Token::Begin(name) if VERBATIM_ENVIRONMENTS.contains(name) => Some(name.to_string()),
+            _ => None,
+        }) {
+            if !trimmed.contains(&format!("\\end{{{}}}", env)) {
+                verbatim = Some(env);
+            }
+        }
+        lines.push((format!("{}{}", unit.repeat(level), trimmed), group));
+    }
+
+    let mut output: Vec<String> = Vec::with_capacity(lines.len());
+    let mut index = 0;
+    while index < lines.len() {
+        let group = lines[index].1;
+        let mut end = index + 1;
+        while group.is_some() && end < lines.len() && lines[end].1 == group {
+            end += 1;
+        }
+        let mut block: Vec<String> = lines[index..end].iter().map(|(l, _)| l.clone()).collect();
+        if group.is_some() && settings.align_columns {
+            align_rows(&mut block);
+        }
+        output.extend(block);
+        index = end;
+    }
+
+    let mut formatted = output.join("\n");
+    if source.ends_with('\n') {
+        formatted.push('\n');
+    }
+    formatted
+}
+
+/// 重新缩进 `source`：环境体、`\item` 的后续行和多行花括号组各缩进一级，`&` 列对齐。
+/// 安装了 latexindent 且设置了 `use_latexindent` 时运行 latexindent
+#[command]
+pub async fn format_latex(source: String, settings: Option<FormatSettings>) -> Result<FormatResult, String> {
+    let settings = settings.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || {
+        let latexindent = if settings.use_latexindent { run_latexindent(&source, &settings)? } else { None };
+        let (formatted, formatter) = match latexindent {
+            Some(formatted) => (formatted, "latexindent"),
+            None => (builtin_format(&source, &settings), "builtin"),
+        };
+        Ok(FormatResult { changed: formatted != source, formatted, formatter })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
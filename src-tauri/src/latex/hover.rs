@@ -0,0 +1,256 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Serialize;
+use tauri::{command, AppHandle, Manager};
+
+use super::completion::{is_citation_command, is_reference_command};
+use super::docs::{command_doc, environment_doc, package_doc};
+use super::root::find_root;
+use super::strip_comment;
+use crate::bibliography::bib_files;
+use crate::bibliography::parser::{clean_value, parse_bib, split_names, BibEntry};
+use crate::index::macros::{extract_macros, MacroDefinition, MacroKind};
+use crate::index::ProjectIndex;
+use crate::scope::WindowScope;
+
+/// 同一行内的 `\command[opt]{argument}`
+static ARGUMENT_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\\([A-Za-z]+)\*?\s*(?:\[[^\]]*\]\s*)*\{([^{}]*)\}").unwrap());
+static COMMAND_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\\([A-Za-z@]+)").unwrap());
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum HoverKind {
+    Command,
+    Environment,
+    Package,
+    /// 项目中的 `\newcommand`/`\newenvironment`
+    UserMacro,
+    Citation,
+}
+
+#[derive(Serialize)]
+pub struct HoverInfo {
+    kind: HoverKind,
+    /// Markdown 格式
+    contents: String,
+    /// 悬停的词：从 1 开始的行号和列号，结束位置不含，按 Monaco 的方式以字符计
+    line: u32,
+    start_column: u32,
+    end_column: u32,
+}
+
+/// 查找之前，光标下的名字及其所指的对象。与转到定义共用
+pub enum Target<'a> {
+    Command(&'a str),
+    Environment(&'a str),
+    Package(&'a str),
+    Citation(&'a str),
+    /// `\ref`、`\cref` 等命令中的标签
+    Reference(&'a str),
+    /// `\input`、`\include`、`\includegraphics` 等命令的参数，连同命令本身
+    File(&'a str, &'a str),
+}
+
+fn char_column(line: &str, byte: usize) -> u32 {
+    line[..byte].chars().count() as u32 + 1
+}
+
+/// `list`（从该行的字节偏移 `offset` 开始）中 `cursor` 所在的逗号分隔元素，及其在该行中的字节范围
+fn list_element(list: &str, offset: usize, cursor: usize) -> Option<(&str, usize, usize)> {
+    let mut start = offset;
+    for part in list.split(',') {
+        let end = start + part.len();
+        if cursor >= start && cursor <= end {
+            let name = part.trim();
+            if name.is_empty() {
+                return None;
+            }
+            let name_start = start + part.find(name).unwrap_or(0);
+            return Some((name, name_start, name_start + name.len()));
+        }
+        start = end + 1;
+    }
+    None
+}
+
+/// `line`（已去掉注释）中字节位置 `cursor` 处的内容，及名字的字节范围
+pub fn target_at(line: &str, cursor: usize) -> Option<(Target<'_>, usize, usize)> {
+    for caps in ARGUMENT_RE.captures_iter(line) {
+        let argument = caps.get(2).unwrap();
+        if cursor < argument.start() || cursor > argument.end() {
+            continue;
+        }
+        let command = caps.get(1).unwrap().as_str();
+        let Some((name, start, end)) = list_element(argument.as_str(), argument.start(), cursor) else {
+            continue;
+        };
+        let target = match command {
+            "begin" | "end" => Target::Environment(name),
+            "usepackage" | "RequirePackage" => Target::Package(name),
+            command if is_citation_command(command) => Target::Citation(name),
+            command if is_reference_command(command) => Target::Reference(name),
+            "input" | "include" | "subfile" | "includegraphics" | "bibliography" | "addbibresource" => {
+                Target::File(command, name)
+            }
+            _ => continue,
+        };
+        return Some((target, start, end));
+    }
+    COMMAND_RE
+        .captures_iter(line)
+        .map(|caps| caps.get(0).unwrap())
+        .find(|found| cursor >= found.start() && cursor <= found.end())
+        .map(|found| (Target::Command(&line[found.start() + 1..found.end()]), found.start(), found.end()))
+}
+
+/// 由各部分重建出的定义在源码中的写法
+fn definition_source(definition: &MacroDefinition) -> String {
+    let arguments = if definition.arguments > 0 { format!("[{}]", definition.arguments) } else { String::new() };
+    let default = definition.optional_default.as_ref().map(|d| format!("[{}]", d)).unwrap_or_default();
+    match (definition.kind, definition.command.as_str()) {
+        (MacroKind::Environment, command) => format!(
+            "\\{}{{{}}}{}{}{{{}}}{{…}}",
+            command, definition.name, arguments, default, definition.definition
+        ),
+        (_, command) if command.ends_with("def") => {
+            let parameters: String = (1..=definition.arguments).map(|i| format!("#{}", i)).collect();
+            format!("\\{}\\{}{}{{{}}}", command, definition.name, parameters, definition.definition)
+        }
+        (_, command) => {
+            format!("\\{}{{\\{}}}{}{}{{{}}}", command, definition.name, arguments, default, definition.definition)
+        }
+    }
+}
+
+fn macro_hover(file: &Path, definition: &MacroDefinition) -> String {
+    let place = file.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_else(|| "this file".to_string());
+    format!(
+        "User-defined in `{}:{}`\n\n```latex\n{}\n```",
+        place,
+        definition.line,
+        definition_source(definition)
+    )
+}
+
+/// 先查缓冲区自己的定义（可能还没保存），再查已建索引的文件夹
+pub fn find_macro(
+    app: &AppHandle,
+    path: Option<&Path>,
+    source: &str,
+    name: &str,
+    environment: bool,
+) -> Option<(PathBuf, MacroDefinition)> {
+    let matches = |definition: &MacroDefinition| {
+        definition.name == name && (definition.kind == MacroKind::Environment) == environment
+    };
+    let buffer = path.map(Path::to_path_buf).unwrap_or_else(|| PathBuf::from("untitled.tex"));
+    if let Some(definition) = extract_macros(&buffer.with_extension("tex"), source).into_iter().find(matches) {
+        return Some((buffer, definition));
+    }
+    let index = app.state::<ProjectIndex>().containing(path?)?;
+    let index = index.read().unwrap();
+    let found = index.macros().find(|(_, definition)| matches(definition));
+    found.map(|(file, definition)| (file.clone(), definition.clone()))
+}
+
+fn citation_hover(entry: &BibEntry, file: &Path) -> String {
+    let field = |name: &str| entry.field(name).map(clean_value).filter(|value| !value.is_empty());
+    let mut lines = vec![format!("**{}** ({})", entry.key, entry.entry_type)];
+    let authors = entry.field("author").or_else(|| entry.field("editor")).map(split_names).unwrap_or_default();
+    if !authors.is_empty() {
+        lines.push(authors.join(", "));
+    }
+    if let Some(title) = field("title") {
+        lines.push(format!("*{}*", title));
+    }
+    let venue = field("journal").or_else(|| field("journaltitle")).or_else(|| field("booktitle")).or_else(|| field("publisher"));
+    let year = field("year").or_else(|| field("date").map(|date| date.chars().take(4).collect()));
+    match (venue, year) {
+        (Some(venue), Some(year)) => lines.push(format!("{}, {}", venue, year)),
+        (Some(venue), None) => lines.push(venue),
+        (None, Some(year)) => lines.push(year),
+        (None, None) => {}
+    }
+    if let Some(doi) = field("doi") {
+        lines.push(format!("DOI: {}", doi));
+    }
+    let place = file.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    lines.push(format!("`{}:{}`", place, entry.line));
+    lines.join("\n\n")
+}
+
+pub fn find_entry(path: Option<&Path>, key: &str) -> Option<(PathBuf, BibEntry)> {
+    let root = PathBuf::from(find_root(path?, None).root);
+    let base_dir = root.parent()?;
+    bib_files(base_dir).into_iter().find_map(|file| {
+        let content = fs::read_to_string(&file).ok()?;
+        let entry = parse_bib(&content).entries.into_iter().find(|entry| entry.key == key)?;
+        Some((file, entry))
+    })
+}
+
+/// `source` 的第 `line` 行去掉注释后的内容，以及从 1 开始的字符列 `column` 在其中的字节偏移
+pub fn cursor_in(source: &str, line: u32, column: u32) -> Option<(&str, usize)> {
+    let line_text = strip_comment(source.lines().nth(line.checked_sub(1)? as usize)?);
+    let cursor = line_text
+        .char_indices()
+        .nth(column.saturating_sub(1) as usize)
+        .map(|(byte, _)| byte)
+        .unwrap_or(line_text.len());
+    Some((line_text, cursor))
+}
+
+fn hover(app: &AppHandle, path: Option<&Path>, source: &str, line: u32, column: u32) -> Option<HoverInfo> {
+    let (line_text, cursor) = cursor_in(source, line, column)?;
+    let (target, start, end) = target_at(line_text, cursor)?;
+
+    let (kind, contents) = match target {
+        Target::Command(name) => match find_macro(app, path, source, name, false) {
+            Some((file, definition)) => (HoverKind::UserMacro, macro_hover(&file, &definition)),
+            None => {
+                let (_, signature, package, description) = command_doc(name)?;
+                let from = if package.is_empty() { String::new() } else { format!(" — `{}`", package) };
+                (HoverKind::Command, format!("```latex\n{}\n```\n{}{}", signature, description, from))
+            }
+        },
+        Target::Environment(name) => match find_macro(app, path, source, name, true) {
+            Some((file, definition)) => (HoverKind::UserMacro, macro_hover(&file, &definition)),
+            None => {
+                let (name, package, description) = environment_doc(name)?;
+                let from = if package.is_empty() { String::new() } else { format!(" — `{}`", package) };
+                (HoverKind::Environment, format!("**{}** environment\n\n{}{}", name, description, from))
+            }
+        },
+        Target::Package(name) => {
+            let (name, description) = package_doc(name)?;
+            (HoverKind::Package, format!("**{}**\n\n{}", name, description))
+        }
+        Target::Citation(key) => {
+            let (file, entry) = find_entry(path, key)?;
+            (HoverKind::Citation, citation_hover(&entry, &file))
+        }
+        Target::Reference(_) | Target::File(..) => return None,
+    };
+    Some(HoverInfo { kind, contents, line, start_column: char_column(line_text, start), end_column: char_column(line_text, end) })
+}
+
+/// `source` 中 `line`/`column`（从 1 开始）处词语的悬停文档：用户宏解析到其定义，标准命令、环境和宏包
+/// 给出内置的说明，引用 key 给出对应的参考文献条目。没有可说明的内容时为 None
+#[command]
+pub async fn hover_info(
+    app: AppHandle,
+    scope: WindowScope,
+    path: Option<String>,
+    source: String,
+    line: u32,
+    column: u32,
+) -> Result<Option<HoverInfo>, String> {
+    scope.check_optional(path.as_ref())?;
+    tauri::async_runtime::spawn_blocking(move || hover(&app, path.as_deref().map(Path::new), &source, line, column))
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,198 @@
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Serialize;
+use tauri::command;
+
+use super::strip_comment;
+use crate::scope::WindowScope;
+
+/// 每行一条警告：行、列、长度、种类、规则编号、消息。`!n` 是 chktex 的换行转义
+const CHKTEX_FORMAT: &str = "%l:%c:%d:%k:%n:%m!n";
+
+/// 环境体不是 LaTeX、不能检查的环境
+const VERBATIM_ENVIRONMENTS: &[&str] = &["verbatim", "verbatim*", "lstlisting", "minted", "comment", "Verbatim"];
+
+static CHKTEX_LINE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d+):(\d+):(\d+):(\w+):(\d+):(.*)$").unwrap());
+static VERBATIM_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\\(begin|end)\s*\{([^}]+)\}").unwrap());
+
+/// 内置规则，编号与对应的 chktex 规则相同，两种后端给出的规则 id 一致
+static BUILTIN_RULES: LazyLock<Vec<(u32, Regex, &'static str)>> = LazyLock::new(|| {
+    vec![
+        (
+            2,
+            Regex::new(r"[^\s~]( +)\\(?:ref|eqref|pageref|autoref|cref|Cref|cite[a-z]*|footcite)\b").unwrap(),
+            "Non-breaking space (`~') should have been used.",
+        ),
+        (11, Regex::new(r"(\.\.\.)").unwrap(), "You should use \\ldots to achieve an ellipsis."),
+        (
+            12,
+            Regex::new(r"\b(?:e\.g|i\.e|cf|etc|vs|resp|approx)\.( )[a-z(]").unwrap(),
+            "Interword spacing (`\\ ') should perhaps be used.",
+        ),
+        (18, Regex::new(r#"(")"#).unwrap(), "Use either `` or '' as an alternative to `\"'."),
+        (24, Regex::new(r"\S( +)\\label\b").unwrap(), "Delete this space to maintain correct pagereferences."),
+        (26, Regex::new(r"\w( +)[,;:.!?](?:\s|$)").unwrap(), "You ought to remove spaces in front of punctuation."),
+    ]
+});
+
+#[derive(Serialize)]
+pub struct LintDiagnostic {
+    /// chktex 警告编号
+    rule: u32,
+    message: String,
+    line: u32,
+    /// 从 1 开始，按字符计
+    column: u32,
+    /// 警告覆盖的字符数，至少为 1
+    length: u32,
+    severity: &'static str,
+}
+
+#[derive(Serialize)]
+pub struct LintResult {
+    diagnostics: Vec<LintDiagnostic>,
+    /// `chktex`，没有安装 chktex 时为 `builtin`
+    linter: &'static str,
+}
+
+fn severity_of(kind: &str) -> &'static str {
+    match kind.to_ascii_lowercase().as_str() {
+        "error" => "error",
+        "message" => "info",
+        _ => "warning",
+    }
+}
+
+fn parse_chktex_output(stdout: &str) -> Vec<LintDiagnostic> {
+    stdout
+        .lines()
+        .filter_map(|line| CHKTEX_LINE_RE.captures(line.trim_end()))
+        .map(|caps| LintDiagnostic {
+            rule: caps[5].parse().unwrap_or(0),
+            message: caps[6].trim().to_string(),
+            line: caps[1].parse().unwrap_or(0),
+            column: caps[2].parse().unwrap_or(1),
+            length: caps[3].parse::<u32>().unwrap_or(1).max(1),
+            severity: severity_of(&caps[4]),
+        })
+        .collect()
+}
+
+/// 对 `content` 运行 chktex，通过 stdin 输入，未保存的修改也能检查。不跟随 `\input` 引入的文件：
+/// 它们的警告会指向别的缓冲区。`Ok(None)` 表示没有安装 chktex
+fn run_chktex(content: &str, dir: Option<&Path>) -> Result<Option<Vec<LintDiagnostic>>, String> {
+    let mut cmd = Command::new("chktex");
+    cmd.args(["-q", "-I0", "-f", CHKTEX_FORMAT])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    // 项目内的 .chktexrc 从工作目录读取
+    if let Some(dir) = dir {
+        cmd.current_dir(dir);
+    }
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("chktex 运行失败: {}", e)),
+    };
+    // 在另一个线程中写入，报告很大时不会因管道写满而死锁
+    let mut stdin = child.stdin.take().expect("chktex stdin piped");
+    let input = content.to_string();
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output().map_err(|e| format!("chktex 运行失败: {}", e))?;
+    let _ = writer.join();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let diagnostics = parse_chktex_output(&stdout);
+    // chktex 报告警告时以非零状态退出，只有它没打印出任何能理解的内容时才当作失败
+    if diagnostics.is_empty() && !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !stderr.trim().is_empty() {
+            return Err(format!("chktex 出错:\n{}", stderr.trim()));
+        }
+    }
+    Ok(Some(diagnostics))
+}
+
+fn column_of(line: &str, byte: usize) -> u32 {
+    line[..byte].chars().count() as u32 + 1
+}
+
+/// 没有 chktex 时的后备：实际中占其大部分警告的几条规则
+fn builtin_lint(content: &str) -> Vec<LintDiagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut verbatim: Option<String> = None;
+    for (index, raw_line) in content.lines().enumerate() {
+        if let Some(env) = &verbatim {
+            let closes = VERBATIM_RE
+                .captures_iter(raw_line)
+                .any(|caps| &caps[1] == "end" && caps[2].trim() == env);
+            if closes {
+                verbatim = None;
+            }
+            continue;
+        }
+        let line = strip_comment(raw_line);
+        if let Some(caps) = VERBATIM_RE
+            .captures_iter(line)
+            .filter(|caps| &caps[1] == "begin" && VERBATIM_ENVIRONMENTS.contains(&caps[2].trim()))
+            .last()
+        {
+            verbatim = Some(caps[2].trim().to_string());
+            continue;
+        }
+
+        for (rule, regex, message) in BUILTIN_RULES.iter() {
+            for caps in regex.captures_iter(line) {
+                let target = caps.get(1).unwrap();
+                // `\"` 是变音符号，不是引号
+                if *rule == 18 && line[..target.start()].ends_with('\\') {
+                    continue;
+                }
+                diagnostics.push(LintDiagnostic {
+                    rule: *rule,
+                    message: message.to_string(),
+                    line: index as u32 + 1,
+                    column: column_of(line, target.start()),
+                    length: target.as_str().chars().count() as u32,
+                    severity: "warning",
+                });
+            }
+        }
+    }
+    diagnostics.sort_by(|a, b| a.line.cmp(&b.line).then(a.column.cmp(&b.column)));
+    diagnostics
+}
+
+/// `path` 的风格警告；给出 `content` 时检查它（未保存的修改，此时 `path` 只用于找到项目的 .chktexrc）。
+/// PATH 中有 chktex 时使用它，否则使用其规则的内置子集
+#[command]
+pub async fn lint_latex(
+    scope: WindowScope,
+    path: Option<String>,
+    content: Option<String>,
+) -> Result<LintResult, String> {
+    scope.check_optional(path.as_ref())?;
+    let path = path.map(PathBuf::from);
+    let content = match (content, &path) {
+        (Some(content), _) => content,
+        (None, Some(path)) => fs::read_to_string(path).map_err(|e| format!("无法读取文件: {}", e))?,
+        (None, None) => return Err("没有可检查的文件或内容".to_string()),
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        let dir = path.as_deref().and_then(Path::parent);
+        Ok(match run_chktex(&content, dir)? {
+            Some(diagnostics) => LintResult { diagnostics, linter: "chktex" },
+            None => LintResult { diagnostics: builtin_lint(&content), linter: "builtin" },
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,40 @@
+pub mod assets;
+pub mod completion;
+pub mod definition;
+pub mod dependencies;
+pub mod docs;
+pub mod format;
+pub mod hover;
+pub mod lint;
+pub mod outline;
+pub mod packages;
+pub mod references;
+pub mod rename;
+pub mod root;
+
+use std::path::{Path, PathBuf};
+
+/// 去掉一行中未转义的 `%` 之后的部分
+pub fn strip_comment(line: &str) -> &str {
+    let bytes = line.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'%' => return &line[..i],
+            _ => i += 1,
+        }
+    }
+    line
+}
+
+/// 按 TeX 的方式解析 `\input{...}` 类参数：相对编译根文档时所在的目录，名字没有扩展名时补上 `.tex`
+pub fn resolve_tex_path(base_dir: &Path, name: &str) -> PathBuf {
+    let name = name.trim();
+    let path = base_dir.join(name);
+    if path.extension().is_none() {
+        path.with_extension("tex")
+    } else {
+        path
+    }
+}
@@ -0,0 +1,517 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Serialize;
+use tauri::command;
+
+use super::root::canonical;
+use super::{resolve_tex_path, strip_comment};
+use crate::scope::WindowScope;
+
+/// 章节命令，由外到内；下标即嵌套层级
+const SECTION_LEVELS: &[&str] = &[
+    "part", "chapter", "section", "subsection", "subsubsection", "paragraph", "subparagraph",
+];
+
+/// 值得在大纲中占一行的环境；其余的（itemize、center 等）都是噪音
+const OUTLINE_ENVIRONMENTS: &[&str] = &[
+    "figure", "table", "equation", "align", "gather", "multline", "algorithm", "lstlisting",
+    "minted", "theorem", "lemma", "proposition", "corollary", "definition", "proof", "example",
+    "remark", "abstract", "frame",
+];
+
+/// 叶子节点位于所有章节层级之下
+const LEAF_LEVEL: usize = usize::MAX;
+
+/// 图、表和公式按章编号的文档类
+const CHAPTER_CLASSES: &[&str] = &["book", "report", "memoir", "scrbook", "scrreprt"];
+/// 只占一个公式编号
+const SINGLE_MATH: &[&str] = &["equation", "multline"];
+/// 没有写 `\nonumber` 或 `\notag` 的每一行都编号
+const MULTILINE_MATH: &[&str] = &["align", "gather", "flalign", "alignat", "eqnarray"];
+/// 文档没有用 `\newtheorem` 声明时，假定存在的定理类环境（各有自己的计数器）
+const DEFAULT_THEOREMS: &[&str] =
+    &["theorem", "lemma", "proposition", "corollary", "definition", "example", "remark"];
+
+static TOKEN_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"\\(?P<section>part|chapter|section|subsection|subsubsection|paragraph|subparagraph)(?P<starred>\*)?",
+        r"\s*(?:\[[^\]]*\])?\s*\{",
+        r"|\\begin\s*\{(?P<begin>[^}]+)\}",
+        r"|\\end\s*\{(?P<end>[^}]+)\}",
+        r"|\\label\s*\{(?P<label>[^}]+)\}",
+        r"|\\(?P<caption>caption|todo)\s*(?:\[[^\]]*\])?\s*\{",
+        r"|\\(?:input|include|subfile)\s*\{(?P<input>[^}]+)\}",
+        r"|\\newtheorem(?P<unnumbered>\*)?\s*\{(?P<theorem>[^}]+)\}\s*(?:\[(?P<shared>[^\]]+)\])?",
+        r"\s*\{[^}]*\}\s*(?:\[(?P<theorem_within>[^\]]+)\])?",
+        r"|\\numberwithin\s*\{(?P<numbered>[^}]+)\}\s*\{(?P<within>[^}]+)\}",
+        r"|\\documentclass\s*(?:\[[^\]]*\])?\s*\{(?P<class>[^}]+)\}",
+        r"|(?P<nonumber>\\nonumber|\\notag)\b",
+        r"|(?P<row>\\\\)",
+    ))
+    .unwrap()
+});
+static TODO_COMMENT_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"%\s*(?:TODO|FIXME|XXX)\b:?\s*(.*)$").unwrap());
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OutlineKind {
+    Section,
+    Environment,
+    Label,
+    Todo,
+}
+
+/// 带编号的环境属于哪一类，用于“图/表/定理目录”面板
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EnvironmentCategory {
+    Figure,
+    Table,
+    Equation,
+    Theorem,
+}
+
+#[derive(Serialize)]
+pub struct OutlineNode {
+    kind: OutlineKind,
+    /// 章节为 `section`、`subsection` 等；环境为环境名
+    name: String,
+    title: String,
+    file: String,
+    line: u32,
+    /// 图、表、公式和定理
+    #[serde(skip_serializing_if = "Option::is_none")]
+    category: Option<EnvironmentCategory>,
+    /// LaTeX 会印出的编号，如 `2.3`；有三行编号的 `align` 则为 `4-6`。带星号的环境为 None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    number: Option<String>,
+    /// 环境中的第一个 `\label`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    children: Vec<OutlineNode>,
+}
+
+/// 位于 `\begin` 和 `\end` 之间的环境
+struct OpenEnvironment {
+    /// 在 `items` 中的下标，环境不显示时为 None
+    item: Option<usize>,
+    name: String,
+    /// 已遇到的换行和 `\nonumber` 数，用于多行公式
+    rows: u32,
+    unnumbered: u32,
+}
+
+struct Collector {
+    /// 为未保存的缓冲区生成大纲时为 None：无法解析包含的文件
+    base_dir: Option<PathBuf>,
+    seen: HashSet<PathBuf>,
+    items: Vec<(usize, OutlineNode)>,
+    has_chapters: bool,
+    chapter: u32,
+    section: u32,
+    counters: HashMap<String, u32>,
+    /// `\numberwithin` 和 `\newtheorem{..}{..}[within]`：让计数器重新开始的章节计数器
+    within: HashMap<String, String>,
+    /// 已声明的定理环境及各自递增的计数器；`\newtheorem*` 为 None
+    theorems: HashMap<String, Option<String>>,
+}
+
+/// 开头的 `{` 在 `start` 处结束的花括号参数的文本。超出行尾的标题在行尾截断
+pub fn braced_argument(line: &str, start: usize) -> String {
+    let mut depth = 1;
+    let mut end = line.len();
+    for (i, c) in line[start..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    end = start + i;
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    line[start..end].split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn is_outline_environment(name: &str) -> bool {
+    OUTLINE_ENVIRONMENTS.contains(&name.trim_end_matches('*'))
+}
+
+/// 紧跟在 `\begin{theorem}` 后面的 `[...]`：定理的名称
+fn optional_argument(rest: &str) -> Option<String> {
+    let rest = rest.trim_start().strip_prefix('[')?;
+    let end = rest.find(']')?;
+    Some(rest[..end].split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+impl Collector {
+    fn new(base_dir: Option<PathBuf>) -> Self {
+        Collector {
+            base_dir,
+            seen: HashSet::new(),
+            items: Vec::new(),
+            has_chapters: false,
+            chapter: 0,
+            section: 0,
+            counters: HashMap::new(),
+            within: HashMap::new(),
+            theorems: HashMap::new(),
+        }
+    }
+
+    fn push(&mut self, level: usize, kind: OutlineKind, name: &str, title: String, file: &str, line: u32) {
+        let node = OutlineNode {
+            kind,
+            name: name.to_string(),
+            title,
+            file: file.to_string(),
+            line,
+            category: None,
+            number: None,
+            label: None,
+            children: Vec::new(),
+        };
+        self.items.push((level, node));
+    }
+
+    /// `env` 的类别及其递增的计数器（带星号时为 None）
+    fn numbering(&self, env: &str) -> Option<(EnvironmentCategory, Option<String>)> {
+        let base = env.trim_end_matches('*');
+        let starred = base != env;
+        let counter = |name: &str| (!starred).then(|| name.to_string());
+        match base {
+            "figure" => Some((EnvironmentCategory::Figure, Some("figure".to_string()))),
+            "table" => Some((EnvironmentCategory::Table, Some("table".to_string()))),
+            _ if SINGLE_MATH.contains(&base) || MULTILINE_MATH.contains(&base) => {
+                Some((EnvironmentCategory::Equation, counter("equation")))
+            }
+            _ => match self.theorems.get(env) {
+                Some(counter) => Some((EnvironmentCategory::Theorem, counter.clone())),
+                None if self.theorems.is_empty() && DEFAULT_THEOREMS.contains(&base) => {
+                    Some((EnvironmentCategory::Theorem, counter(base)))
+                }
+                None => None,
+            },
+        }
+    }
+
+    fn within(&self, counter: &str) -> Option<&str> {
+        match self.within.get(counter) {
+            Some(within) => Some(within),
+            None if self.has_chapters && ["figure", "table", "equation"].contains(&counter) => Some("chapter"),
+            None => None,
+        }
+    }
+
+    fn format_number(&self, counter: &str, value: u32) -> String {
+        match self.within(counter) {
+            Some("chapter") => format!("{}.{}", self.chapter, value),
+            Some("section") if self.has_chapters => format!("{}.{}.{}", self.chapter, self.section, value),
+            Some("section") => format!("{}.{}", self.section, value),
+            _ => value.to_string(),
+        }
+    }
+
+    /// 让 `counter` 递增 `count` 次；返回显示的编号或编号范围
+    fn step(&mut self, counter: &str, count: u32) -> Option<String> {
+        if count == 0 {
+            return None;
+        }
+        let value = self.counters.entry(counter.to_string()).or_insert(0);
+        let first = *value + 1;
+        *value += count;
+        let last = *value;
+        let first = self.format_number(counter, first);
+        Some(if count == 1 { first } else { format!("{}-{}", first, self.format_number(counter, last)) })
+    }
+
+    /// `\chapter` 和 `\section` 让在其内编号的计数器重新开始
+    fn start_section(&mut self, section: &str) {
+        let restarted: &[&str] = match section {
+            "chapter" => {
+                self.chapter += 1;
+                self.section = 0;
+                &["chapter", "section"]
+            }
+            "section" => {
+                self.section += 1;
+                &["section"]
+            }
+            _ => return,
+        };
+        let counters: Vec<String> = self.counters.keys().cloned().collect();
+        for counter in counters {
+            if self.within(&counter).is_some_and(|within| restarted.contains(&within)) {
+                self.counters.insert(counter, 0);
+            }
+        }
+    }
+
+    fn collect(&mut self, path: &Path, content: &str) {
+        let file = path.to_string_lossy().to_string();
+        // `\caption` 或 `\label` 属于最内层显示的环境
+        let mut environments: Vec<OpenEnvironment> = Vec::new();
+
+        for (index, raw_line) in content.lines().enumerate() {
+            let line_number = index as u32 + 1;
+            if let Some(caps) = TODO_COMMENT_RE.captures(raw_line) {
+                self.push(LEAF_LEVEL, OutlineKind::Todo, "todo", caps[1].trim().to_string(), &file, line_number);
+            }
+
+            let line = strip_comment(raw_line);
+            for caps in TOKEN_RE.captures_iter(line) {
+                let end = caps.get(0).map_or(0, |m| m.end());
+                let shown = environments.iter().rev().find_map(|env| env.item);
+                if let Some(section) = caps.name("section") {
+                    let level = SECTION_LEVELS.iter().position(|s| *s == section.as_str()).unwrap_or(0);
+                    if caps.name("starred").is_none() {
+                        self.start_section(section.as_str());
+                    }
+                    let title = braced_argument(line, end);
+                    self.push(level, OutlineKind::Section, section.as_str(), title, &file, line_number);
+                } else if let Some(env) = caps.name("begin") {
+                    let env = env.as_str().trim();
+                    let numbering = self.numbering(env);
+                    let mut item = None;
+                    if is_outline_environment(env) || numbering.is_some() {
+                        item = Some(self.items.len());
+                        let title = optional_argument(&line[end..]).unwrap_or_else(|| env.to_string());
+                        self.push(LEAF_LEVEL, OutlineKind::Environment, env, title, &file, line_number);
+                    }
+                    if let (Some(index), Some((category, counter))) = (item, numbering) {
+                        self.items[index].1.category = Some(category);
+                        // 多行公式在 `\end` 处、行数确定之后编号
+                        let multiline = MULTILINE_MATH.contains(&env.trim_end_matches('*'));
+                        if let Some(counter) = counter.filter(|_| !multiline) {
+                            self.items[index].1.number = self.step(&counter, 1);
+                        }
+                    }
+                    environments.push(OpenEnvironment { item, name: env.to_string(), rows: 0, unnumbered: 0 });
+                } else if caps.name("end").is_some() {
+                    let Some(env) = environments.pop() else {
+                        continue;
+                    };
+                    let base = env.name.trim_end_matches('*');
+                    if MULTILINE_MATH.contains(&base) && base == env.name {
+                        let number = self.step("equation", (env.rows + 1).saturating_sub(env.unnumbered));
+                        if let Some(index) = env.item {
+                            self.items[index].1.number = number;
+                        }
+                    }
+                } else if let Some(label) = caps.name("label") {
+                    let label = label.as_str().trim().to_string();
+                    if let Some(index) = shown {
+                        self.items[index].1.label.get_or_insert_with(|| label.clone());
+                    }
+                    self.push(LEAF_LEVEL, OutlineKind::Label, "label", label, &file, line_number);
+                } else if let Some(command) = caps.name("caption") {
+                    let text = braced_argument(line, end);
+                    if command.as_str() == "todo" {
+                        self.push(LEAF_LEVEL, OutlineKind::Todo, "todo", text, &file, line_number);
+                    } else if let Some(Some(env_index)) = environments.last().map(|env| env.item) {
+                        self.items[env_index].1.title = text;
+                    }
+                } else if let (Some(input), Some(base_dir)) = (caps.name("input"), &self.base_dir) {
+                    let included = resolve_tex_path(base_dir, input.as_str());
+                    if self.seen.insert(canonical(&included)) {
+                        if let Ok(included_content) = fs::read_to_string(&included) {
+                            self.collect(&included, &included_content);
+                        }
+                    }
+                } else if let Some(theorem) = caps.name("theorem") {
+                    let name = theorem.as_str().trim().to_string();
+                    let counter = match (caps.name("unnumbered"), caps.name("shared")) {
+                        (Some(_), _) => None,
+                        (None, Some(shared)) => Some(shared.as_str().trim().to_string()),
+                        (None, None) => Some(name.clone()),
+                    };
+                    if let Some(within) = caps.name("theorem_within") {
+                        self.within.insert(name.clone(), within.as_str().trim().to_string());
+                    }
+                    self.theorems.insert(name, counter);
+                } else if let (Some(counter), Some(within)) = (caps.name("numbered"), caps.name("within")) {
+                    self.within.insert(counter.as_str().trim().to_string(), within.as_str().trim().to_string());
+                } else if let Some(class) = caps.name("class") {
+                    self.has_chapters = CHAPTER_CLASSES.contains(&class.as_str().trim());
+                } else if let Some(env) = environments.last_mut() {
+                    if caps.name("nonumber").is_some() {
+                        env.unnumbered += 1;
+                    } else if caps.name("row").is_some() {
+                        env.rows += 1;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 把带层级的扁平列表转成树：每个节点拥有其后层级更深的条目
+fn nest<I>(items: &mut Peekable<I>, parent_level: Option<usize>) -> Vec<OutlineNode>
+where
+    I: Iterator<Item = (usize, OutlineNode)>,
+{
+    let mut nodes = Vec::new();
+    while let Some((level, _)) = items.peek() {
+        if parent_level.is_some_and(|parent| *level <= parent) {
+            break;
+        }
+        let (level, mut node) = items.next().unwrap();
+        if level != LEAF_LEVEL {
+            node.children = nest(items, Some(level));
+        }
+        nodes.push(node);
+    }
+    nodes
+}
+
+fn collect_outline(path: Option<String>, content: Option<String>) -> Result<Collector, String> {
+    let path = path.map(PathBuf::from);
+    let content = match (&content, &path) {
+        (Some(content), _) => content.clone(),
+        (None, Some(path)) => fs::read_to_string(path).map_err(|e| format!("无法读取文件: {}", e))?,
+        (None, None) => return Err("没有可生成大纲的文件或内容".to_string()),
+    };
+    let base_dir = path.as_deref().map(|p| p.parent().unwrap_or(Path::new(".")).to_path_buf());
+    let mut collector = Collector::new(base_dir);
+    let source = path.unwrap_or_default();
+    collector.seen.insert(canonical(&source));
+    collector.collect(&source, &content);
+    Ok(collector)
+}
+
+/// `path` 的大纲，按其所在目录解析 `\input`/`\include`。`content` 代替磁盘上的文件，未保存的修改也能显示；
+/// 没有路径时只扫描 `content`
+#[command]
+pub fn parse_outline(
+    scope: WindowScope,
+    path: Option<String>,
+    content: Option<String>,
+) -> Result<Vec<OutlineNode>, String> {
+    scope.check_optional(path.as_ref())?;
+    let collector = collect_outline(path, content)?;
+    Ok(nest(&mut collector.items.into_iter().peekable(), None))
+}
+
+/// 按文档顺序列出图、表、公式和定理，附 LaTeX 给出的编号（book 类文档按章编号，遵循 `\numberwithin` 和
+/// `\newtheorem`）、标题和标签。参数与 `parse_outline` 相同
+#[command]
+pub fn list_numbered_environments(
+    scope: WindowScope,
+    path: Option<String>,
+    content: Option<String>,
+) -> Result<Vec<OutlineNode>, String> {
+    scope.check_optional(path.as_ref())?;
+    let collector = collect_outline(path, content)?;
+    Ok(collector.items.into_iter().map(|(_, node)| node).filter(|node| node.category.is_some()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 带编号的环境：名称、编号、标题和标签
+    fn numbered(content: &str) -> Vec<(String, Option<String>, String, Option<String>)> {
+        let collector = collect_outline(None, Some(content.to_string())).unwrap();
+        collector
+            .items
+            .into_iter()
+            .map(|(_, node)| node)
+            .filter(|node| node.category.is_some())
+            .map(|node| (node.name, node.number, node.title, node.label))
+            .collect()
+    }
+
+    fn numbers(content: &str) -> Vec<(String, Option<String>)> {
+        numbered(content).into_iter().map(|(name, number, _, _)| (name, number)).collect()
+    }
+
+    fn entry(name: &str, number: Option<&str>) -> (String, Option<String>) {
+        (name.to_string(), number.map(str::to_string))
+    }
+
+    #[test]
+    fn article_counters_run_through_the_document() {
+        let content = concat!(
+            "\\documentclass{article}\n",
+            "\\begin{document}\n",
+            "\\section{Intro}\n",
+            "\\begin{figure}\\caption{A {nested} caption}\\label{fig:a}\\end{figure}\n",
+            "\\begin{equation} x \\end{equation}\n",
+            "\\begin{align} a \\\\ b \\nonumber \\\\ c \\end{align}\n",
+            "\\section{Next}\n",
+            "\\begin{theorem}\\end{theorem}\n",
+            "\\begin{figure*}\\end{figure*}\n",
+            "\\begin{equation*} y \\end{equation*}\n",
+            "% \\begin{table}\\end{table}\n",
+        );
+        assert_eq!(
+            numbers(content),
+            [
+                entry("figure", Some("1")),
+                entry("equation", Some("1")),
+                entry("align", Some("2-3")),
+                entry("theorem", Some("1")),
+                entry("figure*", Some("2")),
+                entry("equation*", None),
+            ]
+        );
+        let (_, _, title, label) = &numbered(content)[0];
+        assert_eq!((title.as_str(), label.as_deref()), ("A {nested} caption", Some("fig:a")));
+    }
+
+    #[test]
+    fn book_numbers_within_chapters_and_declared_theorems() {
+        let content = concat!(
+            "\\documentclass{book}\n",
+            "\\newtheorem{theorem}{Theorem}[section]\n",
+            "\\newtheorem{lemma}[theorem]{Lemma}\n",
+            "\\newtheorem*{claim}{Claim}\n",
+            "\\numberwithin{equation}{section}\n",
+            "\\chapter{One}\n",
+            "\\section{A}\n",
+            "\\begin{figure}\\end{figure}\n",
+            "\\begin{theorem}\\end{theorem}\n",
+            "\\begin{lemma}\\end{lemma}\n",
+            "\\begin{equation}\\end{equation}\n",
+            "\\chapter{Two}\n",
+            "\\begin{figure}\\end{figure}\n",
+            "\\section*{Unnumbered}\n",
+            "\\section{B}\n",
+            "\\begin{theorem}\\end{theorem}\n",
+            "\\begin{claim}\\end{claim}\n",
+            "\\begin{remark}\\end{remark}\n",
+        );
+        assert_eq!(
+            numbers(content),
+            [
+                entry("figure", Some("1.1")),
+                entry("theorem", Some("1.1.1")),
+                entry("lemma", Some("1.1.2")),
+                entry("equation", Some("1.1.1")),
+                entry("figure", Some("2.1")),
+                entry("theorem", Some("2.1.1")),
+                entry("claim", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn sections_nest_under_their_parents() {
+        let content = "\\section{A}\n\\subsection{A.1}\n\\label{sec:a1}\n\\section{B}\n";
+        let collector = collect_outline(None, Some(content.to_string())).unwrap();
+        let tree = nest(&mut collector.items.into_iter().peekable(), None);
+        let titles: Vec<&str> = tree.iter().map(|node| node.title.as_str()).collect();
+        assert_eq!(titles, ["A", "B"]);
+        assert_eq!(tree[0].children[0].title, "A.1");
+        assert_eq!(tree[0].children[0].children[0].title, "sec:a1");
+    }
+}
@@ -0,0 +1,96 @@
+//! 静态补全数据：LaTeX 内核和最常用宏包的命令与环境。每个命令配有其名字后面的代码片段
+//! （`$1`、`$2` 是制表位）
+
+pub type CommandSpec = (&'static str, &'static str);
+
+pub const KERNEL_COMMANDS: &[CommandSpec] = &[
+    ("documentclass", "{$1}"), ("usepackage", "{$1}"), ("begin", "{$1}"), ("end", "{$1}"),
+    ("part", "{$1}"), ("chapter", "{$1}"), ("section", "{$1}"), ("subsection", "{$1}"),
+    ("subsubsection", "{$1}"), ("paragraph", "{$1}"), ("subparagraph", "{$1}"),
+    ("title", "{$1}"), ("author", "{$1}"), ("date", "{$1}"), ("maketitle", ""),
+    ("tableofcontents", ""), ("listoffigures", ""), ("listoftables", ""), ("appendix", ""),
+    ("label", "{$1}"), ("ref", "{$1}"), ("pageref", "{$1}"), ("cite", "{$1}"), ("nocite", "{$1}"),
+    ("footnote", "{$1}"), ("caption", "{$1}"), ("item", " "), ("input", "{$1}"), ("include", "{$1}"),
+    ("includeonly", "{$1}"), ("bibliography", "{$1}"), ("bibliographystyle", "{$1}"),
+    ("textbf", "{$1}"), ("textit", "{$1}"), ("texttt", "{$1}"), ("textsc", "{$1}"),
+    ("textrm", "{$1}"), ("textsf", "{$1}"), ("emph", "{$1}"), ("underline", "{$1}"),
+    ("mathbf", "{$1}"), ("mathrm", "{$1}"), ("mathit", "{$1}"), ("mathcal", "{$1}"),
+    ("frac", "{$1}{$2}"), ("sqrt", "{$1}"), ("sum", ""), ("prod", ""), ("int", ""),
+    ("left", ""), ("right", ""), ("cdot", ""), ("ldots", ""), ("dots", ""), ("infty", ""),
+    ("alpha", ""), ("beta", ""), ("gamma", ""), ("delta", ""), ("epsilon", ""), ("lambda", ""),
+    ("mu", ""), ("pi", ""), ("sigma", ""), ("theta", ""), ("omega", ""),
+    ("newcommand", "{\\\\$1}{$2}"), ("renewcommand", "{\\\\$1}{$2}"), ("newenvironment", "{$1}{$2}{$3}"),
+    ("newpage", ""), ("clearpage", ""), ("centering", ""), ("noindent", ""), ("hline", ""),
+    ("vspace", "{$1}"), ("hspace", "{$1}"), ("linewidth", ""), ("textwidth", ""),
+    ("small", ""), ("large", ""), ("Large", ""), ("tiny", ""), ("footnotesize", ""), ("normalsize", ""),
+];
+
+pub const KERNEL_ENVIRONMENTS: &[&str] = &[
+    "document", "abstract", "itemize", "enumerate", "description", "figure", "figure*", "table",
+    "table*", "tabular", "equation", "equation*", "eqnarray", "center", "flushleft", "flushright",
+    "quote", "quotation", "verse", "verbatim", "minipage", "thebibliography", "array", "displaymath",
+    "math", "titlepage",
+];
+
+pub const PACKAGE_COMMANDS: &[(&str, &[CommandSpec])] = &[
+    ("amsmath", &[
+        ("eqref", "{$1}"), ("text", "{$1}"), ("dfrac", "{$1}{$2}"), ("tfrac", "{$1}{$2}"),
+        ("binom", "{$1}{$2}"), ("operatorname", "{$1}"), ("DeclareMathOperator", "{\\\\$1}{$2}"),
+        ("tag", "{$1}"), ("notag", ""), ("intertext", "{$1}"), ("boldsymbol", "{$1}"),
+    ]),
+    ("amssymb", &[("mathbb", "{$1}"), ("mathfrak", "{$1}"), ("varnothing", ""), ("leqslant", ""), ("geqslant", "")]),
+    ("amsthm", &[("newtheorem", "{$1}{$2}"), ("theoremstyle", "{$1}"), ("qedhere", "")]),
+    ("graphicx", &[
+        ("includegraphics", "[width=${1:\\\\linewidth}]{$2}"), ("graphicspath", "{{$1}}"),
+        ("scalebox", "{$1}{$2}"), ("resizebox", "{$1}{$2}{$3}"), ("rotatebox", "{$1}{$2}"),
+    ]),
+    ("hyperref", &[("href", "{$1}{$2}"), ("url", "{$1}"), ("autoref", "{$1}"), ("nameref", "{$1}"), ("hypersetup", "{$1}")]),
+    ("cleveref", &[("cref", "{$1}"), ("Cref", "{$1}"), ("crefrange", "{$1}{$2}"), ("cpageref", "{$1}")]),
+    ("xcolor", &[("textcolor", "{$1}{$2}"), ("color", "{$1}"), ("colorbox", "{$1}{$2}"), ("definecolor", "{$1}{$2}{$3}")]),
+    ("natbib", &[("citep", "{$1}"), ("citet", "{$1}"), ("citeauthor", "{$1}"), ("citeyear", "{$1}"), ("citealp", "{$1}")]),
+    ("biblatex", &[
+        ("addbibresource", "{$1}"), ("printbibliography", ""), ("parencite", "{$1}"),
+        ("textcite", "{$1}"), ("autocite", "{$1}"), ("footcite", "{$1}"), ("fullcite", "{$1}"),
+    ]),
+    ("booktabs", &[("toprule", ""), ("midrule", ""), ("bottomrule", ""), ("cmidrule", "{$1}")]),
+    ("siunitx", &[("SI", "{$1}{$2}"), ("si", "{$1}"), ("num", "{$1}"), ("qty", "{$1}{$2}"), ("unit", "{$1}")]),
+    ("todonotes", &[("todo", "{$1}"), ("missingfigure", "{$1}"), ("listoftodos", "")]),
+    ("geometry", &[("geometry", "{$1}"), ("newgeometry", "{$1}"), ("restoregeometry", "")]),
+    ("tikz", &[("tikz", "{$1}"), ("draw", " $1;"), ("node", " {$1};"), ("fill", " $1;"), ("usetikzlibrary", "{$1}")]),
+    ("listings", &[("lstinline", "|$1|"), ("lstinputlisting", "{$1}"), ("lstset", "{$1}")]),
+    ("minted", &[("mintinline", "{$1}{$2}"), ("inputminted", "{$1}{$2}")]),
+    ("subcaption", &[("subcaptionbox", "{$1}{$2}")]),
+    ("enumitem", &[("setlist", "{$1}")]),
+];
+
+pub const PACKAGE_ENVIRONMENTS: &[(&str, &[&str])] = &[
+    ("amsmath", &[
+        "align", "align*", "gather", "gather*", "multline", "multline*", "split", "cases",
+        "pmatrix", "bmatrix", "vmatrix", "matrix", "aligned", "flalign",
+    ]),
+    ("amsthm", &["proof"]),
+    ("tikz", &["tikzpicture", "scope"]),
+    ("listings", &["lstlisting"]),
+    ("minted", &["minted"]),
+    ("subcaption", &["subfigure", "subtable"]),
+    ("algorithm", &["algorithm"]),
+    ("algorithmic", &["algorithmic"]),
+    ("algpseudocode", &["algorithmic"]),
+    ("beamer", &["frame", "block", "columns", "column", "alertblock", "exampleblock"]),
+    ("longtable", &["longtable"]),
+    ("tabularx", &["tabularx"]),
+    ("multicol", &["multicols"]),
+    ("wrapfig", &["wrapfigure"]),
+];
+
+/// `\usepackage{}` 中提供的宏包
+pub fn known_packages() -> impl Iterator<Item = &'static str> {
+    let mut names: Vec<&str> = PACKAGE_COMMANDS
+        .iter()
+        .map(|(name, _)| *name)
+        .chain(PACKAGE_ENVIRONMENTS.iter().map(|(name, _)| *name))
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+    names.into_iter()
+}
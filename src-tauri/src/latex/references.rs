@@ -0,0 +1,238 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::SystemTime;
+
+use regex::Regex;
+use serde::Serialize;
+use tauri::{command, AppHandle, Manager};
+
+use super::root::canonical;
+use super::strip_comment;
+use crate::index::ProjectIndex;
+use crate::scope::WindowScope;
+use crate::workspace::project_walker;
+
+static LABEL_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\\label\s*\{([^}]*)\}").unwrap());
+/// cleveref 的 `\cref{a,b}` 接受列表，其他命令只接受一个标签
+static REF_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\\(ref|eqref|pageref|autoref|nameref|vref|cref|Cref|cpageref|Cpageref|labelcref)\*?\s*\{([^}]*)\}")
+        .unwrap()
+});
+
+#[derive(Serialize, Clone)]
+pub struct LabelDefinition {
+    pub name: String,
+    pub file: String,
+    pub line: u32,
+    /// 从 1 开始，按字符计
+    pub column: u32,
+}
+
+#[derive(Serialize, Clone)]
+pub struct LabelReference {
+    pub name: String,
+    /// `ref`、`eqref`、`cref` 等
+    pub command: String,
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+#[derive(Serialize)]
+pub struct ReferenceDiagnostic {
+    label: String,
+    file: String,
+    line: u32,
+    column: u32,
+    message: String,
+    severity: &'static str,
+}
+
+#[derive(Clone, Default)]
+pub struct FileSymbols {
+    labels: Vec<LabelDefinition>,
+    references: Vec<LabelReference>,
+}
+
+/// 按路径缓存的单文件扫描结果，文件 mtime 不变时复用，重复查询只重读修改过的文件
+#[derive(Default)]
+pub struct ReferenceIndex {
+    files: Mutex<HashMap<PathBuf, (SystemTime, Arc<FileSymbols>)>>,
+}
+
+fn column_of(line: &str, byte: usize) -> u32 {
+    line[..byte].chars().count() as u32 + 1
+}
+
+pub fn scan_file(path: &Path, content: &str) -> FileSymbols {
+    let file = path.to_string_lossy().to_string();
+    let mut symbols = FileSymbols::default();
+    for (index, raw_line) in content.lines().enumerate() {
+        let line = strip_comment(raw_line);
+        let line_number = index as u32 + 1;
+        for caps in LABEL_RE.captures_iter(line) {
+            let name = caps.get(1).unwrap();
+            symbols.labels.push(LabelDefinition {
+                name: name.as_str().trim().to_string(),
+                file: file.clone(),
+                line: line_number,
+                column: column_of(line, name.start()),
+            });
+        }
+        for caps in REF_RE.captures_iter(line) {
+            let list = caps.get(2).unwrap();
+            let mut offset = list.start();
+            for part in list.as_str().split(',') {
+                let name = part.trim();
+                if !name.is_empty() {
+                    let start = offset + part.find(name).unwrap_or(0);
+                    symbols.references.push(LabelReference {
+                        name: name.to_string(),
+                        command: caps[1].to_string(),
+                        file: file.clone(),
+                        line: line_number,
+                        column: column_of(line, start),
+                    });
+                }
+                offset += part.len() + 1;
+            }
+        }
+    }
+    symbols
+}
+
+impl ReferenceIndex {
+    /// 让索引与 `root` 下的所有 `.tex` 文件同步，并返回它们的符号
+    pub fn refresh(&self, root: &Path) -> Vec<Arc<FileSymbols>> {
+        let paths: HashSet<PathBuf> = project_walker(root)
+            .build()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+            .map(|entry| entry.into_path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "tex"))
+            .collect();
+
+        let mut files = self.files.lock().unwrap();
+        // 去掉已删除或移出该根目录的文件
+        files.retain(|path, _| !path.starts_with(root) || paths.contains(path));
+
+        let mut result = Vec::with_capacity(paths.len());
+        for path in paths {
+            let Ok(modified) = fs::metadata(&path).and_then(|m| m.modified()) else {
+                continue;
+            };
+            if let Some((cached_at, symbols)) = files.get(&path) {
+                if *cached_at == modified {
+                    result.push(symbols.clone());
+                    continue;
+                }
+            }
+            let Ok(content) = fs::read_to_string(&path) else {
+                continue;
+            };
+            let symbols = Arc::new(scan_file(&path, &content));
+            files.insert(path, (modified, symbols.clone()));
+            result.push(symbols);
+        }
+        result
+    }
+}
+
+/// `root` 下所有 `.tex` 文件的符号：有被监听的文件夹包含它时直接取自工作区索引，否则取自这个索引
+fn project_symbols(app: &AppHandle, root: &Path) -> Vec<Arc<FileSymbols>> {
+    match app.state::<ProjectIndex>().watched(root) {
+        Some(index) => index.read().unwrap().references(&canonical(root)).cloned().collect(),
+        None => app.state::<ReferenceIndex>().refresh(root),
+    }
+}
+
+fn labels_of(symbols: &[Arc<FileSymbols>]) -> Vec<LabelDefinition> {
+    let mut labels: Vec<LabelDefinition> = symbols.iter().flat_map(|s| s.labels.iter().cloned()).collect();
+    labels.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.file.cmp(&b.file)).then(a.line.cmp(&b.line)));
+    labels
+}
+
+fn validate(symbols: &[Arc<FileSymbols>]) -> Vec<ReferenceDiagnostic> {
+    let mut definitions: HashMap<&str, Vec<&LabelDefinition>> = HashMap::new();
+    for label in symbols.iter().flat_map(|s| s.labels.iter()) {
+        definitions.entry(label.name.as_str()).or_default().push(label);
+    }
+
+    let mut diagnostics = Vec::new();
+    for (name, defined) in &definitions {
+        if defined.len() > 1 {
+            for label in defined {
+                diagnostics.push(ReferenceDiagnostic {
+                    label: name.to_string(),
+                    file: label.file.clone(),
+                    line: label.line,
+                    column: label.column,
+                    message: format!("标签 `{}` 重复定义（{} 次）", name, defined.len()),
+                    severity: "warning",
+                });
+            }
+        }
+    }
+    for reference in symbols.iter().flat_map(|s| s.references.iter()) {
+        if !definitions.contains_key(reference.name.as_str()) {
+            diagnostics.push(ReferenceDiagnostic {
+                label: reference.name.clone(),
+                file: reference.file.clone(),
+                line: reference.line,
+                column: reference.column,
+                message: format!("引用 `{}` 未定义", reference.name),
+                severity: "warning",
+            });
+        }
+    }
+    diagnostics.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)).then(a.column.cmp(&b.column)));
+    diagnostics
+}
+
+/// 单个缓冲区的标签，如编辑器中未保存的内容
+pub fn scan_labels(path: &Path, content: &str) -> Vec<LabelDefinition> {
+    scan_file(path, content).labels
+}
+
+/// 项目中所有的 `\label`，用于 `\ref` 补全
+pub fn project_labels(app: &AppHandle, root: &Path) -> Vec<LabelDefinition> {
+    labels_of(&project_symbols(app, root))
+}
+
+fn check_root(root: &str) -> Result<PathBuf, String> {
+    let root = PathBuf::from(root);
+    if root.is_dir() {
+        Ok(root)
+    } else {
+        Err(format!("无法读取目录: {}", root.to_string_lossy()))
+    }
+}
+
+#[command]
+pub async fn list_labels(
+    app: AppHandle,
+    scope: WindowScope,
+    root: String,
+) -> Result<Vec<LabelDefinition>, String> {
+    scope.check(&root)?;
+    let root = check_root(&root)?;
+    tauri::async_runtime::spawn_blocking(move || project_labels(&app, &root))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 整个项目中未定义的引用和重复的标签，附位置供编辑器画波浪线
+#[command]
+pub async fn validate_references(
+    app: AppHandle,
+    scope: WindowScope,
+    root: String,
+) -> Result<Vec<ReferenceDiagnostic>, String> {
+    scope.check(&root)?;
+    let root = check_root(&root)?;
+    tauri::async_runtime::spawn_blocking(move || validate(&project_symbols(&app, &root)))
+        .await
+        .map_err(|e| e.to_string())
+}
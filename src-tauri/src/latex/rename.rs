@@ -0,0 +1,186 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use super::completion::{is_citation_command, is_reference_command};
+use super::strip_comment;
+use crate::atomic::write_atomic;
+use crate::scope::WindowScope;
+use crate::workspace::project_walker;
+
+/// 需要改写其中标签和引用 key 的文件
+const RENAME_EXTENSIONS: &[&str] = &["tex", "sty", "cls", "bib"];
+
+/// 同一行内的 `\command[opt]{list}`，与悬停提示的读法相同
+static ARGUMENT_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\\([A-Za-z]+)\*?\s*(?:\[[^\]]*\]\s*)*\{([^{}]*)\}").unwrap());
+static LABEL_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\\label\s*\{([^}]*)\}").unwrap());
+/// `.bib` 条目开头的 `@article{key,`
+static BIB_KEY_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*@\s*([A-Za-z]+)\s*[{(]\s*([^,\s]+)\s*,").unwrap());
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RenameKind {
+    Label,
+    Citation,
+}
+
+#[derive(Serialize)]
+pub struct RenameEdit {
+    file: String,
+    /// 从 1 开始；列号按修改前文件的字符计，结束位置不含
+    line: u32,
+    start_column: u32,
+    end_column: u32,
+}
+
+/// 一个文件改写后的内容及改动的位置
+struct FileRename {
+    path: PathBuf,
+    original: String,
+    renamed: String,
+    edits: Vec<RenameEdit>,
+}
+
+fn char_column(line: &str, byte: usize) -> u32 {
+    line[..byte].chars().count() as u32 + 1
+}
+
+/// `line` 中 `name` 作为 `kind` 使用的字节范围：作为类 `\label`/`\ref` 或类 `\cite` 命令的参数，
+/// 引用 key 还包括作为 `.bib` 条目的 key
+fn occurrences(line: &str, kind: RenameKind, bib: bool, name: &str) -> Vec<(usize, usize)> {
+    let mut found = Vec::new();
+    if bib {
+        if let Some(key) = BIB_KEY_RE.captures(line).filter(|caps| kind == RenameKind::Citation && &caps[2] == name) {
+            let key = key.get(2).unwrap();
+            found.push((key.start(), key.end()));
+        }
+        return found;
+    }
+    for caps in ARGUMENT_RE.captures_iter(line) {
+        let command = &caps[1];
+        let wanted = match kind {
+            RenameKind::Label => command == "label" || is_reference_command(command),
+            RenameKind::Citation => is_citation_command(command),
+        };
+        if !wanted {
+            continue;
+        }
+        let list = caps.get(2).unwrap();
+        let mut offset = list.start();
+        for part in list.as_str().split(',') {
+            if part.trim() == name {
+                let start = offset + part.find(name).unwrap_or(0);
+                found.push((start, start + name.len()));
+            }
+            offset += part.len() + 1;
+        }
+    }
+    found
+}
+
+/// 文件中是否已把 `name` 定义为 `kind`
+fn defines(content: &str, kind: RenameKind, bib: bool, name: &str) -> bool {
+    content.lines().map(strip_comment).any(|line| match kind {
+        RenameKind::Label => !bib && LABEL_RE.captures_iter(line).any(|caps| caps[1].trim() == name),
+        RenameKind::Citation => bib && !occurrences(line, kind, bib, name).is_empty(),
+    })
+}
+
+fn rename_file(path: &Path, content: String, kind: RenameKind, old_name: &str, new_name: &str) -> Option<FileRename> {
+    let bib = path.extension().is_some_and(|ext| ext == "bib");
+    let mut renamed = String::with_capacity(content.len());
+    let mut edits = Vec::new();
+    for (index, raw_line) in content.split_inclusive('\n').enumerate() {
+        let line = strip_comment(raw_line);
+        let mut last = 0;
+        for (start, end) in occurrences(line, kind, bib, old_name) {
+            renamed.push_str(&raw_line[last..start]);
+            renamed.push_str(new_name);
+            last = end;
+            edits.push(RenameEdit {
+                file: path.to_string_lossy().to_string(),
+                line: index as u32 + 1,
+                start_column: char_column(line, start),
+                end_column: char_column(line, end),
+            });
+        }
+        renamed.push_str(&raw_line[last..]);
+    }
+    if edits.is_empty() {
+        return None;
+    }
+    Some(FileRename { path: path.to_path_buf(), original: content, renamed, edits })
+}
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.chars().any(|c| c.is_whitespace() || matches!(c, '{' | '}' | ',' | '%' | '\\' | '#'))
+}
+
+fn rename_blocking(root: &Path, kind: RenameKind, old_name: &str, new_name: &str) -> Result<Vec<RenameEdit>, String> {
+    let files: Vec<(PathBuf, String)> = project_walker(root)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| entry.into_path())
+        .filter(|path| path.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| RENAME_EXTENSIONS.contains(&ext)))
+        .filter_map(|path| fs::read_to_string(&path).ok().map(|content| (path, content)))
+        .collect();
+
+    let clash = files.iter().any(|(path, content)| {
+        defines(content, kind, path.extension().is_some_and(|ext| ext == "bib"), new_name)
+    });
+    if clash {
+        let what = if kind == RenameKind::Label { "标签" } else { "引用 key" };
+        return Err(format!("{} `{}` 已存在", what, new_name));
+    }
+
+    let renames: Vec<FileRename> = files
+        .into_iter()
+        .filter(|(_, content)| content.contains(old_name))
+        .filter_map(|(path, content)| rename_file(&path, content, kind, old_name, new_name))
+        .collect();
+
+    // 要么全部写入，要么都不写：某个文件写入失败时，已写入的文件恢复原来的内容
+    for (done, rename) in renames.iter().enumerate() {
+        if let Err(e) = write_atomic(&rename.path, rename.renamed.as_bytes()) {
+            for written in &renames[..done] {
+                let _ = write_atomic(&written.path, written.original.as_bytes());
+            }
+            return Err(format!("无法写入文件: {}: {}", rename.path.to_string_lossy(), e));
+        }
+    }
+    Ok(renames.into_iter().flat_map(|rename| rename.edits).collect())
+}
+
+/// 在 `root` 中到处重命名一个标签或引用 key：其 `\label` 或 `.bib` 条目，以及所有 `.tex`、`.sty`、`.cls` 和 `.bib`
+/// 文件中每一处类 `\ref`/`\cite` 的使用。`new_name` 已被占用或有文件无法写入时什么都不写。
+/// 返回的修改按旧文本定位
+#[command]
+pub async fn rename_symbol(
+    scope: WindowScope,
+    root: String,
+    kind: RenameKind,
+    old_name: String,
+    new_name: String,
+) -> Result<Vec<RenameEdit>, String> {
+    scope.check(&root)?;
+    let root = PathBuf::from(root);
+    if !root.is_dir() {
+        return Err(format!("无法读取目录: {}", root.to_string_lossy()));
+    }
+    let (old_name, new_name) = (old_name.trim().to_string(), new_name.trim().to_string());
+    if !valid_name(&old_name) || !valid_name(&new_name) {
+        return Err(format!("无效的名字: `{}`", if valid_name(&old_name) { &new_name } else { &old_name }));
+    }
+    if old_name == new_name {
+        return Ok(Vec::new());
+    }
+    tauri::async_runtime::spawn_blocking(move || rename_blocking(&root, kind, &old_name, &new_name))
+        .await
+        .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,180 @@
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Serialize;
+use tauri::command;
+
+use super::{resolve_tex_path, strip_comment};
+use crate::project::find_project_config;
+use crate::scope::WindowScope;
+use crate::workspace::project_walker;
+
+/// 与 TeXShop 和 TeXstudio 一样，只认文件开头几行里的魔法注释
+const MAGIC_COMMENT_LINES: usize = 20;
+const MAX_ROOT_CHAIN: usize = 8;
+
+static MAGIC_ROOT_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^\s*%\s*!\s*TEX\s+root\s*=\s*(.+?)\s*$").unwrap());
+static INCLUDE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\\(?:input|include|subfile)\s*\{([^}]+)\}|\\(?:sub)?import\s*\{([^}]*)\}\s*\{([^}]+)\}").unwrap()
+});
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RootSource {
+    /// 文件本身就是完整的文档（有 `\documentclass`）
+    SelfDocument,
+    MagicComment,
+    /// `.mymd/project.toml` 中的 `root_document`
+    ProjectConfig,
+    IncludeScan,
+    /// 没找到更合适的；单独编译该文件
+    Fallback,
+}
+
+#[derive(Serialize)]
+pub struct RootDocument {
+    pub root: String,
+    pub source: RootSource,
+}
+
+pub fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn magic_root(path: &Path, content: &str) -> Option<PathBuf> {
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+    content
+        .lines()
+        .take(MAGIC_COMMENT_LINES)
+        .find_map(|line| MAGIC_ROOT_RE.captures(line))
+        .map(|caps| base_dir.join(caps[1].trim()))
+}
+
+pub fn is_document(content: &str) -> bool {
+    content.lines().any(|line| strip_comment(line).contains("\\documentclass"))
+}
+
+/// `\input`/`\include`/`\subfile`/`\import` 引用的文件，相对 `base_dir` 解析
+pub fn included_files(content: &str, base_dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for line in content.lines() {
+        for caps in INCLUDE_RE.captures_iter(strip_comment(line)) {
+            if let Some(name) = caps.get(1) {
+                files.push(resolve_tex_path(base_dir, name.as_str()));
+            } else if let (Some(dir), Some(name)) = (caps.get(2), caps.get(3)) {
+                files.push(resolve_tex_path(&base_dir.join(dir.as_str()), name.as_str()));
+            }
+        }
+    }
+    files
+}
+
+/// `root` 直接或通过嵌套包含引入的每个 `.tex` 文件。与 TeX 一样，包含路径相对根文档所在目录解析
+pub fn collect_inputs(root: &Path) -> Vec<PathBuf> {
+    let base_dir = root.parent().unwrap_or(Path::new("."));
+    let mut seen = HashSet::new();
+    let mut inputs = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
+    seen.insert(canonical(root));
+    while let Some(current) = stack.pop() {
+        let Ok(content) = fs::read_to_string(&current) else {
+            continue;
+        };
+        for included in included_files(&content, base_dir) {
+            if seen.insert(canonical(&included)) {
+                inputs.push(included.clone());
+                stack.push(included);
+            }
+        }
+    }
+    inputs
+}
+
+/// `root` 是否直接或通过嵌套包含引入了 `target`
+fn includes_transitively(root: &Path, target: &Path) -> bool {
+    collect_inputs(root).iter().any(|input| canonical(input) == target)
+}
+
+fn scan_for_root(target: &Path, workspace: &Path) -> Option<PathBuf> {
+    let target = canonical(target);
+    let mut candidates: Vec<PathBuf> = project_walker(workspace)
+        .build()
+        .filter_map(Result::ok)
+        .map(|entry| entry.into_path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "tex") && canonical(path) != target)
+        .filter(|path| fs::read_to_string(path).is_ok_and(|content| is_document(&content)))
+        .filter(|path| includes_transitively(path, &target))
+        .collect();
+
+    // 多个根文档可能包含同一章节；优先选最近的
+    candidates.sort_by_key(|path| {
+        let common = path.components().zip(target.components()).take_while(|(a, b)| a == b).count();
+        (Reverse(common), path.components().count())
+    });
+    candidates.into_iter().next()
+}
+
+pub fn find_root(path: &Path, workspace: Option<&Path>) -> RootDocument {
+    let mut current = path.to_path_buf();
+    let mut source = RootSource::Fallback;
+
+    // 跟随 `% !TEX root` 链，如 section -> chapter -> main
+    for _ in 0..MAX_ROOT_CHAIN {
+        let Ok(content) = fs::read_to_string(&current) else {
+            break;
+        };
+        if let Some(next) = magic_root(&current, &content) {
+            if next.exists() && canonical(&next) != canonical(&current) {
+                current = next;
+                source = RootSource::MagicComment;
+                continue;
+            }
+        }
+        if is_document(&content) {
+            if source == RootSource::Fallback {
+                source = RootSource::SelfDocument;
+            }
+            return RootDocument { root: current.to_string_lossy().to_string(), source };
+        }
+        break;
+    }
+    if source == RootSource::MagicComment {
+        return RootDocument { root: current.to_string_lossy().to_string(), source };
+    }
+
+    // 项目配置中指定的根文档优先于根据包含关系猜测
+    if let Some(root) = find_project_config(path).and_then(|(dir, config)| config.root_document_path(&dir)) {
+        return RootDocument { root: root.to_string_lossy().to_string(), source: RootSource::ProjectConfig };
+    }
+
+    // 章节通常在主文件下一级目录中
+    let file_dir = path.parent().unwrap_or(Path::new("."));
+    let workspace = workspace
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| file_dir.parent().unwrap_or(file_dir).to_path_buf());
+    if let Some(root) = scan_for_root(path, &workspace) {
+        return RootDocument { root: root.to_string_lossy().to_string(), source: RootSource::IncludeScan };
+    }
+
+    RootDocument { root: path.to_string_lossy().to_string(), source: RootSource::Fallback }
+}
+
+#[command]
+pub fn detect_root_document(
+    scope: WindowScope,
+    path: String,
+    workspace: Option<String>,
+) -> Result<RootDocument, String> {
+    scope.check(&path)?;
+    scope.check_optional(workspace.as_ref())?;
+    let path = PathBuf::from(path);
+    if !path.is_file() {
+        return Err(format!("无法读取文件: {}", path.to_string_lossy()));
+    }
+    Ok(find_root(&path, workspace.as_deref().map(Path::new)))
+}
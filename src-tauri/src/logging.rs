@@ -0,0 +1,130 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{command, AppHandle, Manager};
+use tracing::level_filters::LevelFilter;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, Layer};
+
+/// 文件为 `latex-editor.<date>.log`，每天一个
+const LOG_FILE_PREFIX: &str = "latex-editor";
+const LOG_FILE_SUFFIX: &str = "log";
+/// 日志保留的天数，超过后删除最旧的文件
+const MAX_LOG_FILES: usize = 7;
+const DEFAULT_LOG_LIMIT: usize = 200;
+
+#[derive(Serialize)]
+pub struct LogEntry {
+    timestamp: String,
+    level: String,
+    target: String,
+    /// 消息，后面跟着其他字段，形如 `key=value`
+    message: String,
+}
+
+fn log_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path().app_log_dir().map_err(|e| e.to_string())
+}
+
+/// 本项目的模块从 debug 级别开始记录，依赖只记录警告。文件中每行一个 JSON 对象，`get_recent_logs` 可以读回；
+/// stderr 使用通常的人类可读格式
+pub fn init(app: &AppHandle) {
+    let filter = Targets::new().with_default(LevelFilter::WARN).with_target("latex_editor", LevelFilter::DEBUG);
+    let stderr = fmt::layer().with_writer(std::io::stderr).with_filter(filter.clone());
+    let file = log_dir(app).and_then(|dir| {
+        RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix(LOG_FILE_PREFIX)
+            .filename_suffix(LOG_FILE_SUFFIX)
+            .max_log_files(MAX_LOG_FILES)
+            .build(dir)
+            .map_err(|e| e.to_string())
+    });
+    let (file_layer, file_error) = match file {
+        Ok(appender) => (Some(fmt::layer().json().with_ansi(false).with_writer(appender).with_filter(filter)), None),
+        Err(e) => (None, Some(e)),
+    };
+    tracing_subscriber::registry().with(stderr).with(file_layer).init();
+    if let Some(e) = file_error {
+        tracing::warn!("Logging to stderr only, the log directory is unavailable: {}", e);
+    }
+}
+
+fn level_rank(level: &str) -> u8 {
+    match level.to_ascii_uppercase().as_str() {
+        "ERROR" => 5,
+        "WARN" => 4,
+        "INFO" => 3,
+        "DEBUG" => 2,
+        _ => 1,
+    }
+}
+
+fn parse_entry(line: &str) -> Option<LogEntry> {
+    let value: Value = serde_json::from_str(line).ok()?;
+    let text = |key: &str| value.get(key).and_then(Value::as_str).unwrap_or_default().to_string();
+    let mut message = String::new();
+    if let Some(fields) = value.get("fields").and_then(Value::as_object) {
+        if let Some(text) = fields.get("message").and_then(Value::as_str) {
+            message.push_str(text);
+        }
+        for (key, field) in fields.iter().filter(|(key, _)| *key != "message") {
+            let field = field.as_str().map(str::to_string).unwrap_or_else(|| field.to_string());
+            message.push_str(&format!(" {}={}", key, field));
+        }
+    }
+    Some(LogEntry { timestamp: text("timestamp"), level: text("level"), target: text("target"), message })
+}
+
+/// 所有轮转文件中 `level`（默认为全部）及以上的最新 `limit`（默认 200）条日志，最旧的在前
+#[command]
+pub fn get_recent_logs(app: AppHandle, level: Option<String>, limit: Option<usize>) -> Result<Vec<LogEntry>, String> {
+    let min_rank = level.as_deref().map(level_rank).unwrap_or(0);
+    let limit = limit.unwrap_or(DEFAULT_LOG_LIMIT);
+    let dir = log_dir(&app)?;
+    let mut files: Vec<PathBuf> = match fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name().and_then(|name| name.to_str()).is_some_and(|name| {
+                    name.starts_with(LOG_FILE_PREFIX) && name.ends_with(&format!(".{}", LOG_FILE_SUFFIX))
+                })
+            })
+            .collect(),
+        Err(_) => return Ok(Vec::new()),
+    };
+    // 文件名中的日期让文件按时间排序
+    files.sort();
+
+    let mut entries = Vec::new();
+    for file in files.iter().rev() {
+        let Ok(content) = fs::read_to_string(file) else {
+            continue;
+        };
+        for entry in content.lines().rev().filter_map(parse_entry) {
+            if level_rank(&entry.level) >= min_rank {
+                entries.push(entry);
+                if entries.len() >= limit {
+                    entries.reverse();
+                    return Ok(entries);
+                }
+            }
+        }
+    }
+    entries.reverse();
+    Ok(entries)
+}
+
+/// 在文件管理器中打开日志目录，方便把文件附到错误报告中
+#[command]
+pub fn open_log_folder(app: AppHandle) -> Result<(), String> {
+    let dir = log_dir(&app)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("无法创建目录: {}", e))?;
+    tauri_plugin_opener::open_path(&dir, None::<&str>).map_err(|e| e.to_string())
+}
@@ -1,140 +1,62 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod archive;
+mod atomic;
+mod bibliography;
+mod comments;
+mod compiler;
+mod crash;
+mod document;
+mod export;
+mod file_ops;
+mod fonts;
+mod git;
+mod grammar;
+mod history;
+mod images;
+mod index;
+mod latex;
+mod logging;
+mod markdown;
+mod pdf;
+mod pdf_diff;
+mod project;
+mod recents;
+mod replace;
+mod scope;
+mod search;
+mod secrets;
+mod session;
+mod settings;
+mod snippets;
+mod spellcheck;
+mod sync;
+mod synctex;
+mod tables;
+mod templates;
+mod toolchain;
+mod track_changes;
+mod watcher;
+mod windows;
+mod workspace;
+
 use std::fs;
 use std::process::Command;
 use tauri::command;
 use serde::Serialize;
-use regex::Regex;
 use std::path::{Path, PathBuf};
 
-#[command]
-fn compile_latex(latex_code: String, file_path: Option<String>) -> Result<Vec<u8>, Vec<CompileError>> {
-    println!("Frontend requested compilation...");
-
-    // 情况 A: 未保存的新文件 (Untitled)
-    // 保持原有逻辑：使用系统临时目录，文件名为 input.tex
-    if file_path.is_none() {
-        let mut temp_dir = std::env::temp_dir();
-        temp_dir.push("tauri_latex_build");
-        if !temp_dir.exists() {
-            fs::create_dir(&temp_dir).map_err(|e| vec![CompileError::sys(e)])?;
-        }
-        let tex_file_path = temp_dir.join("input.tex");
-        let pdf_file_path = temp_dir.join("input.pdf");
-
-        fs::write(&tex_file_path, &latex_code).map_err(|e| vec![CompileError::sys(e)])?;
-
-        let output = Command::new("tectonic")
-            .arg("--keep-intermediates")
-            .arg("--synctex")
-            .arg(&tex_file_path)
-            .current_dir(&temp_dir)
-            .output()
-            .map_err(|e| vec![CompileError::sys(e)])?;
-
-        return handle_compilation_result(output, pdf_file_path);
-    }
-
-    // 情况 B: 已存在的本地文件
-    let path_str = file_path.unwrap();
-    let source_path = Path::new(&path_str);
-    let parent_dir = source_path.parent().unwrap_or(Path::new("."));
-
-    // 1. 获取文件名 (如 "main.tex" -> stem 是 "main")
-    let file_stem = source_path.file_stem()
-        .ok_or_else(|| vec![CompileError::simple("无法获取文件名")])?
-        .to_string_lossy();
-
-    // 2. 创建 AuxiliaryFiles 目录
-    let aux_dir = parent_dir.join("AuxiliaryFiles");
-    if !aux_dir.exists() {
-        fs::create_dir_all(&aux_dir).map_err(|e| vec![CompileError::sys(e)])?;
-    }
-
-    // 3. 【关键】保存当前编辑器内容到源文件
-    // Tectonic 需要读取磁盘上的文件，所以我们必须先保存
-    fs::write(source_path, &latex_code).map_err(|e| vec![CompileError::sys(e)])?;
-
-    // 4. 执行编译
-    // 运行命令：tectonic -o <AuxDir> --keep-intermediates --synctex <SourceFile>
-    // 注意：源文件不在 AuxDir 里，而在父目录。Tectonic 会自动处理。
-    println!("Compiling {:?} to output dir {:?}", source_path, aux_dir);
-
-    let output = Command::new("tectonic")
-        .arg("-o")
-        .arg(&aux_dir)
-        .arg("--keep-intermediates") // 保留中间文件
-        .arg("--synctex")            // 生成 synctex
-        .arg(source_path)            // 输入文件
-        .output()
-        .map_err(|e| vec![CompileError::sys(e)])?;
-
-    // 5. 结果处理
-    // PDF 会生成在 aux_dir 下，名字是 <file_stem>.pdf
-    let pdf_filename = format!("{}.pdf", file_stem);
-    let pdf_file_path = aux_dir.join(&pdf_filename);
-
-    handle_compilation_result(output, pdf_file_path)
-}
-
-// 辅助函数：统一处理 Tectonic 输出和错误解析
-fn handle_compilation_result(output: std::process::Output, pdf_path: PathBuf) -> Result<Vec<u8>, Vec<CompileError>> {
-    if !output.status.success() {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let log = format!("{}\n{}", stdout, stderr);
-
-        // 简单的错误解析逻辑
-        let msg_re = Regex::new(r"^error:\s*(.*)$").unwrap();
-        let line_re = Regex::new(r"^l\.(\d+)").unwrap();
-        let mut current_message: Option<String> = None;
-        let mut errors = Vec::new();
-
-        for line in log.lines() {
-            let trimmed = line.trim();
-            if let Some(caps) = msg_re.captures(trimmed) {
-                current_message = Some(caps[1].trim().to_string());
-                continue;
-            }
-            if let Some(caps) = line_re.captures(trimmed) {
-                let line_number = caps.get(1).and_then(|v| v.as_str().parse::<u32>().ok()).unwrap_or(0);
-                let message = current_message.take().unwrap_or_else(|| "Compilation error".to_string());
-                errors.push(CompileError { line: line_number, message, severity: "error".to_string() });
-            }
-        }
-        if errors.is_empty() {
-            errors.push(CompileError::simple(log.trim()));
-        }
-        return Err(errors);
-    }
-
-    if pdf_path.exists() {
-        let pdf_data = fs::read(&pdf_path).map_err(|e| vec![CompileError::sys(e)])?;
-        Ok(pdf_data)
-    } else {
-        Err(vec![CompileError::simple("编译成功但未找到生成的 PDF 文件")])
-    }
-}
-
-// 扩展 CompileError 方便构建
-impl CompileError {
-    fn simple(msg: impl Into<String>) -> Self {
-        Self { line: 0, message: msg.into(), severity: "error".to_string() }
-    }
-    fn sys(e: std::io::Error) -> Self {
-        Self { line: 0, message: e.to_string(), severity: "error".to_string() }
-    }
-}
-
-#[command]
-fn save_file(path: String, content: String) -> Result<(), String> {
-    fs::write(&path, content).map_err(|e| format!("无法写入文件: {}", e))
-}
-
-#[command]
-fn read_file(path: String) -> Result<String, String> {
-    fs::read_to_string(&path).map_err(|e| format!("无法读取文件: {}", e))
-}
+use comments::CommentLocks;
+use compiler::{CompileJobs, CompileQueue, WatchBuilds};
+use index::ProjectIndex;
+use latex::references::ReferenceIndex;
+use recents::Recents;
+use scope::{FsScope, WindowScope};
+use settings::SettingsStore;
+use snippets::Snippets;
+use spellcheck::SpellChecker;
+use watcher::Watchers;
+use windows::ProjectWindows;
 
 #[derive(Serialize)]
 struct FileEntry {
@@ -150,16 +72,10 @@ struct SyncTeXLocation {
     column: i32,
 }
 
-#[derive(Serialize)]
-struct CompileError {
-    line: u32,
-    message: String,
-    severity: String,
-}
-
 #[command]
-fn list_files(root_path: String) -> Result<Vec<FileEntry>, String> {
+fn list_files(scope: WindowScope, root_path: String) -> Result<Vec<FileEntry>, String> {
     let root = PathBuf::from(root_path);
+    scope.check(&root)?;
     let mut entries = Vec::new();
 
     let read_dir = fs::read_dir(&root).map_err(|e| format!("无法读取目录: {}", e))?;
@@ -192,19 +108,25 @@ fn list_files(root_path: String) -> Result<Vec<FileEntry>, String> {
 }
 
 #[command]
-fn synctex_edit(file_path: Option<String>, page: u32, x: f32, y: f32) -> Result<SyncTeXLocation, String> {
+fn synctex_edit(
+    scope: WindowScope,
+    file_path: Option<String>,
+    untitled_id: Option<String>,
+    page: u32,
+    x: f32,
+    y: f32,
+) -> Result<SyncTeXLocation, String> {
+    scope.check_optional(file_path.as_ref())?;
     let (pdf_path, synctex_dir) = if let Some(path_str) = file_path {
         let source_path = Path::new(&path_str);
-        let parent_dir = source_path.parent().ok_or("Invalid source path")?;
         let file_stem = source_path.file_stem()
             .ok_or("Unable to determine source file name")?
             .to_string_lossy();
-        let aux_dir = parent_dir.join("AuxiliaryFiles");
+        let aux_dir = compiler::output_dir_for(source_path, None)?;
         let pdf_filename = format!("{}.pdf", file_stem);
         (aux_dir.join(pdf_filename), aux_dir)
     } else {
-        let mut temp_dir = std::env::temp_dir();
-        temp_dir.push("tauri_latex_build");
+        let temp_dir = compiler::untitled::workspace_dir(untitled_id.as_deref())?;
         (temp_dir.join("input.pdf"), temp_dir)
     };
 
@@ -264,13 +186,173 @@ fn synctex_edit(file_path: Option<String>, page: u32, x: f32, y: f32) -> Result<
 fn main() {
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
-        .invoke_handler(tauri::generate_handler![
-            compile_latex,
-            save_file,
-            read_file,
+        .setup(|app| {
+            logging::init(app.handle());
+            crash::install(app.handle());
+            compiler::init_output_fallback(app.handle());
+            std::thread::spawn(compiler::untitled::cleanup_stale_workspaces);
+            Ok(())
+        })
+        .manage(CompileJobs::default())
+        .manage(CompileQueue::default())
+        .manage(WatchBuilds::default())
+        .manage(Watchers::default())
+        .manage(ReferenceIndex::default())
+        .manage(ProjectIndex::default())
+        .manage(SpellChecker::default())
+        .manage(SettingsStore::default())
+        .manage(Recents::default())
+        .manage(CommentLocks::default())
+        .manage(Snippets::default())
+        .manage(FsScope::default())
+        .manage(ProjectWindows::default())
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Destroyed = event {
+                windows::window_destroyed(window);
+            }
+        })
+        .invoke_handler(crash::recording_commands(tauri::generate_handler![
+            compiler::compile_latex,
+            compiler::cancel_compile,
+            compiler::read_pdf_bytes,
+            compiler::watch::start_watch_build,
+            compiler::watch::stop_watch_build,
+            compiler::markdown::compile_markdown,
+            compiler::clean::clean_aux,
+            compiler::preamble::invalidate_preamble_cache,
+            compiler::latexdiff::latexdiff_compile,
+            compiler::fragment::compile_fragment,
+            compiler::untitled::create_untitled_workspace,
+            compiler::untitled::discard_untitled_workspace,
+            export::export_document,
+            archive::export_project_archive,
+            archive::import_project_zip,
+            images::save_pasted_image,
+            images::convert_image,
+            images::list_figures,
+            file_ops::create_file,
+            file_ops::create_directory,
+            file_ops::rename_path,
+            file_ops::move_path,
+            file_ops::delete_path,
+            file_ops::reveal_in_file_manager,
+            file_ops::open_with_default_app,
+            document::save_file,
+            document::read_file,
+            document::stat_file,
+            git::git_status,
+            git::git_stage,
+            git::git_commit,
+            git::git_discard,
+            git::gutter::git_diff_file,
+            git::gutter::git_blame,
+            git::log::git_log,
+            git::log::git_branches,
+            git::log::git_checkout,
+            git::log::git_show_file_at,
+            git::remote::git_clone,
+            git::remote::git_pull,
+            git::remote::git_push,
+            git::conflicts::detect_merge_conflicts,
+            git::conflicts::resolve_conflict,
+            secrets::store_secret,
+            secrets::delete_secret,
+            sync::configure_remote,
+            sync::sync_project,
+            history::list_file_history,
+            history::read_history_version,
+            history::diff_history,
+            track_changes::start_tracking,
+            track_changes::stop_tracking,
+            track_changes::list_changes,
+            track_changes::accept_change,
+            track_changes::reject_change,
+            track_changes::export_tracked_changes,
+            comments::add_comment,
+            comments::list_comments,
+            comments::resolve_comment,
             list_files,
-            synctex_edit
-        ])
+            synctex_edit,
+            synctex::synctex_forward,
+            synctex::synctex_inverse,
+            watcher::watch_directory,
+            watcher::unwatch_directory,
+            windows::open_project_window,
+            windows::window_project,
+            workspace::list_files_recursive,
+            search::search_project,
+            index::search_index,
+            index::index_status,
+            index::fuzzy::fuzzy_find_files,
+            index::symbols::workspace_symbols,
+            index::macros::list_user_macros,
+            index::todos::list_todos,
+            bibliography::list_citations,
+            bibliography::append_bib_entry,
+            bibliography::fetch::fetch_bibtex,
+            replace::replace_in_project,
+            latex::root::detect_root_document,
+            latex::dependencies::project_dependencies,
+            latex::assets::find_unused_assets,
+            latex::outline::parse_outline,
+            latex::outline::list_numbered_environments,
+            latex::references::list_labels,
+            latex::references::validate_references,
+            latex::completion::complete_at,
+            latex::hover::hover_info,
+            latex::definition::goto_definition,
+            latex::rename::rename_symbol,
+            markdown::diagram::render_diagram,
+            markdown::front_matter::parse_front_matter,
+            markdown::front_matter::update_front_matter,
+            markdown::highlight::highlight_code,
+            markdown::highlight::list_highlight_themes,
+            markdown::lint::lint_markdown,
+            tables::import::import_table,
+            tables::format::format_table,
+            markdown::toc::generate_toc,
+            markdown::toc::renumber_headings,
+            latex::lint::lint_latex,
+            latex::format::format_latex,
+            spellcheck::check_text,
+            spellcheck::add_to_dictionary,
+            grammar::grammar_check,
+            pdf::render_pdf_page,
+            pdf::extract_pdf_text,
+            pdf::search_pdf,
+            pdf::pdf_outline,
+            pdf::print_pdf,
+            pdf_diff::diff_pdfs,
+            fonts::list_system_fonts,
+            fonts::validate_fonts,
+            project::load_project_config,
+            project::save_project_config,
+            templates::list_templates,
+            templates::create_from_template,
+            settings::get_settings,
+            settings::update_settings,
+            recents::record_recent,
+            recents::list_recents,
+            recents::open_recent,
+            recents::pin_recent,
+            recents::remove_recent,
+            snippets::list_snippets,
+            snippets::save_snippet,
+            snippets::delete_snippet,
+            snippets::export_snippets,
+            snippets::import_snippets,
+            session::save_session,
+            session::load_session,
+            scope::open_file_dialog,
+            scope::open_folder_dialog,
+            scope::save_file_dialog,
+            toolchain::check_toolchain,
+            toolchain::install_tectonic,
+            logging::get_recent_logs,
+            logging::open_log_folder,
+            crash::list_crash_reports,
+            crash::submit_crash_report
+        ]))
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -0,0 +1,128 @@
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use layout::backends::svg::SVGWriter;
+use layout::gv::{DotParser, GraphBuilder};
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::atomic::write_atomic;
+use crate::document::content_version;
+use crate::images::svg_data_to_pdf;
+use crate::scope::WindowScope;
+
+/// 按内容哈希缓存渲染好的 Mermaid 图：mmdc 要启动无头浏览器，耗时数秒，预览重新渲染没变的代码块时不该再跑一次
+const MERMAID_CACHE_DIR: &str = "mymd_diagrams";
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagramKind {
+    Mermaid,
+    Graphviz,
+}
+
+#[derive(Serialize)]
+pub struct RenderedDiagram {
+    svg: String,
+    /// `mmdc`、`dot` 或 `layout-rs`
+    renderer: &'static str,
+    /// 要求写出时，图写到的位置
+    output_path: Option<String>,
+}
+
+/// 运行 `program`（或其 Windows `.cmd` 外壳），从 stdin 输入 `source`。`Ok(None)` 表示没有安装
+fn run_with_stdin(programs: &[&str], args: &[&str], source: &str) -> Result<Option<Vec<u8>>, String> {
+    for program in programs {
+        let child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(format!("{} 运行失败: {}", program, e)),
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(source.as_bytes()).map_err(|e| format!("{} 运行失败: {}", program, e))?;
+        }
+        let output = child.wait_with_output().map_err(|e| format!("{} 运行失败: {}", program, e))?;
+        if !output.status.success() {
+            return Err(format!("{} error:\n{}", program, String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        return Ok(Some(output.stdout));
+    }
+    Ok(None)
+}
+
+fn render_mermaid(source: &str) -> Result<String, String> {
+    let dir = std::env::temp_dir().join(MERMAID_CACHE_DIR);
+    let stem = content_version(source.as_bytes());
+    let output = dir.join(format!("{}.svg", stem));
+    if let Ok(svg) = fs::read_to_string(&output) {
+        return Ok(svg);
+    }
+    fs::create_dir_all(&dir).map_err(|e| format!("无法创建目录: {}", e))?;
+    let output_arg = output.to_string_lossy().to_string();
+    let args = ["--input", "-", "--output", output_arg.as_str(), "--backgroundColor", "transparent", "--quiet"];
+    match run_with_stdin(&["mmdc", "mmdc.cmd"], &args, source)? {
+        Some(_) => fs::read_to_string(&output).map_err(|e| format!("无法读取文件: {}", e)),
+        None => Err("未找到 mmdc；渲染 Mermaid 图需要安装 @mermaid-js/mermaid-cli".to_string()),
+    }
+}
+
+/// 安装了 Graphviz 自带的 `dot` 时使用它，大图的布局要好得多；否则用 layout-rs
+fn render_graphviz(source: &str) -> Result<(String, &'static str), String> {
+    if let Some(svg) = run_with_stdin(&["dot"], &["-Tsvg"], source)? {
+        return Ok((String::from_utf8_lossy(&svg).to_string(), "dot"));
+    }
+    let graph = DotParser::new(source).process().map_err(|e| format!("无效的 DOT: {}", e))?;
+    let mut builder = GraphBuilder::new();
+    builder.visit_graph(&graph);
+    let mut visual = builder.get();
+    let mut svg = SVGWriter::new();
+    visual.do_it(false, false, false, &mut svg);
+    Ok((svg.finalize(), "layout-rs"))
+}
+
+fn write_figure(path: &Path, svg: &str) -> Result<(), String> {
+    let bytes = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("pdf") => svg_data_to_pdf(svg.as_bytes())?,
+        Some("svg") => svg.as_bytes().to_vec(),
+        _ => return Err("图只能保存为 .svg 或 .pdf".to_string()),
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("无法创建目录: {}", e))?;
+    }
+    write_atomic(path, &bytes).map(|_| ()).map_err(|e| format!("无法写入文件: {}", e))
+}
+
+/// 把 Mermaid 或 Graphviz 代码块渲染成 SVG 供预览。Mermaid 需要 PATH 中有 `mmdc`；Graphviz 安装了 `dot`
+/// 时使用它，否则用内置布局。给出 `output_path` 时还把图写到那里，按扩展名存为 SVG 或 PDF，供导出时包含
+#[command]
+pub async fn render_diagram(
+    scope: WindowScope,
+    kind: DiagramKind,
+    source: String,
+    output_path: Option<String>,
+) -> Result<RenderedDiagram, String> {
+    let output_path = output_path.map(PathBuf::from);
+    if let Some(path) = &output_path {
+        scope.check(path)?;
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let (svg, renderer) = match kind {
+            DiagramKind::Mermaid => (render_mermaid(&source)?, "mmdc"),
+            DiagramKind::Graphviz => render_graphviz(&source)?,
+        };
+        if let Some(path) = &output_path {
+            write_figure(path, &svg)?;
+        }
+        Ok(RenderedDiagram { svg, renderer, output_path: output_path.map(|path| path.to_string_lossy().to_string()) })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
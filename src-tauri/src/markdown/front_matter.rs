@@ -0,0 +1,125 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tauri::command;
+
+/// 顶层的 `key:` 行；嵌套的键有缩进，列表项以 `-` 开头，都不匹配
+static KEY_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"^("[^"]*"|'[^']*'|[^\s#\-"'][^:]*?)\s*:(?:\s|$)"#).unwrap());
+
+#[derive(Serialize)]
+pub struct FrontMatter {
+    /// 转成 JSON 的 YAML；是一个对象，块为空时为空对象
+    data: Value,
+    /// 开头和结尾 `---` 所在的行，从 1 开始
+    start_line: u32,
+    end_line: u32,
+}
+
+/// 块的位置：两条分隔线之间 YAML 的字节范围，以及结尾分隔线所在的行
+struct Block {
+    yaml_start: usize,
+    yaml_end: usize,
+    end_line: u32,
+}
+
+fn locate(source: &str) -> Option<Block> {
+    let mut lines = source.split_inclusive('\n');
+    let first = lines.next()?;
+    if first.trim_end() != "---" {
+        return None;
+    }
+    let yaml_start = first.len();
+    let mut offset = yaml_start;
+    for (index, line) in lines.enumerate() {
+        if matches!(line.trim_end(), "---" | "...") {
+            return Some(Block { yaml_start, yaml_end: offset, end_line: index as u32 + 2 });
+        }
+        offset += line.len();
+    }
+    None
+}
+
+fn parse_yaml(yaml: &str) -> Result<Value, String> {
+    match serde_yaml::from_str::<Value>(yaml) {
+        Ok(Value::Null) => Ok(Value::Object(Map::new())),
+        Ok(value @ Value::Object(_)) => Ok(value),
+        Ok(_) => Err("front matter 不是映射".to_string()),
+        Err(e) => Err(format!("无效的 front matter: {}", e)),
+    }
+}
+
+fn unquote(key: &str) -> &str {
+    key.trim().trim_matches(|c| c == '"' || c == '\'')
+}
+
+/// 把 `key: value` 写成 YAML，列表和映射用块格式
+fn entry_yaml(key: &str, value: &Value) -> Result<String, String> {
+    let mut entry = Map::new();
+    entry.insert(key.to_string(), value.clone());
+    serde_yaml::to_string(&entry).map_err(|e| e.to_string())
+}
+
+/// 按顶层键分组的 YAML 行：每个键所在的行连同其下缩进的行或列表行。键之间的注释和空行单独成组，没有键
+fn groups(yaml: &str) -> Vec<(Option<String>, String)> {
+    let mut groups: Vec<(Option<String>, String)> = Vec::new();
+    for line in yaml.split_inclusive('\n') {
+        let continues = line.starts_with([' ', '\t', '-']) && !line.trim().is_empty();
+        match (KEY_RE.captures(line), groups.last_mut()) {
+            (Some(caps), _) => groups.push((Some(unquote(&caps[1]).to_string()), line.to_string())),
+            (None, Some((Some(_), text))) if continues => text.push_str(line),
+            _ => groups.push((None, line.to_string())),
+        }
+    }
+    groups
+}
+
+/// 文档的 YAML front matter，没有时为 None。与 pandoc 和 Jekyll 一样，只认从第一行开始的块
+#[command]
+pub fn parse_front_matter(source: String) -> Result<Option<FrontMatter>, String> {
+    let Some(block) = locate(&source) else {
+        return Ok(None);
+    };
+    let data = parse_yaml(&source[block.yaml_start..block.yaml_end])?;
+    Ok(Some(FrontMatter { data, start_line: 1, end_line: block.end_line }))
+}
+
+/// 设置 `patch` 中的顶层键（null 表示删除）并返回新的源码。不在 `patch` 中的键保留原有格式和注释，
+/// 改动的键留在原位；新键追加在后面。没有块时新建一个
+#[command]
+pub fn update_front_matter(source: String, patch: Map<String, Value>) -> Result<String, String> {
+    let (yaml, before, after) = match locate(&source) {
+        Some(block) => {
+            let yaml = &source[block.yaml_start..block.yaml_end];
+            parse_yaml(yaml)?;
+            (yaml.to_string(), &source[..block.yaml_start], source[block.yaml_end..].to_string())
+        }
+        None if patch.values().all(Value::is_null) => return Ok(source),
+        None => (String::new(), "---\n", format!("---\n\n{}", source)),
+    };
+
+    let mut groups = groups(&yaml);
+    for (key, value) in &patch {
+        let position = groups.iter().position(|(existing, _)| existing.as_deref() == Some(key.as_str()));
+        match (position, value) {
+            (Some(position), Value::Null) => {
+                groups.remove(position);
+            }
+            (Some(position), value) => groups[position].1 = entry_yaml(key, value)?,
+            (None, Value::Null) => {}
+            (None, value) => {
+                // 在最后一行后面追加之前保留它的换行
+                if let Some((_, text)) = groups.last_mut().filter(|(_, text)| !text.ends_with('\n')) {
+                    text.push('\n');
+                }
+                groups.push((Some(key.clone()), entry_yaml(key, value)?));
+            }
+        }
+    }
+    let yaml: String = groups.into_iter().map(|(_, text)| text).collect();
+    // 上面手工拼出的内容必须还能读回来
+    parse_yaml(&yaml)?;
+    Ok(format!("{}{}{}", before, yaml, after))
+}
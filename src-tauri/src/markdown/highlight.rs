@@ -0,0 +1,126 @@
+use std::sync::LazyLock;
+
+use serde::{Deserialize, Serialize};
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Color, FontStyle, Theme, ThemeSet};
+use syntect::html::highlighted_html_for_string;
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::util::LinesWithEndings;
+use tauri::command;
+
+/// 浅色主题，与预览的 GitHub 样式接近
+const DEFAULT_THEME: &str = "InspiredGitHub";
+
+static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+static THEMES: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum HighlightOutput {
+    /// 带内联样式的 `<pre>`，用于预览和 HTML 导出
+    #[default]
+    Html,
+    /// 带样式的区间，由前端绘制或由导出器转换
+    Ranges,
+}
+
+#[derive(Serialize)]
+pub struct StyledRange {
+    /// 从 1 开始；列号按字符计，结束位置不含
+    line: u32,
+    start_column: u32,
+    end_column: u32,
+    /// `#rrggbb`
+    foreground: String,
+    bold: bool,
+    italic: bool,
+    underline: bool,
+}
+
+#[derive(Serialize)]
+pub struct HighlightedCode {
+    /// 使用的语法：代码块的语言，未知时为 `Plain Text`
+    language: String,
+    html: Option<String>,
+    ranges: Vec<StyledRange>,
+    /// 主题的背景色，用于代码外面的块
+    background: Option<String>,
+}
+
+fn hex(color: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+/// 围栏信息串对应的语法：`rust`、`py`、`C++`、`latex` 等
+fn syntax_for(language: &str) -> &'static SyntaxReference {
+    let language = language.trim();
+    let token = language.split([' ', '{', ',']).next().unwrap_or_default();
+    SYNTAXES
+        .find_syntax_by_token(token)
+        .or_else(|| SYNTAXES.find_syntax_by_token(&token.to_lowercase()))
+        .unwrap_or_else(|| SYNTAXES.find_syntax_plain_text())
+}
+
+fn theme(name: Option<&str>) -> Result<&'static Theme, String> {
+    let name = name.unwrap_or(DEFAULT_THEME);
+    THEMES.themes.get(name).ok_or_else(|| format!("未知的高亮主题: {}", name))
+}
+
+fn ranges(code: &str, syntax: &SyntaxReference, theme: &Theme) -> Result<Vec<StyledRange>, String> {
+    let mut highlighter = HighlightLines::new(syntax, theme);
+    let mut ranges = Vec::new();
+    for (index, line) in LinesWithEndings::from(code).enumerate() {
+        let tokens = highlighter.highlight_line(line, &SYNTAXES).map_err(|e| e.to_string())?;
+        let mut column = 1;
+        for (style, text) in tokens {
+            let text = text.trim_end_matches(['\r', '\n']);
+            let width = text.chars().count() as u32;
+            if width == 0 {
+                continue;
+            }
+            ranges.push(StyledRange {
+                line: index as u32 + 1,
+                start_column: column,
+                end_column: column + width,
+                foreground: hex(style.foreground),
+                bold: style.font_style.contains(FontStyle::BOLD),
+                italic: style.font_style.contains(FontStyle::ITALIC),
+                underline: style.font_style.contains(FontStyle::UNDERLINE),
+            });
+            column += width;
+        }
+    }
+    Ok(ranges)
+}
+
+/// 用 syntect 自带的主题按 `language`（围栏信息串）高亮围栏代码块的 `code`，
+/// 预览和导出的代码着色一致，不需要 minted 或 listings
+#[command]
+pub async fn highlight_code(
+    code: String,
+    language: String,
+    theme: Option<String>,
+    output: Option<HighlightOutput>,
+) -> Result<HighlightedCode, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let syntax = syntax_for(&language);
+        let theme = self::theme(theme.as_deref())?;
+        let (html, ranges) = match output.unwrap_or_default() {
+            HighlightOutput::Html => {
+                let html = highlighted_html_for_string(&code, &SYNTAXES, syntax, theme).map_err(|e| e.to_string())?;
+                (Some(html), Vec::new())
+            }
+            HighlightOutput::Ranges => (None, ranges(&code, syntax, theme)?),
+        };
+        let background = theme.settings.background.map(hex);
+        Ok(HighlightedCode { language: syntax.name.clone(), html, ranges, background })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// `highlight_code` 的 `theme` 可用的名字
+#[command]
+pub fn list_highlight_themes() -> Vec<String> {
+    THEMES.themes.keys().cloned().collect()
+}
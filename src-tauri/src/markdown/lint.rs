@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Serialize;
+use tauri::command;
+
+use super::{char_column, literal_lines};
+use crate::project::find_project_config;
+use crate::scope::WindowScope;
+
+/// markdownlint 的规则 id 和名字，以及项目和请求都没提到时该规则是否运行
+const RULES: &[(&str, &str, bool)] = &[
+    ("MD001", "heading-increment", true),
+    ("MD004", "ul-style", true),
+    ("MD009", "no-trailing-spaces", true),
+    ("MD010", "no-hard-tabs", true),
+    ("MD012", "no-multiple-blanks", true),
+    ("MD018", "no-missing-space-atx", true),
+    ("MD025", "single-h1", true),
+    ("MD034", "no-bare-urls", true),
+    ("MD047", "single-trailing-newline", true),
+];
+
+static HEADING_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^ {0,3}(#{1,6})(?:[ \t]+|$)").unwrap());
+/// `#Heading`，但不包括单独的 `#` 或 `#!` 行
+static MISSING_SPACE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^ {0,3}(#{1,6})([^#\s!])").unwrap());
+static BULLET_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*(?:>\s*)*([*+-])[ \t]+\S").unwrap());
+static URL_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\bhttps?://[^\s<>()\[\]`]+").unwrap());
+
+/// 把两个位置之间（从 1 开始，结束位置不含）的文本替换为 `text`
+#[derive(Serialize, Clone)]
+pub struct TextEdit {
+    pub line: u32,
+    pub column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+    pub text: String,
+}
+
+#[derive(Serialize)]
+pub struct MarkdownDiagnostic {
+    /// `MD009` 等
+    rule: &'static str,
+    /// `no-trailing-spaces` 等
+    name: &'static str,
+    message: String,
+    line: u32,
+    /// 从 1 开始，按字符计
+    column: u32,
+    end_line: u32,
+    end_column: u32,
+    severity: &'static str,
+    /// 应用它就能消除警告；需要人来处理时为 None
+    fix: Option<TextEdit>,
+}
+
+struct Linter {
+    enabled: HashMap<&'static str, bool>,
+    diagnostics: Vec<MarkdownDiagnostic>,
+}
+
+impl Linter {
+    /// 报告 `text` 中第 `index` 行（从 0 开始）的字节 `start..end`；`fix` 替换它们
+    fn report(
+        &mut self,
+        rule: &'static str,
+        (index, text): (usize, &str),
+        start: usize,
+        end: usize,
+        message: String,
+        fix: Option<String>,
+    ) {
+        let (line, column, end_column) = (index as u32 + 1, char_column(text, start), char_column(text, end));
+        let fix = fix.map(|text| TextEdit { line, column, end_line: line, end_column, text });
+        self.report_range(rule, line, column, end_column, message, fix);
+    }
+
+    fn report_range(
+        &mut self,
+        rule: &'static str,
+        line: u32,
+        column: u32,
+        end_column: u32,
+        message: String,
+        fix: Option<TextEdit>,
+    ) {
+        if !self.enabled[rule] {
+            return;
+        }
+        let name = RULES.iter().find(|(id, _, _)| *id == rule).map(|(_, name, _)| *name).unwrap_or_default();
+        self.diagnostics.push(MarkdownDiagnostic {
+            rule,
+            name,
+            message,
+            line,
+            column,
+            end_line: line,
+            end_column,
+            severity: "warning",
+            fix,
+        });
+    }
+}
+
+/// 规则开关：先取默认值，再取项目配置，最后取请求。键是规则 id 或名字，不区分大小写
+fn enabled_rules(overrides: &[&HashMap<String, bool>]) -> HashMap<&'static str, bool> {
+    let mut enabled: HashMap<&'static str, bool> = RULES.iter().map(|(id, _, on)| (*id, *on)).collect();
+    for rules in overrides {
+        for (key, on) in rules.iter() {
+            let key = key.to_ascii_lowercase();
+            if key == "default" {
+                enabled.values_mut().for_each(|value| *value = *on);
+            }
+            let rule = RULES.iter().find(|(id, name, _)| id.to_ascii_lowercase() == key || *name == key);
+            if let Some((id, _, _)) = rule {
+                enabled.insert(id, *on);
+            }
+        }
+    }
+    enabled
+}
+
+/// `***`、`- - -`、`___`：分隔线，不是列表项
+fn is_thematic_break(line: &str) -> bool {
+    let marks: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    marks.len() >= 3 && matches!(marks[0], '*' | '-' | '_') && marks.iter().all(|&c| c == marks[0])
+}
+
+/// `line` 的字节 `at` 是否在反引号代码片段内
+fn in_code_span(line: &str, at: usize) -> bool {
+    line[..at].matches('`').count() % 2 == 1
+}
+
+fn lint(source: &str, enabled: HashMap<&'static str, bool>) -> Vec<MarkdownDiagnostic> {
+    let literal = literal_lines(source);
+    let lines: Vec<&str> = source.lines().collect();
+    let mut linter = Linter { enabled, diagnostics: Vec::new() };
+    let mut previous_level = 0;
+    let mut seen_h1 = false;
+    let mut bullet: Option<char> = None;
+    let mut blank_run = 0;
+
+    for (index, &line) in lines.iter().enumerate() {
+        let at = (index, line);
+        let line_number = index as u32 + 1;
+
+        // 空白规则在代码块中同样适用
+        let trimmed_len = line.trim_end_matches([' ', '\t']).len();
+        let trailing = &line[trimmed_len..];
+        // 文本后恰好两个空格是硬换行
+        if !trailing.is_empty() && (trailing != "  " || literal[index] || trimmed_len == 0) {
+            let message = format!("Trailing spaces [Expected: 0 or 2; Actual: {}]", trailing.len());
+            linter.report("MD009", at, trimmed_len, line.len(), message, Some(String::new()));
+        }
+        for (tab, _) in line.match_indices('\t').filter(|(tab, _)| *tab < trimmed_len) {
+            linter.report("MD010", at, tab, tab + 1, "Hard tabs".to_string(), Some("    ".to_string()));
+        }
+        if line.trim().is_empty() && !literal[index] {
+            blank_run += 1;
+            if blank_run > 1 {
+                let message = format!("Multiple consecutive blank lines [Expected: 1; Actual: {}]", blank_run);
+                // 删除整行，包括换行符
+                let (end_line, text) = (line_number + 1, String::new());
+                let fix = TextEdit { line: line_number, column: 1, end_line, end_column: 1, text };
+                linter.report_range("MD012", line_number, 1, char_column(line, line.len()), message, Some(fix));
+            }
+            continue;
+        }
+        blank_run = 0;
+        if literal[index] {
+            continue;
+        }
+
+        if let Some(caps) = HEADING_RE.captures(line) {
+            let level = caps[1].len();
+            let start = caps.get(1).unwrap().start();
+            if previous_level > 0 && level > previous_level + 1 {
+                let message = format!(
+                    "Heading levels should only increment by one level at a time [Expected: h{}; Actual: h{}]",
+                    previous_level + 1,
+                    level
+                );
+                linter.report("MD001", at, start, line.len(), message, None);
+            }
+            if level == 1 && seen_h1 {
+                let message = "Multiple top-level headings in the same document".to_string();
+                linter.report("MD025", at, start, line.len(), message, None);
+            }
+            seen_h1 |= level == 1;
+            previous_level = level;
+        } else if let Some(hashes) = MISSING_SPACE_RE.captures(line).and_then(|caps| caps.get(1)) {
+            let message = "No space after hash on atx style heading".to_string();
+            let fix = format!("{} ", hashes.as_str());
+            linter.report("MD018", at, hashes.start(), hashes.end(), message, Some(fix));
+        }
+
+        let marker = BULLET_RE.captures(line).filter(|_| !is_thematic_break(line)).and_then(|caps| caps.get(1));
+        if let Some(marker) = marker {
+            let style = marker.as_str().chars().next().unwrap();
+            match bullet {
+                None => bullet = Some(style),
+                Some(expected) if expected != style => {
+                    let name = |c: char| match c {
+                        '*' => "asterisk",
+                        '+' => "plus",
+                        _ => "dash",
+                    };
+                    let message =
+                        format!("Unordered list style [Expected: {}; Actual: {}]", name(expected), name(style));
+                    linter.report("MD004", at, marker.start(), marker.end(), message, Some(expected.to_string()));
+                }
+                _ => {}
+            }
+        }
+
+        for url in URL_RE.find_iter(line) {
+            // 已经是链接、自动链接或 HTML 属性
+            let previous = line[..url.start()].chars().next_back();
+            if matches!(previous, Some('<' | '(' | '"' | '\'' | '[' | '=')) || in_code_span(line, url.start()) {
+                continue;
+            }
+            let text = url.as_str().trim_end_matches(['.', ',', ';', ':', '!', '?']);
+            let message = format!("Bare URL used [Context: \"{}\"]", text);
+            linter.report("MD034", at, url.start(), url.start() + text.len(), message, Some(format!("<{}>", text)));
+        }
+    }
+
+    if let Some(&last) = lines.last().filter(|_| !source.ends_with('\n')) {
+        let line_number = lines.len() as u32;
+        let end = char_column(last, last.len());
+        let message = "Files should end with a single newline character".to_string();
+        let text = "\n".to_string();
+        let fix = TextEdit { line: line_number, column: end, end_line: line_number, end_column: end, text };
+        linter.report_range("MD047", line_number, end, end, message, Some(fix));
+    }
+
+    linter.diagnostics.sort_by(|a, b| a.line.cmp(&b.line).then(a.column.cmp(&b.column)));
+    linter.diagnostics
+}
+
+/// `source` 的 markdownlint 风格警告，能机械修复的附带修改。规则先由项目的 `markdown_lint` 表（从 `path` 找到）
+/// 开关，再由 `rules` 开关；两者都接受规则 id 或名字，`default` 表示全部规则
+#[command]
+pub async fn lint_markdown(
+    scope: WindowScope,
+    path: Option<String>,
+    source: String,
+    rules: Option<HashMap<String, bool>>,
+) -> Result<Vec<MarkdownDiagnostic>, String> {
+    scope.check_optional(path.as_ref())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let project = path.as_deref().and_then(|path| find_project_config(Path::new(path)));
+        let project_rules: HashMap<String, bool> =
+            project.map(|(_, config)| config.markdown_lint.into_iter().collect()).unwrap_or_default();
+        let rules = rules.unwrap_or_default();
+        lint(&source, enabled_rules(&[&project_rules, &rules]))
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(source: &str) -> Vec<(&'static str, u32)> {
+        lint(source, enabled_rules(&[])).iter().map(|d| (d.rule, d.line)).collect()
+    }
+
+    #[test]
+    fn trailing_spaces_allow_hard_breaks_outside_code() {
+        assert_eq!(rules("a  \nb \n```\nc  \n```\n"), [("MD009", 2), ("MD009", 4)]);
+        let diagnostics = lint("b \n", enabled_rules(&[]));
+        let fix = diagnostics[0].fix.as_ref().unwrap();
+        assert_eq!((fix.column, fix.end_column, fix.text.as_str()), (2, 3, ""));
+    }
+
+    #[test]
+    fn heading_rules_skip_code_blocks() {
+        assert_eq!(rules("# A\n### C\n# B\n#D\n```\n#E\n```\n"), [("MD001", 2), ("MD025", 3), ("MD018", 4)]);
+    }
+
+    #[test]
+    fn blank_lines_and_final_newline() {
+        assert_eq!(rules("a\n\n\nb"), [("MD012", 3), ("MD047", 4)]);
+    }
+
+    #[test]
+    fn rules_are_toggled_by_id_or_name() {
+        let off = HashMap::from([("default".to_string(), false)]);
+        let urls = HashMap::from([("no-bare-urls".to_string(), true)]);
+        let source = "see https://example.org/a.  \n<https://example.org/b>\n";
+        let diagnostics = lint(source, enabled_rules(&[&off, &urls]));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].rule, "MD034");
+        assert_eq!(diagnostics[0].fix.as_ref().unwrap().text, "<https://example.org/a>");
+    }
+}
@@ -0,0 +1,43 @@
+pub mod diagram;
+pub mod front_matter;
+pub mod highlight;
+pub mod lint;
+pub mod toc;
+
+/// `source` 中哪些行不是 Markdown 正文：开头的 YAML front matter 和围栏代码块（含围栏）。下标与 `lines()` 一致
+pub fn literal_lines(source: &str) -> Vec<bool> {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut literal = vec![false; lines.len()];
+    let mut start = 0;
+    if lines.first().is_some_and(|line| line.trim_end() == "---") {
+        if let Some(end) = lines.iter().skip(1).position(|line| matches!(line.trim_end(), "---" | "...")) {
+            literal[..end + 2].fill(true);
+            start = end + 2;
+        }
+    }
+    // 开启代码块的围栏：其字符和长度
+    let mut fence: Option<(char, usize)> = None;
+    for (index, line) in lines.iter().enumerate().skip(start) {
+        let trimmed = line.trim_start();
+        let marker = trimmed.chars().next().filter(|c| matches!(c, '`' | '~'));
+        let run = marker.map(|c| trimmed.chars().take_while(|&d| d == c).count()).unwrap_or(0);
+        match (fence, marker) {
+            (None, Some(c)) if run >= 3 => {
+                fence = Some((c, run));
+                literal[index] = true;
+            }
+            (Some((c, length)), Some(d)) if c == d && run >= length && trimmed[run..].trim().is_empty() => {
+                fence = None;
+                literal[index] = true;
+            }
+            (Some(_), _) => literal[index] = true,
+            _ => {}
+        }
+    }
+    literal
+}
+
+/// `line` 中字节 `byte` 所在的字符列，从 1 开始
+pub fn char_column(line: &str, byte: usize) -> u32 {
+    line[..byte].chars().count() as u32 + 1
+}
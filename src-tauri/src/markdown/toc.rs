@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use super::literal_lines;
+
+/// markdown-toc 和大多数编辑器使用的标记；重新生成目录时替换两者之间的列表
+const TOC_START: &str = "<!-- toc -->";
+const TOC_END: &str = "<!-- tocstop -->";
+
+static ATX_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^ {0,3}(#{1,6})[ \t]+(.*?)(?:[ \t]+#+)?[ \t]*$").unwrap());
+static SETEXT_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^ {0,3}(=+|-+)[ \t]*$").unwrap());
+/// Pandoc 的 `{#anchor}`，无论文本如何都固定锚点
+static EXPLICIT_ID_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\s*\{#([A-Za-z][\w:.-]*)[^}]*\}\s*$").unwrap());
+/// 标题开头的 `1.`、`2.3`、`4.5.6.`，与重新编号写出的格式一致
+static NUMBER_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\d+(?:\.\d+)*\.?[ \t]+").unwrap());
+static LINK_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"!?\[([^\]]*)\]\([^)]*\)").unwrap());
+static EMPHASIS_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"[*_~`]").unwrap());
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct TocOptions {
+    /// 高于 `min_level` 或低于 `max_level` 的标题不列出
+    min_level: usize,
+    max_level: usize,
+    /// 用 `1.` 条目代替 `-`
+    ordered: bool,
+}
+
+impl Default for TocOptions {
+    fn default() -> Self {
+        TocOptions { min_level: 1, max_level: 3, ordered: false }
+    }
+}
+
+#[derive(Serialize)]
+pub struct TocEntry {
+    level: usize,
+    text: String,
+    anchor: String,
+    /// 从 1 开始
+    line: u32,
+}
+
+#[derive(Serialize)]
+pub struct Toc {
+    /// 列表本身，不含标记
+    markdown: String,
+    entries: Vec<TocEntry>,
+    /// 重新生成标记之间列表后的 `source`；源码没有标记时为 None，由前端自己插入 `markdown`
+    updated: Option<String>,
+}
+
+/// 原样的标题：所在行及其文本的位置
+struct Heading {
+    level: usize,
+    line: usize,
+    /// 文本在行中的字节范围（setext 标题为整个文本行）
+    start: usize,
+    end: usize,
+}
+
+fn headings(lines: &[&str], literal: &[bool]) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        if literal[index] {
+            continue;
+        }
+        if let Some(text) = ATX_RE.captures(line).and_then(|caps| caps.get(2)).filter(|text| !text.is_empty()) {
+            let level = line.trim_start().chars().take_while(|&c| c == '#').count();
+            headings.push(Heading { level, line: index, start: text.start(), end: text.end() });
+            continue;
+        }
+        // 后面跟着 `===` 或 `---` 的段落行
+        let underline = lines.get(index + 1).filter(|_| !literal.get(index + 1).copied().unwrap_or(true));
+        let Some(caps) = underline.and_then(|next| SETEXT_RE.captures(next)) else {
+            continue;
+        };
+        let text = line.trim();
+        let starts_block = text.starts_with(['-', '*', '+', '>', '#', '|']) || text.is_empty();
+        let previous_blank = index == 0 || lines[index - 1].trim().is_empty();
+        if starts_block || !previous_blank {
+            continue;
+        }
+        let level = if caps[1].starts_with('=') { 1 } else { 2 };
+        let start = line.len() - line.trim_start().len();
+        headings.push(Heading { level, line: index, start, end: start + text.len() });
+    }
+    headings
+}
+
+/// 标题读出来的文本，不含标记和固定的锚点
+fn plain_text(text: &str) -> String {
+    let text = EXPLICIT_ID_RE.replace(text, "");
+    let text = LINK_RE.replace_all(&text, "$1");
+    EMPHASIS_RE.replace_all(&text, "").trim().to_string()
+}
+
+/// GitHub 为 `text` 生成的锚点：小写，去掉标点，空格换成连字符；重复的加 `-1`、`-2` 等
+fn anchor(text: &str, explicit: Option<&str>, used: &mut HashMap<String, usize>) -> String {
+    let base = match explicit {
+        Some(id) => id.to_string(),
+        None => text
+            .to_lowercase()
+            .chars()
+            .filter(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_'))
+            .map(|c| if c == ' ' { '-' } else { c })
+            .collect(),
+    };
+    let count = used.entry(base.clone()).or_insert(0);
+    let anchor = if *count == 0 { base.clone() } else { format!("{}-{}", base, count) };
+    *count += 1;
+    anchor
+}
+
+fn build_toc(source: &str, options: &TocOptions) -> Toc {
+    let lines: Vec<&str> = source.lines().collect();
+    let literal = literal_lines(source);
+    let mut used = HashMap::new();
+    let mut entries = Vec::new();
+    for heading in headings(&lines, &literal) {
+        let raw = &lines[heading.line][heading.start..heading.end];
+        let text = plain_text(raw);
+        let explicit = EXPLICIT_ID_RE.captures(raw).map(|caps| caps[1].to_string());
+        // 与渲染器一样，锚点计入每个标题，不管是否列出
+        let anchor = anchor(&text, explicit.as_deref(), &mut used);
+        if heading.level >= options.min_level && heading.level <= options.max_level {
+            entries.push(TocEntry { level: heading.level, text, anchor, line: heading.line as u32 + 1 });
+        }
+    }
+
+    let top = entries.iter().map(|entry| entry.level).min().unwrap_or(1);
+    let mut counters = [0usize; 7];
+    let markdown: String = entries
+        .iter()
+        .map(|entry| {
+            let depth = entry.level - top;
+            counters[depth] += 1;
+            counters[depth + 1..].fill(0);
+            let bullet = if options.ordered { format!("{}.", counters[depth]) } else { "-".to_string() };
+            let indent = if options.ordered { 3 } else { 2 };
+            let text = entry.text.replace('[', "\\[").replace(']', "\\]");
+            format!("{}{} [{}](#{})\n", " ".repeat(depth * indent), bullet, text, entry.anchor)
+        })
+        .collect();
+
+    let updated = source.find(TOC_START).and_then(|start| {
+        let body = start + TOC_START.len();
+        let end = body + source[body..].find(TOC_END)?;
+        Some(format!("{}\n\n{}\n{}", &source[..body], markdown, &source[end..]))
+    });
+    Toc { markdown, entries, updated }
+}
+
+/// Markdown `source` 的目录，链接到 GitHub 风格的锚点（以 `{#id}` 属性为准）。
+/// 源码有 `<!-- toc -->`/`<!-- tocstop -->` 块时，`updated` 是重新生成该块后的源码
+#[command]
+pub fn generate_toc(source: String, options: Option<TocOptions>) -> Toc {
+    build_toc(&source, &options.unwrap_or_default())
+}
+
+/// 从出现的最高层级开始，给 ATX 和 setext 标题编号 `1`、`1.1`、`1.2`、`2` 等，替换已有的编号。
+/// `remove` 为 true 时改为去掉编号
+#[command]
+pub fn renumber_headings(source: String, remove: Option<bool>) -> String {
+    let lines: Vec<&str> = source.lines().collect();
+    let literal = literal_lines(&source);
+    let headings = headings(&lines, &literal);
+    let top = headings.iter().map(|heading| heading.level).min().unwrap_or(1);
+    let mut counters = [0usize; 7];
+    let mut rewritten: HashMap<usize, String> = HashMap::new();
+    for heading in &headings {
+        let line = lines[heading.line];
+        let text = &line[heading.start..heading.end];
+        let text = NUMBER_RE.replace(text, "");
+        let depth = heading.level - top;
+        counters[depth] += 1;
+        counters[depth + 1..].fill(0);
+        let number = if remove.unwrap_or(false) {
+            String::new()
+        } else {
+            // 跳过的层级记为 1，而不是印出 `0`
+            let parts: Vec<String> = counters[..=depth].iter().map(|n| (*n).max(1).to_string()).collect();
+            format!("{} ", parts.join("."))
+        };
+        rewritten.insert(heading.line, format!("{}{}{}{}", &line[..heading.start], number, text, &line[heading.end..]));
+    }
+
+    source
+        .split_inclusive('\n')
+        .enumerate()
+        .map(|(index, raw)| match rewritten.get(&index) {
+            Some(line) => format!("{}{}", line, &raw[raw.trim_end_matches(['\r', '\n']).len()..]),
+            None => raw.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toc_links_unique_anchors() {
+        let source = concat!(
+            "# Doc\n",
+            "<!-- toc -->\nold\n<!-- tocstop -->\n",
+            "## Setup {#install}\n",
+            "## Use `it`\n",
+            "### Use it\n",
+            "#### Too deep\n",
+            "## Use it\n",
+        );
+        let toc = build_toc(source, &TocOptions { min_level: 1, max_level: 3, ordered: true });
+        let anchors: Vec<&str> = toc.entries.iter().map(|entry| entry.anchor.as_str()).collect();
+        assert_eq!(anchors, ["doc", "install", "use-it", "use-it-1", "use-it-2"]);
+        let markdown = concat!(
+            "1. [Doc](#doc)\n",
+            "   1. [Setup](#install)\n",
+            "   2. [Use it](#use-it)\n",
+            "      1. [Use it](#use-it-1)\n",
+            "   3. [Use it](#use-it-2)\n",
+        );
+        assert_eq!(toc.markdown, markdown);
+        let updated = toc.updated.unwrap();
+        assert!(updated.starts_with(&format!("# Doc\n<!-- toc -->\n\n{}\n<!-- tocstop -->\n## Setup", markdown)));
+    }
+
+    #[test]
+    fn renumbering_starts_at_the_top_level_and_skips_code() {
+        let source = "## A\n#### Deep\n## 9. B\n```\n## not a heading\n```\n\nSetext\n------\n";
+        let numbered = renumber_headings(source.to_string(), None);
+        assert_eq!(numbered, "## 1 A\n#### 1.1.1 Deep\n## 2 B\n```\n## not a heading\n```\n\n3 Setext\n------\n");
+        let removed = renumber_headings(numbered, Some(true));
+        assert_eq!(removed, source.replace("9. ", ""));
+    }
+
+    #[test]
+    fn renumbering_keeps_crlf_and_closing_hashes() {
+        let numbered = renumber_headings("# One #\r\n# Two\r\n".to_string(), None);
+        assert_eq!(numbered, "# 1 One #\r\n# 2 Two\r\n");
+    }
+}
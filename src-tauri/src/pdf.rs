@@ -0,0 +1,433 @@
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+
+use image::{DynamicImage, ImageFormat};
+use pdfium_render::prelude::*;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Manager};
+
+use crate::scope::WindowScope;
+
+/// 超过这个倍数渲染会变慢且很占内存，肉眼却看不出多少差别
+pub const MAX_ZOOM: f32 = 8.0;
+pub const MIN_ZOOM: f32 = 0.1;
+/// 合理的查询都够用；在论文里搜一个字母不算合理
+const MAX_SEARCH_MATCHES: usize = 1000;
+/// 格式错误的大纲可能自己绕回来；真实文档不会有这么多书签
+const MAX_BOOKMARKS: usize = 10_000;
+
+/// 每个进程只能绑定一次 pdfium，所以实例在整个会话中一直存在。绑定失败不缓存：用户可能装好库后重试
+static PDFIUM: OnceLock<Pdfium> = OnceLock::new();
+static PDFIUM_INIT: Mutex<()> = Mutex::new(());
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct PrintOptions {
+    /// CUPS 的页码列表，如 `1-3,5`；不设置时打印所有页
+    pub page_range: Option<String>,
+    pub copies: Option<u32>,
+    /// 操作系统列出的打印机名称；不设置时用默认打印机
+    pub printer: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct RenderedPage {
+    pub png: Vec<u8>,
+    page_count: u32,
+    /// 从 1 开始
+    page: u32,
+    /// `png` 的尺寸，单位为像素
+    pub width: u32,
+    pub height: u32,
+    /// 页面尺寸，单位为 PDF 点（1/72 英寸），用于把点击位置换算回 SyncTeX
+    page_width: f32,
+    page_height: f32,
+}
+
+#[derive(Serialize)]
+pub struct PageText {
+    page: u32,
+    text: String,
+}
+
+/// 单位为 PDF 点，原点在页面左上角，与 `render_pdf_page` 的图像方向相同（乘以其缩放倍数即可）
+#[derive(Serialize)]
+pub struct PageRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Serialize)]
+pub struct PdfMatch {
+    page: u32,
+    /// 匹配跨越的每一行一个
+    rects: Vec<PageRect>,
+    text: String,
+}
+
+/// PDF 大纲中的一个书签，hyperref 为每个 `\section` 写一个
+#[derive(Serialize)]
+pub struct PdfOutlineItem {
+    title: String,
+    /// 从 1 开始；打开 URL 或其他文件的书签为 None
+    page: Option<u32>,
+    /// 书签指明落在页面何处时，到页面顶部的距离，单位为 PDF 点
+    y: Option<f32>,
+    children: Vec<PdfOutlineItem>,
+}
+
+/// 随应用打包的库（先找资源目录，再找可执行文件旁边）优先于系统安装的库
+fn library_dirs(app: &AppHandle) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Ok(resources) = app.path().resource_dir() {
+        dirs.push(resources);
+    }
+    if let Some(exe_dir) = std::env::current_exe().ok().and_then(|exe| exe.parent().map(PathBuf::from)) {
+        dirs.push(exe_dir);
+    }
+    dirs
+}
+
+pub fn pdfium(app: &AppHandle) -> Result<&'static Pdfium, String> {
+    if let Some(pdfium) = PDFIUM.get() {
+        return Ok(pdfium);
+    }
+    let _guard = PDFIUM_INIT.lock().unwrap();
+    if let Some(pdfium) = PDFIUM.get() {
+        return Ok(pdfium);
+    }
+    let bindings = library_dirs(app)
+        .iter()
+        .map(Pdfium::pdfium_platform_library_name_at_path)
+        .filter(|path| path.is_file())
+        .find_map(|path| Pdfium::bind_to_library(path).ok())
+        .map_or_else(Pdfium::bind_to_system_library, Ok)
+        .map_err(|e| format!("未找到 PDFium 库: {}", e))?;
+    Ok(PDFIUM.get_or_init(|| Pdfium::new(bindings)))
+}
+
+pub fn render_page(app: &AppHandle, pdf_path: &str, page: u32, zoom: f32) -> Result<RenderedPage, String> {
+    let document = open_document(pdfium(app)?, pdf_path)?;
+    let pages = document.pages();
+    let page_count = pages.len() as u32;
+    if page == 0 || page > page_count {
+        return Err(format!("页码 {} 超出范围（1-{}）", page, page_count));
+    }
+    let pdf_page = pages
+        .get((page - 1) as PdfPageIndex)
+        .map_err(|e| format!("无法加载第 {} 页: {}", page, e))?;
+
+    let image = render_image(&pdf_page, page, zoom)?;
+
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| format!("无法编码第 {} 页: {}", page, e))?;
+    Ok(RenderedPage {
+        png,
+        page_count,
+        page,
+        width: image.width(),
+        height: image.height(),
+        page_width: pdf_page.width().value,
+        page_height: pdf_page.height().value,
+    })
+}
+
+/// `page` 从 1 开始，只用于错误信息
+pub fn render_image(pdf_page: &PdfPage, page: u32, zoom: f32) -> Result<DynamicImage, String> {
+    let config = PdfRenderConfig::new()
+        .scale_page_by_factor(zoom.clamp(MIN_ZOOM, MAX_ZOOM))
+        .render_form_data(true)
+        .render_annotations(true);
+    let bitmap = pdf_page
+        .render_with_config(&config)
+        .map_err(|e| format!("无法渲染第 {} 页: {}", page, e))?;
+    bitmap.as_image().map_err(|e| format!("无法渲染第 {} 页: {}", page, e))
+}
+
+pub fn open_document<'a>(pdfium: &'a Pdfium, pdf_path: &str) -> Result<PdfDocument<'a>, String> {
+    pdfium
+        .load_pdf_from_file(pdf_path, None)
+        .map_err(|e| format!("无法打开 PDF: {}", e))
+}
+
+fn page_text<'a>(page: &'a PdfPage) -> Result<PdfPageText<'a>, String> {
+    page.text().map_err(|e| format!("无法读取页面文本: {}", e))
+}
+
+fn extract_blocking(app: &AppHandle, pdf_path: &str, page: Option<u32>) -> Result<Vec<PageText>, String> {
+    let document = open_document(pdfium(app)?, pdf_path)?;
+    let pages = document.pages();
+    let page_count = pages.len() as u32;
+    let range = match page {
+        Some(page) if page == 0 || page > page_count => {
+            return Err(format!("页码 {} 超出范围（1-{}）", page, page_count));
+        }
+        Some(page) => page..=page,
+        None => 1..=page_count,
+    };
+    range
+        .map(|number| {
+            let pdf_page = pages
+                .get((number - 1) as PdfPageIndex)
+                .map_err(|e| format!("无法加载第 {} 页: {}", number, e))?;
+            let text = page_text(&pdf_page)?.all();
+            Ok(PageText { page: number, text })
+        })
+        .collect()
+}
+
+fn search_blocking(
+    app: &AppHandle,
+    pdf_path: &str,
+    query: &str,
+    options: &PdfSearchOptions,
+) -> Result<Vec<PdfMatch>, String> {
+    let document = open_document(pdfium(app)?, pdf_path)?;
+    let mut matches = Vec::new();
+    for (index, pdf_page) in document.pages().iter().enumerate() {
+        let page_height = pdf_page.height().value;
+        let text = page_text(&pdf_page)?;
+        let search = text.search(query, options).map_err(|e| format!("搜索失败: {}", e))?;
+        for segments in search.iter(PdfSearchDirection::SearchForward) {
+            let mut rects = Vec::new();
+            let mut matched = String::new();
+            for segment in segments.iter() {
+                let bounds = segment.bounds();
+                rects.push(PageRect {
+                    x: bounds.left().value,
+                    y: page_height - bounds.top().value,
+                    width: bounds.width().value,
+                    height: bounds.height().value,
+                });
+                matched.push_str(&segment.text());
+            }
+            matches.push(PdfMatch { page: index as u32 + 1, rects, text: matched });
+            if matches.len() >= MAX_SEARCH_MATCHES {
+                return Ok(matches);
+            }
+        }
+    }
+    Ok(matches)
+}
+
+/// 书签自己的目标位置，或其跳转动作的目标：hyperref 使用命名目标，pdfium 只能通过动作解析
+fn bookmark_target(pages: &PdfPages, bookmark: &PdfBookmark) -> (Option<u32>, Option<f32>) {
+    let action = bookmark.action();
+    let destination = bookmark
+        .destination()
+        .or_else(|| action.as_ref()?.as_local_destination_action()?.destination().ok());
+    let index = destination.as_ref().and_then(|destination| destination.page_index().ok());
+    let Some(page) = index.and_then(|index| u32::try_from(index).ok()) else {
+        return (None, None);
+    };
+    let top = match destination.and_then(|destination| destination.view_settings().ok()) {
+        Some(PdfDestinationViewSettings::SpecificCoordinatesAndZoom(_, Some(y), _))
+        | Some(PdfDestinationViewSettings::FitPageHorizontallyToWindow(Some(y)))
+        | Some(PdfDestinationViewSettings::FitBoundsHorizontallyToWindow(Some(y))) => {
+            // PDF 坐标从页面底部向上增长
+            pages.get(page as PdfPageIndex).ok().map(|pdf_page| (pdf_page.height().value - y.value).max(0.0))
+        }
+        _ => None,
+    };
+    (Some(page + 1), top)
+}
+
+fn outline_items(pages: &PdfPages, first: Option<PdfBookmark>, budget: &mut usize) -> Vec<PdfOutlineItem> {
+    let mut items = Vec::new();
+    let mut next = first;
+    while let Some(bookmark) = next {
+        if *budget == 0 {
+            break;
+        }
+        *budget -= 1;
+        let (page, y) = bookmark_target(pages, &bookmark);
+        items.push(PdfOutlineItem {
+            title: bookmark.title().unwrap_or_default(),
+            page,
+            y,
+            children: outline_items(pages, bookmark.first_child(), budget),
+        });
+        next = bookmark.next_sibling();
+    }
+    items
+}
+
+fn outline_blocking(app: &AppHandle, pdf_path: &str) -> Result<Vec<PdfOutlineItem>, String> {
+    let document = open_document(pdfium(app)?, pdf_path)?;
+    let mut budget = MAX_BOOKMARKS;
+    Ok(outline_items(document.pages(), document.bookmarks().root(), &mut budget))
+}
+
+/// 以每点 `zoom` 像素光栅化 `pdf_path` 的一页，供内置查看器使用。`page` 与 SyncTeX 一样从 1 开始
+#[command]
+pub async fn render_pdf_page(
+    app: AppHandle,
+    scope: WindowScope,
+    pdf_path: String,
+    page: u32,
+    zoom: Option<f32>,
+) -> Result<RenderedPage, String> {
+    scope.check(&pdf_path)?;
+    let zoom = zoom.unwrap_or(1.0);
+    tauri::async_runtime::spawn_blocking(move || render_page(&app, &pdf_path, page, zoom))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// 一页（从 1 开始）的文本，`page` 为 None 时为所有页的文本
+#[command]
+pub async fn extract_pdf_text(
+    app: AppHandle,
+    scope: WindowScope,
+    pdf_path: String,
+    page: Option<u32>,
+) -> Result<Vec<PageText>, String> {
+    scope.check(&pdf_path)?;
+    tauri::async_runtime::spawn_blocking(move || extract_blocking(&app, &pdf_path, page))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// 在 PDF 中查找：按页码顺序列出 `query` 的每处匹配及要高亮的矩形
+#[command]
+pub async fn search_pdf(
+    app: AppHandle,
+    scope: WindowScope,
+    pdf_path: String,
+    query: String,
+    match_case: Option<bool>,
+    whole_word: Option<bool>,
+) -> Result<Vec<PdfMatch>, String> {
+    scope.check(&pdf_path)?;
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let options = PdfSearchOptions::new()
+        .match_case(match_case.unwrap_or(false))
+        .match_whole_word(whole_word.unwrap_or(false));
+    tauri::async_runtime::spawn_blocking(move || search_blocking(&app, &pdf_path, &query, &options))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// PDF 的书签树，用于预览中可点击的目录。文档没有书签时为空（没用 hyperref，或是别处来的 PDF）
+#[command]
+pub async fn pdf_outline(
+    app: AppHandle,
+    scope: WindowScope,
+    pdf_path: String,
+) -> Result<Vec<PdfOutlineItem>, String> {
+    scope.check(&pdf_path)?;
+    tauri::async_runtime::spawn_blocking(move || outline_blocking(&app, &pdf_path))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// 只能有数字、逗号和短横线，且每个数字都是实际存在的页：这个列表会作为选项值交给打印队列
+fn validate_page_range(range: &str) -> Result<(), String> {
+    let is_page = |s: &str| s.parse::<u32>().is_ok_and(|n| n > 0);
+    let valid = range.split(',').all(|part| match part.trim().split_once('-') {
+        None => is_page(part.trim()),
+        // `3-` 和 `-3` 是开区间；单独的 `-` 不是
+        Some((start, end)) => {
+            (is_page(start) || is_page(end)) && (start.is_empty() || is_page(start)) && (end.is_empty() || is_page(end))
+        }
+    });
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("无效的页码范围: {}", range))
+    }
+}
+
+/// `lp`，没有 System V 命令的系统退而用 `lpr`
+#[cfg(not(windows))]
+fn print_blocking(path: &Path, options: &PrintOptions) -> Result<(), String> {
+    let range = options.page_range.as_deref().map(|range| format!("page-ranges={}", range.replace(' ', "")));
+    let mut lp = Command::new("lp");
+    if let Some(printer) = &options.printer {
+        lp.arg("-d").arg(printer);
+    }
+    if let Some(copies) = options.copies {
+        lp.arg("-n").arg(copies.to_string());
+    }
+    if let Some(range) = &range {
+        lp.arg("-o").arg(range);
+    }
+    lp.arg("--").arg(path);
+
+    let mut lpr = Command::new("lpr");
+    if let Some(printer) = &options.printer {
+        lpr.arg("-P").arg(printer);
+    }
+    if let Some(copies) = options.copies {
+        lpr.arg("-#").arg(copies.to_string());
+    }
+    if let Some(range) = &range {
+        lpr.arg("-o").arg(range);
+    }
+    lpr.arg(path);
+
+    for mut cmd in [lp, lpr] {
+        let program = cmd.get_program().to_string_lossy().to_string();
+        match cmd.output() {
+            Ok(output) if output.status.success() => return Ok(()),
+            Ok(output) => {
+                return Err(format!("{} error:\n{}", program, String::from_utf8_lossy(&output.stderr).trim()));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(format!("{} 运行失败: {}", program, e)),
+        }
+    }
+    Err("lp 和 lpr 都未找到；打印需要安装 CUPS".to_string())
+}
+
+/// shell 的 "print"/"printto" 动词，即注册的 PDF 处理程序。这个动词无法传页码范围或份数
+#[cfg(windows)]
+fn print_blocking(path: &Path, options: &PrintOptions) -> Result<(), String> {
+    if options.page_range.is_some() || options.copies.is_some_and(|copies| copies > 1) {
+        return Err("Windows 上不支持页码范围和份数；请从 PDF 查看器中打印".to_string());
+    }
+    // 取值通过环境变量传递，路径不需要 PowerShell 转义
+    let script = match options.printer {
+        Some(_) => "Start-Process -FilePath $env:PRINT_PDF_PATH -Verb PrintTo -ArgumentList ('\"' + $env:PRINT_PDF_PRINTER + '\"')",
+        None => "Start-Process -FilePath $env:PRINT_PDF_PATH -Verb Print",
+    };
+    let mut cmd = Command::new("powershell");
+    cmd.args(["-NoProfile", "-NonInteractive", "-Command", script]).env("PRINT_PDF_PATH", path);
+    if let Some(printer) = &options.printer {
+        cmd.env("PRINT_PDF_PRINTER", printer);
+    }
+    let output = cmd.output().map_err(|e| format!("powershell 运行失败: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("打印失败:\n{}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(())
+}
+
+/// 把 `pdf_path` 发送到操作系统的打印系统：macOS 和 Linux 上用 `lp`/`lpr`，Windows 上用注册的 PDF 处理程序的
+/// 打印动词。任务进入队列后返回
+#[command]
+pub async fn print_pdf(scope: WindowScope, pdf_path: String, options: Option<PrintOptions>) -> Result<(), String> {
+    scope.check(&pdf_path)?;
+    let path = PathBuf::from(&pdf_path);
+    if !path.is_file() {
+        return Err(format!("无法读取文件: {}", pdf_path));
+    }
+    let options = options.unwrap_or_default();
+    if let Some(range) = &options.page_range {
+        validate_page_range(range)?;
+    }
+    if options.copies == Some(0) {
+        return Err("份数至少为 1".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || print_blocking(&path, &options))
+        .await
+        .map_err(|e| e.to_string())?
+}
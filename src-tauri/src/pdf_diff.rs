@@ -0,0 +1,191 @@
+use std::io::Cursor;
+
+use image::{ImageFormat, Rgba, RgbaImage};
+use pdfium_render::prelude::*;
+use serde::Serialize;
+use tauri::{command, AppHandle};
+
+use crate::pdf::{open_document, pdfium, render_image, PageRect, MAX_ZOOM, MIN_ZOOM};
+use crate::scope::WindowScope;
+
+/// 比较用的每点像素数：足以发现改动的逗号，又不必以打印分辨率光栅化一篇长论文
+const DEFAULT_ZOOM: f32 = 1.5;
+/// 低于这个值的单通道差异是抗锯齿噪声，不算改动
+const THRESHOLD: u8 = 48;
+/// 改动的像素按这么多像素的方格分组，相距这么多格以内的方格并入同一区域，改写的一行是一个矩形而不是每个字形一个
+const CELL: u32 = 8;
+const REACH: i64 = 2;
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PageStatus {
+    Unchanged,
+    Changed,
+    /// 只在新 PDF 中
+    Added,
+    /// 只在旧 PDF 中
+    Removed,
+}
+
+#[derive(Serialize)]
+pub struct PageDiff {
+    /// 从 1 开始；新旧页面按页码配对
+    page: u32,
+    status: PageStatus,
+    /// 新页面的 PNG，淡化后把改动的像素标红；页面新增或删除时为原样的页面；没有改动时为 None
+    image: Option<Vec<u8>>,
+    /// `image` 的像素尺寸
+    width: u32,
+    height: u32,
+    /// 改动的区域，以 PDF 点为单位，从页面左上角算起，与 `search_pdf` 的矩形一致
+    regions: Vec<PageRect>,
+}
+
+fn page_image(pages: &PdfPages, number: u32, zoom: f32) -> Result<Option<RgbaImage>, String> {
+    if number > pages.len() as u32 {
+        return Ok(None);
+    }
+    let pdf_page = pages
+        .get((number - 1) as PdfPageIndex)
+        .map_err(|e| format!("无法加载第 {} 页: {}", number, e))?;
+    Ok(Some(render_image(&pdf_page, number, zoom)?.to_rgba8()))
+}
+
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/// 大小不同的页面按较大的一页比较，缺少的部分当作空白纸
+fn pixel(image: &RgbaImage, x: u32, y: u32) -> Rgba<u8> {
+    if x < image.width() && y < image.height() {
+        *image.get_pixel(x, y)
+    } else {
+        WHITE
+    }
+}
+
+fn differs(a: Rgba<u8>, b: Rgba<u8>) -> bool {
+    a.0.iter().zip(b.0.iter()).any(|(a, b)| a.abs_diff(*b) > THRESHOLD)
+}
+
+/// 改动方格聚类的包围框（以方格计）
+fn regions(changed: &[bool], columns: u32, rows: u32) -> Vec<(u32, u32, u32, u32)> {
+    let mut seen = vec![false; changed.len()];
+    let mut boxes = Vec::new();
+    for start in 0..changed.len() {
+        if !changed[start] || seen[start] {
+            continue;
+        }
+        seen[start] = true;
+        let mut stack = vec![start];
+        let (mut left, mut top, mut right, mut bottom) = (u32::MAX, u32::MAX, 0, 0);
+        while let Some(cell) = stack.pop() {
+            let (x, y) = (cell as u32 % columns, cell as u32 / columns);
+            (left, top, right, bottom) = (left.min(x), top.min(y), right.max(x), bottom.max(y));
+            for dy in -REACH..=REACH {
+                for dx in -REACH..=REACH {
+                    let (nx, ny) = (i64::from(x) + dx, i64::from(y) + dy);
+                    if nx < 0 || ny < 0 || nx >= i64::from(columns) || ny >= i64::from(rows) {
+                        continue;
+                    }
+                    let neighbour = (ny * i64::from(columns) + nx) as usize;
+                    if changed[neighbour] && !seen[neighbour] {
+                        seen[neighbour] = true;
+                        stack.push(neighbour);
+                    }
+                }
+            }
+        }
+        boxes.push((left, top, right, bottom));
+    }
+    boxes
+}
+
+fn encode(image: &RgbaImage) -> Result<Vec<u8>, String> {
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| format!("无法编码差异图像: {}", e))?;
+    Ok(png)
+}
+
+/// 向白色淡化四分之三，让红色的改动更显眼
+fn faded(color: Rgba<u8>) -> Rgba<u8> {
+    let [r, g, b, _] = color.0;
+    let fade = |c: u8| 255 - (255 - c) / 4;
+    Rgba([fade(r), fade(g), fade(b), 255])
+}
+
+fn whole_page(page: u32, status: PageStatus, image: &RgbaImage, zoom: f32) -> Result<PageDiff, String> {
+    let (width, height) = image.dimensions();
+    let regions = vec![PageRect { x: 0.0, y: 0.0, width: width as f32 / zoom, height: height as f32 / zoom }];
+    Ok(PageDiff { page, status, image: Some(encode(image)?), width, height, regions })
+}
+
+fn compare(page: u32, old: Option<RgbaImage>, new: Option<RgbaImage>, zoom: f32) -> Result<PageDiff, String> {
+    let (old, new) = match (old, new) {
+        (Some(old), Some(new)) => (old, new),
+        (None, Some(new)) => return whole_page(page, PageStatus::Added, &new, zoom),
+        (Some(old), None) => return whole_page(page, PageStatus::Removed, &old, zoom),
+        (None, None) => return Err(format!("两个 PDF 中都没有第 {} 页", page)),
+    };
+    let width = old.width().max(new.width());
+    let height = old.height().max(new.height());
+    let (columns, rows) = (width.div_ceil(CELL).max(1), height.div_ceil(CELL).max(1));
+    let mut changed = vec![false; (columns * rows) as usize];
+    let mut overlay = RgbaImage::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let (before, after) = (pixel(&old, x, y), pixel(&new, x, y));
+            if differs(before, after) {
+                changed[((y / CELL) * columns + x / CELL) as usize] = true;
+                overlay.put_pixel(x, y, Rgba([220, 30, 30, 255]));
+            } else {
+                overlay.put_pixel(x, y, faded(after));
+            }
+        }
+    }
+
+    let (page_width, page_height) = (width as f32 / zoom, height as f32 / zoom);
+    let to_points = |cells: u32| (cells * CELL) as f32 / zoom;
+    let regions: Vec<PageRect> = regions(&changed, columns, rows)
+        .into_iter()
+        .map(|(left, top, right, bottom)| PageRect {
+            x: to_points(left),
+            y: to_points(top),
+            width: to_points(right + 1).min(page_width) - to_points(left),
+            height: to_points(bottom + 1).min(page_height) - to_points(top),
+        })
+        .collect();
+    if regions.is_empty() {
+        return Ok(PageDiff { page, status: PageStatus::Unchanged, image: None, width, height, regions });
+    }
+    Ok(PageDiff { page, status: PageStatus::Changed, image: Some(encode(&overlay)?), width, height, regions })
+}
+
+fn diff_blocking(app: &AppHandle, old_pdf: &str, new_pdf: &str, zoom: f32) -> Result<Vec<PageDiff>, String> {
+    let pdfium = pdfium(app)?;
+    let old = open_document(pdfium, old_pdf)?;
+    let new = open_document(pdfium, new_pdf)?;
+    let (old_pages, new_pages) = (old.pages(), new.pages());
+    let count = old_pages.len().max(new_pages.len()) as u32;
+    (1..=count)
+        .map(|page| compare(page, page_image(old_pages, page, zoom)?, page_image(new_pages, page, zoom)?, zoom))
+        .collect()
+}
+
+/// 直观比较两次编译，例如上一次和这一次编译，或两个 git 版本的 PDF：每对页面以 `zoom` 光栅化后逐像素比较，
+/// 对每个有差异的页面返回高亮的图像和改动的矩形。页面按页码配对，所以插入一页会让之后的每一页都显示为改动
+#[command]
+pub async fn diff_pdfs(
+    app: AppHandle,
+    scope: WindowScope,
+    old_pdf: String,
+    new_pdf: String,
+    zoom: Option<f32>,
+) -> Result<Vec<PageDiff>, String> {
+    scope.check(&old_pdf)?;
+    scope.check(&new_pdf)?;
+    let zoom = zoom.unwrap_or(DEFAULT_ZOOM).clamp(MIN_ZOOM, MAX_ZOOM);
+    tauri::async_runtime::spawn_blocking(move || diff_blocking(&app, &old_pdf, &new_pdf, zoom))
+        .await
+        .map_err(|e| e.to_string())?
+}
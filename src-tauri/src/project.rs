@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::atomic::write_atomic;
+use crate::compiler::EngineKind;
+use crate::index::todos::TodoPriority;
+use crate::scope::WindowScope;
+
+/// 放在项目中，可以提交和共享
+const CONFIG_DIR: &str = ".mymd";
+const CONFIG_FILE: &str = "project.toml";
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct ProjectConfig {
+    /// 相对项目根目录
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root_document: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub engine: Option<EngineKind>,
+    /// 相对项目根目录；默认为根文档旁的 `AuxiliaryFiles`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_directory: Option<String>,
+    /// 传给引擎的额外命令行参数，放在编译请求添加的参数之前
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub compile_flags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spellcheck_language: Option<String>,
+    /// 按 id（`MD009`）或名称（`no-trailing-spaces`）开启或关闭的 Markdown lint 规则；未列出的规则保持默认
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub markdown_lint: BTreeMap<String, bool>,
+    /// 除 `TODO`、`FIXME` 和 `XXX` 外在任务面板中列出的注释标记及其优先级（`REVIEW = "high"`）；
+    /// 内置标记也可以在这里改优先级
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub todo_markers: BTreeMap<String, TodoPriority>,
+    /// 这个版本不认识的键，保留下来，以免保存时丢掉更新版本写入的设置
+    #[serde(flatten)]
+    pub extra: toml::Table,
+}
+
+/// `<root>/.mymd`，项目遍历会跳过它；不属于配置的项目状态也放在这里
+pub fn config_dir(root: &Path) -> PathBuf {
+    root.join(CONFIG_DIR)
+}
+
+pub fn config_path(root: &Path) -> PathBuf {
+    config_dir(root).join(CONFIG_FILE)
+}
+
+pub fn read_config(root: &Path) -> Result<Option<ProjectConfig>, String> {
+    let path = config_path(root);
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("无法读取文件: {}", e)),
+    };
+    toml::from_str(&content)
+        .map(Some)
+        .map_err(|e| format!("无效的 {}: {}", path.to_string_lossy(), e))
+}
+
+/// `path` 外层最近的有配置文件的项目及其根目录。无法读取或无效的配置会被跳过
+pub fn find_project_config(path: &Path) -> Option<(PathBuf, ProjectConfig)> {
+    path.ancestors()
+        .skip(1)
+        .find_map(|dir| read_config(dir).ok().flatten().map(|config| (dir.to_path_buf(), config)))
+}
+
+/// 其 `.mymd/<file>` 保存 `path` 状态的项目：已存在该文件的最近目录，否则是有配置文件的项目，
+/// 再否则是 `path` 所在的目录
+pub fn state_root(path: &Path, file: &str) -> PathBuf {
+    path.ancestors()
+        .skip(1)
+        .find(|dir| config_dir(dir).join(file).is_file())
+        .map(Path::to_path_buf)
+        .or_else(|| find_project_config(path).map(|(root, _)| root))
+        .unwrap_or_else(|| path.parent().unwrap_or(Path::new(".")).to_path_buf())
+}
+
+/// `path` 相对 `root` 的路径，以 `/` 分隔，即状态文件中文件的键
+pub fn state_key(root: &Path, path: &Path) -> Result<String, String> {
+    let relative = path.strip_prefix(root).map_err(|_| format!("{} 不在项目内", path.display()))?;
+    let parts: Vec<String> = relative.iter().map(|part| part.to_string_lossy().to_string()).collect();
+    Ok(parts.join("/"))
+}
+
+pub fn write_config(root: &Path, config: &ProjectConfig) -> Result<(), String> {
+    let content = toml::to_string_pretty(config).map_err(|e| e.to_string())?;
+    fs::create_dir_all(root.join(CONFIG_DIR)).map_err(|e| format!("无法创建目录: {}", e))?;
+    write_atomic(&config_path(root), content.as_bytes()).map_err(|e| format!("无法写入文件: {}", e))?;
+    Ok(())
+}
+
+impl ProjectConfig {
+    /// 相对 `root` 解析的 `root_document`，指向存在的文件时才有
+    pub fn root_document_path(&self, root: &Path) -> Option<PathBuf> {
+        let path = root.join(self.root_document.as_ref()?);
+        path.is_file().then_some(path)
+    }
+}
+
+/// 存在 `<root>/.mymd/project.toml` 中的设置；文件还不存在时为默认值
+#[command]
+pub fn load_project_config(scope: WindowScope, root: String) -> Result<ProjectConfig, String> {
+    scope.check(&root)?;
+    Ok(read_config(Path::new(&root))?.unwrap_or_default())
+}
+
+#[command]
+pub fn save_project_config(scope: WindowScope, root: String, config: ProjectConfig) -> Result<(), String> {
+    let root = Path::new(&root);
+    scope.check(root)?;
+    if !root.is_dir() {
+        return Err(format!("无法读取目录: {}", root.to_string_lossy()));
+    }
+    write_config(root, &config)
+}
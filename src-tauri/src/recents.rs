@@ -0,0 +1,175 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Manager, State};
+
+use crate::atomic::{unix_millis, write_atomic};
+use crate::latex::root::canonical;
+use crate::scope::WindowScope;
+
+const RECENTS_FILE: &str = "recents.json";
+/// 每种类型的上限；固定的条目不计入，也从不被挤出
+const MAX_RECENTS: usize = 20;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RecentKind {
+    File,
+    Project,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RecentEntry {
+    /// 前端打开时的样子，不做规范化，按用户选择的方式显示
+    path: String,
+    kind: RecentKind,
+    /// Unix 毫秒
+    opened_at: u64,
+    #[serde(default)]
+    pinned: bool,
+}
+
+#[derive(Serialize)]
+pub struct RecentItem {
+    #[serde(flatten)]
+    entry: RecentEntry,
+    /// 不存在时只列出固定的条目；其余的隐藏
+    exists: bool,
+}
+
+/// 第一次使用时从磁盘加载，最近的在前
+#[derive(Default)]
+pub struct Recents {
+    entries: Mutex<Option<Vec<RecentEntry>>>,
+}
+
+fn recents_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let base = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(base.join(RECENTS_FILE))
+}
+
+fn read_recents(app: &AppHandle) -> Vec<RecentEntry> {
+    recents_path(app)
+        .and_then(|path| fs::read_to_string(path).map_err(|e| e.to_string()))
+        .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string()))
+        .unwrap_or_default()
+}
+
+fn write_recents(app: &AppHandle, entries: &[RecentEntry]) -> Result<(), String> {
+    let path = recents_path(app)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("无法创建目录: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(entries).map_err(|e| e.to_string())?;
+    write_atomic(&path, content.as_bytes()).map_err(|e| format!("无法写入文件: {}", e))?;
+    Ok(())
+}
+
+fn same_entry(entry: &RecentEntry, path: &Path, kind: RecentKind) -> bool {
+    entry.kind == kind && canonical(Path::new(&entry.path)) == path
+}
+
+impl Recents {
+    /// 对列表运行 `edit` 并保存结果
+    fn edit<T>(&self, app: &AppHandle, edit: impl FnOnce(&mut Vec<RecentEntry>) -> T) -> Result<T, String> {
+        let mut cached = self.entries.lock().unwrap();
+        let entries = cached.get_or_insert_with(|| read_recents(app));
+        let result = edit(entries);
+        write_recents(app, entries)?;
+        Ok(result)
+    }
+}
+
+/// 把 `path` 移到列表最前面，保留其固定状态。同类型中超出 `MAX_RECENTS` 的未固定条目被丢弃。
+/// 只能记录作用域内的路径，因为 `open_recent` 会再次授予它们访问权限
+#[command]
+pub fn record_recent(
+    app: AppHandle,
+    recents: State<'_, Recents>,
+    scope: WindowScope,
+    path: String,
+    kind: RecentKind,
+) -> Result<(), String> {
+    scope.check(&path)?;
+    let key = canonical(Path::new(&path));
+    recents.edit(&app, |entries| {
+        let pinned = entries.iter().any(|e| e.pinned && same_entry(e, &key, kind));
+        entries.retain(|e| !same_entry(e, &key, kind));
+        entries.insert(0, RecentEntry { path, kind, opened_at: unix_millis(SystemTime::now()), pinned });
+
+        let mut kept = 0;
+        entries.retain(|e| {
+            if e.kind != kind || e.pinned {
+                return true;
+            }
+            kept += 1;
+            kept <= MAX_RECENTS
+        });
+    })
+}
+
+/// 先是固定的条目，然后是其余的，最近的在前。`kind` 把列表限定为文件或项目
+#[command]
+pub fn list_recents(app: AppHandle, recents: State<'_, Recents>, kind: Option<RecentKind>) -> Vec<RecentItem> {
+    let mut cached = recents.entries.lock().unwrap();
+    let entries = cached.get_or_insert_with(|| read_recents(&app));
+    let mut items: Vec<RecentItem> = entries
+        .iter()
+        .filter(|e| kind.is_none_or(|kind| e.kind == kind))
+        .map(|e| RecentItem { exists: Path::new(&e.path).exists(), entry: e.clone() })
+        .filter(|item| item.exists || item.entry.pinned)
+        .collect();
+    // 稳定排序，每组保持最近使用的顺序
+    items.sort_by_key(|item| !item.entry.pinned);
+    items
+}
+
+/// 前端打开之前，把记录的条目重新加入作用域
+#[command]
+pub fn open_recent(
+    app: AppHandle,
+    recents: State<'_, Recents>,
+    scope: WindowScope,
+    path: String,
+    kind: RecentKind,
+) -> Result<(), String> {
+    let key = canonical(Path::new(&path));
+    let mut cached = recents.entries.lock().unwrap();
+    let entries = cached.get_or_insert_with(|| read_recents(&app));
+    if !entries.iter().any(|e| same_entry(e, &key, kind)) {
+        return Err(format!("不在最近项目中: {}", path));
+    }
+    scope.allow(Path::new(&path));
+    Ok(())
+}
+
+#[command]
+pub fn pin_recent(
+    app: AppHandle,
+    recents: State<'_, Recents>,
+    path: String,
+    kind: RecentKind,
+    pinned: bool,
+) -> Result<(), String> {
+    let key = canonical(Path::new(&path));
+    let found = recents.edit(&app, |entries| {
+        entries
+            .iter_mut()
+            .filter(|e| same_entry(e, &key, kind))
+            .map(|e| e.pinned = pinned)
+            .count()
+    })?;
+    if found == 0 {
+        return Err(format!("不在最近项目中: {}", path));
+    }
+    Ok(())
+}
+
+#[command]
+pub fn remove_recent(app: AppHandle, recents: State<'_, Recents>, path: String, kind: RecentKind) -> Result<(), String> {
+    let key = canonical(Path::new(&path));
+    recents.edit(&app, |entries| entries.retain(|e| !same_entry(e, &key, kind)))
+}
@@ -0,0 +1,164 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use regex::{NoExpand, Regex};
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::atomic::write_atomic;
+use crate::scope::WindowScope;
+use crate::search::{build_matcher, SearchOptions};
+use crate::workspace::{looks_binary, project_walker};
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct ReplaceOptions {
+    #[serde(flatten)]
+    pub search: SearchOptions,
+    /// `false`（默认）只计算预览；`true` 写入文件
+    pub apply: bool,
+}
+
+#[derive(Serialize)]
+pub struct LineChange {
+    line: u32,
+    before: String,
+    after: String,
+}
+
+#[derive(Serialize)]
+pub struct FileReplaceResult {
+    file: String,
+    replacements: usize,
+    lines: Vec<LineChange>,
+    applied: bool,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ReplaceReport {
+    files: Vec<FileReplaceResult>,
+    /// 只有每个受影响的文件都重写成功时才为 true
+    applied: bool,
+}
+
+struct PendingFile {
+    path: PathBuf,
+    content: String,
+    new_content: String,
+    result: FileReplaceResult,
+}
+
+fn replace_text(matcher: &Regex, input: &str, replacement: &str, expand: bool) -> String {
+    if expand {
+        matcher.replace_all(input, replacement).to_string()
+    } else {
+        matcher.replace_all(input, NoExpand(replacement)).to_string()
+    }
+}
+
+/// 跳过不是有效 UTF-8 的文件：搜索以有损方式读取它们，把这样的文本写回会替换掉每个无法解码的字节。
+/// UTF-8 文本在匹配之外逐字节原样重写，BOM 和 CRLF 换行符都会保留
+fn plan_file(path: &Path, matcher: &Regex, replacement: &str, expand: bool) -> Option<PendingFile> {
+    let bytes = fs::read(path).ok()?;
+    if looks_binary(&bytes) {
+        return None;
+    }
+    let content = String::from_utf8(bytes).ok()?;
+    let replacements = matcher.find_iter(&content).count();
+    if replacements == 0 {
+        return None;
+    }
+
+    let lines = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| matcher.is_match(line))
+        .map(|(index, line)| LineChange {
+            line: index as u32 + 1,
+            before: line.to_string(),
+            after: replace_text(matcher, line, replacement, expand),
+        })
+        .collect();
+
+    Some(PendingFile {
+        path: path.to_path_buf(),
+        new_content: replace_text(matcher, &content, replacement, expand),
+        content,
+        result: FileReplaceResult {
+            file: path.to_string_lossy().to_string(),
+            replacements,
+            lines,
+            applied: false,
+            error: None,
+        },
+    })
+}
+
+/// 逐个写入文件，每个都是原子写入。某个失败时，已写入的文件恢复原来的内容，项目要么全部替换，要么原封不动
+fn apply_pending(pending: &mut [PendingFile]) -> bool {
+    let mut written = 0;
+    for file in pending.iter_mut() {
+        if let Err(e) = write_atomic(&file.path, file.new_content.as_bytes()) {
+            file.result.error = Some(format!("无法写入文件: {}", e));
+            break;
+        }
+        written += 1;
+    }
+    if written == pending.len() {
+        for file in pending.iter_mut() {
+            file.result.applied = true;
+        }
+        return true;
+    }
+
+    for file in &mut pending[..written] {
+        if let Err(e) = write_atomic(&file.path, file.content.as_bytes()) {
+            // 无法恢复：保留替换后的内容
+            file.result.applied = true;
+            file.result.error = Some(format!("无法写入文件: {}", e));
+        }
+    }
+    false
+}
+
+#[command]
+pub async fn replace_in_project(
+    scope: WindowScope,
+    root: String,
+    pattern: String,
+    replacement: String,
+    options: Option<ReplaceOptions>,
+) -> Result<ReplaceReport, String> {
+    scope.check(&root)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let options = options.unwrap_or_default();
+        let root = PathBuf::from(root);
+        if !root.is_dir() {
+            return Err(format!("无法读取目录: {}", root.to_string_lossy()));
+        }
+        let matcher = build_matcher(&pattern, &options.search)?;
+
+        let mut pending = Vec::new();
+        for entry in project_walker(&root).build() {
+            let Ok(entry) = entry else {
+                continue;
+            };
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            if let Some(file) = plan_file(entry.path(), &matcher, &replacement, options.search.regex) {
+                pending.push(file);
+            }
+        }
+        pending.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let applied = options.apply && !pending.is_empty() && apply_pending(&mut pending);
+        Ok(ReplaceReport {
+            files: pending.into_iter().map(|file| file.result).collect(),
+            applied,
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+
+use tauri::ipc::{CommandArg, CommandItem, InvokeError};
+use tauri::{command, AppHandle, Manager, Wry};
+use tauri_plugin_dialog::{DialogExt, FilePath};
+
+use crate::latex::root::canonical;
+
+/// 本次会话中用户打开过的文件夹和文件。文件系统命令只访问其中的路径，即使 webview 被攻破
+/// 也读写不到别处。路径只能通过下面的原生对话框、恢复会话和重新打开最近项目加入，不接受前端直接传入。
+/// 每个窗口有自己的范围，与 watcher 一样按窗口标签区分，在一个窗口打开的文件夹另一个窗口访问不到。
+#[derive(Default)]
+pub struct FsScope {
+    windows: Mutex<HashMap<String, Vec<Root>>>,
+}
+
+struct Root {
+    /// 已规范化
+    path: PathBuf,
+    is_dir: bool,
+}
+
+/// `path` 的规范形式，`path` 可以尚不存在（即将创建的文件）。不存在的部分不能含 `..`，
+/// 否则要到检查之后才会被解析
+fn resolve(path: &Path) -> Option<PathBuf> {
+    if !path.is_absolute() {
+        return None;
+    }
+    let existing = path.ancestors().find(|ancestor| ancestor.exists())?;
+    let tail = path.strip_prefix(existing).ok()?;
+    if tail.components().any(|c| !matches!(c, Component::Normal(_))) {
+        return None;
+    }
+    Some(canonical(existing).join(tail))
+}
+
+impl FsScope {
+    pub fn allow(&self, window: &str, path: &Path) {
+        let path = resolve(path).unwrap_or_else(|| canonical(path));
+        let is_dir = path.is_dir();
+        let mut windows = self.windows.lock().unwrap();
+        let roots = windows.entry(window.to_string()).or_default();
+        if !roots.iter().any(|root| root.path == path) {
+            roots.push(Root { path, is_dir });
+        }
+    }
+
+    pub fn allows(&self, window: &str, path: &Path) -> bool {
+        let Some(path) = resolve(path) else {
+            return false;
+        };
+        self.windows.lock().unwrap().get(window).is_some_and(|roots| {
+            roots.iter().any(|root| if root.is_dir { path.starts_with(&root.path) } else { path == root.path })
+        })
+    }
+
+    /// 窗口关闭时忘掉授予它的路径；同一标签的窗口重新打开后从会话重新恢复
+    pub fn remove_window(&self, window: &str) {
+        self.windows.lock().unwrap().remove(window);
+    }
+}
+
+/// 单个窗口的访问范围。命令用它代替 `State<FsScope>`，拿到的是调用命令的窗口的范围
+#[derive(Clone)]
+pub struct WindowScope {
+    app: AppHandle,
+    window: String,
+}
+
+impl WindowScope {
+    pub fn new(app: &AppHandle, window: &str) -> Self {
+        WindowScope { app: app.clone(), window: window.to_string() }
+    }
+
+    /// 所属窗口的标签
+    pub fn window(&self) -> &str {
+        &self.window
+    }
+
+    pub fn allow(&self, path: &Path) {
+        self.app.state::<FsScope>().allow(&self.window, path);
+    }
+
+    pub fn allows(&self, path: &Path) -> bool {
+        self.app.state::<FsScope>().allows(&self.window, path)
+    }
+
+    /// `path` 不在范围内时返回给前端的错误
+    pub fn check(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        if self.allows(path) {
+            Ok(())
+        } else {
+            Err(format!("拒绝访问: {} 不在已打开的文件夹内", path.to_string_lossy()))
+        }
+    }
+
+    /// 供也能处理未保存文档（没有路径）的命令使用的 `check`
+    pub fn check_optional(&self, path: Option<impl AsRef<Path>>) -> Result<(), String> {
+        path.map_or(Ok(()), |path| self.check(path))
+    }
+}
+
+impl<'de> CommandArg<'de, Wry> for WindowScope {
+    fn from_command(command: CommandItem<'de, Wry>) -> Result<Self, InvokeError> {
+        let webview = command.message.webview_ref();
+        Ok(WindowScope::new(webview.app_handle(), webview.window().label()))
+    }
+}
+
+/// 在异步运行时之外弹出阻塞对话框，并把选中的路径加入范围
+async fn pick(
+    app: AppHandle,
+    scope: WindowScope,
+    show: impl FnOnce(&AppHandle) -> Option<FilePath> + Send + 'static,
+) -> Result<Option<String>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let Some(picked) = show(&app) else {
+            return Ok(None);
+        };
+        let path = picked.into_path().map_err(|e| e.to_string())?;
+        scope.allow(&path);
+        Ok(Some(path.to_string_lossy().to_string()))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[command]
+pub async fn open_file_dialog(app: AppHandle, scope: WindowScope) -> Result<Option<String>, String> {
+    pick(app, scope, |app| app.dialog().file().add_filter("LaTeX", &["tex"]).blocking_pick_file()).await
+}
+
+#[command]
+pub async fn open_folder_dialog(app: AppHandle, scope: WindowScope) -> Result<Option<String>, String> {
+    pick(app, scope, |app| app.dialog().file().blocking_pick_folder()).await
+}
+
+/// 选中的文件即使尚不存在也加入范围
+#[command]
+pub async fn save_file_dialog(app: AppHandle, scope: WindowScope) -> Result<Option<String>, String> {
+    pick(app, scope, |app| app.dialog().file().add_filter("LaTeX", &["tex"]).blocking_save_file()).await
+}
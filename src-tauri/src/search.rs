@@ -0,0 +1,165 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use ignore::WalkState;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::scope::WindowScope;
+use crate::workspace::{looks_binary, project_walker};
+
+const DEFAULT_MAX_RESULTS: usize = 2000;
+const MAX_SNIPPET_CHARS: usize = 240;
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct SearchOptions {
+    /// 把查询当作正则表达式而不是普通字符串
+    pub regex: bool,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub max_results: Option<usize>,
+    /// 每个匹配前后各返回多少行上下文
+    pub context_lines: usize,
+}
+
+#[derive(Serialize)]
+pub struct SearchMatch {
+    pub file: String,
+    /// 行号，从 1 开始
+    pub line: u32,
+    /// 列号，从 1 开始，按字符计
+    pub column: u32,
+    /// 匹配的长度，按字符计
+    pub length: u32,
+    pub snippet: String,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct SearchResults {
+    pub matches: Vec<SearchMatch>,
+    /// 达到 `max_results` 提前停止遍历时为 true
+    pub truncated: bool,
+}
+
+pub fn build_matcher(query: &str, options: &SearchOptions) -> Result<Regex, String> {
+    if query.is_empty() {
+        return Err("搜索内容为空".to_string());
+    }
+    let pattern = if options.regex { query.to_string() } else { regex::escape(query) };
+    let pattern = if options.whole_word { format!(r"\b(?:{})\b", pattern) } else { pattern };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .build()
+        .map_err(|e| format!("无效的搜索模式: {}", e))
+}
+
+/// 按（有损的）UTF-8 文本读取文件，看起来是二进制时返回 `None`
+pub fn read_text_file(path: &Path) -> Option<String> {
+    let bytes = fs::read(path).ok()?;
+    if looks_binary(&bytes) {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&bytes).to_string())
+}
+
+pub fn truncate_snippet(text: &str) -> String {
+    if text.chars().count() <= MAX_SNIPPET_CHARS {
+        text.to_string()
+    } else {
+        let mut snippet: String = text.chars().take(MAX_SNIPPET_CHARS).collect();
+        snippet.push('…');
+        snippet
+    }
+}
+
+fn search_file(path: &Path, matcher: &Regex, context_lines: usize) -> Vec<SearchMatch> {
+    let Some(content) = read_text_file(path) else {
+        return Vec::new();
+    };
+    let lines: Vec<&str> = content.lines().collect();
+    let mut matches = Vec::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        for found in matcher.find_iter(line) {
+            let before_start = index.saturating_sub(context_lines);
+            let after_end = (index + 1 + context_lines).min(lines.len());
+            matches.push(SearchMatch {
+                file: path.to_string_lossy().to_string(),
+                line: index as u32 + 1,
+                column: line[..found.start()].chars().count() as u32 + 1,
+                length: found.as_str().chars().count() as u32,
+                snippet: truncate_snippet(line),
+                before: lines[before_start..index].iter().map(|l| truncate_snippet(l)).collect(),
+                after: lines[index + 1..after_end].iter().map(|l| truncate_snippet(l)).collect(),
+            });
+        }
+    }
+    matches
+}
+
+pub fn search_root(root: &Path, query: &str, options: &SearchOptions) -> Result<SearchResults, String> {
+    if !root.is_dir() {
+        return Err(format!("无法读取目录: {}", root.to_string_lossy()));
+    }
+    let matcher = build_matcher(query, options)?;
+    let max_results = options.max_results.unwrap_or(DEFAULT_MAX_RESULTS);
+
+    let found = Mutex::new(Vec::<SearchMatch>::new());
+    let count = AtomicUsize::new(0);
+    let truncated = AtomicBool::new(false);
+
+    project_walker(root).build_parallel().run(|| {
+        Box::new(|entry| {
+            if truncated.load(Ordering::Relaxed) {
+                return WalkState::Quit;
+            }
+            let Ok(entry) = entry else {
+                return WalkState::Continue;
+            };
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                return WalkState::Continue;
+            }
+            let file_matches = search_file(entry.path(), &matcher, options.context_lines);
+            if file_matches.is_empty() {
+                return WalkState::Continue;
+            }
+            let total = count.fetch_add(file_matches.len(), Ordering::Relaxed) + file_matches.len();
+            found.lock().unwrap().extend(file_matches);
+            if total >= max_results {
+                truncated.store(true, Ordering::Relaxed);
+                return WalkState::Quit;
+            }
+            WalkState::Continue
+        })
+    });
+
+    let mut matches = found.into_inner().unwrap();
+    matches.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)).then(a.column.cmp(&b.column)));
+    matches.truncate(max_results);
+
+    Ok(SearchResults {
+        matches,
+        truncated: truncated.load(Ordering::Relaxed),
+    })
+}
+
+#[command]
+pub async fn search_project(
+    scope: WindowScope,
+    root: String,
+    query: String,
+    options: Option<SearchOptions>,
+) -> Result<SearchResults, String> {
+    scope.check(&root)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        search_root(&PathBuf::from(root), &query, &options.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,71 @@
+use keyring::{Entry, Error};
+use serde::Deserialize;
+use tauri::command;
+
+/// 每个密钥都以这个前缀加上功能的服务名存在系统钥匙串中（macOS 的钥匙串、Windows 的凭据管理器、Linux 的 Secret Service）
+const KEYCHAIN_PREFIX: &str = "MyMD-IDE";
+
+/// 存放密钥的功能。前端只能写入和删除这些服务下的条目，读取只在后端需要凭据的地方进行
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Service {
+    /// HTTPS 令牌，按主机名区分
+    Git,
+    /// SSH 私钥口令，按私钥路径区分
+    GitSsh,
+    /// 远程编译 HTTP 服务的令牌，按 `host` 区分
+    RemoteBuild,
+    /// WebDAV 同步的密码，按服务器 URL 区分；只由 `configure_remote` 设置
+    #[serde(skip_deserializing)]
+    Webdav,
+}
+
+impl Service {
+    fn name(self) -> &'static str {
+        match self {
+            Service::Git => "git",
+            Service::GitSsh => "git_ssh",
+            Service::RemoteBuild => "remote_build",
+            Service::Webdav => "webdav",
+        }
+    }
+}
+
+fn entry(service: Service, key: &str) -> Result<Entry, String> {
+    if key.is_empty() {
+        return Err("密钥需要键".to_string());
+    }
+    Entry::new(&format!("{}/{}", KEYCHAIN_PREFIX, service.name()), key).map_err(|e| format!("钥匙串不可用: {}", e))
+}
+
+pub fn store(service: Service, key: &str, value: &str) -> Result<(), String> {
+    entry(service, key)?.set_password(value).map_err(|e| format!("无法保存密钥: {}", e))
+}
+
+/// `service`/`key` 下没有存储内容时为 None
+pub fn get(service: Service, key: &str) -> Result<Option<String>, String> {
+    match entry(service, key)?.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("无法读取密钥: {}", e)),
+    }
+}
+
+pub fn delete(service: Service, key: &str) -> Result<(), String> {
+    match entry(service, key)?.delete_credential() {
+        Ok(()) | Err(Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("无法删除密钥: {}", e)),
+    }
+}
+
+/// 把令牌或口令存在系统钥匙串而不是配置文件中。`service` 指定功能（`git`、`git_ssh`、`remote_build`），
+/// `key` 指定主机或私钥。只写不读：存进去的值只有后端在连接时取用
+#[command]
+pub async fn store_secret(service: Service, key: String, value: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || store(service, &key, &value)).await.map_err(|e| e.to_string())?
+}
+
+#[command]
+pub async fn delete_secret(service: Service, key: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || delete(service, &key)).await.map_err(|e| e.to_string())?
+}
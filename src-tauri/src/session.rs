@@ -0,0 +1,111 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Manager, State, Window};
+
+use crate::atomic::write_atomic;
+use crate::scope::WindowScope;
+use crate::windows::{ProjectWindows, MAIN_WINDOW};
+
+const SESSION_FILE: &str = "session.json";
+/// 项目窗口的 `<app data>/sessions/<label>.json`
+const WINDOW_SESSIONS_DIR: &str = "sessions";
+
+/// 编辑器在一个缓冲区中停下的位置。行和列从 1 开始，滚动偏移以像素计，都与 Monaco 报告的一致
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct ViewState {
+    line: u32,
+    column: u32,
+    scroll_top: f64,
+    scroll_left: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct OpenFile {
+    path: String,
+    #[serde(default)]
+    view: ViewState,
+}
+
+/// 从未保存的缓冲区；其内容只存在会话中
+#[derive(Serialize, Deserialize)]
+pub struct UntitledBuffer {
+    /// 来自 `create_untitled_workspace`，它也决定了临时编译目录的名称
+    id: String,
+    content: String,
+    #[serde(default)]
+    view: ViewState,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Session {
+    open_folder: Option<String>,
+    /// 按标签页顺序
+    open_files: Vec<OpenFile>,
+    untitled: Vec<UntitledBuffer>,
+    /// 打开的文件的路径或未命名缓冲区的 id
+    active_tab: Option<String>,
+}
+
+/// 主窗口使用 `session.json`；每个项目窗口有自己的文件，两个窗口不会互相覆盖标签页
+fn session_path(app: &AppHandle, label: &str) -> Result<PathBuf, String> {
+    let base = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    if label == MAIN_WINDOW {
+        return Ok(base.join(SESSION_FILE));
+    }
+    Ok(base.join(WINDOW_SESSIONS_DIR).join(format!("{}.json", label)))
+}
+
+/// 前端在状态改变时调用（在前端防抖），即使应用被强制结束，文件也是最新的。作用域外的路径会被丢弃：
+/// `load_session` 会为它恢复的所有内容授予访问权限
+#[command]
+pub fn save_session(app: AppHandle, window: Window, scope: WindowScope, mut state: Session) -> Result<(), String> {
+    state.open_folder = state.open_folder.filter(|folder| scope.allows(Path::new(folder)));
+    state.open_files.retain(|file| scope.allows(Path::new(&file.path)));
+    let path = session_path(&app, window.label())?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("无法创建目录: {}", e))?;
+    }
+    let content = serde_json::to_string(&state).map_err(|e| e.to_string())?;
+    write_atomic(&path, content.as_bytes()).map_err(|e| format!("无法写入文件: {}", e))?;
+    Ok(())
+}
+
+/// 上次保存的会话，去掉之后被删除或移动的文件和文件夹；第一次运行时为空会话。未命名缓冲区总是保留。
+/// 恢复的文件夹和文件会加入作用域。项目窗口总是打开它为之创建的文件夹
+#[command]
+pub fn load_session(
+    app: AppHandle,
+    window: Window,
+    scope: WindowScope,
+    windows: State<'_, ProjectWindows>,
+) -> Result<Session, String> {
+    let path = session_path(&app, window.label())?;
+    let mut session: Session = match fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring invalid {}: {}", path.to_string_lossy(), e);
+            Session::default()
+        }),
+        Err(_) => Session::default(),
+    };
+    if let Some(root) = windows.root_of(window.label()) {
+        let root = root.to_string_lossy().to_string();
+        if session.open_folder.as_ref() != Some(&root) {
+            session = Session { open_folder: Some(root), ..Session::default() };
+        }
+    }
+
+    session.open_folder = session.open_folder.filter(|folder| Path::new(folder).is_dir());
+    session.open_files.retain(|file| Path::new(&file.path).is_file());
+    let tab_exists = |tab: &String| {
+        session.open_files.iter().any(|file| &file.path == tab) || session.untitled.iter().any(|buffer| &buffer.id == tab)
+    };
+    session.active_tab = session.active_tab.take().filter(tab_exists);
+    for path in session.open_folder.iter().chain(session.open_files.iter().map(|file| &file.path)) {
+        scope.allow(Path::new(path));
+    }
+    Ok(session)
+}
@@ -0,0 +1,140 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tauri::{command, AppHandle, Emitter, Manager, State};
+
+use crate::atomic::write_atomic;
+use crate::compiler::{DockerBuildConfig, EngineKind, RemoteBuildConfig};
+use crate::document::LineEnding;
+
+const SETTINGS_FILE: &str = "settings.json";
+/// 每次更新后带着完整的设置发送给所有窗口
+pub const SETTINGS_CHANGED_EVENT: &str = "settings-changed";
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Settings {
+    /// `dark`、`light` 或 `system`
+    pub theme: String,
+    /// 自动保存未保存文件的间隔秒数；0 关闭自动保存
+    pub autosave_interval: u32,
+    /// 请求和项目配置都没有指定引擎时使用
+    pub default_engine: EngineKind,
+    /// 编译最多运行的秒数，超时后结束进程；0 表示一直等待
+    pub compile_timeout: u32,
+    /// 预编译 pdfLaTeX 文档的导言区，导言区不变时复用
+    pub preamble_cache: bool,
+    /// 代替 `PATH` 上的 tectonic 运行的程序
+    pub tectonic_path: Option<String>,
+    /// `remote` 引擎的编译服务器，用于装不了 TeX 引擎的机器
+    pub remote_build: Option<RemoteBuildConfig>,
+    /// `docker` 引擎的容器镜像和运行时；不设置时用 `docker` 运行官方 TeX Live 镜像
+    pub docker_build: Option<DockerBuildConfig>,
+    /// 最近的在前
+    pub recent_projects: Vec<String>,
+    /// 保存时把文件转换成的换行符；None 保留每个文件自己的
+    pub line_ending: Option<LineEnding>,
+    /// `submit_crash_report` 提交报告的地址。不设置时不会上传任何内容
+    pub crash_report_url: Option<String>,
+    /// 这个版本不认识的键，保留下来，以免保存时丢掉更新版本写入的设置
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: "dark".to_string(),
+            autosave_interval: 0,
+            default_engine: EngineKind::default(),
+            compile_timeout: 300,
+            preamble_cache: true,
+            tectonic_path: None,
+            remote_build: None,
+            docker_build: None,
+            recent_projects: Vec::new(),
+            line_ending: None,
+            crash_report_url: None,
+            extra: Map::new(),
+        }
+    }
+}
+
+/// 设置文件在第一次使用时读取并在会话中缓存；所有写入都经过 `update_settings`
+#[derive(Default)]
+pub struct SettingsStore {
+    settings: Mutex<Option<Settings>>,
+}
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let base = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(base.join(SETTINGS_FILE))
+}
+
+/// 文件不存在时使用默认值；文件损坏时也是，以免一次错误的编辑让应用无法启动。下次更新会覆盖它
+fn read_settings(app: &AppHandle) -> Settings {
+    let Ok(content) = settings_path(app).and_then(|path| fs::read_to_string(path).map_err(|e| e.to_string())) else {
+        return Settings::default();
+    };
+    serde_json::from_str(&content).unwrap_or_else(|e| {
+        tracing::warn!("Ignoring invalid {}: {}", SETTINGS_FILE, e);
+        Settings::default()
+    })
+}
+
+fn write_settings(app: &AppHandle, settings: &Settings) -> Result<(), String> {
+    let path = settings_path(app)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("无法创建目录: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    write_atomic(&path, content.as_bytes()).map_err(|e| format!("无法写入文件: {}", e))?;
+    Ok(())
+}
+
+impl SettingsStore {
+    pub fn get(&self, app: &AppHandle) -> Settings {
+        self.settings.lock().unwrap().get_or_insert_with(|| read_settings(app)).clone()
+    }
+
+    /// 把 `partial` 浅合并到当前设置中，保存并广播给所有窗口。值为 `null` 的键恢复默认值
+    pub fn update(&self, app: &AppHandle, partial: Map<String, Value>) -> Result<Settings, String> {
+        let mut cached = self.settings.lock().unwrap();
+        let current = cached.get_or_insert_with(|| read_settings(app));
+        let Value::Object(mut merged) = serde_json::to_value(&*current).map_err(|e| e.to_string())? else {
+            unreachable!("settings serialize to an object");
+        };
+        for (key, value) in partial {
+            if value.is_null() {
+                merged.remove(&key);
+            } else {
+                merged.insert(key, value);
+            }
+        }
+        let updated: Settings =
+            serde_json::from_value(Value::Object(merged)).map_err(|e| format!("无效的设置: {}", e))?;
+        write_settings(app, &updated)?;
+        *current = updated.clone();
+        drop(cached);
+        app.emit(SETTINGS_CHANGED_EVENT, &updated).map_err(|e| e.to_string())?;
+        Ok(updated)
+    }
+}
+
+#[command]
+pub fn get_settings(app: AppHandle, store: State<'_, SettingsStore>) -> Settings {
+    store.get(&app)
+}
+
+/// 应用 `partial` 中的键，保存并以 `settings-changed` 广播给所有窗口。返回完整的设置
+#[command]
+pub fn update_settings(
+    app: AppHandle,
+    store: State<'_, SettingsStore>,
+    partial: Map<String, Value>,
+) -> Result<Settings, String> {
+    store.update(&app, partial)
+}
@@ -0,0 +1,189 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Manager, State};
+
+use crate::atomic::{unix_millis, write_atomic};
+use crate::scope::WindowScope;
+
+/// 放在应用数据而不是应用资源中，重装后也还在；用普通 JSON，可以原样同步或导出
+const SNIPPETS_FILE: &str = "snippets.json";
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SnippetScope {
+    Latex,
+    Markdown,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Snippet {
+    id: String,
+    /// 接受补全前输入的词，例如 `fig`
+    pub trigger: String,
+    /// Monaco 片段语法：`$1`、`${1:default}`、`$0`
+    pub body: String,
+    pub scope: SnippetScope,
+    #[serde(default)]
+    pub description: String,
+    /// Unix 毫秒
+    #[serde(default)]
+    updated_at: u64,
+}
+
+/// 第一次使用时从磁盘加载，按创建顺序排列
+#[derive(Default)]
+pub struct Snippets {
+    entries: Mutex<Option<Vec<Snippet>>>,
+}
+
+fn snippets_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let base = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(base.join(SNIPPETS_FILE))
+}
+
+fn parse_snippets(path: &Path) -> Result<Vec<Snippet>, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("无法读取文件: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
+
+fn write_snippets(path: &Path, entries: &[Snippet]) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("无法创建目录: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(entries).map_err(|e| e.to_string())?;
+    write_atomic(path, content.as_bytes()).map_err(|e| format!("无法写入文件: {}", e))?;
+    Ok(())
+}
+
+/// 基于毫秒，跳过任何已占用的 id
+fn new_id(entries: &[Snippet]) -> String {
+    let mut millis = unix_millis(SystemTime::now());
+    while entries.iter().any(|s| s.id == format!("{:x}", millis)) {
+        millis += 1;
+    }
+    format!("{:x}", millis)
+}
+
+/// 添加 `snippet`；已有相同触发词和作用域的片段时，替换其内容和描述
+fn upsert(entries: &mut Vec<Snippet>, snippet: Snippet) -> Snippet {
+    match entries.iter_mut().find(|s| s.trigger == snippet.trigger && s.scope == snippet.scope) {
+        Some(existing) => {
+            existing.body = snippet.body;
+            existing.description = snippet.description;
+            existing.updated_at = snippet.updated_at;
+            existing.clone()
+        }
+        None => {
+            entries.push(snippet.clone());
+            snippet
+        }
+    }
+}
+
+impl Snippets {
+    /// 对列表运行 `edit` 并保存结果
+    fn edit<T>(&self, app: &AppHandle, edit: impl FnOnce(&mut Vec<Snippet>) -> T) -> Result<T, String> {
+        let path = snippets_path(app)?;
+        let mut cached = self.entries.lock().unwrap();
+        let entries = cached.get_or_insert_with(|| parse_snippets(&path).unwrap_or_default());
+        let result = edit(entries);
+        write_snippets(&path, entries)?;
+        Ok(result)
+    }
+
+    /// 一个作用域的片段，用于补全
+    pub fn in_scope(&self, app: &AppHandle, scope: SnippetScope) -> Vec<Snippet> {
+        let mut cached = self.entries.lock().unwrap();
+        let entries = cached
+            .get_or_insert_with(|| snippets_path(app).and_then(|path| parse_snippets(&path)).unwrap_or_default());
+        entries.iter().filter(|s| s.scope == scope).cloned().collect()
+    }
+}
+
+/// 按触发词排序的用户片段；`scope` 限定为 LaTeX 或 Markdown
+#[command]
+pub fn list_snippets(app: AppHandle, snippets: State<'_, Snippets>, scope: Option<SnippetScope>) -> Vec<Snippet> {
+    let path = snippets_path(&app);
+    let mut cached = snippets.entries.lock().unwrap();
+    let entries = cached.get_or_insert_with(|| path.and_then(|path| parse_snippets(&path)).unwrap_or_default());
+    let mut list: Vec<Snippet> = entries.iter().filter(|s| scope.is_none_or(|scope| s.scope == scope)).cloned().collect();
+    list.sort_by(|a, b| a.trigger.cmp(&b.trigger));
+    list
+}
+
+/// 创建片段，或覆盖相同触发词和作用域的片段。返回带 id 的片段
+#[command]
+pub fn save_snippet(
+    app: AppHandle,
+    snippets: State<'_, Snippets>,
+    trigger: String,
+    body: String,
+    scope: SnippetScope,
+    description: Option<String>,
+) -> Result<Snippet, String> {
+    let trigger = trigger.trim().to_string();
+    if trigger.is_empty() || trigger.contains(char::is_whitespace) {
+        return Err(format!("无效的片段触发词: `{}`", trigger));
+    }
+    snippets.edit(&app, |entries| {
+        let snippet = Snippet {
+            id: new_id(entries),
+            trigger,
+            body,
+            scope,
+            description: description.unwrap_or_default(),
+            updated_at: unix_millis(SystemTime::now()),
+        };
+        upsert(entries, snippet)
+    })
+}
+
+#[command]
+pub fn delete_snippet(app: AppHandle, snippets: State<'_, Snippets>, id: String) -> Result<(), String> {
+    let removed = snippets.edit(&app, |entries| {
+        let before = entries.len();
+        entries.retain(|s| s.id != id);
+        before - entries.len()
+    })?;
+    if removed == 0 {
+        return Err(format!("没有这个片段: {}", id));
+    }
+    Ok(())
+}
+
+/// 把所有片段写入 `path`，例如一个同步的文件夹
+#[command]
+pub fn export_snippets(
+    app: AppHandle,
+    snippets: State<'_, Snippets>,
+    scope: WindowScope,
+    path: String,
+) -> Result<(), String> {
+    scope.check(&path)?;
+    let entries = list_snippets(app, snippets, None);
+    write_snippets(Path::new(&path), &entries)
+}
+
+/// 合并导出文件中的片段；触发词和作用域已存在的替换本地片段。返回读到的数量
+#[command]
+pub fn import_snippets(
+    app: AppHandle,
+    snippets: State<'_, Snippets>,
+    scope: WindowScope,
+    path: String,
+) -> Result<usize, String> {
+    scope.check(&path)?;
+    let imported = parse_snippets(Path::new(&path))?;
+    let count = imported.len();
+    snippets.edit(&app, |entries| {
+        for mut snippet in imported {
+            snippet.id = new_id(entries);
+            upsert(entries, snippet);
+        }
+    })?;
+    Ok(count)
+}
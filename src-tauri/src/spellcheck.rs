@@ -0,0 +1,445 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+
+use regex::Regex;
+use serde::Serialize;
+use tauri::{command, AppHandle, Manager};
+use zspell::Dictionary;
+
+use crate::atomic::write_atomic;
+
+/// 用户词典：每行一个词
+const USER_DICTIONARY: &str = "user.dic";
+/// 单个错词最多给出的建议数
+const MAX_SUGGESTIONS: usize = 5;
+
+/// 这些命令的第一个参数是键名、路径或选项，不是正文
+const NON_PROSE_ARGUMENT_COMMANDS: &[&str] = &[
+    "label", "ref", "eqref", "pageref", "autoref", "nameref", "cref", "Cref", "vref", "cite", "citep", "citet",
+    "citeauthor", "citeyear", "citealp", "parencite", "textcite", "autocite", "footcite", "nocite", "usepackage",
+    "RequirePackage", "documentclass", "input", "include", "includeonly", "subfile", "includegraphics",
+    "bibliography", "bibliographystyle", "addbibresource", "url", "href", "hypersetup", "graphicspath",
+    "newcommand", "renewcommand", "providecommand", "newenvironment", "renewenvironment", "newtheorem",
+    "setlength", "addtolength", "hspace", "vspace", "color", "textcolor", "colorbox", "definecolor", "pagestyle",
+    "thispagestyle", "lstinputlisting", "inputminted", "usetikzlibrary", "geometry", "setlist", "begin", "end",
+];
+/// 环境体整体跳过：公式与代码
+const SKIPPED_ENVIRONMENTS: &[&str] = &[
+    "equation", "equation*", "align", "align*", "gather", "gather*", "multline", "multline*", "flalign",
+    "flalign*", "alignat", "alignat*", "eqnarray", "eqnarray*", "math", "displaymath", "verbatim", "verbatim*",
+    "Verbatim", "lstlisting", "minted", "comment", "tikzpicture",
+];
+/// 其后第二个参数是列格式，也要跳过
+const COLUMN_SPEC_ENVIRONMENTS: &[&str] = &["tabular", "tabular*", "tabularx", "array", "longtable"];
+
+/// 字母开头结尾、中间可带撇号的词
+static WORD_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\p{L}+(?:['’]\p{L}+)*").unwrap());
+static BEGIN_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\\begin\s*\{([^}]*)\}").unwrap());
+static URL_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?:https?|ftp)://\S+|www\.\S+").unwrap());
+
+#[derive(Serialize)]
+pub struct Misspelling {
+    word: String,
+    line: u32,
+    /// 从 1 开始，按字符计
+    column: u32,
+    length: u32,
+    suggestions: Vec<String>,
+}
+
+/// 已加载的词典按语言缓存，构建一次 Hunspell 词典需要数百毫秒
+#[derive(Default)]
+pub struct SpellChecker {
+    dictionaries: Mutex<HashMap<String, Arc<Dictionary>>>,
+    /// None 表示尚未从磁盘读取
+    user_words: Mutex<Option<HashSet<String>>>,
+}
+
+fn user_dictionary_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let base = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(base.join("spelling").join(USER_DICTIONARY))
+}
+
+/// 查找顺序：应用数据目录下的 dictionaries，然后是系统的 Hunspell 目录
+fn dictionary_dirs(app: &AppHandle) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Ok(base) = app.path().app_data_dir() {
+        dirs.push(base.join("dictionaries"));
+    }
+    if let Ok(home) = app.path().home_dir() {
+        dirs.push(home.join("Library/Spelling"));
+        dirs.push(home.join(".local/share/hunspell"));
+    }
+    for dir in ["/usr/share/hunspell", "/usr/share/myspell", "/usr/share/myspell/dicts", "/Library/Spelling"] {
+        dirs.push(PathBuf::from(dir));
+    }
+    dirs
+}
+
+fn find_dictionary(app: &AppHandle, language: &str) -> Option<(PathBuf, PathBuf)> {
+    dictionary_dirs(app).into_iter().find_map(|dir| {
+        let aff = dir.join(format!("{}.aff", language));
+        let dic = dir.join(format!("{}.dic", language));
+        (aff.is_file() && dic.is_file()).then_some((aff, dic))
+    })
+}
+
+impl SpellChecker {
+    fn dictionary(&self, app: &AppHandle, language: &str) -> Result<Arc<Dictionary>, String> {
+        // 词典文件名使用 en_US 形式，前端可能传 en-US
+        let language = language.replace('-', "_");
+        if let Some(dictionary) = self.dictionaries.lock().unwrap().get(&language) {
+            return Ok(dictionary.clone());
+        }
+        let (aff, dic) = find_dictionary(app, &language)
+            .ok_or_else(|| format!("未找到 {} 的 Hunspell 词典（.aff/.dic），可放入应用数据目录的 dictionaries 文件夹", language))?;
+        let config = fs::read_to_string(&aff).map_err(|e| format!("无法读取词典: {}", e))?;
+        let words = fs::read_to_string(&dic).map_err(|e| format!("无法读取词典: {}", e))?;
+        let dictionary = zspell::builder()
+            .config_str(&config)
+            .dict_str(&words)
+            .build()
+            .map_err(|e| format!("词典解析失败: {}", e))?;
+        let dictionary = Arc::new(dictionary);
+        self.dictionaries.lock().unwrap().insert(language, dictionary.clone());
+        Ok(dictionary)
+    }
+
+    fn user_words(&self, app: &AppHandle) -> HashSet<String> {
+        let mut words = self.user_words.lock().unwrap();
+        words
+            .get_or_insert_with(|| {
+                user_dictionary_path(app)
+                    .and_then(|path| fs::read_to_string(path).map_err(|e| e.to_string()))
+                    .map(|content| content.lines().map(str::trim).filter(|w| !w.is_empty()).map(String::from).collect())
+                    .unwrap_or_default()
+            })
+            .clone()
+    }
+}
+
+/// 把不参与拼写检查的字节替换为空格，长度与换行保持不变，偏移可直接对应原文
+struct Mask {
+    bytes: Vec<u8>,
+}
+
+impl Mask {
+    fn new(source: &str) -> Self {
+        Mask { bytes: source.as_bytes().to_vec() }
+    }
+
+    fn blank(&mut self, start: usize, end: usize) {
+        // 结束位置落在多字节字符中间时延伸到字符末尾
+        let mut end = end.min(self.bytes.len());
+        while end < self.bytes.len() && self.bytes[end] & 0xC0 == 0x80 {
+            end += 1;
+        }
+        for byte in &mut self.bytes[start..end] {
+            if *byte != b'\n' {
+                *byte = b' ';
+            }
+        }
+    }
+
+    fn into_string(self) -> String {
+        // 只把完整的字符替换成 ASCII 空格，结果仍是合法 UTF-8
+        String::from_utf8(self.bytes).unwrap_or_default()
+    }
+}
+
+/// 从 `start`（指向 `open`）到匹配的 `close` 之后
+fn balanced_end(bytes: &[u8], start: usize, open: u8, close: u8) -> usize {
+    let mut depth = 0;
+    let mut i = start;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b if b == open => depth += 1,
+            b if b == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+fn skip_spaces(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() && (bytes[i] == b' ' || bytes[i] == b'\t') {
+        i += 1;
+    }
+    i
+}
+
+fn find_from(source: &str, from: usize, needle: &str) -> usize {
+    source[from..].find(needle).map_or(source.len(), |i| from + i + needle.len())
+}
+
+fn mask_latex(source: &str) -> String {
+    let bytes = source.as_bytes();
+    let mut mask = Mask::new(source);
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let end = source[i..].find('\n').map_or(bytes.len(), |n| i + n);
+                mask.blank(i, end);
+                i = end;
+            }
+            b'$' => {
+                let display = bytes.get(i + 1) == Some(&b'$');
+                let end = if display { find_from(source, i + 2, "$$") } else { skip_inline_math(bytes, i + 1) };
+                mask.blank(i, end);
+                i = end;
+            }
+            b'\\' => {
+                let name_end = i + 1 + bytes[i + 1..].iter().take_while(|b| b.is_ascii_alphabetic()).count();
+                if name_end == i + 1 {
+                    // `\(`、`\[` 开始公式，其余是 `\%` 之类的转义
+                    let end = match bytes.get(i + 1) {
+                        Some(b'(') => find_from(source, i + 2, "\\)"),
+                        Some(b'[') => find_from(source, i + 2, "\\]"),
+                        _ => (i + 2).min(bytes.len()),
+                    };
+                    mask.blank(i, end);
+                    i = end;
+                    continue;
+                }
+                let name = &source[i + 1..name_end];
+                let mut end = name_end;
+                if bytes.get(end) == Some(&b'*') {
+                    end += 1;
+                }
+                if name == "verb" {
+                    // `\verb|...|`：分隔符是紧跟的任意字符
+                    end = match bytes.get(end) {
+                        Some(&delimiter) => bytes[end + 1..]
+                            .iter()
+                            .position(|b| *b == delimiter)
+                            .map_or(bytes.len(), |n| end + 2 + n),
+                        None => end,
+                    };
+                } else if NON_PROSE_ARGUMENT_COMMANDS.contains(&name) {
+                    end = skip_arguments(bytes, end, 1);
+                    if name == "begin" {
+                        let env = BEGIN_RE.captures(&source[i..]).map_or("", |caps| caps.get(1).unwrap().as_str()).trim();
+                        if SKIPPED_ENVIRONMENTS.contains(&env) {
+                            end = find_from(source, end, &format!("\\end{{{}}}", env));
+                        } else if COLUMN_SPEC_ENVIRONMENTS.contains(&env) {
+                            end = skip_arguments(bytes, end, if env.ends_with('*') || env == "tabularx" { 2 } else { 1 });
+                        }
+                    }
+                }
+                mask.blank(i, end);
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+    mask.into_string()
+}
+
+/// 跳过可选参数 `[...]` 和 `count` 个必选参数 `{...}`
+fn skip_arguments(bytes: &[u8], mut i: usize, count: usize) -> usize {
+    let mut remaining = count;
+    loop {
+        let next = skip_spaces(bytes, i);
+        match bytes.get(next) {
+            Some(b'[') => i = balanced_end(bytes, next, b'[', b']'),
+            Some(b'{') if remaining > 0 => {
+                i = balanced_end(bytes, next, b'{', b'}');
+                remaining -= 1;
+            }
+            _ => return i,
+        }
+    }
+}
+
+/// 行内公式到下一个未转义的 `$`；跨段落（空行）视为未闭合
+fn skip_inline_math(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'$' => return i + 1,
+            b'\n' if bytes.get(i + 1) == Some(&b'\n') => return i,
+            _ => {}
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+fn mask_markdown(source: &str) -> String {
+    let bytes = source.as_bytes();
+    let mut mask = Mask::new(source);
+    let mut offset = 0;
+    let mut fence: Option<&str> = None;
+    let mut front_matter = source.starts_with("---\n") || source.starts_with("---\r\n");
+    for (index, line) in source.split_inclusive('\n').enumerate() {
+        let start = offset;
+        offset += line.len();
+        let trimmed = line.trim();
+        if front_matter {
+            mask.blank(start, offset);
+            if index > 0 && (trimmed == "---" || trimmed == "...") {
+                front_matter = false;
+            }
+            continue;
+        }
+        if let Some(marker) = fence {
+            mask.blank(start, offset);
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(&trimmed[..3]);
+            mask.blank(start, offset);
+            continue;
+        }
+        // 缩进四格的代码块
+        if line.starts_with("    ") || line.starts_with('\t') {
+            mask.blank(start, offset);
+        }
+    }
+
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'`' => {
+                let ticks = bytes[i..].iter().take_while(|b| **b == b'`').count();
+                let end = find_from(source, i + ticks, &"`".repeat(ticks));
+                mask.blank(i, end);
+                i = end;
+            }
+            b'$' => {
+                let display = bytes.get(i + 1) == Some(&b'$');
+                let end = if display { find_from(source, i + 2, "$$") } else { skip_inline_math(bytes, i + 1) };
+                mask.blank(i, end);
+                i = end;
+            }
+            // 链接目标 `](...)` 与 HTML 标签
+            b']' if bytes.get(i + 1) == Some(&b'(') => {
+                let end = balanced_end(bytes, i + 1, b'(', b')');
+                mask.blank(i, end);
+                i = end;
+            }
+            b'<' if bytes.get(i + 1).is_some_and(|b| b.is_ascii_alphabetic() || *b == b'/' || *b == b'!') => {
+                let end = source[i..].find('>').map_or(i + 1, |n| i + n + 1);
+                mask.blank(i, end);
+                i = end;
+            }
+            b'\\' => {
+                mask.blank(i, i + 2);
+                i += 2;
+            }
+            _ => i += 1,
+        }
+    }
+    let mut masked = mask.into_string();
+    let urls: Vec<(usize, usize)> = URL_RE.find_iter(&masked).map(|m| (m.start(), m.end())).collect();
+    let mut mask = Mask::new(&masked);
+    for (start, end) in urls {
+        mask.blank(start, end);
+    }
+    masked = mask.into_string();
+    masked
+}
+
+/// 非正文部分替换为空格后的文本，字节偏移与原文一致；`format` 为 `markdown` 时按 Markdown 处理，否则按 LaTeX
+pub fn mask_markup(source: &str, format: Option<&str>) -> String {
+    match format {
+        Some("markdown") => mask_markdown(source),
+        _ => mask_latex(source),
+    }
+}
+
+fn should_check(word: &str) -> bool {
+    // 单个字母和全大写的缩写不检查
+    word.chars().count() > 1 && !word.chars().all(|c| !c.is_lowercase())
+}
+
+/// 拼写错误的位置与建议；LaTeX 命令、公式、代码以及 Markdown 的代码块、链接都会跳过。
+#[command]
+pub async fn check_text(
+    app: AppHandle,
+    source: String,
+    language: String,
+    format: Option<String>,
+) -> Result<Vec<Misspelling>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let checker = app.state::<SpellChecker>();
+        let dictionary = checker.dictionary(&app, &language)?;
+        let user_words = checker.user_words(&app);
+        let masked = mask_markup(&source, format.as_deref());
+
+        let mut misspellings = Vec::new();
+        let mut line_start = 0;
+        let mut line_number = 1;
+        for word in WORD_RE.find_iter(&masked) {
+            let text = word.as_str();
+            if !should_check(text) || user_words.contains(text) || dictionary.check_word(text) {
+                continue;
+            }
+            let normalized = text.replace('’', "'");
+            if normalized != text && dictionary.check_word(&normalized) {
+                continue;
+            }
+            // 行号增量计算，避免每个词都从头数换行
+            let newlines = &source[line_start..word.start()];
+            if let Some(last) = newlines.rfind('\n') {
+                line_number += newlines.matches('\n').count() as u32;
+                line_start += last + 1;
+            }
+            let entry = dictionary.entry(text);
+            let suggestions = entry
+                .suggest()
+                .unwrap_or_default()
+                .into_iter()
+                .take(MAX_SUGGESTIONS)
+                .map(String::from)
+                .collect();
+            misspellings.push(Misspelling {
+                word: text.to_string(),
+                line: line_number,
+                column: source[line_start..word.start()].chars().count() as u32 + 1,
+                length: text.chars().count() as u32,
+                suggestions,
+            });
+        }
+        Ok(misspellings)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 把词加入用户词典，之后所有语言的检查都会接受它
+#[command]
+pub fn add_to_dictionary(app: AppHandle, word: String) -> Result<(), String> {
+    let word = word.trim().to_string();
+    if word.is_empty() || word.contains(char::is_whitespace) {
+        return Err(format!("无效的单词: {}", word));
+    }
+    let checker = app.state::<SpellChecker>();
+    let mut words = checker.user_words(&app);
+    if !words.insert(word) {
+        return Ok(());
+    }
+    let path = user_dictionary_path(&app)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("无法创建目录: {}", e))?;
+    }
+    let mut sorted: Vec<&String> = words.iter().collect();
+    sorted.sort();
+    let content: String = sorted.iter().map(|w| format!("{}\n", w)).collect();
+    write_atomic(Path::new(&path), content.as_bytes()).map_err(|e| format!("无法写入用户词典: {}", e))?;
+    *checker.user_words.lock().unwrap() = Some(words);
+    Ok(())
+}
@@ -0,0 +1,671 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use base64::Engine;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Manager};
+use ureq::http::{Request, Response};
+use ureq::{Agent, Body};
+
+use crate::atomic::write_atomic;
+use crate::document::content_version;
+use crate::project::config_dir;
+use crate::scope::WindowScope;
+use crate::secrets::{self, Service};
+use crate::workspace::{is_excluded, project_walker};
+
+/// 配置的服务器，存在应用配置目录中；密码以服务器 URL 存在系统钥匙串里
+const REMOTE_FILE: &str = "sync.json";
+/// 上次同步后两边的状态，存在项目的 `.mymd` 目录中，这个目录本身从不同步
+const STATE_FILE: &str = "sync-state.json";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_DOWNLOAD_SIZE: u64 = 256 * 1024 * 1024;
+
+const PROPFIND_BODY: &str = concat!(
+    r#"<?xml version="1.0" encoding="utf-8"?>"#,
+    r#"<d:propfind xmlns:d="DAV:"><d:prop><d:resourcetype/><d:getetag/>"#,
+    r#"<d:getlastmodified/><d:getcontentlength/></d:prop></d:propfind>"#,
+);
+
+/// Multistatus 响应的命名空间前缀由服务器决定（`d:`、`D:` 或没有），所以按本地名匹配元素
+static RESPONSE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<(?:[\w-]+:)?response\b[^>]*>(.*?)</(?:[\w-]+:)?response>").unwrap());
+static HREF_RE: LazyLock<Regex> = LazyLock::new(|| element_re("href"));
+static ETAG_RE: LazyLock<Regex> = LazyLock::new(|| element_re("getetag"));
+static MODIFIED_RE: LazyLock<Regex> = LazyLock::new(|| element_re("getlastmodified"));
+static LENGTH_RE: LazyLock<Regex> = LazyLock::new(|| element_re("getcontentlength"));
+static COLLECTION_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<(?:[\w-]+:)?collection\s*/?>").unwrap());
+
+fn element_re(name: &str) -> Regex {
+    Regex::new(&format!(r"(?s)<(?:[\w-]+:)?{0}\b[^>]*>(.*?)</(?:[\w-]+:)?{0}>", name)).unwrap()
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct SyncCredentials {
+    pub username: String,
+    /// 只存在钥匙串里，从不写入配置文件
+    #[serde(skip_serializing, default)]
+    pub password: String,
+}
+
+/// WebDAV 文件夹（Nextcloud 的形如 `https://host/remote.php/dav/files/<user>/<folder>/`）；
+/// 每个项目同步到其中的一个子文件夹，默认以项目目录命名
+#[derive(Serialize, Deserialize, Clone)]
+struct SyncRemote {
+    url: String,
+    credentials: Option<SyncCredentials>,
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncDirection {
+    /// 应用两边的改动；两边都改过的文件报告为冲突，不动它
+    #[default]
+    Both,
+    /// 只发送本地改动；冲突时以本地文件为准
+    Push,
+    /// 只接收远程改动；冲突时以远程文件为准
+    Pull,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+struct SyncedFile {
+    /// 本地文件的 `content_version`
+    hash: String,
+    etag: String,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct SyncState {
+    /// 项目文件夹的 URL；同步到别处时重新开始
+    remote: String,
+    /// 项目在服务器上的子文件夹名，第一次同步时选定，之后本地目录改名也不变
+    #[serde(default)]
+    folder: String,
+    files: BTreeMap<String, SyncedFile>,
+}
+
+#[derive(Serialize)]
+pub struct SyncConflict {
+    /// 相对项目根目录，以 `/` 分隔
+    path: String,
+    /// `both_modified`；服务器因文件在列出后又被修改而拒绝写入时为 `changed_during_sync`
+    reason: &'static str,
+}
+
+/// 路径相对项目根目录，以 `/` 分隔
+#[derive(Serialize, Default)]
+pub struct SyncReport {
+    uploaded: Vec<String>,
+    downloaded: Vec<String>,
+    deleted_local: Vec<String>,
+    deleted_remote: Vec<String>,
+    conflicts: Vec<SyncConflict>,
+}
+
+fn remote_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let base = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(base.join(REMOTE_FILE))
+}
+
+fn stored_remote(app: &AppHandle) -> Result<Option<SyncRemote>, String> {
+    Ok(fs::read_to_string(remote_path(app)?).ok().and_then(|content| serde_json::from_str(&content).ok()))
+}
+
+fn read_remote(app: &AppHandle) -> Result<SyncRemote, String> {
+    let mut remote = stored_remote(app)?.ok_or_else(|| "没有配置同步服务器".to_string())?;
+    if let Some(credentials) = &mut remote.credentials {
+        credentials.password = secrets::get(Service::Webdav, &remote.url)?.unwrap_or_default();
+    }
+    Ok(remote)
+}
+
+fn state_path(root: &Path) -> PathBuf {
+    config_dir(root).join(STATE_FILE)
+}
+
+fn read_state(root: &Path) -> SyncState {
+    fs::read_to_string(state_path(root))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write_state(root: &Path, state: &SyncState) -> Result<(), String> {
+    let path = state_path(root);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("无法创建目录: {}", e))?;
+    }
+    let content = serde_json::to_string(state).map_err(|e| e.to_string())?;
+    write_atomic(&path, content.as_bytes()).map_err(|e| format!("无法写入文件: {}", e))?;
+    Ok(())
+}
+
+fn encode_segment(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn decode_percent(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(std::str::from_utf8(h).ok()?, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// `href` 解码后的路径，服务器给出的可能是路径，也可能是完整 URL
+fn href_path(href: &str) -> String {
+    let href = decode_entities(href.trim());
+    let path = match href.find("://") {
+        Some(scheme) => href[scheme + 3..].find('/').map_or("/", |slash| &href[scheme + 3 + slash..]),
+        None => href.as_str(),
+    };
+    decode_percent(path)
+}
+
+/// 远程路径只有在项目内时才使用
+fn safe_relative(relative: &str) -> bool {
+    !relative.is_empty()
+        && !relative.contains('\\')
+        && relative.split('/').all(|part| !part.is_empty() && part != "." && part != "..")
+}
+
+/// `root` 下按相对路径排列的本地文件及其内容哈希
+fn local_files(root: &Path) -> Result<BTreeMap<String, String>, String> {
+    let mut files = BTreeMap::new();
+    for entry in project_walker(root).build().filter_map(Result::ok) {
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let Ok(relative) = entry.path().strip_prefix(root) else {
+            continue;
+        };
+        let relative: Vec<String> = relative.iter().map(|part| part.to_string_lossy().to_string()).collect();
+        let content = fs::read(entry.path()).map_err(|e| format!("无法读取文件: {}", e))?;
+        files.insert(relative.join("/"), content_version(&content));
+    }
+    Ok(files)
+}
+
+/// 项目在 WebDAV 服务器上的文件夹
+struct Dav {
+    agent: Agent,
+    /// 以 `/` 结尾
+    base: String,
+    authorization: Option<String>,
+}
+
+/// 列出项目文件夹得到的内容
+#[derive(Default)]
+struct Listing {
+    /// 文件路径 → ETag（服务器不提供 ETag 时为修改时间和大小）
+    files: BTreeMap<String, String>,
+    /// 子文件夹，包括记为 `""` 的项目文件夹本身
+    dirs: HashSet<String>,
+}
+
+impl Dav {
+    fn new(remote: &SyncRemote, folder: Option<&str>) -> Self {
+        let agent: Agent = Agent::config_builder()
+            .timeout_global(Some(REQUEST_TIMEOUT))
+            .allow_non_standard_methods(true)
+            .http_status_as_error(false)
+            .build()
+            .into();
+        let mut base = format!("{}/", remote.url.trim_end_matches('/'));
+        if let Some(folder) = folder {
+            base.push_str(&encode_segment(folder));
+            base.push('/');
+        }
+        let authorization = remote.credentials.as_ref().map(|credentials| {
+            let pair = format!("{}:{}", credentials.username, credentials.password);
+            format!("Basic {}", base64::engine::general_purpose::STANDARD.encode(pair))
+        });
+        Dav { agent, base, authorization }
+    }
+
+    fn url(&self, relative: &str) -> String {
+        let encoded: Vec<String> = relative.split('/').filter(|part| !part.is_empty()).map(encode_segment).collect();
+        format!("{}{}", self.base, encoded.join("/"))
+    }
+
+    fn send(
+        &self,
+        method: &str,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: &[u8],
+    ) -> Result<Response<Body>, String> {
+        let mut request = Request::builder().method(method).uri(url);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        if let Some(authorization) = &self.authorization {
+            request = request.header("Authorization", authorization);
+        }
+        let request = request.body(body.to_vec()).map_err(|e| e.to_string())?;
+        let response = self.agent.run(request).map_err(|e| format!("同步请求失败: {}", e))?;
+        match response.status().as_u16() {
+            401 | 403 => Err("同步服务器拒绝了登录凭据".to_string()),
+            _ => Ok(response),
+        }
+    }
+
+    fn propfind(&self, url: &str, depth: &str) -> Result<Option<String>, String> {
+        let headers = [("Depth", depth), ("Content-Type", "application/xml; charset=utf-8")];
+        let mut response = self.send("PROPFIND", url, &headers, PROPFIND_BODY.as_bytes())?;
+        match response.status().as_u16() {
+            207 => response.body_mut().read_to_string().map(Some).map_err(|e| e.to_string()),
+            404 => Ok(None),
+            status => Err(format!("PROPFIND {} 失败，HTTP {}", url, status)),
+        }
+    }
+
+    /// 基础文件夹下的所有文件和文件夹，每个文件夹发一次 `Depth: 1` 请求，因为许多服务器（包括 Nextcloud）
+    /// 拒绝 `Depth: infinity`。基础文件夹不存在时列为空
+    fn list(&self) -> Result<Listing, String> {
+        let mut listing = Listing::default();
+        let base_path = href_path(&self.base);
+        let mut pending = vec![String::new()];
+        while let Some(dir) = pending.pop() {
+            let url = if dir.is_empty() { self.base.clone() } else { format!("{}/", self.url(&dir)) };
+            let Some(body) = self.propfind(&url, "1")? else {
+                continue;
+            };
+            listing.dirs.insert(dir.clone());
+            for caps in RESPONSE_RE.captures_iter(&body) {
+                let response = &caps[1];
+                let Some(href) = HREF_RE.captures(response) else {
+                    continue;
+                };
+                let path = href_path(&href[1]);
+                let Some(relative) = path.strip_prefix(&base_path) else {
+                    continue;
+                };
+                let relative = relative.trim_matches('/').to_string();
+                if relative == dir || !safe_relative(&relative) || is_excluded(Path::new(&relative)) {
+                    continue;
+                }
+                if COLLECTION_RE.is_match(response) {
+                    if listing.dirs.insert(relative.clone()) {
+                        pending.push(relative);
+                    }
+                    continue;
+                }
+                let value = |re: &Regex| re.captures(response).map(|c| decode_entities(c[1].trim()));
+                let etag = value(&ETAG_RE).filter(|etag| !etag.is_empty()).unwrap_or_else(|| {
+                    format!("{}/{}", value(&MODIFIED_RE).unwrap_or_default(), value(&LENGTH_RE).unwrap_or_default())
+                });
+                listing.files.insert(relative, etag);
+            }
+        }
+        Ok(listing)
+    }
+
+    /// 文件当前的 ETag，用于 PUT 时不返回 ETag 的服务器
+    fn etag(&self, relative: &str) -> Result<String, String> {
+        let body = self.propfind(&self.url(relative), "0")?.unwrap_or_default();
+        Ok(ETAG_RE.captures(&body).map(|c| decode_entities(c[1].trim())).unwrap_or_default())
+    }
+
+    fn get(&self, relative: &str) -> Result<(Vec<u8>, Option<String>), String> {
+        let mut response = self.send("GET", &self.url(relative), &[], &[])?;
+        if response.status().as_u16() != 200 {
+            return Err(format!("下载 {} 失败，HTTP {}", relative, response.status().as_u16()));
+        }
+        let etag = header(&response, "ETag");
+        let content = response
+            .body_mut()
+            .with_config()
+            .limit(MAX_DOWNLOAD_SIZE)
+            .read_to_vec()
+            .map_err(|e| format!("下载 {} 失败: {}", relative, e))?;
+        Ok((content, etag))
+    }
+
+    /// 创建 `relative` 需要而 `dirs` 中没有的文件夹
+    fn ensure_parents(&self, relative: &str, dirs: &mut HashSet<String>) -> Result<(), String> {
+        let parts: Vec<&str> = relative.split('/').collect();
+        for depth in 0..parts.len() {
+            let dir = parts[..depth].join("/");
+            if dirs.contains(&dir) {
+                continue;
+            }
+            let url = if dir.is_empty() { self.base.clone() } else { format!("{}/", self.url(&dir)) };
+            let response = self.send("MKCOL", &url, &[], &[])?;
+            // 405：已经存在
+            match response.status().as_u16() {
+                200..=299 | 405 => {
+                    dirs.insert(dir);
+                }
+                status => return Err(format!("创建文件夹 {} 失败，HTTP {}", url, status)),
+            }
+        }
+        Ok(())
+    }
+
+    /// 远程文件仍与 `expected`（其 ETag，文件还不应存在时为 None）一致时上传；返回新的 ETag，服务器拒绝时为 None
+    fn put(&self, relative: &str, content: &[u8], expected: Option<&str>) -> Result<Option<String>, String> {
+        let condition = match expected {
+            Some(etag) => ("If-Match", etag),
+            None => ("If-None-Match", "*"),
+        };
+        let response = self.send("PUT", &self.url(relative), &[condition], content)?;
+        match response.status().as_u16() {
+            200..=299 => match header(&response, "ETag") {
+                Some(etag) => Ok(Some(etag)),
+                None => self.etag(relative).map(Some),
+            },
+            412 => Ok(None),
+            status => Err(format!("上传 {} 失败，HTTP {}", relative, status)),
+        }
+    }
+
+    /// 文件在列出之后被修改过时为 false
+    fn delete(&self, relative: &str, expected: &str) -> Result<bool, String> {
+        let response = self.send("DELETE", &self.url(relative), &[("If-Match", expected)], &[])?;
+        match response.status().as_u16() {
+            200..=299 | 404 => Ok(true),
+            412 => Ok(false),
+            status => Err(format!("删除 {} 失败，HTTP {}", relative, status)),
+        }
+    }
+}
+
+fn header(response: &Response<Body>, name: &str) -> Option<String> {
+    response.headers().get(name).and_then(|value| value.to_str().ok()).map(str::to_string)
+}
+
+/// 项目与其远程文件夹的一次同步
+struct ProjectSync<'a> {
+    root: &'a Path,
+    dav: Dav,
+    direction: SyncDirection,
+    state: SyncState,
+    dirs: HashSet<String>,
+    report: SyncReport,
+}
+
+impl ProjectSync<'_> {
+    fn conflict(&mut self, path: &str, reason: &'static str) {
+        self.report.conflicts.push(SyncConflict { path: path.to_string(), reason });
+    }
+
+    fn upload(&mut self, path: &str, expected: Option<&str>) -> Result<(), String> {
+        let content = fs::read(self.root.join(path)).map_err(|e| format!("无法读取文件: {}", e))?;
+        self.dav.ensure_parents(path, &mut self.dirs)?;
+        match self.dav.put(path, &content, expected)? {
+            Some(etag) => {
+                let synced = SyncedFile { hash: content_version(&content), etag };
+                self.state.files.insert(path.to_string(), synced);
+                self.report.uploaded.push(path.to_string());
+            }
+            None => self.conflict(path, "changed_during_sync"),
+        }
+        Ok(())
+    }
+
+    /// 调用方已下载文件时的 `content`
+    fn download(&mut self, path: &str, etag: &str, content: Option<Vec<u8>>) -> Result<(), String> {
+        let (content, fresh) = match content {
+            Some(content) => (content, None),
+            None => self.dav.get(path)?,
+        };
+        let target = self.root.join(path);
+        if let Some(dir) = target.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("无法创建目录: {}", e))?;
+        }
+        write_atomic(&target, &content).map_err(|e| format!("无法写入文件: {}", e))?;
+        let synced = SyncedFile { hash: content_version(&content), etag: fresh.unwrap_or_else(|| etag.to_string()) };
+        self.state.files.insert(path.to_string(), synced);
+        self.report.downloaded.push(path.to_string());
+        Ok(())
+    }
+
+    fn delete_remote(&mut self, path: &str, etag: &str) -> Result<(), String> {
+        if self.dav.delete(path, etag)? {
+            self.state.files.remove(path);
+            self.report.deleted_remote.push(path.to_string());
+        } else {
+            self.conflict(path, "changed_during_sync");
+        }
+        Ok(())
+    }
+
+    /// 移到回收站，在另一台机器上做的删除可以撤销
+    fn delete_local(&mut self, path: &str) -> Result<(), String> {
+        match trash::delete(self.root.join(path)) {
+            Ok(()) => {}
+            Err(_) if !self.root.join(path).exists() => {}
+            Err(e) => return Err(format!("无法删除 {}: {}", path, e)),
+        }
+        self.state.files.remove(path);
+        self.report.deleted_local.push(path.to_string());
+        Ok(())
+    }
+
+    /// 让远程的 `path` 与本地一致
+    fn push(&mut self, path: &str, local: Option<&String>, remote: Option<&String>) -> Result<(), String> {
+        match (local, remote) {
+            (Some(_), remote) => self.upload(path, remote.map(String::as_str)),
+            (None, Some(etag)) => self.delete_remote(path, etag),
+            (None, None) => {
+                self.state.files.remove(path);
+                Ok(())
+            }
+        }
+    }
+
+    /// 让本地的 `path` 与远程一致
+    fn pull(&mut self, path: &str, local: Option<&String>, remote: Option<&String>) -> Result<(), String> {
+        match (local, remote) {
+            (_, Some(etag)) => self.download(path, etag, None),
+            (Some(_), None) => self.delete_local(path),
+            (None, None) => {
+                self.state.files.remove(path);
+                Ok(())
+            }
+        }
+    }
+
+    /// 上次同步后两边都改过 `path`
+    fn reconcile(&mut self, path: &str, local: Option<&String>, remote: Option<&String>) -> Result<(), String> {
+        let (Some(hash), Some(etag)) = (local, remote) else {
+            // 一边修改、另一边删除：双向同步时保留修改过的文件
+            return match self.direction {
+                SyncDirection::Push => self.push(path, local, remote),
+                SyncDirection::Pull => self.pull(path, local, remote),
+                SyncDirection::Both if local.is_some() => self.push(path, local, remote),
+                SyncDirection::Both => self.pull(path, local, remote),
+            };
+        };
+        let (content, fresh) = self.dav.get(path)?;
+        let etag = fresh.unwrap_or_else(|| etag.clone());
+        // 通常是两边都已存在的项目的第一次同步
+        if content_version(&content) == *hash {
+            self.state.files.insert(path.to_string(), SyncedFile { hash: hash.clone(), etag });
+            return Ok(());
+        }
+        match self.direction {
+            SyncDirection::Push => self.upload(path, Some(&etag)),
+            SyncDirection::Pull => self.download(path, &etag, Some(content)),
+            SyncDirection::Both => {
+                self.conflict(path, "both_modified");
+                Ok(())
+            }
+        }
+    }
+
+    fn run(&mut self, local: &BTreeMap<String, String>, remote: &BTreeMap<String, String>) -> Result<(), String> {
+        let paths: BTreeSet<String> =
+            local.keys().chain(remote.keys()).chain(self.state.files.keys()).cloned().collect();
+        for path in paths {
+            let synced = self.state.files.get(&path).cloned();
+            let (local, remote) = (local.get(&path), remote.get(&path));
+            let local_changed = local != synced.as_ref().map(|synced| &synced.hash);
+            let remote_changed = remote != synced.as_ref().map(|synced| &synced.etag);
+            match (local_changed, remote_changed) {
+                (false, false) => {}
+                (true, false) if self.direction != SyncDirection::Pull => self.push(&path, local, remote)?,
+                (false, true) if self.direction != SyncDirection::Push => self.pull(&path, local, remote)?,
+                (true, true) => self.reconcile(&path, local, remote)?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+fn sync(
+    root: &Path,
+    remote: &SyncRemote,
+    direction: SyncDirection,
+    folder: Option<String>,
+) -> Result<SyncReport, String> {
+    let mut state = read_state(root);
+    let explicit = folder.is_some();
+    let folder = match folder {
+        Some(folder) => folder,
+        None if !state.folder.is_empty() => state.folder.clone(),
+        None => root.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default(),
+    };
+    if !safe_relative(&folder) || folder.contains('/') {
+        return Err(format!("无效的远程文件夹名: {}", folder));
+    }
+    let dav = Dav::new(remote, Some(&folder));
+    let known = state.remote == dav.base;
+    if !known {
+        state = SyncState { remote: dav.base.clone(), folder: String::new(), files: BTreeMap::new() };
+    }
+    state.folder = folder.clone();
+    let local = local_files(root)?;
+    let listing = dav.list()?;
+    // 同名的另一个项目可能已经占用了这个文件夹；与它合并之前要调用方明确指定
+    if !known && !explicit && !listing.files.is_empty() {
+        return Err(format!("服务器上已有文件夹 {}，本项目从未与它同步过；请指定要同步到的文件夹", folder));
+    }
+    let mut sync = ProjectSync { root, dav, direction, state, dirs: listing.dirs, report: SyncReport::default() };
+    // 记录失败之前完成的部分，下次同步不会把它误认为冲突
+    let result = sync.run(&local, &listing.files);
+    write_state(root, &sync.state)?;
+    result.map(|()| sync.report)
+}
+
+/// 设置项目同步到的 WebDAV 文件夹，先检查能用 `credentials` 列出它。`url` 为空表示断开。
+/// 带凭据时只接受 https，除非 `allow_insecure` 明确允许以明文发送密码
+#[command]
+pub async fn configure_remote(
+    app: AppHandle,
+    url: String,
+    credentials: Option<SyncCredentials>,
+    allow_insecure: Option<bool>,
+) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = remote_path(&app)?;
+        let url = url.trim().to_string();
+        let previous = stored_remote(&app)?.filter(|previous| previous.url != url);
+        if let Some(previous) = previous {
+            secrets::delete(Service::Webdav, &previous.url)?;
+        }
+        if url.is_empty() {
+            return match fs::remove_file(&path) {
+                Err(e) if e.kind() != ErrorKind::NotFound => Err(format!("无法删除文件: {}", e)),
+                _ => Ok(()),
+            };
+        }
+        if !url.starts_with("https://") && !url.starts_with("http://") {
+            return Err(format!("不是 http(s) URL: {}", url));
+        }
+        if url.starts_with("http://") && credentials.is_some() && !allow_insecure.unwrap_or(false) {
+            return Err("http 连接会以明文发送密码，请改用 https".to_string());
+        }
+        let remote = SyncRemote { url, credentials };
+        let dav = Dav::new(&remote, None);
+        if dav.propfind(&dav.base, "0")?.is_none() {
+            return Err(format!("{} 处没有 WebDAV 文件夹", remote.url));
+        }
+        match &remote.credentials {
+            Some(credentials) => secrets::store(Service::Webdav, &remote.url, &credentials.password)?,
+            None => secrets::delete(Service::Webdav, &remote.url)?,
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("无法创建目录: {}", e))?;
+        }
+        let content = serde_json::to_string_pretty(&remote).map_err(|e| e.to_string())?;
+        write_atomic(&path, content.as_bytes()).map_err(|e| format!("无法写入文件: {}", e))?;
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// `root` 与其在配置的服务器上的文件夹做双向同步：两边都与上次同步时比较，只有两台机器都改过的文件才算冲突。
+/// `folder` 是服务器上的子文件夹名，只在第一次同步时需要：不指定时用项目目录名，但这个文件夹已有文件时报错，
+/// 不会与同名的其他项目混在一起
+#[command]
+pub async fn sync_project(
+    app: AppHandle,
+    scope: WindowScope,
+    root: String,
+    direction: Option<SyncDirection>,
+    folder: Option<String>,
+) -> Result<SyncReport, String> {
+    let root = PathBuf::from(root);
+    scope.check(&root)?;
+    let remote = read_remote(&app)?;
+    tauri::async_runtime::spawn_blocking(move || sync(&root, &remote, direction.unwrap_or_default(), folder))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hrefs_are_decoded_to_paths() {
+        assert_eq!(href_path("https://dav.example.com/files/My%20Project/a.tex"), "/files/My Project/a.tex");
+        assert_eq!(href_path(" /files/a&amp;b/%E4%B8%AD.tex "), "/files/a&b/中.tex");
+        assert_eq!(href_path("https://dav.example.com"), "/");
+        assert_eq!(href_path("/files/100%/x"), "/files/100%/x");
+    }
+
+    #[test]
+    fn only_paths_inside_the_project_are_relative() {
+        assert!(safe_relative("chapters/intro.tex"));
+        for path in ["", "/a", "a//b", "a/", "../a", "a/./b", "a\\b"] {
+            assert!(!safe_relative(path), "{:?}", path);
+        }
+    }
+}
@@ -0,0 +1,533 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use serde::Serialize;
+use tauri::command;
+
+use crate::compiler::output_dir_for;
+use crate::compiler::untitled::workspace_dir;
+use crate::latex::root::find_root;
+use crate::scope::WindowScope;
+
+/// 每个 PDF 大点（bp）对应的缩放点数（1bp = 65781.76sp）
+const SP_PER_BP: f64 = 65781.76;
+
+#[derive(Serialize)]
+pub struct SyncTeXBox {
+    page: u32,
+    /// 左边缘，单位为 PDF 点，从页面左上角算起
+    x: f32,
+    /// 上边缘，单位为 PDF 点，从页面左上角算起
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+#[derive(Serialize)]
+pub struct SyncTeXSource {
+    file: String,
+    line: u32,
+    column: i32,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum RecordKind {
+    HBox,
+    VBox,
+    VoidBox,
+    Point,
+}
+
+struct Record {
+    kind: RecordKind,
+    tag: u32,
+    line: u32,
+    column: i32,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    depth: f64,
+}
+
+impl Record {
+    fn has_extent(&self) -> bool {
+        self.kind != RecordKind::Point && self.width > 0.0
+    }
+}
+
+struct Page {
+    number: u32,
+    records: Vec<Record>,
+}
+
+/// `.synctex(.gz)` 文件的内存表示。坐标按缩放点保存，输出时再换算成 PDF 点
+pub struct SyncTexData {
+    inputs: HashMap<u32, String>,
+    pages: Vec<Page>,
+    unit: f64,
+    magnification: f64,
+    x_offset: f64,
+    y_offset: f64,
+    base_dir: PathBuf,
+}
+
+impl SyncTexData {
+    /// `source_dir` 是根文档所在的文件夹，文件中的相对输入路径都相对于它
+    pub fn load(path: &Path, source_dir: &Path) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|e| format!("无法读取 SyncTeX 文件: {}", e))?;
+        let text = if path.extension().is_some_and(|ext| ext == "gz") {
+            let mut decoded = String::new();
+            GzDecoder::new(bytes.as_slice())
+                .read_to_string(&mut decoded)
+                .map_err(|e| format!("无法解压 SyncTeX 文件: {}", e))?;
+            decoded
+        } else {
+            String::from_utf8_lossy(&bytes).to_string()
+        };
+
+        Ok(Self::parse(&text, source_dir.to_path_buf()))
+    }
+
+    fn parse(text: &str, base_dir: PathBuf) -> Self {
+        let mut data = SyncTexData {
+            inputs: HashMap::new(),
+            pages: Vec::new(),
+            unit: 1.0,
+            magnification: 1000.0,
+            x_offset: 0.0,
+            y_offset: 0.0,
+            base_dir,
+        };
+        let mut current_page: Option<Page> = None;
+
+        for line in text.lines() {
+            if let Some(rest) = line.strip_prefix("Input:") {
+                if let Some((tag, name)) = rest.split_once(':') {
+                    if let Ok(tag) = tag.parse::<u32>() {
+                        data.inputs.insert(tag, name.to_string());
+                    }
+                }
+                continue;
+            }
+            if let Some(rest) = line.strip_prefix("Unit:") {
+                data.unit = rest.trim().parse().unwrap_or(1.0);
+                continue;
+            }
+            if let Some(rest) = line.strip_prefix("Magnification:") {
+                data.magnification = rest.trim().parse().unwrap_or(1000.0);
+                continue;
+            }
+            if let Some(rest) = line.strip_prefix("X Offset:") {
+                data.x_offset = rest.trim().parse().unwrap_or(0.0);
+                continue;
+            }
+            if let Some(rest) = line.strip_prefix("Y Offset:") {
+                data.y_offset = rest.trim().parse().unwrap_or(0.0);
+                continue;
+            }
+
+            let Some(first) = line.chars().next() else {
+                continue;
+            };
+            let rest = &line[first.len_utf8()..];
+            match first {
+                '{' => {
+                    if let Some(page) = current_page.take() {
+                        data.pages.push(page);
+                    }
+                    let number = rest.trim().parse().unwrap_or(data.pages.len() as u32 + 1);
+                    current_page = Some(Page { number, records: Vec::new() });
+                }
+                '}' => {
+                    if let Some(page) = current_page.take() {
+                        data.pages.push(page);
+                    }
+                }
+                '(' | '[' | 'h' | 'v' | 'x' | 'k' | 'g' | '$' => {
+                    let kind = match first {
+                        '(' => RecordKind::HBox,
+                        '[' => RecordKind::VBox,
+                        'h' | 'v' => RecordKind::VoidBox,
+                        _ => RecordKind::Point,
+                    };
+                    if let (Some(page), Some(record)) = (current_page.as_mut(), parse_record(kind, rest)) {
+                        page.records.push(record);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let Some(page) = current_page.take() {
+            data.pages.push(page);
+        }
+        data
+    }
+
+    fn to_bp(&self, value: f64, offset: f64) -> f32 {
+        ((value + offset) * self.unit * self.magnification / 1000.0 / SP_PER_BP) as f32
+    }
+
+    fn input_path(&self, tag: u32) -> Option<PathBuf> {
+        let name = self.inputs.get(&tag)?;
+        let path = Path::new(name);
+        let resolved = if path.is_absolute() { path.to_path_buf() } else { self.base_dir.join(path) };
+        Some(normalize_path(&resolved))
+    }
+
+    fn tags_for(&self, source: &Path) -> Vec<u32> {
+        let target = normalize_path(source);
+        let target_canonical = fs::canonicalize(source).ok();
+        self.inputs
+            .keys()
+            .copied()
+            .filter(|tag| {
+                let Some(input) = self.input_path(*tag) else {
+                    return false;
+                };
+                input == target
+                    || (target_canonical.is_some() && fs::canonicalize(&input).ok() == target_canonical)
+            })
+            .collect()
+    }
+
+    /// 源文件行 -> 第一个含有该行记录的页面上的方框。该行没有产生输出时（空行、注释）
+    /// 退而取其后最近的一行
+    pub fn forward(&self, source: &Path, line: u32, column: i32) -> Option<SyncTeXBox> {
+        let tags = self.tags_for(source);
+        if tags.is_empty() {
+            return None;
+        }
+
+        let best_line = self
+            .pages
+            .iter()
+            .flat_map(|page| page.records.iter())
+            .filter(|record| tags.contains(&record.tag) && record.line >= line)
+            .map(|record| record.line)
+            .min()
+            .or_else(|| {
+                self.pages
+                    .iter()
+                    .flat_map(|page| page.records.iter())
+                    .filter(|record| tags.contains(&record.tag))
+                    .map(|record| record.line)
+                    .max()
+            })?;
+
+        let page = self.pages.iter().find(|page| {
+            page.records.iter().any(|record| tags.contains(&record.tag) && record.line == best_line)
+        })?;
+        let matching: Vec<&Record> = page
+            .records
+            .iter()
+            .filter(|record| tags.contains(&record.tag) && record.line == best_line)
+            .collect();
+
+        // 优先选列号与请求接近的记录
+        let matching = if column >= 0 && matching.iter().any(|record| record.column >= 0) {
+            let closest = matching
+                .iter()
+                .filter(|record| record.column >= 0)
+                .map(|record| (record.column - column).abs())
+                .min()
+                .unwrap_or(0);
+            matching
+                .into_iter()
+                .filter(|record| record.column < 0 || (record.column - column).abs() == closest)
+                .collect()
+        } else {
+            matching
+        };
+
+        let boxes: Vec<&&Record> = matching.iter().filter(|record| record.has_extent()).collect();
+        let (left, top, right, bottom) = if boxes.is_empty() {
+            let first = matching.first()?;
+            (first.x, first.y, first.x, first.y)
+        } else {
+            boxes.iter().fold(
+                (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+                |(left, top, right, bottom), record| {
+                    (
+                        left.min(record.x),
+                        top.min(record.y - record.height),
+                        right.max(record.x + record.width),
+                        bottom.max(record.y + record.depth),
+                    )
+                },
+            )
+        };
+
+        Some(SyncTeXBox {
+            page: page.number,
+            x: self.to_bp(left, self.x_offset),
+            y: self.to_bp(top, self.y_offset),
+            width: self.to_bp(right - left, 0.0),
+            height: self.to_bp(bottom - top, 0.0),
+        })
+    }
+}
+
+impl SyncTexData {
+    /// PDF 坐标 -> 源文件位置。取页面上包含该点的最小方框，没有时取离它最近的记录
+    pub fn inverse(&self, page: u32, x: f32, y: f32) -> Option<SyncTeXSource> {
+        let page = self.pages.iter().find(|p| p.number == page)?;
+        let x = x as f64;
+        let y = y as f64;
+
+        let containing = page
+            .records
+            .iter()
+            .filter(|record| record.has_extent() && record.kind != RecordKind::VBox)
+            .filter(|record| {
+                let left = self.to_bp(record.x, self.x_offset) as f64;
+                let top = self.to_bp(record.y - record.height, self.y_offset) as f64;
+                let right = left + self.to_bp(record.width, 0.0) as f64;
+                let bottom = top + self.to_bp(record.height + record.depth, 0.0) as f64;
+                x >= left && x <= right && y >= top && y <= bottom
+            })
+            .min_by(|a, b| {
+                let area_a = a.width * (a.height + a.depth);
+                let area_b = b.width * (b.height + b.depth);
+                area_a.total_cmp(&area_b)
+            });
+
+        let record = containing.or_else(|| {
+            page.records.iter().min_by(|a, b| {
+                let distance = |record: &Record| {
+                    let dx = self.to_bp(record.x, self.x_offset) as f64 - x;
+                    let dy = self.to_bp(record.y, self.y_offset) as f64 - y;
+                    dx * dx + dy * dy
+                };
+                distance(a).total_cmp(&distance(b))
+            })
+        })?;
+
+        let file = self.input_path(record.tag)?;
+        Some(SyncTeXSource {
+            file: file.to_string_lossy().to_string(),
+            line: record.line.max(1),
+            column: record.column,
+        })
+    }
+}
+
+fn parse_record(kind: RecordKind, rest: &str) -> Option<Record> {
+    // 格式：tag,line[,column]:x,y[:W,H,D]
+    let mut sections = rest.split(':');
+    let mut link = sections.next()?.split(',');
+    let tag = link.next()?.trim().parse().ok()?;
+    let line = link.next()?.trim().parse().ok()?;
+    let column = link.next().and_then(|v| v.trim().parse().ok()).unwrap_or(-1);
+
+    let mut point = sections.next()?.split(',');
+    let x = point.next()?.trim().parse().ok()?;
+    let y = point.next()?.trim().parse().ok()?;
+
+    let mut size = sections.next().map(|s| s.split(',').collect::<Vec<_>>()).unwrap_or_default().into_iter();
+    let mut next_size = || size.next().and_then(|v| v.trim().parse::<f64>().ok()).unwrap_or(0.0);
+    let width = next_size();
+    let height = next_size();
+    let depth = next_size();
+
+    Some(Record { kind, tag, line, column, x, y, width, height, depth })
+}
+
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
+
+/// 找到 tectonic 写在 PDF 旁边的 SyncTeX 文件，视引擎不同可能压缩也可能不压缩
+fn find_synctex_file(dir: &Path, stem: &str) -> Result<PathBuf, String> {
+    ["synctex.gz", "synctex"]
+        .iter()
+        .map(|ext| dir.join(format!("{}.{}", stem, ext)))
+        .find(|path| path.exists())
+        .ok_or_else(|| "未找到 SyncTeX 数据，请先编译".to_string())
+}
+
+#[command]
+pub fn synctex_forward(
+    scope: WindowScope,
+    tex_path: Option<String>,
+    untitled_id: Option<String>,
+    line: u32,
+    column: Option<i32>,
+) -> Result<SyncTeXBox, String> {
+    scope.check_optional(tex_path.as_ref())?;
+    let (source_path, source_dir, synctex_dir, stem) = if let Some(path_str) = tex_path {
+        let source_path = PathBuf::from(&path_str);
+        // 被包含的章节与根文档共用一个 SyncTeX 文件
+        let root_path = PathBuf::from(find_root(&source_path, None).root);
+        let file_stem = root_path.file_stem()
+            .ok_or("无法获取源文件名")?
+            .to_string_lossy()
+            .to_string();
+        let aux_dir = output_dir_for(&root_path, None)?;
+        let root_dir = root_path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        (source_path, root_dir, aux_dir, file_stem)
+    } else {
+        let temp_dir = workspace_dir(untitled_id.as_deref())?;
+        (temp_dir.join("input.tex"), temp_dir.clone(), temp_dir, "input".to_string())
+    };
+
+    let synctex_path = find_synctex_file(&synctex_dir, &stem)?;
+    let data = SyncTexData::load(&synctex_path, &source_dir)?;
+
+    data.forward(&source_path, line.max(1), column.unwrap_or(-1))
+        .ok_or_else(|| format!("{} 第 {} 行没有 SyncTeX 记录", source_path.to_string_lossy(), line))
+}
+
+/// `tex_path` 是文档的任意一个源文件（根文档或被包含的章节）；没有时（未保存的文档）
+/// 输入路径相对于 PDF 所在的文件夹，即未保存文档的临时工作区
+#[command]
+pub fn synctex_inverse(
+    scope: WindowScope,
+    pdf_path: String,
+    tex_path: Option<String>,
+    page: u32,
+    x: f32,
+    y: f32,
+) -> Result<SyncTeXSource, String> {
+    scope.check(&pdf_path)?;
+    scope.check_optional(tex_path.as_ref())?;
+    let pdf_path = PathBuf::from(pdf_path);
+    let synctex_dir = pdf_path.parent().ok_or("无效的 PDF 路径")?;
+    let stem = pdf_path.file_stem()
+        .ok_or("无法获取 PDF 文件名")?
+        .to_string_lossy()
+        .to_string();
+
+    let source_dir = match tex_path {
+        Some(path) => {
+            let root_path = PathBuf::from(find_root(Path::new(&path), None).root);
+            root_path.parent().unwrap_or(Path::new(".")).to_path_buf()
+        }
+        None => synctex_dir.to_path_buf(),
+    };
+
+    let synctex_path = find_synctex_file(synctex_dir, &stem)?;
+    let data = SyncTexData::load(&synctex_path, &source_dir)?;
+
+    data.inverse(page, x, y)
+        .ok_or_else(|| format!("第 {} 页没有 SyncTeX 记录", page))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 100bp 和 25bp 对应的缩放点数
+    const BP_100: &str = "6578176";
+    const BP_25: &str = "1644544";
+
+    fn sample() -> SyncTexData {
+        let text = [
+            "SyncTeX Version:1".to_string(),
+            "Input:1:./main.tex".to_string(),
+            "Input:2:chapters/intro.tex".to_string(),
+            "Output:pdf".to_string(),
+            "Magnification:1000".to_string(),
+            "Unit:1".to_string(),
+            "X Offset:0".to_string(),
+            "Y Offset:0".to_string(),
+            "Content:".to_string(),
+            "{1".to_string(),
+            format!("[1,1:0,0:{0}0,{0}0,0", BP_100),
+            format!("(1,5:{0},{0}:{0},{1},0", BP_100, BP_25),
+            format!("x1,7:{0},{0}00", BP_100),
+            "}1".to_string(),
+            "{2".to_string(),
+            format!("(2,3,4:{0},{0}:{0},{1},{1}", BP_100, BP_25),
+            "}2".to_string(),
+        ]
+        .join("\n");
+        SyncTexData::parse(&text, PathBuf::from("/project"))
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 0.01, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn parses_inputs_and_pages() {
+        let data = sample();
+        assert_eq!(data.inputs.len(), 2);
+        assert_eq!(data.pages.len(), 2);
+        assert_eq!(data.pages[0].number, 1);
+        assert_eq!(data.pages[0].records.len(), 3);
+        assert_eq!(data.pages[1].records[0].column, 4);
+        assert_eq!(data.input_path(1), Some(PathBuf::from("/project/main.tex")));
+        assert_eq!(data.input_path(2), Some(PathBuf::from("/project/chapters/intro.tex")));
+    }
+
+    #[test]
+    fn record_without_size_is_a_point() {
+        let record = parse_record(RecordKind::Point, "1,7:10,20").unwrap();
+        assert_eq!((record.tag, record.line, record.column), (1, 7, -1));
+        assert!(!record.has_extent());
+        assert!(parse_record(RecordKind::HBox, "1:10,20").is_none());
+    }
+
+    #[test]
+    fn forward_returns_the_box_of_the_line() {
+        let data = sample();
+        let found = data.forward(Path::new("/project/main.tex"), 5, -1).unwrap();
+        assert_eq!(found.page, 1);
+        assert_close(found.x, 100.0);
+        assert_close(found.y, 75.0);
+        assert_close(found.width, 100.0);
+        assert_close(found.height, 25.0);
+    }
+
+    #[test]
+    fn forward_falls_back_to_the_next_line_with_output() {
+        let data = sample();
+        let found = data.forward(Path::new("/project/main.tex"), 6, -1).unwrap();
+        assert_eq!(found.page, 1);
+        assert_close(found.x, 100.0);
+        assert_close(found.y, 10000.0);
+        assert_close(found.width, 0.0);
+
+        let last = data.forward(Path::new("/project/main.tex"), 99, -1).unwrap();
+        assert_close(last.y, 10000.0);
+    }
+
+    #[test]
+    fn forward_finds_included_files() {
+        let data = sample();
+        let found = data.forward(Path::new("/project/chapters/../chapters/intro.tex"), 3, 4).unwrap();
+        assert_eq!(found.page, 2);
+        assert_close(found.height, 50.0);
+        assert!(data.forward(Path::new("/project/other.tex"), 1, -1).is_none());
+    }
+
+    #[test]
+    fn inverse_prefers_the_box_containing_the_point() {
+        let data = sample();
+        let found = data.inverse(1, 150.0, 90.0).unwrap();
+        assert_eq!(found.file, "/project/main.tex");
+        assert_eq!(found.line, 5);
+
+        let nearest = data.inverse(1, 100.0, 9990.0).unwrap();
+        assert_eq!(nearest.line, 7);
+
+        let included = data.inverse(2, 150.0, 110.0).unwrap();
+        assert_eq!(included.file, "/project/chapters/intro.tex");
+        assert_eq!((included.line, included.column), (3, 4));
+        assert!(data.inverse(3, 0.0, 0.0).is_none());
+    }
+}
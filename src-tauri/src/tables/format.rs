@@ -0,0 +1,246 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+use tauri::command;
+
+use super::{pad, width, Align, Table, TableFormat};
+use crate::latex::outline::braced_argument;
+
+/// `\begin{tabular}[pos]`，到列格式（或它前面的宽度）为止
+static BEGIN_TABULAR_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\\begin\s*\{(tabular\*?|tabularx|tabulary|longtable|array)\}\s*(?:\[[^\]]*\]\s*)?\{").unwrap()
+});
+
+/// 管道表格一行的单元格，按未转义的 `|` 分割
+fn markdown_cells(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = if line.ends_with('|') && !line.ends_with("\\|") { &line[..line.len() - 1] } else { line };
+    let mut cells = vec![String::new()];
+    let mut escaped = false;
+    for c in line.chars() {
+        match c {
+            '|' if !escaped => cells.push(String::new()),
+            _ => cells.last_mut().unwrap().push(c),
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    cells.into_iter().map(|cell| cell.trim().to_string()).collect()
+}
+
+/// 分隔行单元格（`:---`、`---:`、`:-:`）要求的对齐方式；不是分隔单元格时为 None
+fn delimiter_alignment(cell: &str) -> Option<Align> {
+    let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+    if dashes.is_empty() || !dashes.chars().all(|c| c == '-') {
+        return None;
+    }
+    Some(match (cell.starts_with(':'), cell.ends_with(':')) {
+        (true, true) => Align::Center,
+        (false, true) => Align::Right,
+        _ => Align::Left,
+    })
+}
+
+fn format_markdown(fragment: &str) -> Result<String, String> {
+    let lines: Vec<&str> = fragment.lines().filter(|line| !line.trim().is_empty()).collect();
+    if lines.len() < 2 || lines.iter().any(|line| !line.contains('|')) {
+        return Err("不是 Markdown 管道表格".to_string());
+    }
+    let delimiter = markdown_cells(lines[1]);
+    let alignments: Option<Vec<Align>> = delimiter.iter().map(|cell| delimiter_alignment(cell)).collect();
+    let Some(mut alignments) = alignments else {
+        return Err("表格的第二行必须是 `---` 分隔行".to_string());
+    };
+    let rows: Vec<Vec<String>> =
+        lines.iter().enumerate().filter(|(index, _)| *index != 1).map(|(_, line)| markdown_cells(line)).collect();
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0).max(alignments.len());
+    alignments.resize(columns, Align::Left);
+    // 保留片段的缩进，例如在列表项中
+    let indent = &lines[0][..lines[0].len() - lines[0].trim_start().len()];
+    let table = Table { rows, header: true, alignments };
+    Ok(table.to_markdown().lines().map(|line| format!("{}{}\n", indent, line)).collect())
+}
+
+/// `|l|c|r|`、`lp{3cm}S` 或 `*{3}{c}` 这类 `tabular` 列格式给出的列对齐；不认识的一律算左对齐
+fn spec_alignments(spec: &str) -> Vec<Align> {
+    let mut alignments = Vec::new();
+    let mut chars = spec.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            'l' | 'p' | 'm' | 'b' | 'X' | 'L' | 'J' => alignments.push(Align::Left),
+            'c' | 'C' | 'S' => alignments.push(Align::Center),
+            'r' | 'R' => alignments.push(Align::Right),
+            _ => {}
+        }
+        // 跳过 `p{3cm}`、`@{}`、`>{\bfseries}` 等的参数
+        if chars.peek() == Some(&'{') {
+            let mut depth = 0;
+            for d in chars.by_ref() {
+                match d {
+                    '{' => depth += 1,
+                    '}' => depth -= 1,
+                    _ => {}
+                }
+                if depth == 0 {
+                    break;
+                }
+            }
+        }
+    }
+    alignments
+}
+
+/// `tabular` 的一行：其单元格（按花括号外的 `&` 分割），以及从 `\\` 或注释开始的后续部分（`\\ \hline`、`\\[2pt]`）。
+/// 不是行的内容（线、注释、`\begin`/`\end`）为 None
+fn latex_row(line: &str) -> Option<(Vec<String>, String)> {
+    let line = line.trim();
+    let mut cells = vec![String::new()];
+    let mut depth = 0;
+    let mut chars = line.char_indices().peekable();
+    let mut end = None;
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => match chars.peek() {
+                Some((_, '\\')) if depth == 0 => {
+                    end = Some(i);
+                    break;
+                }
+                Some(&(_, next)) => {
+                    cells.last_mut().unwrap().push(c);
+                    cells.last_mut().unwrap().push(next);
+                    chars.next();
+                    continue;
+                }
+                None => {}
+            },
+            '%' => {
+                end = Some(i);
+                break;
+            }
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            '&' if depth == 0 => {
+                cells.push(String::new());
+                continue;
+            }
+            _ => {}
+        }
+        cells.last_mut().unwrap().push(c);
+    }
+    if cells.len() == 1 && end.is_none() {
+        return None;
+    }
+    let rest = end.map(|i| line[i..].to_string()).unwrap_or_default();
+    Some((cells.into_iter().map(|cell| cell.trim().to_string()).collect(), rest))
+}
+
+fn format_latex(fragment: &str) -> Result<String, String> {
+    let lines: Vec<&str> = fragment.lines().collect();
+    let mut alignments = Vec::new();
+    let begin = lines.iter().find_map(|line| BEGIN_TABULAR_RE.captures(line).map(|caps| (line, caps)));
+    if let Some((line, caps)) = begin {
+        let mut at = caps.get(0).unwrap().end();
+        // `tabular*`、`tabularx` 和 `tabulary` 先接受一个宽度
+        if matches!(&caps[1], "tabular*" | "tabularx" | "tabulary") {
+            let mut depth = 1;
+            let close = line[at..].find(|c| {
+                match c {
+                    '{' => depth += 1,
+                    '}' => depth -= 1,
+                    _ => {}
+                }
+                depth == 0
+            });
+            let after = close.map_or(line.len(), |i| at + i + 1);
+            at = line[after..].find('{').map_or(line.len(), |i| after + i + 1);
+        }
+        alignments = spec_alignments(&braced_argument(line, at));
+    }
+    let rows: Vec<Option<(Vec<String>, String)>> = lines.iter().map(|line| latex_row(line)).collect();
+    let Some(first_row) = rows.iter().position(Option::is_some) else {
+        return Err("没有以 `&` 分隔的行可以对齐".to_string());
+    };
+    let columns = rows.iter().flatten().map(|(cells, _)| cells.len()).max().unwrap_or(0);
+    alignments.resize(columns, Align::Left);
+    // `\multicolumn` 单元格跨多列，不参与计算宽度，也不补齐
+    let spans = |cell: &str| cell.contains("\\multicolumn");
+    let widths: Vec<usize> = (0..columns)
+        .map(|column| {
+            let cells = rows.iter().flatten().filter_map(|(cells, _)| cells.get(column)).filter(|cell| !spans(cell));
+            cells.map(|cell| width(cell)).max().unwrap_or(0)
+        })
+        .collect();
+    let indent = &lines[first_row][..lines[first_row].len() - lines[first_row].trim_start().len()];
+
+    let mut result = String::new();
+    for (line, row) in lines.iter().zip(&rows) {
+        let Some((cells, rest)) = row else {
+            result.push_str(line);
+            result.push('\n');
+            continue;
+        };
+        let padded: Vec<String> = cells
+            .iter()
+            .enumerate()
+            .map(|(column, cell)| match spans(cell) {
+                true => cell.clone(),
+                false => pad(cell, widths[column], alignments[column]),
+            })
+            .collect();
+        let row = padded.join(" & ");
+        let row = if rest.is_empty() { row.trim_end().to_string() } else { format!("{} {}", row, rest) };
+        result.push_str(&format!("{}{}\n", indent, row));
+    }
+    Ok(result)
+}
+
+/// 重排 Markdown 管道表格或 LaTeX `tabular` 的各行，让 `|` 或 `&` 分隔符对齐，保留列对齐方式、线和注释。
+/// 没有给出 `format` 时从片段推断。返回替换选区的文本
+#[command]
+pub fn format_table(source_fragment: String, format: Option<TableFormat>) -> Result<String, String> {
+    let latex = match format {
+        Some(format) => format != TableFormat::Markdown,
+        None => source_fragment.contains('&') || source_fragment.contains("\\begin"),
+    };
+    let mut formatted = if latex { format_latex(&source_fragment)? } else { format_markdown(&source_fragment)? };
+    if !source_fragment.ends_with('\n') {
+        formatted.pop();
+    }
+    Ok(formatted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_columns_are_padded_and_keep_alignment() {
+        let formatted = format_table("  |a|bb|\n  |:-|-:|\n  |ccc|d \\| e|".to_string(), None).unwrap();
+        assert_eq!(formatted, "  | a   |     bb |\n  | --- | -----: |\n  | ccc | d \\| e |");
+    }
+
+    #[test]
+    fn latex_ampersands_are_aligned_and_rules_kept() {
+        let fragment = concat!(
+            "\\begin{tabular}{|l|r|}\n",
+            "  a & bb \\\\ \\hline\n",
+            "  ccc & d \\\\ % total\n",
+            "  \\multicolumn{2}{c}{wide cell} \\\\\n",
+            "\\end{tabular}\n",
+        );
+        let expected = concat!(
+            "\\begin{tabular}{|l|r|}\n",
+            "  a   & bb \\\\ \\hline\n",
+            "  ccc &  d \\\\ % total\n",
+            "  \\multicolumn{2}{c}{wide cell} \\\\\n",
+            "\\end{tabular}\n",
+        );
+        assert_eq!(format_table(fragment.to_string(), None).unwrap(), expected);
+    }
+
+    #[test]
+    fn column_specs_skip_arguments() {
+        let alignments = spec_alignments("@{}p{3cm}>{\\bfseries}c*{2}{r}");
+        assert!(matches!(alignments[..], [Align::Left, Align::Center, Align::Right]));
+    }
+}
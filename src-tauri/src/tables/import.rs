@@ -0,0 +1,172 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use calamine::{open_workbook_auto, Data, Reader};
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use super::{detect_alignments, escape_latex, escape_markdown, Table, TableFormat};
+use crate::scope::WindowScope;
+
+/// 超过这个规模的电子表格几乎不可能是一张表
+const MAX_ROWS: usize = 2000;
+const SPREADSHEET_EXTENSIONS: &[&str] = &["xlsx", "xlsm", "xls", "xlsb", "ods"];
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct ImportOptions {
+    /// 第一行是表头
+    header: bool,
+    /// 电子表格：工作表名称；默认为第一个工作表
+    sheet: Option<String>,
+    /// CSV 和粘贴的文本：没有给出时从内容推断
+    delimiter: Option<char>,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        ImportOptions { header: true, sheet: None, delimiter: None }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ImportedTable {
+    /// 可以直接插入
+    text: String,
+    rows: usize,
+    columns: usize,
+    /// 输入的行数多于保留的行数
+    truncated: bool,
+}
+
+/// 从电子表格复制的文本用制表符，否则取第一行中 `,` 和 `;` 较多的那个（`;` 是以逗号作小数点的地区的 CSV）
+fn guess_delimiter(text: &str) -> u8 {
+    let first = text.lines().next().unwrap_or_default();
+    if first.contains('\t') {
+        b'\t'
+    } else if first.matches(';').count() > first.matches(',').count() {
+        b';'
+    } else {
+        b','
+    }
+}
+
+fn read_delimited(text: &str, delimiter: Option<char>) -> Result<Vec<Vec<String>>, String> {
+    let delimiter = match delimiter {
+        Some(c) if c.is_ascii() => c as u8,
+        Some(c) => return Err(format!("不支持的分隔符: {}", c)),
+        None => guess_delimiter(text),
+    };
+    let mut reader =
+        csv::ReaderBuilder::new().delimiter(delimiter).has_headers(false).flexible(true).from_reader(text.as_bytes());
+    reader
+        .records()
+        .take(MAX_ROWS + 1)
+        .map(|record| {
+            record.map(|record| record.iter().map(str::to_string).collect()).map_err(|e| format!("无效的 CSV: {}", e))
+        })
+        .collect()
+}
+
+/// 1899-12-30（Excel 的纪元，包括其闰年错误）以来的天数，转成 `YYYY-MM-DD`，有时间时再加 `HH:MM`
+fn excel_date(serial: f64) -> String {
+    let days = serial.floor() as i64 - 25569; // 转成 1970-01-01 以来的天数
+    // Howard Hinnant 的 civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    let minutes = ((serial - serial.floor()) * 1440.0).round() as i64;
+    if minutes == 0 {
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    } else {
+        format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, minutes / 60, minutes % 60)
+    }
+}
+
+fn cell_text(cell: &Data) -> String {
+    match cell {
+        Data::DateTime(date) => excel_date(date.as_f64()),
+        Data::Error(_) => String::new(),
+        cell => cell.to_string(),
+    }
+}
+
+fn read_spreadsheet(path: &Path, sheet: Option<&str>) -> Result<Vec<Vec<String>>, String> {
+    let mut workbook = open_workbook_auto(path).map_err(|e| format!("无法读取文件: {}", e))?;
+    let name = match sheet {
+        Some(name) => name.to_string(),
+        None => workbook.sheet_names().into_iter().next().ok_or("工作簿中没有工作表")?,
+    };
+    let range = workbook.worksheet_range(&name).map_err(|e| format!("无法读取工作表 {}: {}", name, e))?;
+    Ok(range.rows().take(MAX_ROWS + 1).map(|row| row.iter().map(cell_text).collect()).collect())
+}
+
+fn import_blocking(
+    path: Option<PathBuf>,
+    text: Option<String>,
+    format: TableFormat,
+    options: &ImportOptions,
+) -> Result<ImportedTable, String> {
+    let mut rows = match (path, text) {
+        (_, Some(text)) => read_delimited(&text, options.delimiter)?,
+        (Some(path), None) => {
+            let extension = path.extension().map(|ext| ext.to_string_lossy().to_lowercase()).unwrap_or_default();
+            if SPREADSHEET_EXTENSIONS.contains(&extension.as_str()) {
+                read_spreadsheet(&path, options.sheet.as_deref())?
+            } else {
+                let text = fs::read_to_string(&path).map_err(|e| format!("无法读取文件: {}", e))?;
+                let delimiter = options.delimiter.or((extension == "tsv").then_some('\t'));
+                read_delimited(&text, delimiter)?
+            }
+        }
+        (None, None) => return Err("没有要导入的文件或文本".to_string()),
+    };
+
+    // 去掉末尾的空行和空列，电子表格里到处都是
+    while rows.last().is_some_and(|row| row.iter().all(|cell| cell.trim().is_empty())) {
+        rows.pop();
+    }
+    let columns = (0..rows.iter().map(Vec::len).max().unwrap_or(0))
+        .rev()
+        .find(|&column| rows.iter().any(|row| row.get(column).is_some_and(|cell| !cell.trim().is_empty())))
+        .map_or(0, |last| last + 1);
+    if columns == 0 {
+        return Err("表格是空的".to_string());
+    }
+    let truncated = rows.len() > MAX_ROWS;
+    rows.truncate(MAX_ROWS);
+    for row in &mut rows {
+        row.resize(columns, String::new());
+    }
+
+    let alignments = detect_alignments(&rows, options.header);
+    let escape = if format == TableFormat::Markdown { escape_markdown } else { escape_latex };
+    let cells: Vec<Vec<String>> = rows.iter().map(|row| row.iter().map(|cell| escape(cell.trim())).collect()).collect();
+    let table = Table { rows: cells, header: options.header, alignments };
+    Ok(ImportedTable { text: table.render(format), rows: rows.len(), columns, truncated })
+}
+
+/// 把 CSV、TSV 或电子表格文件，或从中粘贴的文本（`text`，从 Excel 复制时以制表符分隔），转成 Markdown 管道表格或
+/// LaTeX `tabular`。数值列右对齐
+#[command]
+pub async fn import_table(
+    scope: WindowScope,
+    path: Option<String>,
+    text: Option<String>,
+    format: TableFormat,
+    options: Option<ImportOptions>,
+) -> Result<ImportedTable, String> {
+    let path = path.map(PathBuf::from);
+    if let Some(path) = &path {
+        scope.check(path)?;
+    }
+    tauri::async_runtime::spawn_blocking(move || import_blocking(path, text, format, &options.unwrap_or_default()))
+        .await
+        .map_err(|e| e.to_string())?
+}
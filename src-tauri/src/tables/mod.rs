@@ -0,0 +1,166 @@
+pub mod format;
+pub mod import;
+
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TableFormat {
+    Markdown,
+    /// 带 `\hline` 的 `tabular`
+    Latex,
+    /// 带 booktabs 的 `\toprule`/`\midrule`/`\bottomrule` 的 `tabular`
+    Booktabs,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// 按行排列的单元格，已按目标格式转义。各行长度可以不同，由渲染函数补齐
+pub struct Table {
+    pub rows: Vec<Vec<String>>,
+    /// 第一行是表头
+    pub header: bool,
+    pub alignments: Vec<Align>,
+}
+
+pub fn width(cell: &str) -> usize {
+    cell.chars().count()
+}
+
+pub fn pad(cell: &str, width: usize, align: Align) -> String {
+    let fill = width.saturating_sub(self::width(cell));
+    match align {
+        Align::Left => format!("{}{}", cell, " ".repeat(fill)),
+        Align::Center => format!("{}{}{}", " ".repeat(fill / 2), cell, " ".repeat(fill - fill / 2)),
+        Align::Right => format!("{}{}", " ".repeat(fill), cell),
+    }
+}
+
+/// 数字，可以带货币符号、千位分隔符或 `%`
+fn is_numeric(cell: &str) -> bool {
+    let cleaned: String = cell.trim().chars().filter(|c| !matches!(c, ',' | '%' | '$' | '€' | '£' | ' ')).collect();
+    !cleaned.is_empty() && cleaned.parse::<f64>().is_ok()
+}
+
+/// 正文单元格全是数字的列右对齐，否则左对齐
+pub fn detect_alignments(rows: &[Vec<String>], header: bool) -> Vec<Align> {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let body = &rows[usize::from(header).min(rows.len())..];
+    (0..columns)
+        .map(|column| {
+            let mut cells =
+                body.iter().filter_map(|row| row.get(column)).filter(|cell| !cell.trim().is_empty()).peekable();
+            if cells.peek().is_some() && cells.all(|cell| is_numeric(cell)) {
+                Align::Right
+            } else {
+                Align::Left
+            }
+        })
+        .collect()
+}
+
+pub fn escape_latex(cell: &str) -> String {
+    let mut escaped = String::with_capacity(cell.len());
+    for c in cell.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' | '\r' => escaped.push(' '),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub fn escape_markdown(cell: &str) -> String {
+    cell.replace('|', "\\|").replace(['\n', '\r'], " ")
+}
+
+impl Table {
+    fn column_widths(&self, minimum: usize) -> Vec<usize> {
+        (0..self.alignments.len())
+            .map(|column| {
+                let widest = self.rows.iter().filter_map(|row| row.get(column)).map(|cell| width(cell)).max();
+                widest.unwrap_or(0).max(minimum)
+            })
+            .collect()
+    }
+
+    fn cells<'a>(&'a self, row: &'a [String], widths: &[usize]) -> Vec<String> {
+        (0..widths.len())
+            .map(|column| {
+                let cell = row.get(column).map(String::as_str).unwrap_or("");
+                pad(cell.trim(), widths[column], self.alignments[column])
+            })
+            .collect()
+    }
+
+    /// 列已补齐对齐的管道表格。Markdown 需要表头行，没有时加一个空的
+    pub fn to_markdown(&self) -> String {
+        let widths = self.column_widths(3);
+        let line = |cells: Vec<String>| format!("| {} |\n", cells.join(" | "));
+        let blank = vec![String::new(); widths.len()];
+        let (header, body) = match self.header {
+            true => (self.rows.first().unwrap_or(&blank), self.rows.get(1..).unwrap_or_default()),
+            false => (&blank, &self.rows[..]),
+        };
+        let rule: Vec<String> = widths
+            .iter()
+            .zip(&self.alignments)
+            .map(|(&width, align)| match align {
+                Align::Left => "-".repeat(width),
+                Align::Center => format!(":{}:", "-".repeat(width - 2)),
+                Align::Right => format!("{}:", "-".repeat(width - 1)),
+            })
+            .collect();
+        let mut table = line(self.cells(header, &widths));
+        table.push_str(&line(rule));
+        for row in body {
+            table.push_str(&line(self.cells(row, &widths)));
+        }
+        table
+    }
+
+    /// `&` 已对齐的 `tabular`
+    pub fn to_latex(&self, booktabs: bool) -> String {
+        let widths = self.column_widths(0);
+        let spec: String = self
+            .alignments
+            .iter()
+            .map(|align| match align {
+                Align::Left => 'l',
+                Align::Center => 'c',
+                Align::Right => 'r',
+            })
+            .collect();
+        let (top, mid, bottom) =
+            if booktabs { ("\\toprule", "\\midrule", "\\bottomrule") } else { ("\\hline", "\\hline", "\\hline") };
+        let mut table = format!("\\begin{{tabular}}{{{}}}\n  {}\n", spec, top);
+        for (index, row) in self.rows.iter().enumerate() {
+            table.push_str(&format!("  {} \\\\\n", self.cells(row, &widths).join(" & ").trim_end()));
+            if index == 0 && self.header && self.rows.len() > 1 {
+                table.push_str(&format!("  {}\n", mid));
+            }
+        }
+        table.push_str(&format!("  {}\n\\end{{tabular}}\n", bottom));
+        table
+    }
+
+    pub fn render(&self, format: TableFormat) -> String {
+        match format {
+            TableFormat::Markdown => self.to_markdown(),
+            TableFormat::Latex => self.to_latex(false),
+            TableFormat::Booktabs => self.to_latex(true),
+        }
+    }
+}
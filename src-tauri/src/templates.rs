@@ -0,0 +1,326 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::SystemTime;
+
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Manager};
+
+use crate::atomic::{unix_millis, write_atomic};
+use crate::project::{config_path, write_config, ProjectConfig};
+use crate::scope::WindowScope;
+
+/// `<app data>/templates/<id>/`，每个用户模板一个目录
+const USER_TEMPLATES_DIR: &str = "templates";
+/// 用户模板目录中可选的元数据；从不复制
+const TEMPLATE_MANIFEST: &str = "template.toml";
+
+static PLACEHOLDER_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{\s*([A-Za-z_][A-Za-z0-9_]*)\s*\}\}").unwrap());
+
+struct BundledTemplate {
+    id: &'static str,
+    name: &'static str,
+    description: &'static str,
+    main: &'static str,
+    variables: &'static [&'static str],
+    files: &'static [(&'static str, &'static str)],
+}
+
+macro_rules! template_file {
+    ($id:literal, $path:literal) => {
+        ($path, include_str!(concat!("../templates/", $id, "/", $path)))
+    };
+}
+
+const BUNDLED: &[BundledTemplate] = &[
+    BundledTemplate {
+        id: "article",
+        name: "文章",
+        description: "带摘要和 BibTeX 参考文献的单文件论文",
+        main: "main.tex",
+        variables: &["title", "author"],
+        files: &[template_file!("article", "main.tex"), template_file!("article", "references.bib")],
+    },
+    BundledTemplate {
+        id: "beamer",
+        name: "Beamer 演示文稿",
+        description: "带标题页和目录的幻灯片",
+        main: "main.tex",
+        variables: &["title", "author", "institute"],
+        files: &[template_file!("beamer", "main.tex")],
+    },
+    BundledTemplate {
+        id: "thesis",
+        name: "学位论文",
+        description: "每章一个文件的报告",
+        main: "main.tex",
+        variables: &["title", "author"],
+        files: &[
+            template_file!("thesis", "main.tex"),
+            template_file!("thesis", "chapters/introduction.tex"),
+            template_file!("thesis", "chapters/conclusion.tex"),
+            template_file!("thesis", "references.bib"),
+        ],
+    },
+    BundledTemplate {
+        id: "cv",
+        name: "简历",
+        description: "一页的简历",
+        main: "main.tex",
+        variables: &["author", "email"],
+        files: &[template_file!("cv", "main.tex")],
+    },
+    BundledTemplate {
+        id: "markdown-note",
+        name: "Markdown 笔记",
+        description: "带 YAML front matter、可导出为 PDF 的笔记",
+        main: "note.md",
+        variables: &["title", "author", "date"],
+        files: &[template_file!("markdown-note", "note.md")],
+    },
+];
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateSource {
+    Bundled,
+    User,
+}
+
+#[derive(Serialize)]
+pub struct TemplateInfo {
+    id: String,
+    name: String,
+    description: String,
+    source: TemplateSource,
+    /// 前端应当询问的占位符；`date` 默认为今天
+    variables: Vec<String>,
+}
+
+/// 用户模板的 `template.toml`。所有字段都是可选的：目录名即 id，`main.tex`（或唯一的 `.tex`/`.md` 文件）为主文件，
+/// 变量从文件中的占位符读取
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct TemplateManifest {
+    name: Option<String>,
+    description: Option<String>,
+    main: Option<String>,
+    variables: Option<Vec<String>>,
+}
+
+#[derive(Serialize)]
+pub struct CreatedProject {
+    /// 最先打开的文件
+    main_file: String,
+    files: Vec<String>,
+}
+
+/// 模板的文件，相对其根目录，以字节存储，用户模板可以带图片
+struct TemplateFiles {
+    main: String,
+    variables: Vec<String>,
+    files: Vec<(PathBuf, Vec<u8>)>,
+}
+
+fn user_templates_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let base = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(base.join(USER_TEMPLATES_DIR))
+}
+
+fn read_manifest(dir: &Path) -> TemplateManifest {
+    fs::read_to_string(dir.join(TEMPLATE_MANIFEST))
+        .ok()
+        .and_then(|content| toml::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// `dir` 下的文件，相对 `dir`。跳过符号链接，模板无法引入别处的文件
+fn collect_files(dir: &Path, relative: &Path, files: &mut Vec<(PathBuf, Vec<u8>)>) -> Result<(), String> {
+    let entries = fs::read_dir(dir.join(relative)).map_err(|e| format!("无法读取目录: {}", e))?;
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = relative.join(entry.file_name());
+        if file_type.is_dir() {
+            collect_files(dir, &path, files)?;
+        } else if file_type.is_file() && path != Path::new(TEMPLATE_MANIFEST) {
+            let bytes = fs::read(dir.join(&path)).map_err(|e| format!("无法读取文件: {}", e))?;
+            files.push((path, bytes));
+        }
+    }
+    Ok(())
+}
+
+fn placeholders(files: &[(PathBuf, Vec<u8>)]) -> Vec<String> {
+    let mut names: Vec<String> = files
+        .iter()
+        .filter_map(|(_, bytes)| std::str::from_utf8(bytes).ok())
+        .flat_map(|text| PLACEHOLDER_RE.captures_iter(text).map(|caps| caps[1].to_string()))
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+fn guess_main(files: &[(PathBuf, Vec<u8>)]) -> Option<String> {
+    let names: Vec<String> = files.iter().map(|(path, _)| path.to_string_lossy().replace('\\', "/")).collect();
+    if let Some(main) = names.iter().find(|name| *name == "main.tex") {
+        return Some(main.clone());
+    }
+    let mut top_level = names.iter().filter(|name| !name.contains('/') && (name.ends_with(".tex") || name.ends_with(".md")));
+    match (top_level.next(), top_level.next()) {
+        (Some(only), None) => Some(only.clone()),
+        _ => None,
+    }
+}
+
+fn user_template_info(dir: &Path) -> Option<TemplateInfo> {
+    let id = dir.file_name()?.to_string_lossy().to_string();
+    let manifest = read_manifest(dir);
+    let variables = match manifest.variables {
+        Some(variables) => variables,
+        None => {
+            let mut files = Vec::new();
+            collect_files(dir, Path::new(""), &mut files).ok()?;
+            placeholders(&files)
+        }
+    };
+    Some(TemplateInfo {
+        name: manifest.name.unwrap_or_else(|| id.clone()),
+        description: manifest.description.unwrap_or_default(),
+        id,
+        source: TemplateSource::User,
+        variables,
+    })
+}
+
+fn load_template(app: &AppHandle, id: &str) -> Result<TemplateFiles, String> {
+    if let Some(template) = BUNDLED.iter().find(|template| template.id == id) {
+        return Ok(TemplateFiles {
+            main: template.main.to_string(),
+            variables: template.variables.iter().map(|name| name.to_string()).collect(),
+            files: template.files.iter().map(|(path, content)| (PathBuf::from(path), content.as_bytes().to_vec())).collect(),
+        });
+    }
+    // id 会成为路径的一部分；只允许单个目录名
+    let mut components = Path::new(id).components();
+    if !matches!((components.next(), components.next()), (Some(Component::Normal(_)), None)) {
+        return Err(format!("未知的模板: {}", id));
+    }
+    let dir = user_templates_dir(app)?.join(id);
+    if !dir.is_dir() {
+        return Err(format!("未知的模板: {}", id));
+    }
+    let manifest = read_manifest(&dir);
+    let mut files = Vec::new();
+    collect_files(&dir, Path::new(""), &mut files)?;
+    let main = manifest.main.or_else(|| guess_main(&files)).ok_or("模板没有 main.tex；请在 template.toml 中指定主文件")?;
+    let variables = manifest.variables.unwrap_or_else(|| placeholders(&files));
+    Ok(TemplateFiles { main, variables, files })
+}
+
+/// 今天的 `YYYY-MM-DD`（UTC），由 Unix 纪元以来的天数算出
+fn today() -> String {
+    let days = (unix_millis(SystemTime::now()) / 86_400_000) as i64;
+    // Howard Hinnant 的 civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// 替换模板声明的 `{{name}}` 占位符。没有值的已声明名称替换为空；其他 `{{...}}` 文本是 LaTeX，原样保留
+fn substitute(text: &str, declared: &[String], values: &HashMap<String, String>) -> String {
+    PLACEHOLDER_RE
+        .replace_all(text, |caps: &Captures| {
+            let name = &caps[1];
+            if !declared.iter().any(|declared| declared == name) {
+                return caps[0].to_string();
+            }
+            match values.get(name) {
+                Some(value) => value.clone(),
+                None if name == "date" => today(),
+                None => String::new(),
+            }
+        })
+        .into_owned()
+}
+
+/// 先是内置模板，然后是用户在 `<app data>/templates` 中的模板
+#[command]
+pub fn list_templates(app: AppHandle) -> Vec<TemplateInfo> {
+    let mut templates: Vec<TemplateInfo> = BUNDLED
+        .iter()
+        .map(|template| TemplateInfo {
+            id: template.id.to_string(),
+            name: template.name.to_string(),
+            description: template.description.to_string(),
+            source: TemplateSource::Bundled,
+            variables: template.variables.iter().map(|name| name.to_string()).collect(),
+        })
+        .collect();
+    let mut user: Vec<TemplateInfo> = user_templates_dir(&app)
+        .and_then(|dir| fs::read_dir(dir).map_err(|e| e.to_string()))
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+                .filter(|entry| BUNDLED.iter().all(|template| entry.file_name() != template.id))
+                .filter_map(|entry| user_template_info(&entry.path()))
+                .collect()
+        })
+        .unwrap_or_default();
+    user.sort_by_key(|template| template.name.to_lowercase());
+    templates.extend(user);
+    templates
+}
+
+/// 把模板复制到 `dest_dir`（必须在作用域内，用 `open_folder_dialog` 选择），并填入 `variables`。
+/// 模板的任何文件在那里已存在时不写入任何内容。LaTeX 模板还会得到一个指定根文档的项目配置
+#[command]
+pub fn create_from_template(
+    app: AppHandle,
+    scope: WindowScope,
+    template_id: String,
+    dest_dir: String,
+    variables: HashMap<String, String>,
+) -> Result<CreatedProject, String> {
+    scope.check(&dest_dir)?;
+    let dest = Path::new(&dest_dir);
+    let template = load_template(&app, &template_id)?;
+
+    if let Some((existing, _)) = template.files.iter().find(|(path, _)| dest.join(path).exists()) {
+        return Err(format!("文件已存在: {}", dest.join(existing).to_string_lossy()));
+    }
+
+    let mut written = Vec::new();
+    for (relative, bytes) in &template.files {
+        let path = dest.join(relative);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("无法创建目录: {}", e))?;
+        }
+        let content = match std::str::from_utf8(bytes) {
+            Ok(text) => substitute(text, &template.variables, &variables).into_bytes(),
+            Err(_) => bytes.clone(),
+        };
+        write_atomic(&path, &content).map_err(|e| format!("无法写入文件: {}", e))?;
+        written.push(path.to_string_lossy().to_string());
+    }
+
+    if template.main.ends_with(".tex") && !config_path(dest).exists() {
+        let config = ProjectConfig { root_document: Some(template.main.clone()), ..ProjectConfig::default() };
+        write_config(dest, &config)?;
+    }
+
+    Ok(CreatedProject { main_file: dest.join(&template.main).to_string_lossy().to_string(), files: written })
+}
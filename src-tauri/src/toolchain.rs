@@ -0,0 +1,210 @@
+use std::env;
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use flate2::read::GzDecoder;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tauri::{command, AppHandle, Manager};
+use ureq::Agent;
+
+use crate::atomic::write_atomic;
+use crate::settings::SettingsStore;
+
+/// `install_tectonic` 下载的版本。升级时要一并用它的输出测试日志解析器
+const TECTONIC_VERSION: &str = "0.15.0";
+const TECTONIC_RELEASES: &str = "https://github.com/tectonic-typesetting/tectonic/releases/download";
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(300);
+/// 发布的归档大约 20 MB
+const MAX_DOWNLOAD_SIZE: u64 = 200 * 1024 * 1024;
+
+struct Tool {
+    name: &'static str,
+    version_args: &'static [&'static str],
+    /// 没有它无法编译 LaTeX
+    required: bool,
+    purpose: &'static str,
+}
+
+const TOOLS: &[Tool] = &[
+    // 启用 `embedded-tectonic` 时引擎是内置的，只有配置了路径时才使用这个程序
+    Tool {
+        name: "tectonic",
+        version_args: &["--version"],
+        required: !cfg!(feature = "embedded-tectonic"),
+        purpose: "默认的 LaTeX 引擎",
+    },
+    Tool { name: "latexmk", version_args: &["-v"], required: false, purpose: "pdfLaTeX、XeLaTeX 和 LuaLaTeX 编译" },
+    Tool { name: "biber", version_args: &["--version"], required: false, purpose: "biblatex 参考文献" },
+    Tool { name: "bibtex", version_args: &["--version"], required: false, purpose: "BibTeX 参考文献" },
+    Tool { name: "latexdiff", version_args: &["--version"], required: false, purpose: "标出修订的 PDF" },
+    Tool { name: "dvisvgm", version_args: &["--version"], required: false, purpose: "公式的 SVG 预览" },
+    Tool { name: "gs", version_args: &["--version"], required: false, purpose: "把 EPS 图片转换为 PDF" },
+    Tool { name: "pandoc", version_args: &["--version"], required: false, purpose: "Markdown 导出" },
+    Tool { name: "synctex", version_args: &["help"], required: false, purpose: "在源文件和 PDF 之间跳转" },
+];
+
+#[derive(Serialize)]
+pub struct ToolStatus {
+    name: &'static str,
+    required: bool,
+    purpose: &'static str,
+    /// 没有找到工具时为 None
+    path: Option<String>,
+    /// 其版本输出的第一行
+    version: Option<String>,
+}
+
+fn executable_name(name: &str) -> String {
+    if cfg!(windows) {
+        format!("{}.exe", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// 与 `which` 相同：路径原样使用，单独的名称在 `PATH` 中查找
+fn find_executable(program: &str) -> Option<PathBuf> {
+    let path = Path::new(program);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    let names = if cfg!(windows) {
+        vec![executable_name(program), format!("{}.cmd", program), format!("{}.bat", program)]
+    } else {
+        vec![program.to_string()]
+    };
+    env::split_paths(&env::var_os("PATH")?)
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|candidate| candidate.is_file())
+}
+
+fn version_of(path: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new(path).args(args).output().ok()?;
+    // 有些工具把横幅输出到 stderr
+    [&output.stdout, &output.stderr].iter().find_map(|stream| {
+        let text = String::from_utf8_lossy(stream);
+        text.lines().map(str::trim).find(|line| !line.is_empty()).map(str::to_string)
+    })
+}
+
+/// `install_tectonic` 放置程序的位置
+pub fn installed_tectonic(app: &AppHandle) -> Result<PathBuf, String> {
+    let base = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(base.join("bin").join(executable_name("tectonic")))
+}
+
+/// 与编译器的顺序相同：配置的路径，然后是之前 `install_tectonic` 安装的，然后是 `PATH`
+fn locate(app: &AppHandle, tool: &Tool) -> Option<PathBuf> {
+    if tool.name != "tectonic" {
+        return find_executable(tool.name);
+    }
+    let configured = app.state::<SettingsStore>().get(app).tectonic_path.filter(|p| !p.is_empty());
+    configured
+        .and_then(|path| find_executable(&path))
+        .or_else(|| installed_tectonic(app).ok().filter(|path| path.is_file()))
+        .or_else(|| find_executable(tool.name))
+}
+
+fn status(app: &AppHandle, tool: &Tool) -> ToolStatus {
+    let path = locate(app, tool);
+    ToolStatus {
+        name: tool.name,
+        required: tool.required,
+        purpose: tool.purpose,
+        version: path.as_deref().and_then(|path| version_of(path, tool.version_args)),
+        path: path.map(|path| path.to_string_lossy().to_string()),
+    }
+}
+
+/// 本平台发布资源的目标三元组和归档格式
+fn release_asset() -> Result<(&'static str, &'static str), String> {
+    let asset = match (env::consts::OS, env::consts::ARCH) {
+        ("linux", "x86_64") => ("x86_64-unknown-linux-musl", "tar.gz"),
+        ("linux", "aarch64") => ("aarch64-unknown-linux-musl", "tar.gz"),
+        ("macos", "x86_64") => ("x86_64-apple-darwin", "tar.gz"),
+        ("macos", "aarch64") => ("aarch64-apple-darwin", "tar.gz"),
+        ("windows", "x86_64") => ("x86_64-pc-windows-msvc", "zip"),
+        (os, arch) => return Err(format!("没有 {}-{} 的预编译 tectonic；请手动安装", os, arch)),
+    };
+    Ok(asset)
+}
+
+fn download(url: &str) -> Result<Vec<u8>, String> {
+    let agent: Agent = Agent::config_builder().timeout_global(Some(DOWNLOAD_TIMEOUT)).build().into();
+    let mut response = agent.get(url).call().map_err(|e| format!("下载失败: {}", e))?;
+    response
+        .body_mut()
+        .with_config()
+        .limit(MAX_DOWNLOAD_SIZE)
+        .read_to_vec()
+        .map_err(|e| format!("下载失败: {}", e))
+}
+
+fn extract(archive: &[u8], format: &str, binary: &str) -> Result<Vec<u8>, String> {
+    let mut contents = Vec::new();
+    if format == "zip" {
+        let mut zip = zip::ZipArchive::new(Cursor::new(archive)).map_err(|e| format!("无效的归档: {}", e))?;
+        let mut entry = zip.by_name(binary).map_err(|e| format!("无效的归档: {}", e))?;
+        entry.read_to_end(&mut contents).map_err(|e| e.to_string())?;
+        return Ok(contents);
+    }
+    let mut tar = tar::Archive::new(GzDecoder::new(archive));
+    for entry in tar.entries().map_err(|e| format!("无效的归档: {}", e))? {
+        let mut entry = entry.map_err(|e| format!("无效的归档: {}", e))?;
+        let is_binary = entry.path().ok().and_then(|p| p.file_name().map(|n| n == binary)).unwrap_or(false);
+        if is_binary {
+            entry.read_to_end(&mut contents).map_err(|e| e.to_string())?;
+            return Ok(contents);
+        }
+    }
+    Err(format!("归档中没有找到 {}", binary))
+}
+
+fn install_blocking(app: &AppHandle) -> Result<ToolStatus, String> {
+    let (target, format) = release_asset()?;
+    let url = format!(
+        "{}/tectonic%40{}/tectonic-{}-{}.{}",
+        TECTONIC_RELEASES, TECTONIC_VERSION, TECTONIC_VERSION, target, format
+    );
+    let binary_name = executable_name("tectonic");
+    let binary = extract(&download(&url)?, format, &binary_name)?;
+
+    let path = installed_tectonic(app)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("无法创建目录: {}", e))?;
+    }
+    write_atomic(&path, &binary).map_err(|e| format!("无法写入文件: {}", e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).map_err(|e| e.to_string())?;
+    }
+
+    let tool = TOOLS.iter().find(|tool| tool.name == "tectonic").expect("tectonic is listed");
+    let version = version_of(&path, tool.version_args).ok_or("下载的 tectonic 无法在本系统上运行")?;
+    let path = path.to_string_lossy().to_string();
+    let mut partial = Map::new();
+    partial.insert("tectonic_path".to_string(), Value::String(path.clone()));
+    app.state::<SettingsStore>().update(app, partial)?;
+    Ok(ToolStatus { name: tool.name, required: tool.required, purpose: tool.purpose, path: Some(path), version: Some(version) })
+}
+
+/// 哪些外部工具可用，及其路径和版本，前端可以在编译失败之前说明缺了什么
+#[command]
+pub async fn check_toolchain(app: AppHandle) -> Result<Vec<ToolStatus>, String> {
+    tauri::async_runtime::spawn_blocking(move || TOOLS.iter().map(|tool| status(&app, tool)).collect())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 把固定版本的 tectonic 下载到应用数据目录，并让 `tectonic_path` 设置指向它
+#[command]
+pub async fn install_tectonic(app: AppHandle) -> Result<ToolStatus, String> {
+    tauri::async_runtime::spawn_blocking(move || install_blocking(&app))
+        .await
+        .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,381 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle};
+
+use crate::atomic::{unix_millis, write_atomic};
+use crate::compiler::latexdiff::{compile_diff, OldVersion};
+use crate::compiler::{CompileError, CompileResult, EngineKind};
+use crate::document::content_version;
+use crate::history::{diff_lines, DiffOp};
+use crate::latex::root::canonical;
+use crate::project::{config_dir, state_key, state_root};
+use crate::scope::WindowScope;
+
+/// 在项目的 `.mymd` 目录中，随项目一起流转（git、同步），每个协作者看到的审阅都相同
+const CHANGES_FILE: &str = "changes.json";
+/// 计入修改 id 的修改前未变文本的字节数，文件其他地方的编辑不影响 id
+const ID_CONTEXT: usize = 32;
+
+/// 谁做了修改，以及第一次看到它的时间
+#[derive(Serialize, Deserialize, Clone)]
+struct Annotation {
+    author: String,
+    /// Unix 毫秒
+    time: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TrackedFile {
+    /// 衡量修改的基准文本：开始追踪时的文件，加上所有已接受的修改
+    baseline: String,
+    /// 开始追踪的人；调用方没有指定别人时，新的修改记在他名下
+    author: String,
+    annotations: BTreeMap<String, Annotation>,
+}
+
+/// 按相对项目根目录的路径（以 `/` 分隔）记录的追踪文件
+#[derive(Serialize, Deserialize, Default)]
+struct ChangesFile {
+    files: BTreeMap<String, TrackedFile>,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Insertion,
+    Deletion,
+    /// 删除的文本和替换它插入的文本
+    Replacement,
+}
+
+#[derive(Serialize)]
+pub struct TrackedChange {
+    id: String,
+    kind: ChangeKind,
+    /// 修改在当前文本中的位置：从 1 开始的行号和按字符计的列号，结束位置不含，与 Monaco 一致。
+    /// 删除为原文本所在处的空区间
+    start_line: u32,
+    start_column: u32,
+    end_line: u32,
+    end_column: u32,
+    inserted: String,
+    deleted: String,
+    author: String,
+    /// Unix 毫秒
+    time: u64,
+}
+
+/// 基准与当前文本之间的一处修改，以字节范围表示
+struct Change {
+    id: String,
+    baseline: Range<usize>,
+    current: Range<usize>,
+}
+
+/// 单词、连续空白和单个其他字符，diff 读起来像 Word 的修订，不会出现半个单词的修改
+fn tokens(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut previous: Option<u8> = None;
+    for (index, c) in text.char_indices() {
+        let class = if c.is_alphanumeric() || c == '_' {
+            0
+        } else if c.is_whitespace() {
+            1
+        } else {
+            2
+        };
+        if index > start && (previous != Some(class) || class == 2) {
+            tokens.push(&text[start..index]);
+            start = index;
+        }
+        previous = Some(class);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+/// 每个词元开始的字节偏移，外加文本的结尾
+fn token_offsets(tokens: &[&str]) -> Vec<usize> {
+    let mut offsets = Vec::with_capacity(tokens.len() + 1);
+    let mut offset = 0;
+    offsets.push(0);
+    for token in tokens {
+        offset += token.len();
+        offsets.push(offset);
+    }
+    offsets
+}
+
+fn diff(baseline: &str, current: &str) -> Vec<Change> {
+    let (old, new) = (tokens(baseline), tokens(current));
+    let (old_offsets, new_offsets) = (token_offsets(&old), token_offsets(&new));
+    let ops = diff_lines(&old, &new);
+    let mut changes = Vec::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut k = 0;
+    while k < ops.len() {
+        if ops[k].0 == DiffOp::Equal {
+            k += 1;
+            continue;
+        }
+        let (_, old_start, new_start) = ops[k];
+        let (mut old_end, mut new_end) = (old_start, new_start);
+        while k < ops.len() && ops[k].0 != DiffOp::Equal {
+            match ops[k].0 {
+                DiffOp::Delete => old_end = ops[k].1 + 1,
+                _ => new_end = ops[k].2 + 1,
+            }
+            k += 1;
+        }
+        let baseline_range = old_offsets[old_start]..old_offsets[old_end];
+        let current_range = new_offsets[new_start]..new_offsets[new_end];
+        let mut context_start = baseline_range.start.saturating_sub(ID_CONTEXT);
+        while !baseline.is_char_boundary(context_start) {
+            context_start -= 1;
+        }
+        let key = format!(
+            "{}\0{}\0{}",
+            &baseline[context_start..baseline_range.start],
+            &baseline[baseline_range.clone()],
+            &current[current_range.clone()]
+        );
+        let mut id = content_version(key.as_bytes());
+        // 紧跟在相同文本之后的同一处编辑做了两次
+        let count = seen.entry(id.clone()).or_default();
+        *count += 1;
+        if *count > 1 {
+            id = format!("{}-{}", id, count);
+        }
+        changes.push(Change { id, baseline: baseline_range, current: current_range });
+    }
+    changes
+}
+
+/// 字节 `offset` 所在的行和字符列，从 1 开始
+fn position(text: &str, offset: usize) -> (u32, u32) {
+    let before = &text[..offset];
+    let line = before.matches('\n').count() as u32 + 1;
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    (line, before[line_start..].chars().count() as u32 + 1)
+}
+
+fn read_changes(root: &Path) -> Result<ChangesFile, String> {
+    let path = config_dir(root).join(CHANGES_FILE);
+    match fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content).map_err(|e| format!("无效的 {}: {}", path.display(), e)),
+        Err(_) => Ok(ChangesFile::default()),
+    }
+}
+
+fn write_changes(root: &Path, changes: &ChangesFile) -> Result<(), String> {
+    let dir = config_dir(root);
+    if changes.files.is_empty() {
+        let _ = fs::remove_file(dir.join(CHANGES_FILE));
+        return Ok(());
+    }
+    fs::create_dir_all(&dir).map_err(|e| format!("无法创建目录: {}", e))?;
+    let content = serde_json::to_string_pretty(changes).map_err(|e| e.to_string())?;
+    write_atomic(&dir.join(CHANGES_FILE), content.as_bytes()).map_err(|e| format!("无法写入文件: {}", e))?;
+    Ok(())
+}
+
+fn read_text(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| format!("无法读取文件: {}", e))
+}
+
+/// 项目中的一个追踪文件，为一次操作加载
+struct Tracked {
+    root: PathBuf,
+    key: String,
+    changes: ChangesFile,
+}
+
+impl Tracked {
+    fn open(path: &Path) -> Result<Self, String> {
+        let path = canonical(path);
+        let root = canonical(&state_root(&path, CHANGES_FILE));
+        let key = state_key(&root, &path)?;
+        let changes = read_changes(&root)?;
+        if !changes.files.contains_key(&key) {
+            return Err(format!("{} 的修改没有被追踪", path.display()));
+        }
+        Ok(Tracked { root, key, changes })
+    }
+
+    fn file(&mut self) -> &mut TrackedFile {
+        self.changes.files.get_mut(&self.key).expect("tracked file checked in open")
+    }
+
+    /// `current` 中的修改，第一次看到的记在 `author` 名下，并忘掉已不存在的修改的标注
+    fn list(&mut self, current: &str, author: Option<&str>) -> Vec<TrackedChange> {
+        let file = self.file();
+        let changes = diff(&file.baseline, current);
+        let now = unix_millis(SystemTime::now());
+        let author = author.filter(|author| !author.is_empty()).unwrap_or(&file.author).to_string();
+        file.annotations.retain(|id, _| changes.iter().any(|change| &change.id == id));
+        changes
+            .into_iter()
+            .map(|change| {
+                let annotation = file
+                    .annotations
+                    .entry(change.id.clone())
+                    .or_insert_with(|| Annotation { author: author.clone(), time: now })
+                    .clone();
+                let deleted = file.baseline[change.baseline].to_string();
+                let inserted = current[change.current.clone()].to_string();
+                let kind = match (deleted.is_empty(), inserted.is_empty()) {
+                    (true, _) => ChangeKind::Insertion,
+                    (_, true) => ChangeKind::Deletion,
+                    _ => ChangeKind::Replacement,
+                };
+                let (start_line, start_column) = position(current, change.current.start);
+                let (end_line, end_column) = position(current, change.current.end);
+                TrackedChange {
+                    id: change.id,
+                    kind,
+                    start_line,
+                    start_column,
+                    end_line,
+                    end_column,
+                    inserted,
+                    deleted,
+                    author: annotation.author,
+                    time: annotation.time,
+                }
+            })
+            .collect()
+    }
+
+    fn find(&mut self, current: &str, id: &str) -> Result<Change, String> {
+        diff(&self.file().baseline, current)
+            .into_iter()
+            .find(|change| change.id == id)
+            .ok_or_else(|| "该修改已不存在；请重新列出修改".to_string())
+    }
+
+    fn save(&self) -> Result<(), String> {
+        write_changes(&self.root, &self.changes)
+    }
+}
+
+fn start_blocking(path: &Path, author: &str) -> Result<(), String> {
+    let path = canonical(path);
+    let root = canonical(&state_root(&path, CHANGES_FILE));
+    let key = state_key(&root, &path)?;
+    let mut changes = read_changes(&root)?;
+    if changes.files.contains_key(&key) {
+        return Ok(());
+    }
+    let file = TrackedFile { baseline: read_text(&path)?, author: author.to_string(), annotations: BTreeMap::new() };
+    changes.files.insert(key, file);
+    write_changes(&root, &changes)
+}
+
+fn resolve_blocking(path: &Path, id: &str, accept: bool) -> Result<Vec<TrackedChange>, String> {
+    let mut tracked = Tracked::open(path)?;
+    let mut current = read_text(path)?;
+    let change = tracked.find(&current, id)?;
+    let file = tracked.file();
+    if accept {
+        file.baseline.replace_range(change.baseline, &current[change.current]);
+    } else {
+        current.replace_range(change.current, &file.baseline[change.baseline]);
+        write_atomic(path, current.as_bytes()).map_err(|e| format!("无法写入文件: {}", e))?;
+    }
+    let changes = tracked.list(&current, None);
+    tracked.save()?;
+    Ok(changes)
+}
+
+async fn run_blocking<T, F>(f: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(f).await.map_err(|e| e.to_string())?
+}
+
+/// 以 `path` 当前的内容为基准开始记录修改；没人认领的修改记在 `author` 名下
+#[command]
+pub async fn start_tracking(scope: WindowScope, path: String, author: String) -> Result<(), String> {
+    scope.check(&path)?;
+    run_blocking(move || start_blocking(Path::new(&path), &author)).await
+}
+
+/// 停止追踪 `path`，保留其当前内容，丢弃修改记录
+#[command]
+pub async fn stop_tracking(scope: WindowScope, path: String) -> Result<(), String> {
+    scope.check(&path)?;
+    run_blocking(move || {
+        let mut tracked = Tracked::open(Path::new(&path))?;
+        tracked.changes.files.remove(&tracked.key);
+        tracked.save()
+    })
+    .await
+}
+
+/// 开始追踪以来 `path` 中的插入和删除，按文档顺序排列。`content` 是编辑器中未保存的文本；
+/// 没有时与磁盘上的文件比较。新的修改记在 `author` 名下
+#[command]
+pub async fn list_changes(
+    scope: WindowScope,
+    path: String,
+    content: Option<String>,
+    author: Option<String>,
+) -> Result<Vec<TrackedChange>, String> {
+    scope.check(&path)?;
+    run_blocking(move || {
+        let mut tracked = Tracked::open(Path::new(&path))?;
+        let current = match content {
+            Some(content) => content,
+            None => read_text(Path::new(&path))?,
+        };
+        let changes = tracked.list(&current, author.as_deref());
+        tracked.save()?;
+        Ok(changes)
+    })
+    .await
+}
+
+/// 保留一处修改：它成为基准的一部分。返回剩下的修改
+#[command]
+pub async fn accept_change(scope: WindowScope, path: String, id: String) -> Result<Vec<TrackedChange>, String> {
+    scope.check(&path)?;
+    run_blocking(move || resolve_blocking(Path::new(&path), &id, true)).await
+}
+
+/// 在磁盘上的文件中撤销一处修改，所以要先保存编辑器。返回剩下的修改
+#[command]
+pub async fn reject_change(scope: WindowScope, path: String, id: String) -> Result<Vec<TrackedChange>, String> {
+    scope.check(&path)?;
+    run_blocking(move || resolve_blocking(Path::new(&path), &id, false)).await
+}
+
+/// 为 `path` 的根文档编译 latexdiff PDF，标出项目中所有追踪的修改，删除用删除线、插入用下划线
+#[command]
+pub async fn export_tracked_changes(
+    app: AppHandle,
+    scope: WindowScope,
+    path: String,
+    job_id: Option<String>,
+    engine: Option<EngineKind>,
+    return_path: Option<bool>,
+) -> Result<CompileResult, Vec<CompileError>> {
+    scope.check(&path).map_err(|e| vec![CompileError::simple(e)])?;
+    let canonical_path = canonical(Path::new(&path));
+    let root = canonical(&state_root(&canonical_path, CHANGES_FILE));
+    let changes = read_changes(&root).map_err(|e| vec![CompileError::simple(e)])?;
+    if changes.files.is_empty() {
+        return Err(vec![CompileError::simple("项目中没有追踪的修改")]);
+    }
+    let baselines = changes.files.into_iter().map(|(key, file)| (canonical(&root.join(key)), file.baseline)).collect();
+    compile_diff(app, scope, OldVersion::Baselines(baselines), path, job_id, engine, return_path).await
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::thread;
+
+use notify::event::{EventKind, ModifyKind};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use tauri::{command, AppHandle, Emitter, Manager, State, Window};
+
+use crate::index::ProjectIndex;
+use crate::scope::WindowScope;
+
+/// 监视的根目录下有任何改动时发送的事件
+pub const FS_CHANGED_EVENT: &str = "fs-changed";
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FsChangeKind {
+    Created,
+    Modified,
+    Removed,
+    Renamed,
+}
+
+#[derive(Clone, Serialize)]
+pub struct FsChangeEvent {
+    pub root: String,
+    pub kind: FsChangeKind,
+    /// 重命名时，如果平台报告了两边，则为 `[from, to]`
+    pub paths: Vec<String>,
+}
+
+/// 活动的监视器，以启动它的窗口和启动时的根路径为键；每个窗口只收到自己根目录的事件。
+/// 丢弃监视器会关闭其通道，转发线程随之结束
+#[derive(Default)]
+pub struct Watchers {
+    watchers: Mutex<HashMap<(String, String), RecommendedWatcher>>,
+}
+
+impl Watchers {
+    /// 停止一个窗口启动的所有监视器；窗口关闭时调用
+    pub fn remove_window(&self, label: &str) {
+        self.watchers.lock().unwrap().retain(|(owner, _), _| owner != label);
+    }
+}
+
+#[command]
+pub fn watch_directory(
+    app: AppHandle,
+    window: Window,
+    watchers: State<'_, Watchers>,
+    scope: WindowScope,
+    root: String,
+) -> Result<(), String> {
+    let root_path = PathBuf::from(&root);
+    scope.check(&root_path)?;
+    if !root_path.is_dir() {
+        return Err(format!("不是目录: {}", root));
+    }
+
+    let key = (window.label().to_string(), root.clone());
+    let mut active = watchers.watchers.lock().unwrap();
+    if active.contains_key(&key) {
+        return Ok(());
+    }
+
+    let (tx, rx) = mpsc::channel::<notify::Result<notify::Event>>();
+    let mut watcher = notify::recommended_watcher(tx).map_err(|e| format!("无法创建文件监听: {}", e))?;
+    watcher
+        .watch(&root_path, RecursiveMode::Recursive)
+        .map_err(|e| format!("无法监听目录: {}", e))?;
+
+    // 在用户还在看文件树时就为文件夹建立索引；监视器让它保持最新，标签和引用都从它读取
+    app.state::<ProjectIndex>().watch(&root_path);
+    let index_app = app.clone();
+    let index_root = root_path.clone();
+    thread::spawn(move || {
+        index_app.state::<ProjectIndex>().build(&index_root);
+    });
+
+    let event_root = root;
+    let label = key.0.clone();
+    thread::spawn(move || {
+        for event in rx {
+            let Ok(event) = event else {
+                continue;
+            };
+            let kind = match event.kind {
+                EventKind::Create(_) => FsChangeKind::Created,
+                EventKind::Remove(_) => FsChangeKind::Removed,
+                EventKind::Modify(ModifyKind::Name(_)) => FsChangeKind::Renamed,
+                EventKind::Modify(_) | EventKind::Any | EventKind::Other => FsChangeKind::Modified,
+                EventKind::Access(_) => continue,
+            };
+            app.state::<ProjectIndex>().update(&root_path, &event.paths);
+            let payload = FsChangeEvent {
+                root: event_root.clone(),
+                kind,
+                paths: event.paths.iter().map(|p| p.to_string_lossy().to_string()).collect(),
+            };
+            let _ = app.emit_to(label.as_str(), FS_CHANGED_EVENT, payload);
+        }
+        // 监视器已被丢弃：索引不再跟随磁盘
+        app.state::<ProjectIndex>().unwatch(&root_path);
+    });
+
+    active.insert(key, watcher);
+    Ok(())
+}
+
+#[command]
+pub fn unwatch_directory(window: Window, watchers: State<'_, Watchers>, root: String) -> Result<(), String> {
+    watchers
+        .watchers
+        .lock()
+        .unwrap()
+        .remove(&(window.label().to_string(), root.clone()))
+        .map(|_| ())
+        .ok_or_else(|| format!("没有在监视该目录: {}", root))
+}
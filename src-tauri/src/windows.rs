@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use tauri::{command, AppHandle, Manager, State, WebviewUrl, WebviewWindowBuilder, Window};
+
+use crate::compiler::{CompileQueue, WatchBuilds};
+use crate::document::content_version;
+use crate::latex::root::canonical;
+use crate::scope::{FsScope, WindowScope};
+use crate::watcher::Watchers;
+
+/// 由 `tauri.conf.json` 创建的窗口的标签
+pub const MAIN_WINDOW: &str = "main";
+/// 项目窗口的标签为 `project-<根目录的哈希>`，重新打开文件夹时能再找到它的窗口（及其保存的会话）
+const PROJECT_WINDOW_PREFIX: &str = "project-";
+
+/// 每个项目窗口为之打开的文件夹。主窗口不在其中；它打开会话中的或用户选择的任何内容
+#[derive(Default)]
+pub struct ProjectWindows {
+    roots: Mutex<HashMap<String, PathBuf>>,
+}
+
+impl ProjectWindows {
+    pub fn root_of(&self, label: &str) -> Option<PathBuf> {
+        self.roots.lock().unwrap().get(label).cloned()
+    }
+}
+
+fn project_label(root: &Path) -> String {
+    let hash = content_version(root.to_string_lossy().as_bytes());
+    format!("{}{}", PROJECT_WINDOW_PREFIX, &hash[..12])
+}
+
+/// 在单独的窗口中打开 `root`，或聚焦已经打开它的窗口。返回窗口的标签。新窗口从一个文件夹为 `root` 的空会话
+/// 和只含 `root` 的作用域开始；它的监视器和监视编译属于它，也只向它报告
+#[command]
+pub fn open_project_window(
+    app: AppHandle,
+    scope: WindowScope,
+    windows: State<'_, ProjectWindows>,
+    root: String,
+) -> Result<String, String> {
+    scope.check(&root)?;
+    let root = canonical(Path::new(&root));
+    if !root.is_dir() {
+        return Err(format!("不是目录: {}", root.to_string_lossy()));
+    }
+    let label = project_label(&root);
+    if let Some(window) = app.get_webview_window(&label) {
+        window.unminimize().ok();
+        window.set_focus().map_err(|e| e.to_string())?;
+        return Ok(label);
+    }
+
+    let folder_name = root.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    app.state::<FsScope>().allow(&label, &root);
+    windows.roots.lock().unwrap().insert(label.clone(), root);
+    let created = WebviewWindowBuilder::new(&app, &label, WebviewUrl::App("index.html".into()))
+        .title(format!("{} — latex-editor", folder_name))
+        .inner_size(800.0, 600.0)
+        .build();
+    if let Err(e) = created {
+        app.state::<FsScope>().remove_window(&label);
+        windows.roots.lock().unwrap().remove(&label);
+        return Err(format!("无法打开窗口: {}", e));
+    }
+    Ok(label)
+}
+
+/// 调用窗口为之打开的文件夹，主窗口为 `None`
+#[command]
+pub fn window_project(window: Window, windows: State<'_, ProjectWindows>) -> Option<String> {
+    windows.root_of(window.label()).map(|root| root.to_string_lossy().to_string())
+}
+
+/// 停止已关闭窗口启动的一切，它的监视器不会继续向已不存在的标签发送事件，并丢弃它的作用域
+pub fn window_destroyed(window: &Window) {
+    let label = window.label();
+    let app = window.app_handle();
+    app.state::<Watchers>().remove_window(label);
+    app.state::<WatchBuilds>().remove_window(label);
+    app.state::<CompileQueue>().remove_window(label);
+    app.state::<FsScope>().remove_window(label);
+    app.state::<ProjectWindows>().roots.lock().unwrap().remove(label);
+}
@@ -0,0 +1,132 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use ignore::WalkBuilder;
+use serde::Serialize;
+use tauri::command;
+
+use crate::scope::WindowScope;
+
+/// 项目内的忽略文件，语法与 `.gitignore` 相同
+pub const IGNORE_FILE_NAME: &str = ".mymdignore";
+
+/// 永远不在项目视图中显示的目录
+const EXCLUDED_DIRS: &[&str] = &["AuxiliaryFiles", ".git", "node_modules", ".mymd"];
+
+/// TeX 引擎在源文件旁边生成的中间文件
+const BUILD_ARTIFACT_EXTENSIONS: &[&str] = &[
+    "aux", "log", "out", "toc", "lof", "lot", "bbl", "blg", "bcf", "fls",
+    "fdb_latexmk", "synctex", "xdv", "nav", "snm", "run.xml",
+];
+
+#[derive(Serialize)]
+pub struct FileTreeNode {
+    name: String,
+    path: String,
+    is_dir: bool,
+    children: Vec<FileTreeNode>,
+}
+
+pub fn is_build_artifact(path: &Path) -> bool {
+    let Some(name) = path.file_name().map(|n| n.to_string_lossy().to_lowercase()) else {
+        return false;
+    };
+    if name.ends_with(".synctex.gz") {
+        return true;
+    }
+    BUILD_ARTIFACT_EXTENSIONS
+        .iter()
+        .any(|ext| name.ends_with(&format!(".{}", ext)))
+}
+
+/// `relative`（相对项目根目录）是否会被 `project_walker` 按名字跳过：编译产物或位于排除目录下的任何文件。
+/// 不读取忽略文件
+pub fn is_excluded(relative: &Path) -> bool {
+    let in_excluded_dir = relative
+        .parent()
+        .is_some_and(|dir| dir.iter().any(|part| EXCLUDED_DIRS.contains(&part.to_string_lossy().as_ref())));
+    in_excluded_dir || relative.file_name().is_some_and(|name| name == ".DS_Store") || is_build_artifact(relative)
+}
+
+/// 所有项目级功能共用的遍历器：遵循 `.gitignore`（即使不在 git 仓库中）和 `.mymdignore`，并跳过编译输出
+pub fn project_walker(root: &Path) -> WalkBuilder {
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .hidden(false)
+        .require_git(false)
+        .add_custom_ignore_filename(IGNORE_FILE_NAME)
+        .filter_entry(|entry| {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            let name = entry.file_name().to_string_lossy();
+            if is_dir {
+                !EXCLUDED_DIRS.contains(&name.as_ref())
+            } else {
+                name != ".DS_Store" && !is_build_artifact(entry.path())
+            }
+        });
+    builder
+}
+
+fn sort_nodes(nodes: &mut [FileTreeNode]) {
+    nodes.sort_by(|a, b| {
+        if a.is_dir == b.is_dir {
+            a.name.cmp(&b.name)
+        } else if a.is_dir {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    });
+}
+
+#[command]
+pub fn list_files_recursive(
+    scope: WindowScope,
+    root: String,
+    max_depth: Option<usize>,
+) -> Result<Vec<FileTreeNode>, String> {
+    let root = PathBuf::from(root);
+    scope.check(&root)?;
+    if !root.is_dir() {
+        return Err(format!("无法读取目录: {}", root.to_string_lossy()));
+    }
+
+    let mut entries = Vec::new();
+    for entry in project_walker(&root).max_depth(max_depth).build() {
+        // 某个子文件夹读不了不应让项目的其余部分也看不到
+        let Ok(entry) = entry else {
+            continue;
+        };
+        if entry.depth() == 0 {
+            continue;
+        }
+        let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+        entries.push((entry.depth(), entry.into_path(), is_dir));
+    }
+
+    // 自底向上构建，每个目录挂到父目录上时，它的子项都已收集好
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.0));
+    let mut children: HashMap<PathBuf, Vec<FileTreeNode>> = HashMap::new();
+    for (_, path, is_dir) in entries {
+        let mut node_children = children.remove(&path).unwrap_or_default();
+        sort_nodes(&mut node_children);
+        let node = FileTreeNode {
+            name: path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+            path: path.to_string_lossy().to_string(),
+            is_dir,
+            children: node_children,
+        };
+        let parent = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        children.entry(parent).or_default().push(node);
+    }
+
+    let mut tree = children.remove(&root).unwrap_or_default();
+    sort_nodes(&mut tree);
+    Ok(tree)
+}
+
+/// 与 git 和 ripgrep 相同的判断方法：前 8 KiB 中出现 NUL 字节即视为二进制
+pub fn looks_binary(bytes: &[u8]) -> bool {
+    bytes.iter().take(8192).any(|b| *b == 0)
+}
@@ -1,6 +1,6 @@
 import { useEffect, useMemo, useRef, useState } from "react";
-import { invoke } from "@tauri-apps/api/core";
-import { open, save } from "@tauri-apps/plugin-dialog";
+import { convertFileSrc, invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import Editor from "@monaco-editor/react";
 import * as pdfjsLib from "pdfjs-dist/legacy/build/pdf.mjs";
 import pdfjsWorker from "pdfjs-dist/legacy/build/pdf.worker.min.mjs?url";
@@ -8,6 +8,17 @@ import "./App.css";
 
 pdfjsLib.GlobalWorkerOptions.workerSrc = pdfjsWorker;
 
+// 与后端 compiler::queue::SUPERSEDED_MESSAGE 一致
+const COMPILE_SUPERSEDED = "Compilation superseded by a newer request";
+
+// 应用设置中的主题 -> Monaco 主题；system 跟随系统深浅色
+function editorTheme(theme) {
+    if (theme === "system") {
+        return window.matchMedia("(prefers-color-scheme: light)").matches ? "vs" : "vs-dark";
+    }
+    return theme === "light" ? "vs" : "vs-dark";
+}
+
 function registerLatexLanguage(monaco) {
     monaco.languages.register({ id: "latex" });
     monaco.languages.setMonarchTokensProvider("latex", {
@@ -44,51 +55,105 @@ function registerLatexLanguage(monaco) {
     });
 }
 
-function registerLatexCompletions(monaco) {
+function staticLatexSuggestions(monaco, range) {
+    return [
+        {
+            label: "\\item",
+            kind: monaco.languages.CompletionItemKind.Keyword,
+            insertText: "\\item",
+            range
+        },
+        {
+            label: "\\begin{itemize}",
+            kind: monaco.languages.CompletionItemKind.Snippet,
+            insertText: "\\begin{itemize}\n\\item ${1:item}\n\\end{itemize}",
+            insertTextRules: monaco.languages.CompletionItemInsertTextRule.InsertAsSnippet,
+            range
+        },
+        {
+            label: "\\begin{enumerate}",
+            kind: monaco.languages.CompletionItemKind.Snippet,
+            insertText: "\\begin{enumerate}\n\\item ${1:item}\n\\end{enumerate}",
+            insertTextRules: monaco.languages.CompletionItemInsertTextRule.InsertAsSnippet,
+            range
+        },
+        {
+            label: "\\begin{equation}",
+            kind: monaco.languages.CompletionItemKind.Snippet,
+            insertText: "\\begin{equation}\n${1:equation}\n\\end{equation}",
+            insertTextRules: monaco.languages.CompletionItemInsertTextRule.InsertAsSnippet,
+            range
+        }
+    ];
+}
+
+function completionKind(monaco, kind) {
+    const kinds = monaco.languages.CompletionItemKind;
+    switch (kind) {
+        case "command":
+            return kinds.Function;
+        case "environment":
+            return kinds.Struct;
+        case "label":
+            return kinds.Reference;
+        case "citation":
+            return kinds.Value;
+        case "file":
+            return kinds.File;
+        case "package":
+            return kinds.Module;
+        default:
+            return kinds.Text;
+    }
+}
+
+function registerLatexCompletions(monaco, getPath) {
     return monaco.languages.registerCompletionItemProvider("latex", {
-        triggerCharacters: ["\\"],
-        provideCompletionItems(model, position) {
+        triggerCharacters: ["\\", "{", ",", "/"],
+        async provideCompletionItems(model, position) {
             const word = model.getWordUntilPosition(position);
             const linePrefix = model.getLineContent(position.lineNumber);
             const backslashColumn =
                 word.startColumn > 1 && linePrefix[word.startColumn - 2] === "\\" ? word.startColumn - 1 : word.startColumn;
-            const range = {
+            const fallbackRange = {
                 startLineNumber: position.lineNumber,
                 startColumn: backslashColumn,
                 endLineNumber: position.lineNumber,
                 endColumn: word.endColumn
             };
-            return {
-                suggestions: [
-                    {
-                        label: "\\item",
-                        kind: monaco.languages.CompletionItemKind.Keyword,
-                        insertText: "\\item",
-                        range
-                    },
-                    {
-                        label: "\\begin{itemize}",
-                        kind: monaco.languages.CompletionItemKind.Snippet,
-                        insertText: "\\begin{itemize}\n\\item ${1:item}\n\\end{itemize}",
-                        insertTextRules: monaco.languages.CompletionItemInsertTextRule.InsertAsSnippet,
-                        range
-                    },
-                    {
-                        label: "\\begin{enumerate}",
-                        kind: monaco.languages.CompletionItemKind.Snippet,
-                        insertText: "\\begin{enumerate}\n\\item ${1:item}\n\\end{enumerate}",
-                        insertTextRules: monaco.languages.CompletionItemInsertTextRule.InsertAsSnippet,
-                        range
-                    },
-                    {
-                        label: "\\begin{equation}",
-                        kind: monaco.languages.CompletionItemKind.Snippet,
-                        insertText: "\\begin{equation}\n${1:equation}\n\\end{equation}",
-                        insertTextRules: monaco.languages.CompletionItemInsertTextRule.InsertAsSnippet,
+            try {
+                const result = await invoke("complete_at", {
+                    path: getPath() || null,
+                    content: model.getValue(),
+                    line: position.lineNumber,
+                    column: position.column
+                });
+                const range = {
+                    startLineNumber: position.lineNumber,
+                    startColumn: result.from,
+                    endLineNumber: position.lineNumber,
+                    endColumn: position.column
+                };
+                return {
+                    suggestions: result.items.map((item) => ({
+                        label: item.label,
+                        kind: completionKind(monaco, item.kind),
+                        detail: item.detail || undefined,
+                        insertText: item.insert_text,
+                        insertTextRules: item.snippet
+                            ? monaco.languages.CompletionItemInsertTextRule.InsertAsSnippet
+                            : undefined,
+                        // 目录补全后继续弹出下一级
+                        command: item.kind === "file" && item.label.endsWith("/")
+                            ? { id: "editor.action.triggerSuggest" }
+                            : undefined,
                         range
-                    }
-                ]
-            };
+                    }))
+                };
+            } catch (error) {
+                console.error("Completion failed:", error);
+                return { suggestions: staticLatexSuggestions(monaco, fallbackRange) };
+            }
         }
     });
 }
@@ -230,6 +295,8 @@ $$ E = mc^2 $$
     const [pdfKey, setPdfKey] = useState(0);
     const [logs, setLogs] = useState("");
     const [isDirty, setIsDirty] = useState(false);
+    const [settings, setSettings] = useState(null);
+    const settingsRef = useRef(settings);
     const monacoRef = useRef(null);
     const editorRef = useRef(null);
     const completionRef = useRef(null);
@@ -239,12 +306,104 @@ $$ E = mc^2 $$
     const [fetchedPaths, setFetchedPaths] = useState(new Set());
     const codeRef = useRef(code);
     const currentPathRef = useRef(currentPath);
+    // 未保存文档的临时工作区 id，由后端分配，编译和 SyncTeX 都与之对应
+    const untitledIdRef = useRef(null);
 
     useEffect(() => { codeRef.current = code; }, [code]);
     useEffect(() => { currentPathRef.current = currentPath; }, [currentPath]);
+    useEffect(() => { settingsRef.current = settings; }, [settings]);
+
+    async function ensureUntitledId() {
+        if (!untitledIdRef.current) {
+            untitledIdRef.current = await invoke("create_untitled_workspace");
+        }
+        return untitledIdRef.current;
+    }
+
+    // 另存为正式文件后临时工作区就没用了
+    function discardUntitledWorkspace() {
+        const id = untitledIdRef.current;
+        if (!id) {
+            return;
+        }
+        untitledIdRef.current = null;
+        invoke("discard_untitled_workspace", { id }).catch((e) => console.error(e));
+    }
+
+    // 设置由后端保存；任一窗口修改后都会广播 settings-changed
+    useEffect(() => {
+        invoke("get_settings").then(setSettings).catch((e) => console.error("Failed to load settings:", e));
+        const unlisten = listen("settings-changed", (event) => setSettings(event.payload));
+        return () => {
+            unlisten.then((fn) => fn());
+        };
+    }, []);
 
     const normalizePath = (value) => value.replace(/\\\\/g, "/");
 
+    // 记录每个文件读取/保存时的内容版本，用于检测外部修改
+    const versionsRef = useRef(new Map());
+    // 每个文件读取时检测到的编码，保存时按原编码写回
+    const encodingsRef = useRef(new Map());
+
+    // 最近打开记录失败不影响打开本身
+    const rememberRecent = (path, kind) => {
+        invoke("record_recent", { path, kind }).catch((e) => console.error("Failed to record recent item:", e));
+    };
+
+    const formatSize = (bytes) => `${(bytes / 1024 / 1024).toFixed(1)} MB`;
+
+    const readDocument = async (path) => {
+        let result;
+        try {
+            result = await invoke("read_file", { path });
+        } catch (e) {
+            if (e?.kind === "binary") {
+                throw `二进制文件无法在编辑器中打开 (${formatSize(e.size)})`;
+            }
+            if (e?.kind === "too_large") {
+                throw `文件过大 (${formatSize(e.size)}，上限 ${formatSize(e.limit)})`;
+            }
+            throw e?.message ?? e;
+        }
+        versionsRef.current.set(path, result.version);
+        encodingsRef.current.set(path, result.encoding);
+        return result.content;
+    };
+
+    const writeDocument = async (path, content) => {
+        let expectedVersion = versionsRef.current.get(path) ?? null;
+        let encoding = encodingsRef.current.get(path) ?? null;
+        // 换行符与 BOM 默认沿用文件原样，设置中指定了换行符时统一转换
+        const lineEnding = settingsRef.current?.line_ending ?? null;
+        for (;;) {
+            try {
+                const options = { encoding, line_ending: lineEnding };
+                const result = await invoke("save_file", { path, content, expectedVersion, options });
+                versionsRef.current.set(path, result.version);
+                encodingsRef.current.set(path, encoding);
+                return;
+            } catch (e) {
+                if (e?.kind === "unencodable") {
+                    const convert = confirm(`${path}\n内容包含 ${e.encoding} 无法表示的字符。是否转换为 UTF-8 保存？`);
+                    if (!convert) {
+                        throw `Content not representable in ${e.encoding}; save canceled`;
+                    }
+                    encoding = "UTF-8";
+                    continue;
+                }
+                if (e?.kind !== "conflict") {
+                    throw e?.message ?? e;
+                }
+                const overwrite = confirm(`${path}\n文件已在外部被修改。是否覆盖磁盘上的版本？`);
+                if (!overwrite) {
+                    throw "File changed on disk; save canceled";
+                }
+                expectedVersion = null;
+            }
+        }
+    };
+
     // 辅助函数：提取父目录路径
     const getParentPath = (path) => {
         if (!path) return "";
@@ -272,6 +431,22 @@ $$ E = mc^2 $$
         }
     };
 
+    // tectonic 不在 PATH 上时提供一键安装，装好后后端会自动使用
+    async function offerTectonicInstall() {
+        if (!confirm("未找到 tectonic。是否下载并安装到应用数据目录？")) {
+            setLogs("Compilation failed: tectonic not found.");
+            return;
+        }
+        setLogs("Downloading tectonic...");
+        try {
+            const tool = await invoke("install_tectonic");
+            setLogs(`Installed ${tool.version} (${tool.path}). Compile again to use it.`);
+        } catch (e) {
+            console.error(e);
+            setLogs("Tectonic install failed: " + e);
+        }
+    }
+
     async function handleCompile() {
         setLoading(true);
         setLogs("Compiling... (Check terminal for details)");
@@ -282,30 +457,76 @@ $$ E = mc^2 $$
             }
         }
         try {
-            const pdfBytes = await invoke("compile_latex", {
+            const result = await invoke("compile_latex", {
                 latexCode: code,
-                filePath: currentPath || null
+                filePath: currentPath || null,
+                returnPath: true,
+                options: currentPath ? null : { untitled_id: await ensureUntitledId() }
             });
 
-            const byteArray = new Uint8Array(pdfBytes);
-            const blob = new Blob([byteArray], { type: "application/pdf" });
-            const url = URL.createObjectURL(blob);
-            if (pdfUrl) URL.revokeObjectURL(pdfUrl);
-            setPdfUrl(url);
+            if (pdfUrl.startsWith("blob:")) URL.revokeObjectURL(pdfUrl);
+            if (currentPath) {
+                // 通过 asset 协议直接读取磁盘上的 PDF，时间戳避免读到缓存的旧版本
+                setPdfUrl(`${convertFileSrc(result.pdf_path)}?t=${Date.now()}`);
+            } else {
+                const bytes = await invoke("read_pdf_bytes", { path: result.pdf_path });
+                const blob = new Blob([bytes], { type: "application/pdf" });
+                setPdfUrl(URL.createObjectURL(blob));
+            }
             setPdfKey((prev) => prev + 1);
 
-            if (currentPath) {
+            const diagnostics = Array.isArray(result.diagnostics) ? result.diagnostics : [];
+            if (monacoRef.current && editorRef.current) {
+                const model = editorRef.current.getModel();
+                if (model) {
+                    const markers = diagnostics
+                        .filter((diag) => !diag.file || !currentPath || normalizePath(diag.file) === normalizePath(currentPath))
+                        .map((diag) => {
+                            const line = Math.max(1, Number(diag.line) || 1);
+                            return {
+                                severity: diag.severity === "warning"
+                                    ? monacoRef.current.MarkerSeverity.Warning
+                                    : monacoRef.current.MarkerSeverity.Info,
+                                message: diag.message,
+                                startLineNumber: line,
+                                startColumn: 1,
+                                endLineNumber: line,
+                                endColumn: model.getLineMaxColumn(Math.min(line, model.getLineCount()))
+                            };
+                        });
+                    monacoRef.current.editor.setModelMarkers(model, "latex", markers);
+                }
+            }
+            const warningNote = diagnostics.length ? ` (${diagnostics.length} warnings)` : "";
+
+            if (currentPath && result.output_redirected) {
+                // 源文件目录只读，产物在应用缓存里，文件树中看不到
+                setLogs(`Success! Source folder is read-only, output written to ${result.output_dir}.${warningNote}`);
+            } else if (currentPath) {
                 const parentDir = getParentPath(currentPath);
                 await refreshFolder(parentDir);
-                setLogs("Success! PDF generated & Saved. File tree updated.");
+                setLogs(`Success! PDF generated & Saved. File tree updated.${warningNote}`);
             } else {
-                setLogs("Success! PDF generated (Temp mode).");
+                setLogs(`Success! PDF generated (Temp mode).${warningNote}`);
             }
 
             setIsDirty(false);
         } catch (e) {
-            console.error(e);
             const errors = Array.isArray(e) ? e : e?.error;
+            // 被后来的编译请求取代，结果由那次请求负责显示
+            if (Array.isArray(errors) && errors.some((err) => err?.message === COMPILE_SUPERSEDED)) {
+                return;
+            }
+            if (Array.isArray(errors) && errors.some((err) => err?.message?.startsWith("未找到 tectonic"))) {
+                return offerTectonicInstall();
+            }
+            // 超时没有行号可标，直接提示
+            const timeout = Array.isArray(errors) && errors.find((err) => err?.severity === "timeout");
+            if (timeout) {
+                setLogs(timeout.message);
+                return;
+            }
+            console.error(e);
             if (Array.isArray(errors) && monacoRef.current && editorRef.current) {
                 const model = editorRef.current.getModel();
                 if (model) {
@@ -342,9 +563,8 @@ $$ E = mc^2 $$
     }
 
     async function handleSaveAs() {
-        const path = await save({
-            filters: [{ name: "LaTeX", extensions: ["tex"] }]
-        });
+        // 对话框由后端弹出，选中的路径才会加入允许访问的范围
+        const path = await invoke("save_file_dialog");
 
         if (!path) {
             setLogs("Save canceled.");
@@ -353,9 +573,10 @@ $$ E = mc^2 $$
 
         setLogs("Saving...");
         try {
-            await invoke("save_file", { path, content: code });
+            await writeDocument(path, code);
             setCurrentPath(path);
             setIsDirty(false);
+            discardUntitledWorkspace();
             setLogs(`Saved: ${path}`);
         } catch (e) {
             console.error(e);
@@ -372,7 +593,7 @@ $$ E = mc^2 $$
 
         setLogs("Saving...");
         try {
-            await invoke("save_file", { path: currentPath, content: code });
+            await writeDocument(currentPath, code);
             setIsDirty(false);
             setLogs(`Saved: ${currentPath}`);
             await handleCompile();
@@ -384,23 +605,20 @@ $$ E = mc^2 $$
     }
 
     async function handleOpen() {
-        const selected = await open({
-            filters: [{ name: "LaTeX", extensions: ["tex"] }],
-            multiple: false
-        });
+        const path = await invoke("open_file_dialog");
 
-        if (!selected) {
+        if (!path) {
             setLogs("Open canceled.");
             return;
         }
 
-        const path = Array.isArray(selected) ? selected[0] : selected;
         setLogs("Opening...");
         try {
-            const content = await invoke("read_file", { path });
+            const content = await readDocument(path);
             setCode(content);
             setCurrentPath(path);
             setIsDirty(false);
+            rememberRecent(path, "file");
             setLogs(`Opened: ${path}`);
         } catch (e) {
             console.error(e);
@@ -410,23 +628,20 @@ $$ E = mc^2 $$
     }
 
     async function handleOpenFolder() {
-        const selected = await open({
-            directory: true,
-            multiple: false
-        });
+        const path = await invoke("open_folder_dialog");
 
-        if (!selected) {
+        if (!path) {
             setLogs("Open folder canceled.");
             return;
         }
 
-        const path = Array.isArray(selected) ? selected[0] : selected;
         setLogs("Loading folder...");
         try {
             const entries = await invoke("list_files", { rootPath: path });
             setFileTree(entries);
             setRootPath(path);
             setExpandedPaths(new Set([normalizePath(path)]));
+            rememberRecent(path, "project");
             setLogs(`Loaded folder: ${path}`);
         } catch (e) {
             console.error(e);
@@ -537,11 +752,12 @@ $$ E = mc^2 $$
             await handleSave();
         }
         setLogs("Opening...");
-        invoke("read_file", { path })
+        readDocument(path)
             .then((content) => {
                 setCode(content);
                 setCurrentPath(path);
                 setIsDirty(false);
+                rememberRecent(path, "file");
                 setLogs(`Opened: ${path}`);
             })
             .catch((e) => {
@@ -619,11 +835,94 @@ $$ E = mc^2 $$
         applySyncHighlight(line, column);
     }, [code, currentPath]);
 
+    // ---- 会话恢复：打开的目录、当前文件、光标与滚动位置，以及未保存的新文件 ----
+    const UNTITLED_ID = "untitled";
+    const sessionRestoredRef = useRef(false);
+    const sessionTimerRef = useRef(null);
+    const pendingViewRef = useRef(null);
+    const rootPathRef = useRef(rootPath);
+    const isDirtyRef = useRef(isDirty);
+    useEffect(() => { rootPathRef.current = rootPath; }, [rootPath]);
+    useEffect(() => { isDirtyRef.current = isDirty; }, [isDirty]);
+
+    const currentViewState = () => {
+        const editor = editorRef.current;
+        const position = editor?.getPosition();
+        return {
+            line: position?.lineNumber ?? 1,
+            column: position?.column ?? 1,
+            scroll_top: editor?.getScrollTop() ?? 0,
+            scroll_left: editor?.getScrollLeft() ?? 0
+        };
+    };
+
+    const scheduleSessionSave = () => {
+        // 恢复完成前保存会用默认状态覆盖上次的会话
+        if (!sessionRestoredRef.current) {
+            return;
+        }
+        clearTimeout(sessionTimerRef.current);
+        sessionTimerRef.current = setTimeout(() => {
+            const path = currentPathRef.current;
+            const view = currentViewState();
+            const untitledId = untitledIdRef.current || UNTITLED_ID;
+            const state = {
+                open_folder: rootPathRef.current || null,
+                open_files: path ? [{ path, view }] : [],
+                untitled: !path && isDirtyRef.current ? [{ id: untitledId, content: codeRef.current, view }] : [],
+                active_tab: path || (isDirtyRef.current ? untitledId : null)
+            };
+            invoke("save_session", { state }).catch((e) => console.error("Failed to save session:", e));
+        }, 1000);
+    };
+
+    useEffect(scheduleSessionSave, [code, currentPath, rootPath, isDirty]);
+
+    useEffect(() => {
+        const restore = async () => {
+            const session = await invoke("load_session");
+            if (session.open_folder) {
+                const entries = await invoke("list_files", { rootPath: session.open_folder });
+                setFileTree(entries);
+                setRootPath(session.open_folder);
+                setExpandedPaths(new Set([normalizePath(session.open_folder)]));
+            }
+            const file = session.open_files.find((f) => f.path === session.active_tab);
+            const untitled = session.untitled.find((b) => b.id === session.active_tab);
+            if (file) {
+                const content = await readDocument(file.path);
+                setCode(content);
+                setCurrentPath(file.path);
+                setIsDirty(false);
+                pendingViewRef.current = file.view;
+            } else if (untitled) {
+                untitledIdRef.current = untitled.id === UNTITLED_ID ? null : untitled.id;
+                setCode(untitled.content);
+                setIsDirty(true);
+                pendingViewRef.current = untitled.view;
+            }
+        };
+        restore()
+            .catch((e) => console.error("Failed to restore session:", e))
+            .finally(() => { sessionRestoredRef.current = true; });
+    }, []);
+
+    useEffect(() => {
+        const view = pendingViewRef.current;
+        if (!view || !editorRef.current) {
+            return;
+        }
+        pendingViewRef.current = null;
+        editorRef.current.setPosition({ lineNumber: view.line || 1, column: view.column || 1 });
+        editorRef.current.setScrollPosition({ scrollTop: view.scroll_top, scrollLeft: view.scroll_left });
+    }, [code, currentPath]);
+
     const handleSyncFromPdf = async ({ page, x, y }) => {
         setLogs("SyncTeX: locating source...");
         try {
             const result = await invoke("synctex_edit", {
                 filePath: currentPath || null,
+                untitledId: currentPath ? null : untitledIdRef.current,
                 page,
                 x,
                 y
@@ -635,7 +934,7 @@ $$ E = mc^2 $$
 
             const hasCurrentFile = Boolean(currentPathRef.current);
             if (inputPath && hasCurrentFile && normalizePath(inputPath) !== normalizePath(currentPathRef.current)) {
-                const content = await invoke("read_file", { path: inputPath });
+                const content = await readDocument(inputPath);
                 setCode(content);
                 setCurrentPath(inputPath);
                 setIsDirty(false);
@@ -677,9 +976,9 @@ $$ E = mc^2 $$
 
             setLogs("Saving...");
             try {
-                await invoke("save_file", { path, content });
+                await writeDocument(path, content);
                 setLogs(`Saved: ${path}`);
-                await invoke("compile_latex", { latexCode: content });
+                await invoke("compile_latex", { latexCode: content, returnPath: true });
             } catch (e) {
                 console.error(e);
                 setLogs("Save failed: " + e);
@@ -744,7 +1043,7 @@ $$ E = mc^2 $$
                     <Editor
                         height="100%"
                         language="latex"
-                        theme="vs-dark"
+                        theme={editorTheme(settings?.theme)}
                         value={code}
                         onChange={(value) => {
                             setCode(value || "");
@@ -755,8 +1054,10 @@ $$ E = mc^2 $$
                             editorRef.current = editor;
                             monacoRef.current = monaco;
                             if (!completionRef.current) {
-                                completionRef.current = registerLatexCompletions(monaco);
+                                completionRef.current = registerLatexCompletions(monaco, () => currentPathRef.current);
                             }
+                            editor.onDidChangeCursorPosition(scheduleSessionSave);
+                            editor.onDidScrollChange(scheduleSessionSave);
                             editor.onKeyDown((event) => {
                                 if (event.keyCode !== monaco.KeyCode.Enter) {
                                     return;